@@ -0,0 +1,135 @@
+use crate::cli::{AltArgs, AltCommand};
+use crate::core::{Config, SwapResult};
+use crate::transaction::{alt::frequent_pools, AltManager, LookupTable, SwapJournal};
+use colored::*;
+use console::style;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, signature::Signature, signer::Signer};
+use std::sync::Arc;
+
+pub async fn execute(args: AltArgs, config: Config) -> SwapResult<()> {
+    println!("{}", "🗂️  Address Lookup Tables".bold().cyan());
+
+    let keypair = config.get_keypair()?;
+    let rpc_client = Arc::new(RpcClient::new_with_commitment(
+        config.rpc_url.clone(),
+        CommitmentConfig::confirmed(),
+    ));
+    let manager = AltManager::new(rpc_client, config.lookup_tables.clone(), Some(keypair.pubkey()));
+
+    match args.command {
+        AltCommand::List => {
+            let owned = manager.tables_owned_by(&keypair.pubkey()).await?;
+            println!("\n{} {}", style("Owned by").bold(), keypair.pubkey());
+            if owned.is_empty() {
+                println!("  {}", style("None yet: create one with `alt create` or `alt sync`").dim());
+            }
+            for table in &owned {
+                display_table(table);
+            }
+
+            if !config.lookup_tables.is_empty() {
+                println!("\n{}", style("From LOOKUP_TABLES").bold());
+                for address in &config.lookup_tables {
+                    match manager.get_alt(address).await {
+                        Ok(table) => println!("  {} {} addresses", address, table.addresses.len()),
+                        Err(e) => println!("  {} {} {}", style("⚠").yellow(), address, style(e).dim()),
+                    }
+                }
+            }
+        }
+        AltCommand::Create => {
+            let (table, signature) = manager.create_table(&keypair).await?;
+            println!("{} Created {}", "✅".green(), table.to_string().bold());
+            print_signatures(&[signature]);
+        }
+        AltCommand::Extend { table, pools, addresses } => {
+            let mut groups = Vec::new();
+            for pool in &pools {
+                groups.push(manager.pool_accounts(pool).await?);
+            }
+            if !addresses.is_empty() {
+                groups.push(addresses);
+            }
+            if groups.is_empty() {
+                println!("{}", "Nothing to add: pass --pool or --address".yellow());
+                return Ok(());
+            }
+
+            let mut signatures = Vec::new();
+            for group in &groups {
+                signatures.extend(match table {
+                    Some(table) => manager.extend_table(&keypair, &table, group).await?,
+                    None => manager.add_addresses(&keypair, group).await?,
+                });
+            }
+            report_added(&signatures);
+        }
+        AltCommand::Sync { min_trades, dry_run } => {
+            let entries = SwapJournal::from_config(&config).entries()?;
+            let min_trades = min_trades.unwrap_or(config.alt_min_pool_trades);
+            let pools = frequent_pools(&entries, min_trades);
+            if pools.is_empty() {
+                println!("{}", format!("No pool traded at least {} times in the journal", min_trades).yellow());
+                return Ok(());
+            }
+
+            let mut signatures = Vec::new();
+            for (pool, trades) in &pools {
+                let accounts = manager.pool_accounts(pool).await?;
+                println!("  {} {} swaps, {} accounts", pool, trades, accounts.len());
+                if !dry_run {
+                    signatures.extend(manager.add_addresses(&keypair, &accounts).await?);
+                }
+            }
+            if !dry_run {
+                report_added(&signatures);
+            }
+        }
+        AltCommand::Deactivate { table } => {
+            let signature = manager.deactivate_table(&keypair, &table).await?;
+            println!(
+                "{} Deactivated {}; it can be closed in about 513 slots",
+                "✅".green(),
+                table.to_string().bold()
+            );
+            print_signatures(&[signature]);
+        }
+        AltCommand::Close { table } => {
+            let signature = manager.close_table(&keypair, &table).await?;
+            println!("{} Closed {} and reclaimed its rent", "✅".green(), table.to_string().bold());
+            print_signatures(&[signature]);
+        }
+    }
+
+    Ok(())
+}
+
+fn display_table(table: &LookupTable) {
+    let status = if table.is_active() {
+        "active".green()
+    } else {
+        format!("deactivated in slot {}", table.deactivation_slot).yellow()
+    };
+    println!(
+        "  {} {}/256 addresses, {}",
+        table.address.to_string().bold(),
+        table.addresses.len(),
+        status
+    );
+}
+
+fn report_added(signatures: &[Signature]) {
+    if signatures.is_empty() {
+        println!("{} Every account is already in a table", "✅".green());
+    } else {
+        println!("{} Tables updated", "✅".green());
+        print_signatures(signatures);
+    }
+}
+
+fn print_signatures(signatures: &[Signature]) {
+    for signature in signatures {
+        println!("  {}", style(format!("https://solscan.io/tx/{}", signature)).dim());
+    }
+}
@@ -0,0 +1,37 @@
+use crate::cli::{display::PoolDisplay, HistoryArgs};
+use crate::core::{Config, SwapResult};
+use crate::transaction::SwapJournal;
+use colored::*;
+use console::style;
+
+pub async fn execute(args: HistoryArgs, config: Config) -> SwapResult<()> {
+    println!("{}", "📜 Swap History".bold().cyan());
+
+    let journal = SwapJournal::from_config(&config);
+    let mut entries = journal.history(&args.filter.filter()?)?;
+
+    if entries.is_empty() {
+        println!(
+            "{}",
+            format!("No swaps recorded in {}", journal.path().display()).yellow()
+        );
+        return Ok(());
+    }
+
+    let total = entries.len();
+    if let Some(limit) = args.limit {
+        entries.drain(..total.saturating_sub(limit));
+    }
+
+    println!();
+    for entry in &entries {
+        PoolDisplay::display_journal_entry(entry);
+    }
+
+    println!(
+        "\n{}",
+        style(format!("Showing {} of {} swaps from {}", entries.len(), total, journal.path().display())).dim()
+    );
+
+    Ok(())
+}
@@ -1,3 +1,6 @@
+pub mod alt;
+pub mod history;
+pub mod pnl;
 pub mod pools;
 pub mod quote;
 pub mod swap;
@@ -0,0 +1,30 @@
+use crate::cli::{display::PoolDisplay, PnlArgs};
+use crate::core::{Config, SwapResult};
+use crate::transaction::{journal::realized_pnl, SwapJournal};
+use colored::*;
+use console::style;
+
+pub async fn execute(args: PnlArgs, config: Config) -> SwapResult<()> {
+    println!("{}", "📈 Realized PnL (FIFO)".bold().cyan());
+
+    let journal = SwapJournal::from_config(&config);
+    let pnl = realized_pnl(&journal.entries()?, &args.filter.filter()?);
+
+    if pnl.is_empty() {
+        println!(
+            "{}",
+            format!("No matching swaps recorded in {}", journal.path().display()).yellow()
+        );
+        return Ok(());
+    }
+
+    for token in &pnl {
+        PoolDisplay::display_token_pnl(token);
+    }
+
+    let total: f64 = pnl.iter().map(|token| token.realized_usd).sum();
+    println!("\n{} {:+.2} USD", style("Total realized:").bold(), total);
+    println!("{}", style("Values are USD prices at execution time; network fees are not deducted").dim());
+
+    Ok(())
+}
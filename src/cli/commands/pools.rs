@@ -9,7 +9,8 @@ pub async fn execute(args: PoolsArgs) -> SwapResult<()> {
     println!("{}", "🚀 Raydium Multi-Pool Discovery Tool".bold().cyan());
     
     // Load configuration
-    let config = Config::from_env()?;
+    let mut config = Config::from_env()?;
+    args.scoring.apply(&mut config);
     config.validate()?;
 
     // Create progress bar
@@ -2,11 +2,12 @@ use crate::cli::{display::PoolDisplay, QuoteArgs};
 use crate::core::{Config, QuoteRequest, SwapResult};
 use crate::discovery::PoolDiscovery;
 use crate::quotes::QuoteEngine;
-use crate::selection::PoolSelector;
+use crate::selection::{CostModel, PoolSelector, SplitConfig};
+use crate::transaction::PriorityFeeEstimator;
 use colored::*;
 use console::style;
 use log::info;
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -14,7 +15,8 @@ pub async fn execute(args: QuoteArgs) -> SwapResult<()> {
     println!("{}", "🚀 Raydium Multi-Pool Quote Tool".bold().cyan());
     
     // Load configuration
-    let config = Config::from_env()?;
+    let mut config = Config::from_env()?;
+    args.scoring.apply(&mut config);
     config.validate()?;
 
     // Create progress bar
@@ -22,8 +24,21 @@ pub async fn execute(args: QuoteArgs) -> SwapResult<()> {
 
     // Initialize components
     let discovery = Arc::new(PoolDiscovery::new(config.clone())?);
-    let quote_engine = Arc::new(QuoteEngine::new());
-    let selector = PoolSelector::new(discovery.clone(), quote_engine);
+    let rpc_client = Arc::new(
+        solana_client::nonblocking::rpc_client::RpcClient::new_with_commitment(
+            config.rpc_url.clone(),
+            solana_sdk::commitment_config::CommitmentConfig::confirmed(),
+        ),
+    );
+    let quote_engine = Arc::new(QuoteEngine::with_rpc_client(rpc_client.clone()));
+
+    // Without a wallet every token account is assumed to need creating
+    let wallet = args
+        .wallet
+        .or_else(|| config.get_keypair().ok().map(|keypair| keypair.pubkey()));
+    // Price execution costs at the compute unit price the swap would pay
+    let cost_model = CostModel::new(rpc_client, wallet).with_priority_fee(PriorityFeeEstimator::from_config(&config));
+    let selector = PoolSelector::new(discovery.clone(), quote_engine).with_cost_model(cost_model);
 
     pb.set_message("Discovering pools...");
 
@@ -40,26 +55,64 @@ pub async fn execute(args: QuoteArgs) -> SwapResult<()> {
         return Ok(());
     }
 
-    // Convert amount to smallest units based on token decimals
+    // Convert amount to smallest units based on decimals of the token it fixes
+    let amount_mint = if args.exact_out { token_out } else { args.token_in };
     let decimals = crate::core::get_token_decimals(
         &solana_client::rpc_client::RpcClient::new(config.rpc_url.clone()),
-        &args.token_in
+        &amount_mint
     )?;
-    let amount_in = (args.amount * 10f64.powi(decimals as i32)) as u64;
+    let amount = (args.amount * 10f64.powi(decimals as i32)) as u64;
 
-    let request = QuoteRequest {
-        token_in: args.token_in,
-        token_out,
-        amount_in,
-        slippage_bps: args.slippage,
+    let request = if args.exact_out {
+        QuoteRequest::exact_out(args.token_in, token_out, amount, args.slippage)
+    } else {
+        QuoteRequest::exact_in(args.token_in, token_out, amount, args.slippage)
     };
 
     info!(
-        "Getting quotes for {} -> {} (amount: {}, slippage: {} bps)",
-        args.token_in, token_out, amount_in, args.slippage
+        "Getting quotes for {} -> {} (amount: {}, mode: {}, slippage: {} bps)",
+        args.token_in, token_out, amount, request.swap_mode, args.slippage
     );
 
-    if args.all {
+    if args.route {
+        // Search direct and multi-hop routes through hub tokens
+        pb.set_message("Searching routes through hub tokens...");
+        let route = selector.select_best_route(&request, config.hub_tokens.clone()).await?;
+
+        pb.finish_and_clear();
+
+        match route {
+            Some(route) => {
+                println!("\n{}", "🏆 Best Route Found".bold().green());
+                PoolDisplay::display_route_quote(&route);
+            }
+            None => {
+                println!(
+                    "{}",
+                    "❌ No route found for this token pair".red().bold()
+                );
+            }
+        }
+    } else if args.split {
+        // Split the order across pools of the pair
+        pb.set_message("Optimizing split across pools...");
+        let split = selector.select_best_split(&request, SplitConfig::default()).await?;
+
+        pb.finish_and_clear();
+
+        match split {
+            Some(split) => {
+                println!("\n{}", "🏆 Best Split Found".bold().green());
+                PoolDisplay::display_split_quote(&split);
+            }
+            None => {
+                println!(
+                    "{}",
+                    "❌ No pools found for this token pair".red().bold()
+                );
+            }
+        }
+    } else if args.all {
         // Get quotes from all pools
         pb.set_message("Getting quotes from all pools...");
         let quotes_by_type = selector.get_quotes_by_type(&request).await?;
@@ -86,6 +139,13 @@ pub async fn execute(args: QuoteArgs) -> SwapResult<()> {
             Some(quote) => {
                 println!("\n{}", "🏆 Best Quote Found".bold().green());
                 PoolDisplay::display_quote(&quote, true);
+
+                if !quote.is_worth_executing() {
+                    println!(
+                        "{}",
+                        "⚠️  Execution costs exceed the output of this trade".yellow().bold()
+                    );
+                }
             }
             None => {
                 println!(
@@ -1,12 +1,16 @@
 use crate::cli::{display::PoolDisplay, SwapArgs};
-use crate::core::{Config, QuoteRequest, SwapError, SwapParams, SwapResult};
+use crate::core::{
+    Config, QuoteRequest, QuoteResult, RouteSwapParams, SplitSwapParams, SwapError, SwapParams,
+    SwapResult, TokenInfo, TransactionResult,
+};
 use crate::discovery::PoolDiscovery;
 use crate::quotes::QuoteEngine;
-use crate::selection::PoolSelector;
-use crate::transaction::TransactionExecutor;
+use crate::selection::{CostModel, PoolSelector, SplitConfig};
+use crate::transaction::{JournalEntry, PriorityFeeEstimator, SwapJournal, TransactionExecutor};
 use colored::*;
 use console::style;
 use dialoguer::{theme::ColorfulTheme, Confirm, Password};
+use indicatif::ProgressBar;
 use log::{info, warn};
 use solana_sdk::{
     pubkey::Pubkey,
@@ -20,7 +24,11 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
     println!("{}", "🚀 Raydium Multi-Pool Swap Tool".bold().cyan());
     
     // Load configuration
-    let config = Config::from_env()?;
+    let mut config = Config::from_env()?;
+    args.scoring.apply(&mut config);
+    if let Some(priority_fee) = args.priority_fee {
+        config.priority_fee = priority_fee;
+    }
     config.validate()?;
 
     // Create progress bar
@@ -28,8 +36,19 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
 
     // Initialize components
     let discovery = Arc::new(PoolDiscovery::new(config.clone())?);
-    let quote_engine = Arc::new(QuoteEngine::new());
-    let selector = PoolSelector::new(discovery.clone(), quote_engine);
+    let rpc_client = Arc::new(
+        solana_client::nonblocking::rpc_client::RpcClient::new_with_commitment(
+            config.rpc_url.clone(),
+            solana_sdk::commitment_config::CommitmentConfig::confirmed(),
+        ),
+    );
+    let quote_engine = Arc::new(QuoteEngine::with_rpc_client(rpc_client.clone()));
+
+    // Check the wallet's existing token accounts when pricing execution costs
+    let wallet = config.get_keypair().ok().map(|keypair| keypair.pubkey());
+    let cost_model = CostModel::new(rpc_client, wallet).with_priority_fee(PriorityFeeEstimator::from_config(&config));
+    let selector = PoolSelector::new(discovery.clone(), quote_engine.clone())
+        .with_cost_model(cost_model);
 
     pb.set_message("Finding best pool...");
 
@@ -51,22 +70,38 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
         return Ok(());
     }
 
-    // Convert amount to smallest units based on token decimals
+    // Convert amount to smallest units based on decimals of the token it fixes
+    let amount_mint = if args.exact_out { token_out } else { args.token_in };
     let decimals = crate::core::get_token_decimals(
         &solana_client::rpc_client::RpcClient::new(config.rpc_url.clone()),
-        &args.token_in
+        &amount_mint
     )?;
-    let amount_in = (args.amount * 10f64.powi(decimals as i32)) as u64;
+    let amount = (args.amount * 10f64.powi(decimals as i32)) as u64;
 
-    let request = QuoteRequest {
-        token_in: args.token_in,
-        token_out,
-        amount_in,
-        slippage_bps: args.slippage,
+    let request = if args.exact_out {
+        QuoteRequest::exact_out(args.token_in, token_out, amount, args.slippage)
+    } else {
+        QuoteRequest::exact_in(args.token_in, token_out, amount, args.slippage)
     };
 
-    // Find best pool
-    let best_quote = selector.select_best_pool(&request).await?;
+    // Find best pool, best split across pools, or best multi-hop route
+    let (best_quote, split, route) = if args.route {
+        match selector.select_best_route(&request, config.hub_tokens.clone()).await? {
+            // A single-hop route is just a regular swap
+            Some(route) if route.hops.len() > 1 => (route.hops.last().cloned(), None, Some(route)),
+            Some(route) => (route.hops.into_iter().next(), None, None),
+            None => (None, None, None),
+        }
+    } else if args.split {
+        match selector.select_best_split(&request, SplitConfig::default()).await? {
+            // A single-leg split is just a regular swap
+            Some(split) if split.legs.len() > 1 => (split.primary_leg().cloned(), Some(split), None),
+            Some(split) => (split.legs.into_iter().next(), None, None),
+            None => (None, None, None),
+        }
+    } else {
+        (selector.select_best_pool(&request).await?, None, None)
+    };
 
     pb.finish_and_clear();
 
@@ -82,7 +117,28 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
     };
 
     // Display swap details
-    PoolDisplay::display_swap_confirmation(&quote);
+    match (&split, &route) {
+        (Some(split), _) => {
+            println!("\n{}", style("💱 Split Swap Confirmation").bold().underlined());
+            PoolDisplay::display_split_quote(split);
+        }
+        (_, Some(route)) => {
+            println!("\n{}", style("💱 Route Swap Confirmation").bold().underlined());
+            PoolDisplay::display_route_quote(route);
+        }
+        _ => PoolDisplay::display_swap_confirmation(&quote),
+    }
+
+    if split.is_none() && route.is_none() && !quote.is_worth_executing() {
+        println!(
+            "{}",
+            "⚠️  Execution costs exceed the output of this trade".yellow().bold()
+        );
+        if args.yes {
+            println!("{}", "❌ Swap aborted: not worth executing".red().bold());
+            return Ok(());
+        }
+    }
 
     // Confirm swap
     let proceed = if args.yes {
@@ -180,9 +236,22 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
         info!("Using v0 transaction format");
     }
     
-    // Enable ALT if requested
-    if args.use_alt && !args.legacy {
-        executor.enable_alts(config.rpc_url.clone()).await;
+    executor.set_amm_swap_v2(args.amm_v2);
+    executor.set_priority_fee(PriorityFeeEstimator::from_config(&config));
+    info!("Priority fee: {}", config.priority_fee);
+
+    // Refresh quotes that went stale while waiting for confirmation
+    executor.enable_requote(
+        discovery.clone(),
+        quote_engine.clone(),
+        config.max_quote_age_slots,
+        config.requote_threshold_bps,
+    );
+
+    // Enable ALT if requested; split and route swaps rarely fit without them
+    let use_alt = args.use_alt || split.is_some() || route.is_some();
+    if use_alt && !args.legacy {
+        executor.enable_alts(config.rpc_url.clone(), config.lookup_tables.clone()).await;
         info!("Address Lookup Tables enabled");
     } else if args.use_alt && args.legacy {
         warn!("ALT is only supported with v0 transactions, ignoring --use-alt flag");
@@ -196,10 +265,49 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
         token_out,
     };
 
+    // Token details for the journal, before the quotes move into the swap
+    let pools: Vec<&QuoteResult> = match (&split, &route) {
+        (Some(split), _) => split.legs.iter().collect(),
+        (_, Some(route)) => route.hops.iter().collect(),
+        _ => vec![&quote],
+    };
+    let journal_tokens = (token_info(&pools, args.token_in), token_info(&pools, token_out));
+    // Recorded at these amounts if the swap lands without a readable output
+    let quoted_pools: Vec<QuoteResult> = pools.into_iter().cloned().collect();
+    let quoted_amounts = match (&split, &route) {
+        (Some(split), _) => (split.amount_in, split.amount_out),
+        (_, Some(route)) => (route.amount_in, route.amount_out),
+        _ => (quote.amount_in, quote.amount_out),
+    };
+
     pb.set_message("Executing swap...");
 
     // Execute swap
-    match executor.execute_swap(swap_params).await {
+    let outcome = match (split, route) {
+        (_, Some(route)) => {
+            executor
+                .execute_route_swap(RouteSwapParams {
+                    quote: route,
+                    user_pubkey,
+                    slippage_bps: args.slippage,
+                })
+                .await
+        }
+        (Some(split), _) => {
+            executor
+                .execute_split_swap(SplitSwapParams {
+                    quote: split,
+                    user_pubkey,
+                    slippage_bps: args.slippage,
+                    token_in: args.token_in,
+                    token_out,
+                })
+                .await
+        }
+        (None, None) => execute_requoting(&executor, swap_params, args.yes, &pb).await,
+    };
+
+    match outcome {
         Ok(result) => {
             pb.finish_and_clear();
             
@@ -218,6 +326,32 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
                 result.actual_slippage,
                 output_token,
             );
+            if let Some(units) = result.compute_units_consumed {
+                println!(
+                    "  {} {} / {} limit",
+                    style("Compute units:").dim(),
+                    units,
+                    result.compute_unit_limit
+                );
+            }
+            if let Some(price) = result.executions.last().and_then(|execution| execution.price_after) {
+                println!("  {} {:.6}", style("Pool price after swap:").dim(), price);
+            }
+
+            let (token_in_info, token_out_info) = journal_tokens;
+            let entry = JournalEntry::new(
+                &result,
+                token_in_info,
+                token_out_info,
+                discovery.price(&result.token_in),
+                discovery.price(&result.token_out),
+            );
+            record_swap(&config, &entry);
+
+            // Tables only help v0 transactions
+            if config.alt_auto_extend && !args.legacy {
+                extend_lookup_tables(&executor, &config, &entry).await;
+            }
             
             // Optionally show balance changes
             if args.show_balance_changes {
@@ -269,10 +403,102 @@ pub async fn execute(args: SwapArgs) -> SwapResult<()> {
         }
         Err(e) => {
             pb.finish_and_clear();
+            // The swap moved funds even though its output is unknown
+            if let SwapError::UnresolvedOutput { signature, .. } = &e {
+                let (token_in_info, token_out_info) = journal_tokens;
+                let entry = JournalEntry::unresolved(
+                    signature.clone(),
+                    &quoted_pools,
+                    quoted_amounts,
+                    token_in_info,
+                    token_out_info,
+                    discovery.price(&args.token_in),
+                    discovery.price(&token_out),
+                );
+                record_swap(&config, &entry);
+            }
             println!("{} {}", "❌ Swap failed:".red().bold(), e);
             return Err(e);
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Add the pools of `entry` that are now traded often to the wallet's
+/// lookup tables; a failure here never fails the swap
+async fn extend_lookup_tables(executor: &TransactionExecutor, config: &Config, entry: &JournalEntry) {
+    let extended = match SwapJournal::from_config(config).entries() {
+        Ok(entries) => {
+            executor
+                .add_frequent_pools_to_alts(&entries, &entry.traded_pools(), config.alt_min_pool_trades)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    match extended {
+        Ok(signatures) if signatures.is_empty() => {}
+        Ok(signatures) => {
+            println!("  {} Added frequently traded pool accounts to lookup tables", style("🗂").dim());
+            for signature in signatures {
+                println!("  {}", style(format!("https://solscan.io/tx/{}", signature)).dim());
+            }
+        }
+        Err(e) => warn!("Failed to extend lookup tables: {}", e),
+    }
+}
+
+/// Append `entry` to the swap journal, warning if it cannot be written
+fn record_swap(config: &Config, entry: &JournalEntry) {
+    let journal = SwapJournal::from_config(config);
+    if let Err(e) = journal.append(entry) {
+        warn!("Failed to record swap in {}: {}", journal.path().display(), e);
+    }
+}
+
+/// Details of `mint` from the pools being swapped through
+fn token_info(pools: &[&QuoteResult], mint: Pubkey) -> TokenInfo {
+    pools
+        .iter()
+        .flat_map(|quote| [&quote.pool_info.token_a, &quote.pool_info.token_b])
+        .find(|token| token.mint == mint)
+        .cloned()
+        .unwrap_or_else(|| TokenInfo {
+            mint,
+            symbol: mint.to_string()[..8].to_string(),
+            decimals: 0,
+            name: String::new(),
+        })
+}
+
+/// Execute a single-pool swap, asking again whenever the quote moved past
+/// the requote threshold before it could be sent
+async fn execute_requoting(
+    executor: &TransactionExecutor,
+    mut params: SwapParams,
+    auto_confirm: bool,
+    pb: &ProgressBar,
+) -> SwapResult<TransactionResult> {
+    loop {
+        match executor.execute_swap(params.clone()).await {
+            Err(e @ SwapError::QuoteMoved { .. }) if !auto_confirm => {
+                params.quote = executor.requote(&params.quote, params.slippage_bps).await?;
+
+                let proceed = pb.suspend(|| {
+                    println!("\n{} {}", "⚠️".yellow(), e.to_string().yellow());
+                    PoolDisplay::display_swap_confirmation(&params.quote);
+
+                    Confirm::with_theme(&ColorfulTheme::default())
+                        .with_prompt("Proceed at the new price?")
+                        .default(false)
+                        .interact()
+                        .unwrap()
+                });
+                if !proceed {
+                    return Err(SwapError::Other("Swap cancelled after requote".to_string()));
+                }
+            }
+            outcome => return outcome,
+        }
+    }
+}
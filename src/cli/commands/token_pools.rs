@@ -5,7 +5,6 @@ use colored::*;
 use console::style;
 use std::sync::Arc;
 use std::collections::HashMap;
-use solana_sdk::pubkey::Pubkey;
 
 pub async fn execute(args: TokenPoolsArgs) -> SwapResult<()> {
     println!("{}", "🔍 Searching pools containing token...".bold().cyan());
@@ -26,81 +25,23 @@ pub async fn execute(args: TokenPoolsArgs) -> SwapResult<()> {
             "amm" => vec![PoolType::AMM],
             "stable" => vec![PoolType::Stable],
             "clmm" => vec![PoolType::CLMM],
+            "standard" | "cp" => vec![PoolType::Standard],
             _ => {
                 pb.finish_and_clear();
                 return Err(SwapError::InvalidInput(
-                    "Invalid pool type. Use: amm, stable, or clmm".to_string()
+                    "Invalid pool type. Use: amm, stable, clmm, or standard".to_string()
                 ));
             }
         }
     } else {
-        vec![PoolType::AMM, PoolType::Stable, PoolType::CLMM]
+        vec![PoolType::AMM, PoolType::Stable, PoolType::CLMM, PoolType::Standard]
     };
 
-    pb.set_message("Searching AMM pools...");
-
     // Find all pools containing the token
     pb.set_message("Searching for pools...");
-    
-    let all_pools = if pool_types.len() == 1 && pool_types[0] == PoolType::AMM {
-        // If only searching AMM pools, use the optimized method
-        discovery.find_pools_by_token(args.token).await?
-    } else {
-        // For other pool types or all types, we need to check against common pairs
-        let mut pools = Vec::new();
-        
-        // Common tokens to check against
-        let common_tokens = vec![
-            // SOL
-            Pubkey::try_from("So11111111111111111111111111111111111111112").unwrap(),
-            // USDC
-            Pubkey::try_from("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap(),
-            // USDT
-            Pubkey::try_from("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB").unwrap(),
-            // RAY
-            Pubkey::try_from("4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R").unwrap(),
-        ];
-
-        for pool_type in &pool_types {
-            match pool_type {
-                PoolType::AMM => {
-                    pb.set_message("Searching AMM pools...");
-                    if let Ok(amm_pools) = discovery.find_pools_by_token(args.token).await {
-                        pools.extend(amm_pools);
-                    }
-                }
-                PoolType::Stable | PoolType::CLMM => {
-                    pb.set_message(format!("Searching {} pools...", pool_type));
-                    // For Stable and CLMM, check against common pairs
-                    for other_token in &common_tokens {
-                        if *other_token == args.token {
-                            continue;
-                        }
-                        
-                        // Try both directions and filter by type
-                        if let Ok(found_pools) = discovery.find_pools_by_type(args.token, *other_token, *pool_type).await {
-                            for pool in found_pools {
-                                if !pools.iter().any(|p: &crate::core::PoolInfo| p.address == pool.address) {
-                                    pools.push(pool);
-                                }
-                            }
-                        }
-                        
-                        if let Ok(found_pools) = discovery.find_pools_by_type(*other_token, args.token, *pool_type).await {
-                            for pool in found_pools {
-                                if !pools.iter().any(|p: &crate::core::PoolInfo| p.address == pool.address) {
-                                    pools.push(pool);
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-        
-        pools
-    };
+    let all_pools = discovery
+        .find_pools_by_token_and_type(args.token, &pool_types)
+        .await?;
 
     pb.finish_and_clear();
 
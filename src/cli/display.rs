@@ -1,5 +1,6 @@
-use crate::core::{PoolInfo, PoolType, PoolState, QuoteResult};
+use crate::core::{PoolInfo, PoolType, PoolState, QuoteResult, RouteQuote, SplitQuote, SwapMode};
 use crate::selection::QuotesByType;
+use crate::transaction::{JournalEntry, TokenPnl};
 use colored::*;
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
@@ -53,12 +54,29 @@ impl PoolDisplay {
             input_token.symbol
         );
 
-        println!(
-            "  Min Output: {} {} ({}% slippage)",
-            format_amount(quote.min_amount_out, output_token),
-            output_token.symbol,
-            ((quote.amount_out - quote.min_amount_out) as f64 / quote.amount_out as f64 * 100.0)
-        );
+        match quote.swap_mode {
+            SwapMode::ExactIn => println!(
+                "  Min Output: {} {} ({}% slippage)",
+                format_amount(quote.min_amount_out, output_token),
+                output_token.symbol,
+                ((quote.amount_out - quote.min_amount_out) as f64 / quote.amount_out as f64 * 100.0)
+            ),
+            SwapMode::ExactOut => println!(
+                "  Max Input: {} {} ({}% slippage)",
+                format_amount(quote.max_amount_in, input_token),
+                input_token.symbol,
+                ((quote.max_amount_in - quote.amount_in) as f64 / quote.amount_in as f64 * 100.0)
+            ),
+        }
+
+        if let Some(traversal) = &quote.tick_traversal {
+            println!(
+                "  Ticks Crossed: {} | Tick Arrays Crossed: {} | End Tick: {}",
+                traversal.ticks_crossed, traversal.tick_arrays_crossed, traversal.end_tick
+            );
+        }
+
+        display_execution_costs(quote, "  ");
 
         println!(
             "  Pool: {}",
@@ -67,6 +85,127 @@ impl PoolDisplay {
         println!();
     }
 
+    /// Display a quote split across several pools
+    pub fn display_split_quote(split: &SplitQuote) {
+        let Some(first) = split.legs.first() else {
+            return;
+        };
+        let (input_token, output_token) = if split.token_in == first.pool_info.token_a.mint {
+            (&first.pool_info.token_a, &first.pool_info.token_b)
+        } else {
+            (&first.pool_info.token_b, &first.pool_info.token_a)
+        };
+
+        println!(
+            "{} {} Pool{}",
+            style("►").cyan(),
+            "Split".bright_magenta().bold(),
+            if split.legs.len() == 1 { "" } else { "s" }
+        );
+        println!(
+            "  {} {} → {} {}",
+            format_amount(split.amount_in, input_token),
+            input_token.symbol,
+            format_amount(split.amount_out, output_token),
+            output_token.symbol
+        );
+        println!(
+            "  Price Impact: {} | Fee: {} {}",
+            format_impact(split.price_impact),
+            format_amount(split.fee, input_token),
+            input_token.symbol
+        );
+        match split.swap_mode {
+            SwapMode::ExactIn => println!(
+                "  Min Output: {} {}",
+                format_amount(split.min_amount_out, output_token),
+                output_token.symbol
+            ),
+            SwapMode::ExactOut => println!(
+                "  Max Input: {} {}",
+                format_amount(split.max_amount_in, input_token),
+                input_token.symbol
+            ),
+        }
+
+        for leg in &split.legs {
+            println!(
+                "  {} {:>5.1}% {:?}: {} {} → {} {} (impact {})",
+                style("•").dim(),
+                split.leg_share(leg),
+                leg.pool_info.pool_type,
+                format_amount(leg.amount_in, input_token),
+                input_token.symbol,
+                format_amount(leg.amount_out, output_token),
+                output_token.symbol,
+                format_impact(leg.price_impact)
+            );
+            println!("    Pool: {}", style(format!("{}", leg.pool_info.address)).dim());
+        }
+        println!();
+    }
+
+    /// Display a multi-hop route with per-hop details
+    pub fn display_route_quote(route: &RouteQuote) {
+        let (Some(first), Some(last)) = (route.hops.first(), route.hops.last()) else {
+            return;
+        };
+        let input_token = hop_tokens(first).0;
+        let output_token = hop_tokens(last).1;
+
+        let path = std::iter::once(input_token.symbol.as_str())
+            .chain(route.hops.iter().map(|hop| hop_tokens(hop).1.symbol.as_str()))
+            .collect::<Vec<_>>()
+            .join(" → ");
+        println!(
+            "{} {} ({} hop{})",
+            style("►").cyan(),
+            "Route".bright_magenta().bold(),
+            route.hops.len(),
+            if route.hops.len() == 1 { "" } else { "s" }
+        );
+        println!("  Path: {}", path);
+        println!(
+            "  {} {} → {} {}",
+            format_amount(route.amount_in, input_token),
+            input_token.symbol,
+            format_amount(route.amount_out, output_token),
+            output_token.symbol
+        );
+        println!("  Price Impact: {}", format_impact(route.price_impact));
+        match route.swap_mode {
+            SwapMode::ExactIn => println!(
+                "  Min Output: {} {}",
+                format_amount(route.min_amount_out, output_token),
+                output_token.symbol
+            ),
+            SwapMode::ExactOut => println!(
+                "  Max Input: {} {}",
+                format_amount(route.max_amount_in, input_token),
+                input_token.symbol
+            ),
+        }
+
+        for (index, hop) in route.hops.iter().enumerate() {
+            let (hop_in, hop_out) = hop_tokens(hop);
+            println!(
+                "  {} Hop {} {:?}: {} {} → {} {} (impact {}, fee {} {})",
+                style("•").dim(),
+                index + 1,
+                hop.pool_info.pool_type,
+                format_amount(hop.amount_in, hop_in),
+                hop_in.symbol,
+                format_amount(hop.amount_out, hop_out),
+                hop_out.symbol,
+                format_impact(hop.price_impact),
+                format_amount(hop.fee, hop_in),
+                hop_in.symbol
+            );
+            println!("    Pool: {}", style(format!("{}", hop.pool_info.address)).dim());
+        }
+        println!();
+    }
+
     /// Display all quotes grouped by type
     pub fn display_quotes_by_type(quotes: &QuotesByType) {
         let summary = quotes.summary();
@@ -142,7 +281,7 @@ impl PoolDisplay {
                     pool.token_b.symbol
                 );
             }
-            PoolState::Stable { reserves, amp_factor } => {
+            PoolState::Stable { reserves, amp_factor, .. } => {
                 println!("Reserves: {} {} / {} {}", 
                     format_amount(*reserves.get(0).unwrap_or(&0), &pool.token_a),
                     pool.token_a.symbol,
@@ -151,13 +290,14 @@ impl PoolDisplay {
                 );
                 println!("Amplification Factor: {}", amp_factor);
             }
-            PoolState::CLMM { current_tick, tick_spacing, liquidity, fee_tier } => {
+            PoolState::CLMM { current_tick, tick_spacing, liquidity, sqrt_price_x64, fee_tier } => {
                 println!("Current Tick: {}", current_tick);
                 println!("Tick Spacing: {}", tick_spacing);
                 println!("Liquidity: {}", liquidity);
-                println!("Fee Tier: {} bps", fee_tier);
+                println!("Sqrt Price (Q64.64): {}", sqrt_price_x64);
+                println!("Fee Tier: {:.4}%", *fee_tier as f64 / 10000.0);
             }
-            PoolState::Standard { reserve_a, reserve_b } => {
+            PoolState::Standard { reserve_a, reserve_b, .. } => {
                 println!("Reserves: {} {} / {} {}", 
                     format_amount(*reserve_a, &pool.token_a),
                     pool.token_a.symbol,
@@ -207,7 +347,7 @@ impl PoolDisplay {
                             pool.token_b.symbol
                         );
                     }
-                    crate::core::PoolState::Stable { reserves, amp_factor } => {
+                    crate::core::PoolState::Stable { reserves, amp_factor, .. } => {
                         println!(
                             "   Reserves: {} {} | {} {} | Amp: {}",
                             format_amount(reserves[0], &pool.token_a),
@@ -230,7 +370,7 @@ impl PoolDisplay {
                             *fee_tier as f64 / 10000.0
                         );
                     }
-                    crate::core::PoolState::Standard { reserve_a, reserve_b } => {
+                    crate::core::PoolState::Standard { reserve_a, reserve_b, .. } => {
                         println!(
                             "   Reserves: {} {} | {} {}",
                             format_amount(*reserve_a, &pool.token_a),
@@ -293,12 +433,23 @@ impl PoolDisplay {
             format_amount(quote.fee, input_token),
             input_token.symbol
         );
-        println!(
-            "Min Output: {} {} (with slippage)",
-            format_amount(quote.min_amount_out, output_token),
-            output_token.symbol
-        );
+        match quote.swap_mode {
+            SwapMode::ExactIn => println!(
+                "Min Output: {} {} (with slippage)",
+                format_amount(quote.min_amount_out, output_token),
+                output_token.symbol
+            ),
+            SwapMode::ExactOut => println!(
+                "Max Input: {} {} (with slippage)",
+                format_amount(quote.max_amount_in, input_token),
+                input_token.symbol
+            ),
+        }
+        display_execution_costs(quote, "");
         println!("Pool: {}", style(format!("{}", quote.pool_info.address)).dim());
+        if quote.context_slot() > 0 {
+            println!("Quoted at slot: {}", style(quote.context_slot()).dim());
+        }
     }
 
     /// Display transaction result
@@ -341,9 +492,147 @@ impl PoolDisplay {
             slippage_colored
         );
     }
+
+    /// Display one journaled swap
+    pub fn display_journal_entry(entry: &JournalEntry) {
+        let time = chrono::DateTime::from_timestamp(entry.timestamp, 0)
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| entry.timestamp.to_string());
+        let usd = entry
+            .value_out_usd
+            .or(entry.value_in_usd)
+            .map(|usd| format!(" (${:.2})", usd))
+            .unwrap_or_default();
+
+        println!(
+            "{} {} {} {} → {} {}{}",
+            style(time).dim(),
+            format!("{:<8}", format!("{:?}", entry.pool_type)).cyan(),
+            format_amount(entry.amount_in, &entry.token_in),
+            entry.token_in.symbol,
+            format_amount(entry.amount_out, &entry.token_out).bold(),
+            entry.token_out.symbol,
+            usd
+        );
+        let network_fee = entry
+            .transaction_fee
+            .map(|fee| format!("{:.6} SOL", fee as f64 / 1e9))
+            .unwrap_or_else(|| "unknown".to_string());
+        if entry.unresolved {
+            println!(
+                "    {} Output could not be read back; quoted amounts shown | {}",
+                style("⚠").yellow(),
+                style(&entry.signature).dim()
+            );
+            return;
+        }
+        println!(
+            "    Slippage: {:.3}% | Pool fee: {} {} | Network fee: {} | {}",
+            entry.actual_slippage,
+            format_amount(entry.pool_fee, &entry.token_in),
+            entry.token_in.symbol,
+            network_fee,
+            style(&entry.signature).dim()
+        );
+    }
+
+    /// Display realized PnL of one token
+    pub fn display_token_pnl(pnl: &TokenPnl) {
+        let realized = format!("{:+.2} USD", pnl.realized_usd);
+        let realized = if pnl.realized_usd >= 0.0 { realized.green() } else { realized.red() };
+
+        println!("\n{} {}", pnl.token.symbol.bold(), style(pnl.token.mint).dim());
+        println!(
+            "  Bought: {} | Sold: {} | Realized: {}",
+            format_amount(pnl.bought, &pnl.token),
+            format_amount(pnl.sold, &pnl.token),
+            realized.bold()
+        );
+        if pnl.open_amount > 0 {
+            println!(
+                "  Open: {} at a cost of {:.2} USD",
+                format_amount(pnl.open_amount, &pnl.token),
+                pnl.open_cost_usd
+            );
+        }
+        if pnl.unmatched_sold > 0 {
+            println!(
+                "  {} {} sold without a journaled buy, counted at cost",
+                style("⚠").yellow(),
+                format_amount(pnl.unmatched_sold, &pnl.token)
+            );
+        }
+        if pnl.unpriced_swaps > 0 {
+            println!(
+                "  {} {} swap(s) skipped without a USD price",
+                style("⚠").yellow(),
+                pnl.unpriced_swaps
+            );
+        }
+        if pnl.unresolved_swaps > 0 {
+            println!(
+                "  {} {} swap(s) counted at quoted amounts; their output could not be read back",
+                style("⚠").yellow(),
+                pnl.unresolved_swaps
+            );
+        }
+    }
+}
+
+/// Input and output token of a single-pool quote
+fn hop_tokens(quote: &QuoteResult) -> (&crate::core::TokenInfo, &crate::core::TokenInfo) {
+    if quote.token_in == quote.pool_info.token_a.mint {
+        (&quote.pool_info.token_a, &quote.pool_info.token_b)
+    } else {
+        (&quote.pool_info.token_b, &quote.pool_info.token_a)
+    }
 }
 
 /// Format token amount with decimals
+/// Print the execution cost breakdown and the gross vs net amount of a quote
+fn display_execution_costs(quote: &QuoteResult, indent: &str) {
+    let Some(costs) = &quote.execution_costs else {
+        return;
+    };
+    let (input_token, output_token) = hop_tokens(quote);
+    let sol = |lamports: u64| format!("{:.6}", lamports as f64 / 1e9);
+
+    println!(
+        "{}Execution Costs: {} SOL (network {} | rent {} | wSOL {})",
+        indent,
+        sol(costs.total_lamports()),
+        sol(costs.signature_fee + costs.priority_fee),
+        sol(costs.ata_rent),
+        sol(costs.wsol_overhead)
+    );
+
+    if let Some(net) = quote.net_amount_out() {
+        let net_str = if net < 0 {
+            format!("-{}", format_amount(net.unsigned_abs() as u64, output_token)).red()
+        } else {
+            format_amount(net as u64, output_token).green()
+        };
+        println!(
+            "{}Net Output: {} {} (gross {})",
+            indent,
+            net_str.bold(),
+            output_token.symbol,
+            format_amount(quote.amount_out, output_token)
+        );
+    } else if let Some(net) = quote.net_amount_in() {
+        let net = net.min(u64::MAX as u128) as u64;
+        println!(
+            "{}Net Input: {} {} (gross {})",
+            indent,
+            format_amount(net, input_token).bold(),
+            input_token.symbol,
+            format_amount(quote.amount_in, input_token)
+        );
+    } else {
+        println!("{}{}", indent, style("Net amount unavailable: no SOL price for this token").dim());
+    }
+}
+
 fn format_amount(amount: u64, token_info: &crate::core::TokenInfo) -> String {
     let divisor = 10u64.pow(token_info.decimals as u32);
     let whole = amount / divisor;
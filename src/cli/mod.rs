@@ -1,3 +1,5 @@
+use crate::core::{Config, PoolType, PriorityFee, ScoringOverrides, ScoringStrategy, SwapResult};
+use crate::transaction::{journal::parse_date, JournalFilter};
 use clap::{Parser, Subcommand};
 use solana_sdk::pubkey::Pubkey;
 
@@ -28,6 +30,15 @@ pub enum Commands {
     
     /// Wrap SOL to wSOL or unwrap wSOL to SOL
     Wrap(WrapArgs),
+
+    /// List swaps recorded in the journal
+    History(HistoryArgs),
+
+    /// Realized profit and loss per token from the journal
+    Pnl(PnlArgs),
+
+    /// Manage the wallet's Address Lookup Tables
+    Alt(AltArgs),
 }
 
 #[derive(Parser)]
@@ -50,6 +61,26 @@ pub struct QuoteArgs {
     /// Show quotes from all pools, not just the best
     #[arg(short, long)]
     pub all: bool,
+
+    /// Treat amount as the exact output to receive instead of the input to spend
+    #[arg(long)]
+    pub exact_out: bool,
+
+    /// Split the order across several pools when that fills better
+    #[arg(long)]
+    pub split: bool,
+
+    /// Also search two- and three-hop routes through hub tokens (HUB_TOKENS)
+    #[arg(long, conflicts_with = "split")]
+    pub route: bool,
+
+    /// Wallet whose existing token accounts are checked when estimating
+    /// execution costs (defaults to the configured private key, if any)
+    #[arg(long, value_parser = parse_pubkey)]
+    pub wallet: Option<Pubkey>,
+
+    #[command(flatten)]
+    pub scoring: ScoringArgs,
 }
 
 #[derive(Parser)]
@@ -69,6 +100,19 @@ pub struct SwapArgs {
     #[arg(short, long, default_value = "50")]
     pub slippage: u16,
     
+    /// Treat amount as the exact output to receive instead of the input to spend
+    #[arg(long)]
+    pub exact_out: bool,
+
+    /// Split the order across several pools in one transaction when that fills better
+    #[arg(long)]
+    pub split: bool,
+
+    /// Also search two- and three-hop routes through hub tokens (HUB_TOKENS),
+    /// executed atomically in one v0 transaction
+    #[arg(long, conflicts_with = "split")]
+    pub route: bool,
+
     /// Skip confirmation prompt
     #[arg(long)]
     pub yes: bool,
@@ -82,12 +126,26 @@ pub struct SwapArgs {
     pub legacy: bool,
 
     /// Use Address Lookup Tables (ALT) for transaction optimization
+    /// (always on for split and route swaps)
     #[arg(long)]
     pub use_alt: bool,
+
+    /// Swap AMM V4 pools without OpenBook accounts (always done for pools
+    /// without a market)
+    #[arg(long)]
+    pub amm_v2: bool,
+
+    /// Compute unit price: "auto" to follow recent fees on the swap's
+    /// accounts, or micro-lamports per compute unit (defaults to PRIORITY_FEE)
+    #[arg(long, value_parser = parse_priority_fee)]
+    pub priority_fee: Option<PriorityFee>,
     
     /// Show detailed balance changes after swap
     #[arg(long)]
     pub show_balance_changes: bool,
+
+    #[command(flatten)]
+    pub scoring: ScoringArgs,
 }
 
 #[derive(Parser)]
@@ -103,6 +161,9 @@ pub struct PoolsArgs {
     /// Show detailed pool information
     #[arg(short, long)]
     pub detailed: bool,
+
+    #[command(flatten)]
+    pub scoring: ScoringArgs,
 }
 
 #[derive(Parser)]
@@ -115,7 +176,7 @@ pub struct TokenPoolsArgs {
     #[arg(short, long)]
     pub detailed: bool,
     
-    /// Filter by pool type (amm, stable, clmm)
+    /// Filter by pool type (amm, stable, clmm, standard)
     #[arg(long)]
     pub pool_type: Option<String>,
 }
@@ -130,7 +191,212 @@ pub struct WrapArgs {
     pub unwrap: bool,
 }
 
+/// Pool scoring preset and per-field overrides shared by the commands that
+/// rank pools
+#[derive(Parser)]
+#[command(next_help_heading = "Scoring")]
+pub struct ScoringArgs {
+    /// Pool scoring preset: balanced, deepest or cheapest-fee
+    /// (defaults to SCORING_STRATEGY)
+    #[arg(long, value_parser = parse_strategy)]
+    pub strategy: Option<ScoringStrategy>,
+
+    /// Weight of pool liquidity in the score
+    #[arg(long)]
+    pub liquidity_weight: Option<f64>,
+
+    /// Weight of 24h volume in the score
+    #[arg(long)]
+    pub volume_weight: Option<f64>,
+
+    /// Weight of price-impact depth in the score
+    #[arg(long)]
+    pub depth_weight: Option<f64>,
+
+    /// Price impact depth is measured at (0.01 = 1%)
+    #[arg(long)]
+    pub depth_impact: Option<f64>,
+
+    /// Share of the score lost per 1% of pool fee
+    #[arg(long)]
+    pub fee_penalty: Option<f64>,
+
+    /// Share of the score lost per risk flag (0-1)
+    #[arg(long)]
+    pub risk_penalty: Option<f64>,
+
+    /// Score multiplier for AMM V4 pools
+    #[arg(long)]
+    pub amm_bonus: Option<f64>,
+
+    /// Score multiplier for Stable pools
+    #[arg(long)]
+    pub stable_bonus: Option<f64>,
+
+    /// Extra multiplier for Stable pools of two stablecoins
+    #[arg(long)]
+    pub stable_pair_bonus: Option<f64>,
+
+    /// Score multiplier for CLMM pools
+    #[arg(long)]
+    pub clmm_bonus: Option<f64>,
+
+    /// Score multiplier for CP-Swap pools
+    #[arg(long)]
+    pub standard_bonus: Option<f64>,
+
+    /// Pools opened more recently than this many seconds are flagged
+    #[arg(long)]
+    pub min_pool_age_secs: Option<u64>,
+
+    /// Outputs within this many bps of the best are decided by pool score
+    #[arg(long)]
+    pub tie_tolerance_bps: Option<u16>,
+}
+
+impl ScoringArgs {
+    fn overrides(&self) -> ScoringOverrides {
+        ScoringOverrides {
+            liquidity_weight: self.liquidity_weight,
+            volume_weight: self.volume_weight,
+            depth_weight: self.depth_weight,
+            depth_impact: self.depth_impact,
+            fee_penalty: self.fee_penalty,
+            risk_penalty: self.risk_penalty,
+            amm_bonus: self.amm_bonus,
+            stable_bonus: self.stable_bonus,
+            stable_pair_bonus: self.stable_pair_bonus,
+            clmm_bonus: self.clmm_bonus,
+            standard_bonus: self.standard_bonus,
+            min_pool_age_secs: self.min_pool_age_secs,
+            tie_tolerance_bps: self.tie_tolerance_bps,
+        }
+    }
+
+    /// Apply the preset, then the `SCORING_*` overrides, then these flags
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(strategy) = self.strategy.clone() {
+            config.set_scoring_preset(strategy);
+        }
+        self.overrides().apply(&mut config.scoring_strategy);
+    }
+}
+
+/// Filters shared by the journal commands
+#[derive(Parser)]
+pub struct JournalFilterArgs {
+    /// Only swaps paying or receiving this token mint
+    #[arg(long, value_parser = parse_pubkey)]
+    pub token: Option<Pubkey>,
+
+    /// Only swaps on or after this date (YYYY-MM-DD, UTC)
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Only swaps on or before this date (YYYY-MM-DD, UTC)
+    #[arg(long)]
+    pub until: Option<String>,
+
+    /// Only swaps through this pool type (amm, stable, clmm, standard)
+    #[arg(long, value_parser = parse_pool_type)]
+    pub pool_type: Option<PoolType>,
+}
+
+impl JournalFilterArgs {
+    pub fn filter(&self) -> SwapResult<JournalFilter> {
+        Ok(JournalFilter {
+            token: self.token,
+            since: self.since.as_deref().map(parse_date).transpose()?,
+            // Through the end of the given day
+            until: self.until.as_deref().map(parse_date).transpose()?.map(|until| until + 86_399),
+            pool_type: self.pool_type,
+        })
+    }
+}
+
+#[derive(Parser)]
+pub struct HistoryArgs {
+    #[command(flatten)]
+    pub filter: JournalFilterArgs,
+
+    /// Show only the most recent swaps
+    #[arg(short, long)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Parser)]
+pub struct PnlArgs {
+    #[command(flatten)]
+    pub filter: JournalFilterArgs,
+}
+
+#[derive(Parser)]
+pub struct AltArgs {
+    #[command(subcommand)]
+    pub command: AltCommand,
+}
+
+#[derive(Subcommand)]
+pub enum AltCommand {
+    /// List the wallet's tables and the ones in LOOKUP_TABLES
+    List,
+
+    /// Create an empty table owned by the wallet
+    Create,
+
+    /// Add the accounts of pools, or single addresses, to a table
+    Extend {
+        /// Table to extend (defaults to the wallet's table already holding
+        /// most of the accounts, creating one when none has room)
+        #[arg(long, value_parser = parse_pubkey)]
+        table: Option<Pubkey>,
+
+        /// Pool whose swap accounts are added (repeatable)
+        #[arg(long = "pool", value_parser = parse_pubkey)]
+        pools: Vec<Pubkey>,
+
+        /// Address to add (repeatable)
+        #[arg(long = "address", value_parser = parse_pubkey)]
+        addresses: Vec<Pubkey>,
+    },
+
+    /// Add the accounts of the pools traded most often to the wallet's tables
+    Sync {
+        /// Swaps through a pool before it is added (defaults to ALT_MIN_POOL_TRADES)
+        #[arg(long)]
+        min_trades: Option<usize>,
+
+        /// Only list the pools that would be added
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Deactivate a table so it can be closed
+    Deactivate {
+        #[arg(value_parser = parse_pubkey)]
+        table: Pubkey,
+    },
+
+    /// Close a deactivated table and reclaim its rent
+    Close {
+        #[arg(value_parser = parse_pubkey)]
+        table: Pubkey,
+    },
+}
+
 fn parse_pubkey(s: &str) -> Result<Pubkey, String> {
     s.parse::<Pubkey>()
         .map_err(|e| format!("Invalid pubkey: {}", e))
-}
\ No newline at end of file
+}
+
+fn parse_pool_type(s: &str) -> Result<PoolType, String> {
+    s.parse::<PoolType>().map_err(|e| e.to_string())
+}
+
+fn parse_strategy(s: &str) -> Result<ScoringStrategy, String> {
+    s.parse::<ScoringStrategy>().map_err(|e| e.to_string())
+}
+
+fn parse_priority_fee(s: &str) -> Result<PriorityFee, String> {
+    s.parse::<PriorityFee>().map_err(|e| e.to_string())
+}
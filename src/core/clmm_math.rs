@@ -0,0 +1,632 @@
+//! Fixed-point math for Raydium CLMM pools
+//!
+//! Mirrors the program's Q64.64 sqrt-price arithmetic (tick math, sqrt price
+//! updates and token amount deltas) so simulated swaps match on-chain results
+//! exactly, including rounding direction.
+
+use crate::core::{SwapError, SwapResult};
+
+pub use self::uints::{U128, U256, U512};
+
+mod uints {
+    // Lints fire on code generated by the macro
+    #![allow(clippy::assign_op_pattern, clippy::manual_div_ceil, clippy::reversed_empty_ranges)]
+    use uint::construct_uint;
+
+    construct_uint! {
+        pub struct U128(2);
+    }
+
+    construct_uint! {
+        pub struct U256(4);
+    }
+
+    construct_uint! {
+        pub struct U512(8);
+    }
+}
+
+/// Lowest tick supported by the CLMM program
+pub const MIN_TICK: i32 = -443636;
+/// Highest tick supported by the CLMM program
+pub const MAX_TICK: i32 = 443636;
+/// sqrt price at MIN_TICK
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+/// sqrt price at MAX_TICK
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+/// Fee rates are expressed in hundredths of a basis point
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+/// Number of fractional bits in a Q64.64 value
+const RESOLUTION: u32 = 64;
+/// 1.0 in Q64.64
+const Q64: u128 = 1u128 << RESOLUTION;
+/// Fractional bits computed when taking log2 of a sqrt price
+const BIT_PRECISION: u32 = 16;
+
+/// Magic factors 2^64 / sqrt(1.0001)^(2^i) used by the program
+const TICK_RATIOS: [u64; 19] = [
+    0xfffcb933bd6fb800,
+    0xfff97272373d4000,
+    0xfff2e50f5f657000,
+    0xffe5caca7e10f000,
+    0xffcb9843d60f7000,
+    0xff973b41fa98e800,
+    0xff2ea16466c9b000,
+    0xfe5dee046a9a3800,
+    0xfcbe86c7900bb000,
+    0xf987a7253ac65800,
+    0xf3392b0822bb6000,
+    0xe7159475a2caf000,
+    0xd097f3bdfd2f2000,
+    0xa9f746462d9f8000,
+    0x70d869a156f31c00,
+    0x31be135f97ed3200,
+    0x09aa508b5b85a500,
+    0x005d6af8dedc582c,
+    0x00002216e584f5fa,
+];
+
+fn u256_to_u512(value: U256) -> U512 {
+    let mut words = [0u64; 8];
+    words[..4].copy_from_slice(&value.0);
+    U512(words)
+}
+
+fn u512_to_u256(value: U512) -> Option<U256> {
+    if value.0[4..].iter().any(|word| *word != 0) {
+        return None;
+    }
+    let mut words = [0u64; 4];
+    words.copy_from_slice(&value.0[..4]);
+    Some(U256(words))
+}
+
+/// (a * b) / denominator rounded down, with a 512-bit intermediate
+pub fn mul_div_floor(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    let result = (u256_to_u512(a) * u256_to_u512(b)) / u256_to_u512(denominator);
+    u512_to_u256(result)
+}
+
+/// (a * b) / denominator rounded up, with a 512-bit intermediate
+pub fn mul_div_ceil(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    let denominator = u256_to_u512(denominator);
+    let result = (u256_to_u512(a) * u256_to_u512(b) + denominator - U512::one()) / denominator;
+    u512_to_u256(result)
+}
+
+fn div_rounding_up(numerator: U256, denominator: U256) -> U256 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if remainder.is_zero() {
+        quotient
+    } else {
+        quotient + U256::one()
+    }
+}
+
+fn mul_div_floor_u64(a: u64, b: u64, denominator: u64) -> u64 {
+    (a as u128 * b as u128 / denominator as u128) as u64
+}
+
+fn mul_div_ceil_u64(a: u64, b: u64, denominator: u64) -> Option<u64> {
+    let result = (a as u128 * b as u128).div_ceil(denominator as u128);
+    u64::try_from(result).ok()
+}
+
+/// sqrt(1.0001^tick) as a Q64.64 value
+pub fn get_sqrt_price_at_tick(tick: i32) -> SwapResult<u128> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        return Err(SwapError::InvalidInput(format!("Tick {} out of range", tick)));
+    }
+
+    let mut ratio = if abs_tick & 0x1 != 0 {
+        U128::from(TICK_RATIOS[0])
+    } else {
+        U128::from(Q64)
+    };
+    for (bit, factor) in TICK_RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U128::from(*factor)) >> RESOLUTION;
+        }
+    }
+
+    // Factors are for negative ticks; invert for positive ones
+    if tick > 0 {
+        ratio = U128::MAX / ratio;
+    }
+
+    Ok(ratio.as_u128())
+}
+
+/// Greatest tick whose sqrt price is <= `sqrt_price_x64`
+pub fn get_tick_at_sqrt_price(sqrt_price_x64: u128) -> SwapResult<i32> {
+    if !(MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        return Err(SwapError::InvalidInput(format!(
+            "sqrt price {} out of range",
+            sqrt_price_x64
+        )));
+    }
+
+    // Integer part of log2 is the position of the most significant bit
+    let msb = 128 - sqrt_price_x64.leading_zeros() - 1;
+    let log2p_integer_x32 = (msb as i128 - 64) << 32;
+
+    // Fractional part by repeated squaring, starting from 0.5 in Q64.64
+    let mut bit: i128 = 0x8000_0000_0000_0000;
+    let mut precision = 0;
+    let mut log2p_fraction_x64: i128 = 0;
+    let mut r = if msb >= 64 {
+        sqrt_price_x64 >> (msb - 63)
+    } else {
+        sqrt_price_x64 << (63 - msb)
+    };
+
+    while bit > 0 && precision < BIT_PRECISION {
+        r *= r;
+        let is_r_more_than_two = (r >> 127) as u32;
+        r >>= 63 + is_r_more_than_two;
+        log2p_fraction_x64 += bit * is_r_more_than_two as i128;
+        bit >>= 1;
+        precision += 1;
+    }
+
+    let log2p_fraction_x32 = log2p_fraction_x64 >> 32;
+    let log2p_x32 = log2p_integer_x32 + log2p_fraction_x32;
+
+    // Change of base: multiply by 2^16 / log2(sqrt(1.0001))
+    let log_sqrt_10001_x64 = log2p_x32 * 59543866431248i128;
+
+    // Error bounds of the 14-bit approximation
+    let tick_low = ((log_sqrt_10001_x64 - 184467440737095516i128) >> 64) as i32;
+    let tick_high = ((log_sqrt_10001_x64 + 15793534762490258745i128) >> 64) as i32;
+
+    Ok(if tick_low == tick_high {
+        tick_low
+    } else if get_sqrt_price_at_tick(tick_high)? <= sqrt_price_x64 {
+        tick_high
+    } else {
+        tick_low
+    })
+}
+
+/// Token 0 amount between two sqrt prices: L * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b)
+pub fn get_delta_amount_0_unsigned(
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> SwapResult<u64> {
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+    if sqrt_ratio_a_x64 == 0 {
+        return Err(SwapError::MathOverflow);
+    }
+
+    let numerator_1 = U256::from(liquidity) << RESOLUTION;
+    let numerator_2 = U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64);
+
+    let result = if round_up {
+        div_rounding_up(
+            mul_div_ceil(numerator_1, numerator_2, U256::from(sqrt_ratio_b_x64))
+                .ok_or(SwapError::MathOverflow)?,
+            U256::from(sqrt_ratio_a_x64),
+        )
+    } else {
+        mul_div_floor(numerator_1, numerator_2, U256::from(sqrt_ratio_b_x64))
+            .ok_or(SwapError::MathOverflow)?
+            / U256::from(sqrt_ratio_a_x64)
+    };
+
+    if result > U256::from(u64::MAX) {
+        return Err(SwapError::MathOverflow);
+    }
+    Ok(result.as_u64())
+}
+
+/// Token 1 amount between two sqrt prices: L * (sqrt_b - sqrt_a)
+pub fn get_delta_amount_1_unsigned(
+    mut sqrt_ratio_a_x64: u128,
+    mut sqrt_ratio_b_x64: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> SwapResult<u64> {
+    if sqrt_ratio_a_x64 > sqrt_ratio_b_x64 {
+        std::mem::swap(&mut sqrt_ratio_a_x64, &mut sqrt_ratio_b_x64);
+    }
+
+    let liquidity = U256::from(liquidity);
+    let delta = U256::from(sqrt_ratio_b_x64 - sqrt_ratio_a_x64);
+    let result = if round_up {
+        mul_div_ceil(liquidity, delta, U256::from(Q64))
+    } else {
+        mul_div_floor(liquidity, delta, U256::from(Q64))
+    }
+    .ok_or(SwapError::MathOverflow)?;
+
+    if result > U256::from(u64::MAX) {
+        return Err(SwapError::MathOverflow);
+    }
+    Ok(result.as_u64())
+}
+
+/// Next sqrt price after adding (or removing) `amount` of token 0, rounded up
+fn get_next_sqrt_price_from_amount_0_rounding_up(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount: u64,
+    add: bool,
+) -> SwapResult<u128> {
+    if amount == 0 {
+        return Ok(sqrt_price_x64);
+    }
+    let numerator_1 = U256::from(liquidity) << RESOLUTION;
+    let product = U256::from(amount) * U256::from(sqrt_price_x64);
+
+    let next = if add {
+        let denominator = numerator_1 + product;
+        mul_div_ceil(numerator_1, U256::from(sqrt_price_x64), denominator)
+            .ok_or(SwapError::MathOverflow)?
+    } else {
+        let denominator = numerator_1
+            .checked_sub(product)
+            .filter(|denominator| !denominator.is_zero())
+            .ok_or(SwapError::MathOverflow)?;
+        mul_div_ceil(numerator_1, U256::from(sqrt_price_x64), denominator)
+            .ok_or(SwapError::MathOverflow)?
+    };
+
+    if next > U256::from(u128::MAX) {
+        return Err(SwapError::MathOverflow);
+    }
+    Ok(next.as_u128())
+}
+
+/// Next sqrt price after adding (or removing) `amount` of token 1, rounded down
+fn get_next_sqrt_price_from_amount_1_rounding_down(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount: u64,
+    add: bool,
+) -> SwapResult<u128> {
+    let numerator = U256::from(amount) << RESOLUTION;
+    if add {
+        let quotient = numerator / U256::from(liquidity);
+        if quotient > U256::from(u128::MAX) {
+            return Err(SwapError::MathOverflow);
+        }
+        sqrt_price_x64
+            .checked_add(quotient.as_u128())
+            .ok_or(SwapError::MathOverflow)
+    } else {
+        let quotient = div_rounding_up(numerator, U256::from(liquidity));
+        if quotient > U256::from(u128::MAX) {
+            return Err(SwapError::MathOverflow);
+        }
+        sqrt_price_x64
+            .checked_sub(quotient.as_u128())
+            .ok_or(SwapError::MathOverflow)
+    }
+}
+
+/// Next sqrt price after swapping `amount_in` into the pool
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_in: u64,
+    zero_for_one: bool,
+) -> SwapResult<u128> {
+    if sqrt_price_x64 == 0 || liquidity == 0 {
+        return Err(SwapError::MathOverflow);
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_in, true)
+    } else {
+        get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64, liquidity, amount_in, true)
+    }
+}
+
+/// Next sqrt price after taking `amount_out` out of the pool
+pub fn get_next_sqrt_price_from_output(
+    sqrt_price_x64: u128,
+    liquidity: u128,
+    amount_out: u64,
+    zero_for_one: bool,
+) -> SwapResult<u128> {
+    if sqrt_price_x64 == 0 || liquidity == 0 {
+        return Err(SwapError::MathOverflow);
+    }
+    if zero_for_one {
+        get_next_sqrt_price_from_amount_1_rounding_down(sqrt_price_x64, liquidity, amount_out, false)
+    } else {
+        get_next_sqrt_price_from_amount_0_rounding_up(sqrt_price_x64, liquidity, amount_out, false)
+    }
+}
+
+/// Result of a single swap step between two sqrt prices
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next_x64: u128,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Amount needed to move the price to the target, or None if it exceeds u64
+fn calculate_amount_in_range(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    zero_for_one: bool,
+    is_base_input: bool,
+) -> SwapResult<Option<u64>> {
+    let result = match (zero_for_one, is_base_input) {
+        (true, true) => get_delta_amount_0_unsigned(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, true),
+        (true, false) => get_delta_amount_1_unsigned(sqrt_price_target_x64, sqrt_price_current_x64, liquidity, false),
+        (false, true) => get_delta_amount_1_unsigned(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, true),
+        (false, false) => get_delta_amount_0_unsigned(sqrt_price_current_x64, sqrt_price_target_x64, liquidity, false),
+    };
+    match result {
+        Ok(amount) => Ok(Some(amount)),
+        Err(SwapError::MathOverflow) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Swap within a single liquidity range, as the program's `compute_swap_step`
+///
+/// For exact input `amount_remaining` is the input left (fee included); for
+/// exact output it is the output still owed.
+pub fn compute_swap_step(
+    sqrt_price_current_x64: u128,
+    sqrt_price_target_x64: u128,
+    liquidity: u128,
+    amount_remaining: u64,
+    fee_rate: u32,
+    is_base_input: bool,
+    zero_for_one: bool,
+) -> SwapResult<SwapStep> {
+    if fee_rate >= FEE_RATE_DENOMINATOR {
+        return Err(SwapError::InvalidPoolState(format!("Invalid CLMM fee rate: {}", fee_rate)));
+    }
+
+    let mut step = SwapStep::default();
+    let amount_in_range = calculate_amount_in_range(
+        sqrt_price_current_x64,
+        sqrt_price_target_x64,
+        liquidity,
+        zero_for_one,
+        is_base_input,
+    )?;
+
+    if is_base_input {
+        let amount_remaining_less_fee = mul_div_floor_u64(
+            amount_remaining,
+            (FEE_RATE_DENOMINATOR - fee_rate) as u64,
+            FEE_RATE_DENOMINATOR as u64,
+        );
+        if let Some(amount_in) = amount_in_range {
+            step.amount_in = amount_in;
+        }
+        step.sqrt_price_next_x64 = match amount_in_range {
+            Some(amount_in) if amount_remaining_less_fee >= amount_in => sqrt_price_target_x64,
+            _ => get_next_sqrt_price_from_input(
+                sqrt_price_current_x64,
+                liquidity,
+                amount_remaining_less_fee,
+                zero_for_one,
+            )?,
+        };
+    } else {
+        if let Some(amount_out) = amount_in_range {
+            step.amount_out = amount_out;
+        }
+        step.sqrt_price_next_x64 = match amount_in_range {
+            Some(amount_out) if amount_remaining >= amount_out => sqrt_price_target_x64,
+            _ => get_next_sqrt_price_from_output(
+                sqrt_price_current_x64,
+                liquidity,
+                amount_remaining,
+                zero_for_one,
+            )?,
+        };
+    }
+
+    // Whether the step reaches the target price
+    let max = sqrt_price_target_x64 == step.sqrt_price_next_x64;
+
+    if zero_for_one {
+        if !(max && is_base_input) {
+            step.amount_in = get_delta_amount_0_unsigned(
+                step.sqrt_price_next_x64,
+                sqrt_price_current_x64,
+                liquidity,
+                true,
+            )?;
+        }
+        if !max || is_base_input {
+            step.amount_out = get_delta_amount_1_unsigned(
+                step.sqrt_price_next_x64,
+                sqrt_price_current_x64,
+                liquidity,
+                false,
+            )?;
+        }
+    } else {
+        if !(max && is_base_input) {
+            step.amount_in = get_delta_amount_1_unsigned(
+                sqrt_price_current_x64,
+                step.sqrt_price_next_x64,
+                liquidity,
+                true,
+            )?;
+        }
+        if !max || is_base_input {
+            step.amount_out = get_delta_amount_0_unsigned(
+                sqrt_price_current_x64,
+                step.sqrt_price_next_x64,
+                liquidity,
+                false,
+            )?;
+        }
+    }
+
+    // Exact output never pays out more than requested
+    if !is_base_input && step.amount_out > amount_remaining {
+        step.amount_out = amount_remaining;
+    }
+
+    step.fee_amount = if is_base_input && step.sqrt_price_next_x64 != sqrt_price_target_x64 {
+        // Target not reached: the remainder of the input is the fee (dust included)
+        amount_remaining
+            .checked_sub(step.amount_in)
+            .ok_or(SwapError::MathOverflow)?
+    } else {
+        mul_div_ceil_u64(
+            step.amount_in,
+            fee_rate as u64,
+            (FEE_RATE_DENOMINATOR - fee_rate) as u64,
+        )
+        .ok_or(SwapError::MathOverflow)?
+    };
+
+    Ok(step)
+}
+
+/// Convert a Q64.64 sqrt price to a floating point price (token 1 per token 0)
+pub fn sqrt_price_x64_to_price(sqrt_price_x64: u128) -> f64 {
+    let sqrt_price = sqrt_price_x64 as f64 / Q64 as f64;
+    sqrt_price * sqrt_price
+}
+
+/// Add a signed liquidity delta, as the program does when crossing a tick
+pub fn add_liquidity_delta(liquidity: u128, delta: i128) -> SwapResult<u128> {
+    if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    }
+    .ok_or(SwapError::MathOverflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt_price_at_tick_bounds() {
+        assert_eq!(get_sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(get_sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+        assert_eq!(get_sqrt_price_at_tick(0).unwrap(), Q64);
+        assert!(get_sqrt_price_at_tick(MAX_TICK + 1).is_err());
+        assert!(get_sqrt_price_at_tick(MIN_TICK - 1).is_err());
+    }
+
+    #[test]
+    fn test_sqrt_price_at_tick_known_values() {
+        // Values produced by the on-chain tick math
+        assert_eq!(get_sqrt_price_at_tick(1).unwrap(), 18447666387855957090);
+        assert_eq!(get_sqrt_price_at_tick(-1).unwrap(), 18445821805675395072);
+        assert_eq!(get_sqrt_price_at_tick(10).unwrap(), 18455969290605287889);
+        assert_eq!(get_sqrt_price_at_tick(-10).unwrap(), 18437523468038803493);
+    }
+
+    #[test]
+    fn test_tick_at_sqrt_price_round_trip() {
+        for tick in [MIN_TICK, -100_000, -1_234, -1, 0, 1, 60, 1_234, 100_000, MAX_TICK - 1] {
+            let sqrt_price = get_sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            // Just below a tick's price belongs to the previous tick
+            if tick > MIN_TICK {
+                assert_eq!(get_tick_at_sqrt_price(sqrt_price - 1).unwrap(), tick - 1);
+            }
+        }
+        assert!(get_tick_at_sqrt_price(MAX_SQRT_PRICE_X64).is_err());
+        assert!(get_tick_at_sqrt_price(MIN_SQRT_PRICE_X64 - 1).is_err());
+    }
+
+    #[test]
+    fn test_delta_amounts_rounding() {
+        let sqrt_a = get_sqrt_price_at_tick(-10).unwrap();
+        let sqrt_b = get_sqrt_price_at_tick(10).unwrap();
+        let liquidity = 1_000_000_000_000u128;
+
+        let amount_0_down = get_delta_amount_0_unsigned(sqrt_a, sqrt_b, liquidity, false).unwrap();
+        let amount_0_up = get_delta_amount_0_unsigned(sqrt_a, sqrt_b, liquidity, true).unwrap();
+        assert_eq!(amount_0_up, amount_0_down + 1);
+
+        let amount_1_down = get_delta_amount_1_unsigned(sqrt_a, sqrt_b, liquidity, false).unwrap();
+        let amount_1_up = get_delta_amount_1_unsigned(sqrt_a, sqrt_b, liquidity, true).unwrap();
+        assert_eq!(amount_1_up, amount_1_down + 1);
+
+        // Symmetric range around price 1 holds the same amount of each token
+        assert_eq!(amount_0_down, 999_950_044);
+        assert_eq!(amount_1_down, 999_950_044);
+        // Argument order does not matter
+        assert_eq!(get_delta_amount_0_unsigned(sqrt_b, sqrt_a, liquidity, false).unwrap(), amount_0_down);
+    }
+
+    #[test]
+    fn test_next_sqrt_price_from_input() {
+        let liquidity = 1_000_000_000_000u128;
+
+        // Selling token 0 lowers the price, selling token 1 raises it
+        let down = get_next_sqrt_price_from_input(Q64, liquidity, 1_000_000, true).unwrap();
+        let up = get_next_sqrt_price_from_input(Q64, liquidity, 1_000_000, false).unwrap();
+        assert!(down < Q64);
+        assert!(up > Q64);
+
+        // Token 1 input moves the price by exactly amount / L
+        assert_eq!(up - Q64, (1_000_000u128 << 64) / liquidity);
+
+        // Zero input leaves the price unchanged
+        assert_eq!(get_next_sqrt_price_from_input(Q64, liquidity, 0, true).unwrap(), Q64);
+    }
+
+    #[test]
+    fn test_compute_swap_step_exact_input() {
+        let liquidity = 1_000_000_000_000u128;
+        let target = get_sqrt_price_at_tick(-10).unwrap();
+
+        // Small swap stays inside the range; unused input becomes fee
+        let step = compute_swap_step(Q64, target, liquidity, 1_000_000, 500, true, true).unwrap();
+        assert!(step.sqrt_price_next_x64 > target);
+        assert_eq!(step.amount_in + step.fee_amount, 1_000_000);
+        assert_eq!(step.fee_amount, 500);
+        assert_eq!(step.amount_out, 999_499);
+
+        // Large swap stops at the target
+        let step = compute_swap_step(Q64, target, liquidity, 10_000_000_000, 500, true, true).unwrap();
+        assert_eq!(step.sqrt_price_next_x64, target);
+        assert_eq!(
+            step.amount_in,
+            get_delta_amount_0_unsigned(target, Q64, liquidity, true).unwrap()
+        );
+        assert!(step.amount_in + step.fee_amount < 10_000_000_000);
+    }
+
+    #[test]
+    fn test_compute_swap_step_exact_output() {
+        let liquidity = 1_000_000_000_000u128;
+        let target = get_sqrt_price_at_tick(10).unwrap();
+
+        let step = compute_swap_step(Q64, target, liquidity, 1_000_000, 500, false, false).unwrap();
+        assert_eq!(step.amount_out, 1_000_000);
+        assert!(step.amount_in > 1_000_000);
+        assert!(step.fee_amount > 0);
+    }
+
+    #[test]
+    fn test_add_liquidity_delta() {
+        assert_eq!(add_liquidity_delta(100, -40).unwrap(), 60);
+        assert_eq!(add_liquidity_delta(100, 40).unwrap(), 140);
+        assert!(add_liquidity_delta(10, -40).is_err());
+    }
+}
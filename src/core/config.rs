@@ -1,7 +1,8 @@
-use crate::core::{constants::*, error::SwapResult, SwapError};
+use crate::core::{constants::*, error::SwapResult, PriorityFee, ScoringOverrides, ScoringStrategy, SwapError};
 use serde::{Deserialize, Serialize};
-use solana_sdk::signature::Keypair;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair};
 use std::env;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -17,6 +18,34 @@ pub struct Config {
     pub transaction_timeout_secs: u64,
     pub max_transaction_retries: u32,
     pub enable_transaction_monitoring: bool,
+    /// Intermediate tokens tried by multi-hop routing
+    pub hub_tokens: Vec<Pubkey>,
+    /// Slots a quote may age before it is refreshed ahead of sending
+    pub max_quote_age_slots: u64,
+    /// How far a refreshed quote may fall behind the confirmed one, in basis points
+    pub requote_threshold_bps: u16,
+    /// Transactions read per pool when estimating 24h volume (0 disables it)
+    pub volume_sample_size: usize,
+    /// How discovered pools are ranked and near-equal quotes decided
+    pub scoring_strategy: ScoringStrategy,
+    /// Strategy fields set through `SCORING_*` variables, applied on top of
+    /// whichever preset is chosen
+    pub scoring_overrides: ScoringOverrides,
+    /// Compute unit price for swap transactions
+    pub priority_fee: PriorityFee,
+    /// Percentile of recent prioritization fees used by `PriorityFee::Auto`
+    pub priority_fee_percentile: u8,
+    /// Most a swap may pay in priority fees, in lamports
+    pub max_priority_fee_lamports: u64,
+    /// File executed swaps are appended to
+    pub journal_path: String,
+    /// Address Lookup Tables swaps may use besides the wallet's own
+    pub lookup_tables: Vec<Pubkey>,
+    /// Swaps through a pool before `alt sync` adds its accounts to a table
+    pub alt_min_pool_trades: usize,
+    /// Add a pool's accounts to the wallet's tables after the swap that makes
+    /// it frequent, instead of waiting for `alt sync`; this spends SOL
+    pub alt_auto_extend: bool,
 }
 
 impl Config {
@@ -33,6 +62,13 @@ impl Config {
 
         let helius_api_key = env::var("HELIUS_API_KEY").ok();
 
+        let scoring_overrides = ScoringOverrides::from_env()?;
+        let mut scoring_strategy = match env::var("SCORING_STRATEGY") {
+            Ok(name) => name.parse()?,
+            Err(_) => ScoringStrategy::default(),
+        };
+        scoring_overrides.apply(&mut scoring_strategy);
+
         Ok(Self {
             rpc_url,
             helius_api_key,
@@ -76,6 +112,49 @@ impl Config {
                 .unwrap_or_default()
                 .parse()
                 .unwrap_or(true),
+            hub_tokens: match env::var("HUB_TOKENS") {
+                Ok(value) => parse_pubkeys(&value, "hub token mint")?,
+                Err(_) => default_hub_tokens(),
+            },
+            max_quote_age_slots: env::var("MAX_QUOTE_AGE_SLOTS")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(DEFAULT_MAX_QUOTE_AGE_SLOTS),
+            requote_threshold_bps: env::var("REQUOTE_THRESHOLD_BPS")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(DEFAULT_REQUOTE_THRESHOLD_BPS),
+            volume_sample_size: env::var("VOLUME_SAMPLE_SIZE")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(DEFAULT_VOLUME_SAMPLE_SIZE),
+            scoring_strategy,
+            scoring_overrides,
+            priority_fee: match env::var("PRIORITY_FEE") {
+                Ok(value) => value.parse()?,
+                Err(_) => PriorityFee::default(),
+            },
+            priority_fee_percentile: env::var("PRIORITY_FEE_PERCENTILE")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(DEFAULT_PRIORITY_FEE_PERCENTILE),
+            max_priority_fee_lamports: env::var("MAX_PRIORITY_FEE_LAMPORTS")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(DEFAULT_MAX_PRIORITY_FEE_LAMPORTS),
+            journal_path: env::var("SWAP_JOURNAL_PATH").unwrap_or_else(|_| DEFAULT_JOURNAL_PATH.to_string()),
+            lookup_tables: match env::var("LOOKUP_TABLES") {
+                Ok(value) => parse_pubkeys(&value, "lookup table")?,
+                Err(_) => Vec::new(),
+            },
+            alt_min_pool_trades: env::var("ALT_MIN_POOL_TRADES")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(DEFAULT_ALT_MIN_POOL_TRADES),
+            alt_auto_extend: env::var("ALT_AUTO_EXTEND")
+                .unwrap_or_default()
+                .parse()
+                .unwrap_or(false),
         })
     }
 
@@ -98,9 +177,23 @@ impl Config {
             ));
         }
 
+        self.scoring_strategy.validate()?;
+
+        if self.priority_fee_percentile > 100 {
+            return Err(SwapError::ConfigError(
+                "Priority fee percentile cannot exceed 100".to_string(),
+            ));
+        }
+
         Ok(())
     }
 
+    /// Switch to the `strategy` preset, keeping the configured overrides
+    pub fn set_scoring_preset(&mut self, strategy: ScoringStrategy) {
+        self.scoring_strategy = strategy;
+        self.scoring_overrides.apply(&mut self.scoring_strategy);
+    }
+
     /// Get keypair from environment variable
     pub fn get_keypair(&self) -> SwapResult<Keypair> {
         let private_key = env::var("WALLET_PRIVATE_KEY")
@@ -147,6 +240,40 @@ impl Default for Config {
             transaction_timeout_secs: 60,
             max_transaction_retries: 3,
             enable_transaction_monitoring: true,
+            hub_tokens: default_hub_tokens(),
+            max_quote_age_slots: DEFAULT_MAX_QUOTE_AGE_SLOTS,
+            requote_threshold_bps: DEFAULT_REQUOTE_THRESHOLD_BPS,
+            volume_sample_size: DEFAULT_VOLUME_SAMPLE_SIZE,
+            scoring_strategy: ScoringStrategy::default(),
+            scoring_overrides: ScoringOverrides::default(),
+            priority_fee: PriorityFee::default(),
+            priority_fee_percentile: DEFAULT_PRIORITY_FEE_PERCENTILE,
+            max_priority_fee_lamports: DEFAULT_MAX_PRIORITY_FEE_LAMPORTS,
+            journal_path: DEFAULT_JOURNAL_PATH.to_string(),
+            lookup_tables: Vec::new(),
+            alt_min_pool_trades: DEFAULT_ALT_MIN_POOL_TRADES,
+            alt_auto_extend: false,
         }
     }
+}
+
+/// Default intermediate tokens for multi-hop routing (SOL, USDC, USDT, RAY)
+pub fn default_hub_tokens() -> Vec<Pubkey> {
+    DEFAULT_HUB_TOKENS
+        .iter()
+        .map(|mint| Pubkey::from_str(mint).unwrap())
+        .collect()
+}
+
+/// Parse a comma-separated list of hub token mints
+fn parse_pubkeys(value: &str, kind: &str) -> SwapResult<Vec<Pubkey>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|mint| !mint.is_empty())
+        .map(|mint| {
+            Pubkey::from_str(mint)
+                .map_err(|_| SwapError::ConfigError(format!("Invalid {}: {}", kind, mint)))
+        })
+        .collect()
 }
\ No newline at end of file
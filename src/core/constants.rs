@@ -8,6 +8,13 @@ pub const RAYDIUM_STABLE_PROGRAM_ID: &str = "5quBtoiQqxF9Jv6KYKctB59NT3gtJD2Y65k
 pub const RAYDIUM_CLMM_PROGRAM_ID: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
 pub const RAYDIUM_ROUTING_PROGRAM_ID: &str = "routeUGWgWzqBWFcrCfv8tritsqukccJPu3q5GPP3xS";
 
+// SPL programs
+pub const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+pub const MEMO_PROGRAM_ID: &str = "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr";
+
+// Curve table shared by every Stable pool
+pub const RAYDIUM_STABLE_MODEL_DATA_ID: &str = "CDSr3ssLcRB6XYPJwAfFt18MZvEZp4LjHcvzBVZ45duo";
+
 // Program IDs as Pubkey
 lazy_static::lazy_static! {
     pub static ref RAYDIUM_AMM_V4_PROGRAM: Pubkey = Pubkey::from_str(RAYDIUM_AMM_V4_PROGRAM_ID).unwrap();
@@ -15,6 +22,9 @@ lazy_static::lazy_static! {
     pub static ref RAYDIUM_STABLE_PROGRAM: Pubkey = Pubkey::from_str(RAYDIUM_STABLE_PROGRAM_ID).unwrap();
     pub static ref RAYDIUM_CLMM_PROGRAM: Pubkey = Pubkey::from_str(RAYDIUM_CLMM_PROGRAM_ID).unwrap();
     pub static ref RAYDIUM_ROUTING_PROGRAM: Pubkey = Pubkey::from_str(RAYDIUM_ROUTING_PROGRAM_ID).unwrap();
+    pub static ref RAYDIUM_STABLE_MODEL_DATA: Pubkey = Pubkey::from_str(RAYDIUM_STABLE_MODEL_DATA_ID).unwrap();
+    pub static ref TOKEN_2022_PROGRAM: Pubkey = Pubkey::from_str(TOKEN_2022_PROGRAM_ID).unwrap();
+    pub static ref MEMO_PROGRAM: Pubkey = Pubkey::from_str(MEMO_PROGRAM_ID).unwrap();
     
     // Короткие алиасы для обратной совместимости
     pub static ref AMM_V4_PROGRAM: Pubkey = *RAYDIUM_AMM_V4_PROGRAM;
@@ -26,16 +36,24 @@ lazy_static::lazy_static! {
 pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
 pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
 pub const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+pub const RAY_MINT: &str = "4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R";
+
+// Intermediate tokens tried by multi-hop routing
+pub const DEFAULT_HUB_TOKENS: [&str; 4] = [WSOL_MINT, USDC_MINT, USDT_MINT, RAY_MINT];
 
 // Fee rates by pool type
 pub const AMM_FEE_RATE: f64 = 0.0025; // 0.25%
 pub const STABLE_FEE_RATE: f64 = 0.0004; // 0.04%
-pub const STANDARD_FEE_RATE: f64 = 0.003; // 0.3%
+
+// Amplification assumed for Stable pools when scoring their depth
+pub const STABLE_AMP_FACTOR: u64 = 1000;
 
 // Cache TTL in seconds
 pub const POOL_CACHE_TTL: u64 = 30;
 pub const METADATA_CACHE_TTL: u64 = 300;
 pub const TOKEN_INFO_CACHE_TTL: u64 = 3600;
+pub const PRICE_CACHE_TTL: u64 = 60;
+pub const VOLUME_CACHE_TTL: u64 = 300;
 
 // RPC Configuration
 pub const DEFAULT_RPC_TIMEOUT: u64 = 30;
@@ -44,7 +62,29 @@ pub const MAX_RPC_RETRIES: u32 = 3;
 // Transaction Configuration
 pub const DEFAULT_SLIPPAGE_BPS: u16 = 50; // 0.5%
 pub const MAX_SLIPPAGE_BPS: u16 = 1000; // 10%
+pub const DEFAULT_COMPUTE_UNIT_LIMIT: u32 = 400_000;
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000; // per transaction
+pub const COMPUTE_UNIT_LIMIT_MARGIN_PERCENT: u64 = 15; // over simulated usage
+pub const DEFAULT_COMPUTE_UNIT_PRICE: u64 = 1_000; // microlamports per CU
+// Typical compute units a swap instruction consumes, by pool type
+pub const AMM_SWAP_COMPUTE_UNITS: u64 = 40_000;
+pub const STABLE_SWAP_COMPUTE_UNITS: u64 = 90_000;
+pub const CLMM_SWAP_COMPUTE_UNITS: u64 = 60_000;
+pub const CLMM_TICK_ARRAY_COMPUTE_UNITS: u64 = 20_000; // per tick array crossed
+pub const CP_SWAP_COMPUTE_UNITS: u64 = 45_000;
+pub const ACCOUNT_CREATION_COMPUTE_UNITS: u64 = 25_000; // idempotent ATA creation
+pub const DEFAULT_PRIORITY_FEE_PERCENTILE: u8 = 75;
+pub const DEFAULT_MAX_PRIORITY_FEE_LAMPORTS: u64 = 1_000_000; // 0.001 SOL
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+pub const TOKEN_ACCOUNT_RENT_LAMPORTS: u64 = 2_039_280; // rent-exempt 165-byte account
+pub const DEFAULT_MAX_QUOTE_AGE_SLOTS: u64 = 25; // ~10 seconds
+pub const DEFAULT_REQUOTE_THRESHOLD_BPS: u16 = 50; // 0.5%
+pub const DEFAULT_JOURNAL_PATH: &str = "swap_journal.jsonl";
+pub const DEFAULT_ALT_MIN_POOL_TRADES: usize = 3;
 
 // Pool Discovery Configuration
 pub const MAX_POOLS_PER_TYPE: usize = 10;
+pub const DEFAULT_VOLUME_SAMPLE_SIZE: usize = 10; // transactions read per pool refresh
+pub const VOLUME_POOL_CONCURRENCY: usize = 4; // pools whose volume is estimated at once
+pub const VOLUME_WINDOW_SECS: i64 = 86_400;
 pub const MIN_LIQUIDITY_USD: f64 = 1000.0;
\ No newline at end of file
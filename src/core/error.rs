@@ -16,6 +16,9 @@ pub enum SwapError {
         required: u64,
     },
 
+    #[error("Swap exceeds loaded tick range: {remaining} left at boundary tick {boundary_tick}")]
+    TickRangeExceeded { boundary_tick: i32, remaining: u64 },
+
     #[error("Slippage exceeded: expected {expected}, got {actual} (max allowed: {max_slippage}%)")]
     SlippageExceeded {
         expected: u64,
@@ -29,11 +32,15 @@ pub enum SwapError {
     #[error("Invalid token mint: {0}")]
     InvalidTokenMint(String),
 
+    #[error("Unsupported token mint: {0}")]
+    UnsupportedMint(String),
+
     #[error("Invalid amount: {0}")]
     InvalidAmount(String),
 
+    /// Boxed to keep `SwapResult` small; `ClientError` dwarfs every other variant
     #[error("RPC error: {0}")]
-    RpcError(#[from] ClientError),
+    RpcError(Box<ClientError>),
 
     #[error("Serialization error: {0}")]
     SerializationError(String),
@@ -86,6 +93,19 @@ pub enum SwapError {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    #[error("Quote moved {drift_bps} bps since it was confirmed: expected {expected}, now {requoted}")]
+    QuoteMoved {
+        expected: u64,
+        requoted: u64,
+        drift_bps: u64,
+    },
+
+    #[error("Swap {signature} landed but its output could not be determined: {reason}")]
+    UnresolvedOutput { signature: String, reason: String },
+
+    #[error("Transaction is {size} bytes, over the {limit} byte limit")]
+    TransactionTooLarge { size: usize, limit: usize },
+
     #[error("Insufficient balance: {0}")]
     InsufficientBalance(String),
 
@@ -93,6 +113,12 @@ pub enum SwapError {
     Other(String),
 }
 
+impl From<ClientError> for SwapError {
+    fn from(err: ClientError) -> Self {
+        SwapError::RpcError(Box::new(err))
+    }
+}
+
 impl From<anyhow::Error> for SwapError {
     fn from(err: anyhow::Error) -> Self {
         SwapError::Other(err.to_string())
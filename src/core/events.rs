@@ -0,0 +1,649 @@
+use crate::core::{
+    constants::{AMM_V4_PROGRAM, CLMM_PROGRAM, RAYDIUM_CP_SWAP_PROGRAM, STABLE_PROGRAM},
+    PoolType, QuoteResult, SwapExecution,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::BorshDeserialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+    UiCompiledInstruction, UiInstruction, UiMessage,
+};
+use std::str::FromStr;
+
+/// Log prefix of AMM V4 `ray_log` entries
+pub const RAY_LOG_PREFIX: &str = "Program log: ray_log: ";
+/// Log prefix of Anchor events emitted with `emit!`
+pub const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// `ray_log` type tags
+const RAY_LOG_INIT: u8 = 0;
+const RAY_LOG_DEPOSIT: u8 = 1;
+const RAY_LOG_WITHDRAW: u8 = 2;
+const RAY_LOG_SWAP_BASE_IN: u8 = 3;
+const RAY_LOG_SWAP_BASE_OUT: u8 = 4;
+
+/// AMM V4 `direction` value for coin -> pc swaps (pc -> coin is 1)
+pub const AMM_DIRECTION_COIN_TO_PC: u64 = 2;
+
+/// AMM V4 `Init` log: pool creation with its opening reserves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct InitLog {
+    pub time: u64,
+    pub pc_decimals: u8,
+    pub coin_decimals: u8,
+    pub pc_lot_size: u64,
+    pub coin_lot_size: u64,
+    pub pc_amount: u64,
+    pub coin_amount: u64,
+    pub market: Pubkey,
+}
+
+/// AMM V4 `Deposit` log: liquidity added, with reserves before the deposit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct DepositLog {
+    pub max_coin: u64,
+    pub max_pc: u64,
+    pub base: u64,
+    pub pool_coin: u64,
+    pub pool_pc: u64,
+    pub pool_lp: u64,
+    pub calc_pnl_x: u128,
+    pub calc_pnl_y: u128,
+    pub deduct_coin: u64,
+    pub deduct_pc: u64,
+    pub mint_lp: u64,
+}
+
+/// AMM V4 `Withdraw` log: liquidity removed, with reserves before the withdrawal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct WithdrawLog {
+    pub withdraw_lp: u64,
+    pub user_lp: u64,
+    pub pool_coin: u64,
+    pub pool_pc: u64,
+    pub pool_lp: u64,
+    pub calc_pnl_x: u128,
+    pub calc_pnl_y: u128,
+    pub out_coin: u64,
+    pub out_pc: u64,
+}
+
+/// AMM V4 `SwapBaseIn` log: exact input swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct SwapBaseInLog {
+    pub amount_in: u64,
+    pub minimum_out: u64,
+    pub direction: u64,
+    pub user_source: u64,
+    pub pool_coin: u64,
+    pub pool_pc: u64,
+    pub out_amount: u64,
+}
+
+/// AMM V4 `SwapBaseOut` log: exact output swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct SwapBaseOutLog {
+    pub max_in: u64,
+    pub amount_out: u64,
+    pub direction: u64,
+    pub user_source: u64,
+    pub pool_coin: u64,
+    pub pool_pc: u64,
+    pub deduct_in: u64,
+}
+
+/// Decoded AMM V4 `ray_log` entry
+///
+/// Pool amounts in every entry are the reserves before the instruction,
+/// excluding fees the protocol has yet to take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RayLog {
+    Init(InitLog),
+    Deposit(DepositLog),
+    Withdraw(WithdrawLog),
+    SwapBaseIn(SwapBaseInLog),
+    SwapBaseOut(SwapBaseOutLog),
+}
+
+impl RayLog {
+    /// Decode a `Program log: ray_log: ...` line; other lines yield `None`
+    pub fn decode(line: &str) -> Option<Self> {
+        let data = STANDARD.decode(line.strip_prefix(RAY_LOG_PREFIX)?).ok()?;
+        let (log_type, mut body) = data.split_first()?;
+        match *log_type {
+            RAY_LOG_INIT => InitLog::deserialize(&mut body).ok().map(Self::Init),
+            RAY_LOG_DEPOSIT => DepositLog::deserialize(&mut body).ok().map(Self::Deposit),
+            RAY_LOG_WITHDRAW => WithdrawLog::deserialize(&mut body).ok().map(Self::Withdraw),
+            RAY_LOG_SWAP_BASE_IN => SwapBaseInLog::deserialize(&mut body).ok().map(Self::SwapBaseIn),
+            RAY_LOG_SWAP_BASE_OUT => SwapBaseOutLog::deserialize(&mut body).ok().map(Self::SwapBaseOut),
+            _ => None,
+        }
+    }
+
+    /// Decode every `ray_log` entry of a transaction, in log order
+    pub fn decode_all(logs: &[String]) -> Vec<Self> {
+        logs.iter().filter_map(|line| Self::decode(line)).collect()
+    }
+
+    /// For swaps, the amount paid into the pool and whether it was the coin side
+    pub fn swap_input(&self) -> Option<(u64, bool)> {
+        match self {
+            Self::SwapBaseIn(log) => Some((log.amount_in, log.direction == AMM_DIRECTION_COIN_TO_PC)),
+            Self::SwapBaseOut(log) => Some((log.deduct_in, log.direction == AMM_DIRECTION_COIN_TO_PC)),
+            _ => None,
+        }
+    }
+
+    /// For swaps, the amount the pool paid out
+    pub fn swap_output(&self) -> Option<u64> {
+        match self {
+            Self::SwapBaseIn(log) => Some(log.out_amount),
+            Self::SwapBaseOut(log) => Some(log.amount_out),
+            _ => None,
+        }
+    }
+
+    /// Coin and pc reserves after the instruction
+    pub fn reserves_after(&self) -> (u64, u64) {
+        let swapped = |pool_coin: u64, pool_pc: u64, amount_in: u64, amount_out: u64, direction: u64| {
+            if direction == AMM_DIRECTION_COIN_TO_PC {
+                (pool_coin.saturating_add(amount_in), pool_pc.saturating_sub(amount_out))
+            } else {
+                (pool_coin.saturating_sub(amount_out), pool_pc.saturating_add(amount_in))
+            }
+        };
+        match self {
+            Self::Init(log) => (log.coin_amount, log.pc_amount),
+            Self::Deposit(log) => (
+                log.pool_coin.saturating_add(log.deduct_coin),
+                log.pool_pc.saturating_add(log.deduct_pc),
+            ),
+            Self::Withdraw(log) => (
+                log.pool_coin.saturating_sub(log.out_coin),
+                log.pool_pc.saturating_sub(log.out_pc),
+            ),
+            Self::SwapBaseIn(log) => swapped(log.pool_coin, log.pool_pc, log.amount_in, log.out_amount, log.direction),
+            Self::SwapBaseOut(log) => swapped(log.pool_coin, log.pool_pc, log.deduct_in, log.amount_out, log.direction),
+        }
+    }
+}
+
+/// CLMM `SwapEvent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct ClmmSwapEvent {
+    pub pool_state: Pubkey,
+    pub sender: Pubkey,
+    pub token_account_0: Pubkey,
+    pub token_account_1: Pubkey,
+    pub amount_0: u64,
+    pub transfer_fee_0: u64,
+    pub amount_1: u64,
+    pub transfer_fee_1: u64,
+    pub zero_for_one: bool,
+    pub sqrt_price_x64: u128,
+    pub liquidity: u128,
+    pub tick: i32,
+}
+
+/// CP-Swap `SwapEvent`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct CpSwapEvent {
+    pub pool_id: Pubkey,
+    pub input_vault_before: u64,
+    pub output_vault_before: u64,
+    pub input_amount: u64,
+    pub output_amount: u64,
+    pub input_transfer_fee: u64,
+    pub output_transfer_fee: u64,
+    pub base_input: bool,
+}
+
+/// Mints appended to the CP-Swap `SwapEvent` by newer program versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshDeserialize)]
+pub struct CpSwapEventMints {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+}
+
+/// Anchor discriminator of `SwapEvent`, shared by CLMM and CP-Swap
+pub fn swap_event_discriminator() -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(b"event:SwapEvent");
+    hash.to_bytes()[..8].try_into().unwrap()
+}
+
+/// Body of a `Program data:` line if it carries a `SwapEvent`
+fn swap_event_body(line: &str) -> Option<Vec<u8>> {
+    let data = STANDARD.decode(line.strip_prefix(PROGRAM_DATA_PREFIX)?).ok()?;
+    (data.len() >= 8 && data[..8] == swap_event_discriminator()).then(|| data[8..].to_vec())
+}
+
+impl ClmmSwapEvent {
+    /// Decode a CLMM `SwapEvent` from a `Program data:` line
+    pub fn decode(line: &str) -> Option<Self> {
+        let body = swap_event_body(line)?;
+        Self::deserialize(&mut body.as_slice()).ok()
+    }
+}
+
+impl CpSwapEvent {
+    /// Decode a CP-Swap `SwapEvent` from a `Program data:` line, with the
+    /// swap's mints when the program version logs them
+    pub fn decode(line: &str) -> Option<(Self, Option<CpSwapEventMints>)> {
+        let body = swap_event_body(line)?;
+        let mut reader = body.as_slice();
+        let event = Self::deserialize(&mut reader).ok()?;
+        let mints = CpSwapEventMints::deserialize(&mut reader).ok();
+        Some((event, mints))
+    }
+}
+
+/// Swap reported by a Raydium pool program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapEvent {
+    /// AMM V4 or Stable `ray_log` swap entry
+    Amm(RayLog),
+    Clmm(ClmmSwapEvent),
+    CpSwap(CpSwapEvent, Option<CpSwapEventMints>),
+}
+
+/// Program a `Program <id> invoke [n]` line starts
+fn invoked_program(line: &str) -> Option<Pubkey> {
+    let (id, rest) = line.strip_prefix("Program ")?.split_once(' ')?;
+    rest.starts_with("invoke [").then(|| id.parse().ok()).flatten()
+}
+
+/// Whether `line` ends the innermost program invocation
+fn is_program_exit(line: &str) -> bool {
+    line.strip_prefix("Program ")
+        .and_then(|line| line.split_once(' '))
+        .is_some_and(|(id, rest)| {
+            (rest == "success" || rest.starts_with("failed")) && id.parse::<Pubkey>().is_ok()
+        })
+}
+
+/// Lines each invocation of `program_id` logged while it was the innermost
+/// running program, one entry per invocation in execution order
+pub fn invocation_logs<'a>(logs: &'a [String], program_id: &Pubkey) -> Vec<Vec<&'a str>> {
+    // Running programs, with the invocation index of `program_id` frames
+    let mut stack: Vec<Option<usize>> = Vec::new();
+    let mut invocations: Vec<Vec<&str>> = Vec::new();
+
+    for line in logs {
+        if let Some(program) = invoked_program(line) {
+            if program == *program_id {
+                stack.push(Some(invocations.len()));
+                invocations.push(Vec::new());
+            } else {
+                stack.push(None);
+            }
+            continue;
+        }
+        if is_program_exit(line) {
+            stack.pop();
+            continue;
+        }
+        if let Some(Some(index)) = stack.last() {
+            invocations[*index].push(line);
+        }
+    }
+
+    invocations
+}
+
+/// Instruction a transaction executed, with its accounts resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// Instructions a JSON-encoded transaction executed, each top-level
+/// instruction followed by the inner instructions it invoked, which is the
+/// order their invocations appear in the logs; empty for other encodings
+pub fn executed_instructions(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<ExecutedInstruction> {
+    let EncodedTransaction::Json(ui_transaction) = &transaction.transaction.transaction else {
+        return Vec::new();
+    };
+    let UiMessage::Raw(message) = &ui_transaction.message else {
+        return Vec::new();
+    };
+    let meta = transaction.transaction.meta.as_ref();
+
+    // Static keys, then lookup table writable and readonly addresses
+    let mut keys: Vec<&String> = message.account_keys.iter().collect();
+    if let Some(OptionSerializer::Some(loaded)) = meta.map(|meta| &meta.loaded_addresses) {
+        keys.extend(loaded.writable.iter().chain(&loaded.readonly));
+    }
+    let keys: Vec<Pubkey> = keys.iter().filter_map(|key| Pubkey::from_str(key).ok()).collect();
+    let resolve = |instruction: &UiCompiledInstruction| {
+        Some(ExecutedInstruction {
+            program_id: *keys.get(instruction.program_id_index as usize)?,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|index| keys.get(*index as usize).copied())
+                .collect::<Option<_>>()?,
+            data: bs58::decode(&instruction.data).into_vec().ok()?,
+        })
+    };
+
+    let inner = match meta.map(|meta| &meta.inner_instructions) {
+        Some(OptionSerializer::Some(inner)) => inner.as_slice(),
+        _ => &[],
+    };
+    let mut instructions = Vec::new();
+    for (index, instruction) in message.instructions.iter().enumerate() {
+        instructions.extend(resolve(instruction));
+        for group in inner.iter().filter(|group| group.index as usize == index) {
+            instructions.extend(group.instructions.iter().filter_map(|instruction| match instruction {
+                UiInstruction::Compiled(instruction) => resolve(instruction),
+                UiInstruction::Parsed(_) => None,
+            }));
+        }
+    }
+    instructions
+}
+
+impl SwapEvent {
+    /// Swap events in a transaction's `logs`, in execution order
+    ///
+    /// Each line is read as the innermost program running when it was logged,
+    /// which is what tells CLMM and CP-Swap events apart.
+    pub fn decode_all(logs: &[String]) -> Vec<Self> {
+        let mut programs: Vec<Pubkey> = Vec::new();
+        let mut events = Vec::new();
+
+        for line in logs {
+            if let Some(program) = invoked_program(line) {
+                programs.push(program);
+                continue;
+            }
+            if is_program_exit(line) {
+                programs.pop();
+                continue;
+            }
+            let Some(program) = programs.last() else {
+                continue;
+            };
+
+            let event = if *program == *AMM_V4_PROGRAM || *program == *STABLE_PROGRAM {
+                RayLog::decode(line).filter(|log| log.swap_output().is_some()).map(Self::Amm)
+            } else if *program == *CLMM_PROGRAM {
+                ClmmSwapEvent::decode(line).map(Self::Clmm)
+            } else if *program == *RAYDIUM_CP_SWAP_PROGRAM {
+                CpSwapEvent::decode(line).map(|(event, mints)| Self::CpSwap(event, mints))
+            } else {
+                None
+            };
+            events.extend(event);
+        }
+
+        events
+    }
+
+    /// Pool the event names; `ray_log` entries carry none
+    pub fn pool(&self) -> Option<Pubkey> {
+        match self {
+            Self::Amm(_) => None,
+            Self::Clmm(event) => Some(event.pool_state),
+            Self::CpSwap(event, _) => Some(event.pool_id),
+        }
+    }
+
+    /// Whether the event could come from `pool_type`'s program
+    pub fn matches_pool_type(&self, pool_type: PoolType) -> bool {
+        matches!(
+            (self, pool_type),
+            (Self::Amm(_), PoolType::AMM | PoolType::Stable)
+                | (Self::Clmm(_), PoolType::CLMM)
+                | (Self::CpSwap(..), PoolType::Standard)
+        )
+    }
+
+    /// Amount paid into the pool
+    pub fn amount_in(&self) -> u64 {
+        match self {
+            Self::Amm(log) => log.swap_input().map_or(0, |(amount, _)| amount),
+            Self::Clmm(event) if event.zero_for_one => event.amount_0,
+            Self::Clmm(event) => event.amount_1,
+            Self::CpSwap(event, _) => event.input_amount,
+        }
+    }
+
+    /// Amount the pool paid out, before any output transfer fee
+    pub fn amount_out(&self) -> u64 {
+        match self {
+            Self::Amm(log) => log.swap_output().unwrap_or(0),
+            Self::Clmm(event) if event.zero_for_one => event.amount_1,
+            Self::Clmm(event) => event.amount_0,
+            Self::CpSwap(event, _) => event.output_amount,
+        }
+    }
+
+    /// Token-2022 transfer fees withheld on the input and the output
+    pub fn transfer_fees(&self) -> (u64, u64) {
+        match self {
+            Self::Amm(_) => (0, 0),
+            Self::Clmm(event) if event.zero_for_one => (event.transfer_fee_0, event.transfer_fee_1),
+            Self::Clmm(event) => (event.transfer_fee_1, event.transfer_fee_0),
+            Self::CpSwap(event, _) => (event.input_transfer_fee, event.output_transfer_fee),
+        }
+    }
+
+    /// Amount that reached the user
+    pub fn amount_received(&self) -> u64 {
+        self.amount_out().saturating_sub(self.transfer_fees().1)
+    }
+
+    /// Price after the swap in raw output units per raw input unit
+    pub fn price_after(&self) -> Option<f64> {
+        let (input_reserve, output_reserve) = match self {
+            Self::Amm(log) => {
+                let (_, coin_in) = log.swap_input()?;
+                let (coin, pc) = log.reserves_after();
+                if coin_in {
+                    (coin as f64, pc as f64)
+                } else {
+                    (pc as f64, coin as f64)
+                }
+            }
+            Self::Clmm(event) => {
+                let sqrt_price = event.sqrt_price_x64 as f64 / 2f64.powi(64);
+                let price_0_in_1 = sqrt_price * sqrt_price;
+                if event.zero_for_one {
+                    (1.0, price_0_in_1)
+                } else {
+                    (price_0_in_1, 1.0)
+                }
+            }
+            Self::CpSwap(event, _) => (
+                event.input_vault_before.saturating_add(event.input_amount) as f64,
+                event.output_vault_before.saturating_sub(event.output_amount) as f64,
+            ),
+        };
+        (input_reserve > 0.0).then(|| output_reserve / input_reserve)
+    }
+
+    /// Execution report of the swap `quote` asked for
+    pub fn execution(&self, quote: &QuoteResult) -> SwapExecution {
+        let (token_in, token_out) = if quote.token_in == quote.pool_info.token_a.mint {
+            (&quote.pool_info.token_a, &quote.pool_info.token_b)
+        } else {
+            (&quote.pool_info.token_b, &quote.pool_info.token_a)
+        };
+        let decimal_shift = 10f64.powi(token_in.decimals as i32 - token_out.decimals as i32);
+        let (input_transfer_fee, output_transfer_fee) = self.transfer_fees();
+        let clmm = match self {
+            Self::Clmm(event) => Some(event),
+            _ => None,
+        };
+
+        SwapExecution {
+            pool_address: quote.pool_info.address,
+            amount_in: self.amount_in(),
+            amount_out: self.amount_out(),
+            input_transfer_fee,
+            output_transfer_fee,
+            price_after: self.price_after().map(|price| price * decimal_shift),
+            sqrt_price_x64: clmm.map(|event| event.sqrt_price_x64),
+            liquidity: clmm.map(|event| event.liquidity),
+            tick: clmm.map(|event| event.tick),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+
+    fn encode_line(prefix: &str, data: &[u8]) -> String {
+        format!("{}{}", prefix, STANDARD.encode(data))
+    }
+
+    #[test]
+    fn test_decode_ray_log_swap_base_in() {
+        let mut data = vec![RAY_LOG_SWAP_BASE_IN];
+        for value in [1_000_000u64, 990, AMM_DIRECTION_COIN_TO_PC, 5_000_000, 10_000, 20_000, 1_950] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let log = RayLog::decode(&encode_line(RAY_LOG_PREFIX, &data)).unwrap();
+        let RayLog::SwapBaseIn(swap) = log else {
+            panic!("expected SwapBaseIn, got {:?}", log);
+        };
+        assert_eq!(swap.out_amount, 1_950);
+        assert_eq!(log.swap_input(), Some((1_000_000, true)));
+        assert_eq!(log.swap_output(), Some(1_950));
+        assert_eq!(log.reserves_after(), (1_010_000, 18_050));
+
+        assert!(RayLog::decode("Program log: Instruction: Swap").is_none());
+        assert!(RayLog::decode(&encode_line(RAY_LOG_PREFIX, &[0, 1, 2])).is_none());
+    }
+
+    #[test]
+    fn test_decode_ray_log_liquidity_entries() {
+        let market = Pubkey::new_unique();
+        let mut init = vec![RAY_LOG_INIT];
+        init.extend_from_slice(&1_700_000_000u64.to_le_bytes());
+        init.extend_from_slice(&[6, 9]);
+        for value in [1u64, 1_000, 5_000_000, 2_000_000_000] {
+            init.extend_from_slice(&value.to_le_bytes());
+        }
+        init.extend_from_slice(&market.to_bytes());
+        let RayLog::Init(log) = RayLog::decode(&encode_line(RAY_LOG_PREFIX, &init)).unwrap() else {
+            panic!("expected Init");
+        };
+        assert_eq!((log.pc_decimals, log.coin_decimals, log.market), (6, 9, market));
+
+        let mut withdraw = vec![RAY_LOG_WITHDRAW];
+        for value in [100u64, 100, 10_000, 20_000, 1_000] {
+            withdraw.extend_from_slice(&value.to_le_bytes());
+        }
+        withdraw.extend_from_slice(&[0u8; 32]);
+        for value in [1_000u64, 2_000] {
+            withdraw.extend_from_slice(&value.to_le_bytes());
+        }
+        let log = RayLog::decode(&encode_line(RAY_LOG_PREFIX, &withdraw)).unwrap();
+        assert!(matches!(log, RayLog::Withdraw(WithdrawLog { withdraw_lp: 100, .. })));
+        assert_eq!(log.swap_output(), None);
+        assert_eq!(log.reserves_after(), (9_000, 18_000));
+
+        // Truncated entries are rejected
+        assert!(RayLog::decode(&encode_line(RAY_LOG_PREFIX, &withdraw[..withdraw.len() - 1])).is_none());
+    }
+
+    #[derive(BorshSerialize)]
+    struct CpEventWithMints {
+        pool_id: Pubkey,
+        amounts: [u64; 6],
+        base_input: bool,
+        input_mint: Pubkey,
+        output_mint: Pubkey,
+    }
+
+    #[test]
+    fn test_decode_cp_swap_event() {
+        let (pool_id, input_mint, output_mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let event = CpEventWithMints {
+            pool_id,
+            amounts: [100, 200, 30, 59, 0, 0],
+            base_input: true,
+            input_mint,
+            output_mint,
+        };
+        let mut data = swap_event_discriminator().to_vec();
+        event.serialize(&mut data).unwrap();
+
+        let (decoded, mints) = CpSwapEvent::decode(&encode_line(PROGRAM_DATA_PREFIX, &data)).unwrap();
+        assert_eq!(decoded.pool_id, pool_id);
+        assert_eq!((decoded.input_amount, decoded.output_amount), (30, 59));
+        assert_eq!(mints, Some(CpSwapEventMints { input_mint, output_mint }));
+
+        // Older program versions stop after `base_input`
+        data.truncate(data.len() - 64);
+        let (_, mints) = CpSwapEvent::decode(&encode_line(PROGRAM_DATA_PREFIX, &data)).unwrap();
+        assert_eq!(mints, None);
+
+        // Other events are ignored
+        data[0] ^= 0xff;
+        assert!(CpSwapEvent::decode(&encode_line(PROGRAM_DATA_PREFIX, &data)).is_none());
+    }
+
+    #[test]
+    fn test_decode_all_reads_events_as_the_logging_program() {
+        let pool = Pubkey::new_unique();
+        let mut clmm = swap_event_discriminator().to_vec();
+        for key in [pool, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()] {
+            clmm.extend_from_slice(&key.to_bytes());
+        }
+        for value in [990u64, 10, 1_000, 0] {
+            clmm.extend_from_slice(&value.to_le_bytes());
+        }
+        clmm.push(0);
+        clmm.extend_from_slice(&(2u128 << 64).to_le_bytes());
+        clmm.extend_from_slice(&5_000u128.to_le_bytes());
+        clmm.extend_from_slice(&13_863i32.to_le_bytes());
+        let clmm_line = encode_line(PROGRAM_DATA_PREFIX, &clmm);
+
+        let mut cp = swap_event_discriminator().to_vec();
+        cp.extend_from_slice(&pool.to_bytes());
+        for value in [1_000u64, 2_000, 100, 180, 0, 0] {
+            cp.extend_from_slice(&value.to_le_bytes());
+        }
+        cp.push(1);
+        let cp_line = encode_line(PROGRAM_DATA_PREFIX, &cp);
+
+        let (clmm_program, cp_program) = (CLMM_PROGRAM.to_string(), RAYDIUM_CP_SWAP_PROGRAM.to_string());
+        let token_program = spl_token::id().to_string();
+        let logs: Vec<String> = [
+            format!("Program {} invoke [1]", clmm_program),
+            "Program log: Instruction: SwapV2".to_string(),
+            format!("Program {} invoke [2]", token_program),
+            format!("Program {} success", token_program),
+            clmm_line.clone(),
+            format!("Program {} consumed 60000 of 200000 compute units", clmm_program),
+            format!("Program {} success", clmm_program),
+            // Outside any Raydium program
+            clmm_line,
+            format!("Program {} invoke [1]", cp_program),
+            cp_line,
+            format!("Program {} success", cp_program),
+        ]
+        .to_vec();
+
+        let events = SwapEvent::decode_all(&logs);
+        assert_eq!(events.len(), 2);
+        let (clmm, cp) = (&events[0], &events[1]);
+        assert!(clmm.matches_pool_type(PoolType::CLMM) && cp.matches_pool_type(PoolType::Standard));
+        assert_eq!((clmm.pool(), cp.pool()), (Some(pool), Some(pool)));
+
+        // One for zero: token 1 in, token 0 out less its transfer fee
+        assert_eq!((clmm.amount_in(), clmm.amount_out(), clmm.amount_received()), (1_000, 990, 980));
+        assert_eq!(clmm.price_after(), Some(0.25));
+        assert_eq!((cp.amount_in(), cp.amount_received()), (100, 180));
+        assert_eq!(cp.price_after(), Some(1_820.0 / 1_100.0));
+    }
+}
@@ -0,0 +1,157 @@
+//! Shared test fixtures: token and pool factories, and loaders for mainnet
+//! data captured under `fixtures/`
+//!
+//! See `fixtures/README.md` for how each kind of file is captured.
+
+use crate::core::{
+    events::{executed_instructions, ExecutedInstruction},
+    PoolInfo, PoolState, PoolType, TokenInfo,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Token with a placeholder symbol and name
+pub fn token(mint: Pubkey, decimals: u8) -> TokenInfo {
+    TokenInfo {
+        mint,
+        symbol: "TKN".to_string(),
+        decimals,
+        name: "Token".to_string(),
+    }
+}
+
+/// Pool of `token_a` and `token_b` at a fresh address, typed after `pool_state`
+pub fn pool(token_a: TokenInfo, token_b: TokenInfo, pool_state: PoolState) -> PoolInfo {
+    let pool_type = match pool_state {
+        PoolState::AMM { .. } => PoolType::AMM,
+        PoolState::Stable { .. } => PoolType::Stable,
+        PoolState::CLMM { .. } => PoolType::CLMM,
+        PoolState::Standard { .. } => PoolType::Standard,
+    };
+    PoolInfo {
+        pool_type,
+        address: Pubkey::new_unique(),
+        token_a,
+        token_b,
+        liquidity_usd: 0.0,
+        volume_24h_usd: 0.0,
+        fee_rate: 0.0025,
+        program_id: Pubkey::new_unique(),
+        context_slot: 0,
+        open_time: 0,
+        pool_state,
+    }
+}
+
+/// CP-Swap pool holding `reserve_a` and `reserve_b` at a 0.25% fee
+pub fn standard_pool(token_a: TokenInfo, token_b: TokenInfo, reserve_a: u64, reserve_b: u64) -> PoolInfo {
+    pool(token_a, token_b, PoolState::Standard { reserve_a, reserve_b, trade_fee_rate: 2_500 })
+}
+
+/// Path of `relative` inside the fixtures directory
+pub fn fixture_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(relative)
+}
+
+/// JSON files in a fixtures subdirectory, sorted by name; none when the
+/// directory has not been captured
+pub fn fixture_files(dir: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(fixture_path(dir)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn read_json(path: &Path) -> Value {
+    let text = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Failed to read fixture {}: {}", path.display(), e));
+    let value: Value = serde_json::from_str(&text)
+        .unwrap_or_else(|e| panic!("Fixture {} is not JSON: {}", path.display(), e));
+    // Raw RPC responses wrap the payload in `result`
+    match value.get("result") {
+        Some(result) => result.clone(),
+        None => value,
+    }
+}
+
+/// Account saved with `solana account <address> --output json`
+#[derive(Debug, Clone)]
+pub struct AccountFixture {
+    pub address: Pubkey,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// Load an account dump
+pub fn load_account(path: &Path) -> AccountFixture {
+    let value = read_json(path);
+    let field = |value: &Value, name: &str| -> String {
+        value[name]
+            .as_str()
+            .unwrap_or_else(|| panic!("Fixture {} has no `{}`", path.display(), name))
+            .to_string()
+    };
+    let account = &value["account"];
+    let data = account["data"][0]
+        .as_str()
+        .and_then(|data| STANDARD.decode(data).ok())
+        .unwrap_or_else(|| panic!("Fixture {} has no base64 account data", path.display()));
+
+    AccountFixture {
+        address: Pubkey::from_str(&field(&value, "pubkey")).unwrap(),
+        owner: Pubkey::from_str(&field(account, "owner")).unwrap(),
+        data,
+    }
+}
+
+/// Transaction saved from `getTransaction` with `json` encoding
+#[derive(Debug, Clone)]
+pub struct TransactionFixture {
+    pub slot: u64,
+    pub signature: String,
+    pub logs: Vec<String>,
+    /// Every instruction in execution order, each top-level instruction
+    /// followed by the inner instructions it invoked
+    pub instructions: Vec<ExecutedInstruction>,
+}
+
+impl TransactionFixture {
+    /// Instructions that ran `program_id`, in execution order
+    pub fn instructions_of(&self, program_id: &Pubkey) -> Vec<&ExecutedInstruction> {
+        self.instructions
+            .iter()
+            .filter(|instruction| instruction.program_id == *program_id)
+            .collect()
+    }
+}
+
+/// Load a transaction dump
+pub fn load_transaction(path: &Path) -> TransactionFixture {
+    let transaction: EncodedConfirmedTransactionWithStatusMeta = serde_json::from_value(read_json(path))
+        .unwrap_or_else(|e| panic!("Fixture {} is not a getTransaction result: {}", path.display(), e));
+    let EncodedTransaction::Json(ui_transaction) = &transaction.transaction.transaction else {
+        panic!("Fixture {} was not captured with json encoding", path.display());
+    };
+    let logs = match transaction.transaction.meta.as_ref().map(|meta| &meta.log_messages) {
+        Some(OptionSerializer::Some(logs)) => logs.clone(),
+        _ => panic!("Fixture {} has no log messages", path.display()),
+    };
+
+    TransactionFixture {
+        slot: transaction.slot,
+        signature: ui_transaction.signatures.first().cloned().unwrap_or_default(),
+        logs,
+        instructions: executed_instructions(&transaction),
+    }
+}
@@ -1,3 +1,4 @@
+use crate::core::clmm_math::{MAX_TICK, MIN_TICK};
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_sdk::pubkey::Pubkey;
 
@@ -34,8 +35,11 @@ pub struct AmmInfoLayoutV4 {
     pub need_take_pnl_pc: u64,
     pub total_pnl_pc: u64,
     pub total_pnl_coin: u64,
-    pub pool_total_deposit_pc: u128,
-    pub pool_total_deposit_coin: u128,
+    /// Unix time swaps open at
+    pub pool_open_time: u64,
+    pub punish_pc_amount: u64,
+    pub punish_coin_amount: u64,
+    pub orderbook_to_init_time: u64,
     pub system_decimals_value: u64,
     // Accounts
     pub pool_coin_token_account: Pubkey,
@@ -67,11 +71,6 @@ impl AmmInfoLayoutV4 {
             u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
         };
         
-        // Helper to read u128
-        let read_u128 = |offset: usize| -> u128 {
-            u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
-        };
-        
         // Helper to read Pubkey
         let read_pubkey = |offset: usize| -> Pubkey {
             Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
@@ -79,7 +78,7 @@ impl AmmInfoLayoutV4 {
         
         // Parse all fields at their specific offsets
         Ok(Self {
-            // u64 fields (0-239)
+            // u64 fields (0-255)
             status: read_u64(0),
             nonce: read_u64(8),
             order_num: read_u64(16),
@@ -109,9 +108,10 @@ impl AmmInfoLayoutV4 {
             total_pnl_pc: read_u64(208),
             total_pnl_coin: read_u64(216),
             
-            // u128 fields (224-255)
-            pool_total_deposit_pc: read_u128(224),
-            pool_total_deposit_coin: read_u128(240),
+            pool_open_time: read_u64(224),
+            punish_pc_amount: read_u64(232),
+            punish_coin_amount: read_u64(240),
+            orderbook_to_init_time: read_u64(248),
             
             // u64 field (256-263)
             system_decimals_value: read_u64(256),
@@ -156,93 +156,584 @@ impl AmmInfoLayoutV4 {
     }
 }
 
+/// Raydium CLMM reward info (embedded in the pool state)
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct ClmmRewardInfo {
+    pub reward_state: u8,
+    pub open_time: u64,
+    pub end_time: u64,
+    pub last_update_time: u64,
+    pub emissions_per_second_x64: u128,
+    pub reward_total_emissioned: u64,
+    pub reward_claimed: u64,
+    pub token_mint: Pubkey,
+    pub token_vault: Pubkey,
+    pub authority: Pubkey,
+    pub reward_growth_global_x64: u128,
+}
+
 /// Raydium CLMM Pool State Layout
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct ClmmPoolState {
+    pub discriminator: [u8; 8],
     pub bump: [u8; 1],
+    pub amm_config: Pubkey,
+    pub owner: Pubkey,
     pub token_mint_0: Pubkey,
     pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
     pub tick_spacing: u16,
     pub liquidity: u128,
+    /// Q64.64 square root of the price (token 1 per token 0)
     pub current_price_sqrt: u128,
     pub current_tick: i32,
+    pub padding3: u16,
+    pub padding4: u16,
     pub fee_growth_global_0: u128,
     pub fee_growth_global_1: u128,
-    pub fee_rate: u64,
-    pub protocol_fee_rate: u64,
-    pub protocol_fee_owed_0: u64,
-    pub protocol_fee_owed_1: u64,
-    pub fund_fee_owed_0: u64,
-    pub fund_fee_owed_1: u64,
-    pub padding: [u64; 32],
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub swap_in_amount_token_0: u128,
+    pub swap_out_amount_token_1: u128,
+    pub swap_in_amount_token_1: u128,
+    pub swap_out_amount_token_0: u128,
+    pub status: u8,
+    pub padding: [u8; 7],
+    pub reward_infos: [ClmmRewardInfo; 3],
+    pub tick_array_bitmap: [u64; 16],
+    pub total_fees_token_0: u64,
+    pub total_fees_claimed_token_0: u64,
+    pub total_fees_token_1: u64,
+    pub total_fees_claimed_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    pub open_time: u64,
+    pub recent_epoch: u64,
+    pub padding1: [u64; 24],
+    pub padding2: [u64; 32],
 }
 
 impl ClmmPoolState {
     pub const LEN: usize = 1544;
 
-    /// Get fee rate in basis points
-    pub fn get_fee_rate_bps(&self) -> u32 {
-        (self.fee_rate * 10000 / 1_000_000) as u32
+    /// Swaps are disabled when status bit 4 is set
+    pub fn is_swap_enabled(&self) -> bool {
+        self.status & (1 << 4) == 0
+    }
+
+    /// Tick arrays tracked by the pool's own bitmap on each side of tick 0;
+    /// arrays further out are tracked by the bitmap extension account
+    pub const TICK_ARRAY_BITMAP_HALF: i32 = 512;
+    pub const BITMAP_EXTENSION_SEED: &'static [u8] = b"pool_tick_array_bitmap_extension";
+
+    /// Whether the tick array starting at `start_index` is initialized, or
+    /// `None` when it lies outside the pool's bitmap
+    pub fn is_tick_array_initialized(&self, start_index: i32) -> Option<bool> {
+        let position = start_index.div_euclid(TickArrayState::tick_count(self.tick_spacing)) + Self::TICK_ARRAY_BITMAP_HALF;
+        if !(0..2 * Self::TICK_ARRAY_BITMAP_HALF).contains(&position) {
+            return None;
+        }
+        let position = position as usize;
+        Some((self.tick_array_bitmap[position / 64] >> (position % 64)) & 1 == 1)
+    }
+
+    /// Derive the tick array bitmap extension PDA for a pool
+    pub fn find_bitmap_extension_address(pool_id: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        let (address, _) =
+            Pubkey::find_program_address(&[Self::BITMAP_EXTENSION_SEED, pool_id.as_ref()], program_id);
+        address
+    }
+
+    /// Walk tick arrays from the one holding `current_tick` in the swap direction
+    /// Arrays either bitmap marks uninitialized are skipped. Stops after `count`
+    /// initialized arrays, or `max_untracked` arrays no bitmap covers (left for the
+    /// caller to check on chain).
+    pub fn tick_array_walk(
+        &self,
+        extension: Option<&TickArrayBitmapExtension>,
+        current_tick: i32,
+        zero_for_one: bool,
+        count: usize,
+        max_untracked: usize,
+    ) -> TickArrayWalk {
+        let ticks_in_array = TickArrayState::tick_count(self.tick_spacing);
+        let in_range = |start_index: i32| start_index <= MAX_TICK && start_index + ticks_in_array > MIN_TICK;
+        let mut start_index = TickArrayState::get_array_start_index(current_tick, self.tick_spacing);
+        let mut initialized = 0;
+        let mut untracked = 0;
+        let mut start_indices = Vec::new();
+
+        while initialized < count && untracked < max_untracked && in_range(start_index) {
+            let status = self.is_tick_array_initialized(start_index).or_else(|| {
+                extension.and_then(|extension| extension.is_tick_array_initialized(start_index, self.tick_spacing))
+            });
+            match status {
+                Some(true) => {
+                    initialized += 1;
+                    start_indices.push(start_index);
+                }
+                Some(false) => {}
+                None => {
+                    untracked += 1;
+                    start_indices.push(start_index);
+                }
+            }
+            start_index += if zero_for_one { -ticks_in_array } else { ticks_in_array };
+        }
+
+        // Past the last array walked the range is only known when the walk ran off the tick range
+        let boundary_tick = match start_indices.last() {
+            Some(last) if in_range(start_index) => {
+                if zero_for_one { *last } else { last + ticks_in_array }
+            }
+            _ => {
+                if zero_for_one { MIN_TICK } else { MAX_TICK }
+            }
+        };
+
+        TickArrayWalk { start_indices, boundary_tick }
     }
 }
 
-/// Raydium Stable Pool State Layout (simplified)
+/// Tick arrays a swap may walk through and how far they reach
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickArrayWalk {
+    /// Start indices in the swap direction, current array first when initialized
+    pub start_indices: Vec<i32>,
+    /// Furthest tick the walked arrays cover; the price cannot be simulated past it
+    pub boundary_tick: i32,
+}
+
+/// Raydium CLMM tick array bitmap extension, tracking arrays beyond the pool's own bitmap
+#[derive(Debug, Clone)]
+pub struct TickArrayBitmapExtension {
+    pub pool_id: Pubkey,
+    /// Each entry covers the next `TICK_ARRAY_BITMAP_HALF` arrays past the pool bitmap
+    pub positive_tick_array_bitmap: [[u64; 8]; TickArrayBitmapExtension::BITMAP_COUNT],
+    pub negative_tick_array_bitmap: [[u64; 8]; TickArrayBitmapExtension::BITMAP_COUNT],
+}
+
+impl TickArrayBitmapExtension {
+    pub const BITMAP_COUNT: usize = 14;
+    pub const LEN: usize = 8 + 32 + 2 * Self::BITMAP_COUNT * 64;
+
+    /// Extension with no arrays initialized, standing in for a missing account
+    pub fn empty(pool_id: Pubkey) -> Self {
+        Self {
+            pool_id,
+            positive_tick_array_bitmap: [[0; 8]; Self::BITMAP_COUNT],
+            negative_tick_array_bitmap: [[0; 8]; Self::BITMAP_COUNT],
+        }
+    }
+
+    /// Parse from raw bytes (Anchor account, 8-byte discriminator)
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() < Self::LEN {
+            return Err(format!("Invalid bitmap extension data length: {} (expected {})", data.len(), Self::LEN));
+        }
+
+        let read_bitmaps = |offset: usize| {
+            let mut bitmaps = [[0u64; 8]; Self::BITMAP_COUNT];
+            for (i, word) in bitmaps.iter_mut().flatten().enumerate() {
+                let start = offset + i * 8;
+                *word = u64::from_le_bytes(data[start..start + 8].try_into().unwrap());
+            }
+            bitmaps
+        };
+
+        // Layout: discriminator (8) | pool_id (32) | positive bitmaps (14 * 64) | negative bitmaps (14 * 64)
+        Ok(Self {
+            pool_id: Pubkey::new_from_array(data[8..40].try_into().unwrap()),
+            positive_tick_array_bitmap: read_bitmaps(40),
+            negative_tick_array_bitmap: read_bitmaps(40 + Self::BITMAP_COUNT * 64),
+        })
+    }
+
+    /// Whether the tick array starting at `start_index` is initialized, or `None`
+    /// when the pool's own bitmap covers it
+    pub fn is_tick_array_initialized(&self, start_index: i32, tick_spacing: u16) -> Option<bool> {
+        let half = ClmmPoolState::TICK_ARRAY_BITMAP_HALF;
+        let array_index = start_index.div_euclid(TickArrayState::tick_count(tick_spacing));
+        // Positive bitmaps count outwards from array 512, negative ones inwards towards array -513
+        let (bitmap, bit) = if array_index >= half {
+            (self.positive_tick_array_bitmap.get((array_index / half - 1) as usize)?, array_index % half)
+        } else if array_index < -half {
+            let distance = -array_index;
+            (
+                self.negative_tick_array_bitmap.get(((distance - 1) / half - 1) as usize)?,
+                (half - distance % half) % half,
+            )
+        } else {
+            return None;
+        };
+        let bit = bit as usize;
+        Some((bitmap[bit / 64] >> (bit % 64)) & 1 == 1)
+    }
+}
+
+/// Raydium CLMM AmmConfig Layout (fee settings shared by pools)
+#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct ClmmAmmConfig {
+    pub discriminator: [u8; 8],
+    pub bump: u8,
+    pub index: u16,
+    pub owner: Pubkey,
+    /// Share of the trade fee taken by the protocol (hundredths of a bp)
+    pub protocol_fee_rate: u32,
+    /// Trade fee rate (hundredths of a bp, denominator 1_000_000)
+    pub trade_fee_rate: u32,
+    pub tick_spacing: u16,
+    pub fund_fee_rate: u32,
+    pub padding_u32: u32,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 3],
+}
+
+impl ClmmAmmConfig {
+    pub const LEN: usize = 117;
+
+    /// Parse from raw bytes
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() != Self::LEN {
+            return Err(format!("Invalid CLMM config data length: {} (expected {})", data.len(), Self::LEN));
+        }
+
+        Self::try_from_slice(data)
+            .map_err(|e| format!("Failed to deserialize CLMM config: {}", e))
+    }
+}
+
+/// Raydium CP-Swap AmmConfig Layout (fee settings shared by pools)
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+pub struct CpAmmConfig {
+    pub discriminator: [u8; 8],
+    pub bump: u8,
+    pub disable_create_pool: bool,
+    pub index: u16,
+    /// Trade fee rate (denominator 1_000_000)
+    pub trade_fee_rate: u64,
+    /// Share of the trade fee taken by the protocol (denominator 1_000_000)
+    pub protocol_fee_rate: u64,
+    /// Share of the trade fee taken by the fund (denominator 1_000_000)
+    pub fund_fee_rate: u64,
+    pub create_pool_fee: u64,
+    pub protocol_owner: Pubkey,
+    pub fund_owner: Pubkey,
+    pub padding: [u64; 16],
+}
+
+impl CpAmmConfig {
+    pub const LEN: usize = 236;
+    pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+    /// Parse from raw bytes
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() != Self::LEN {
+            return Err(format!("Invalid CP config data length: {} (expected {})", data.len(), Self::LEN));
+        }
+
+        Self::try_from_slice(data)
+            .map_err(|e| format!("Failed to deserialize CP config: {}", e))
+    }
+}
+
+/// Raydium CLMM tick state (one slot of a tick array)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TickState {
+    pub tick: i32,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+impl TickState {
+    pub const LEN: usize = 168;
+
+    /// A tick is initialized when at least one position references it
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross != 0
+    }
+}
+
+/// Raydium CLMM Tick Array Layout
+/// Each array holds 60 consecutive ticks (spaced by the pool's tick spacing)
+#[derive(Debug, Clone)]
+pub struct TickArrayState {
+    pub pool_id: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: Vec<TickState>,
+    pub initialized_tick_count: u8,
+}
+
+impl TickArrayState {
+    pub const LEN: usize = 10240;
+    pub const TICK_COUNT: usize = 60;
+    pub const SEED: &'static [u8] = b"tick_array";
+
+    /// Parse from raw bytes (Anchor account, 8-byte discriminator)
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() != Self::LEN {
+            return Err(format!("Invalid tick array data length: {} (expected {})", data.len(), Self::LEN));
+        }
+
+        let read_i32 = |offset: usize| -> i32 {
+            i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+        };
+        let read_u128 = |offset: usize| -> u128 {
+            u128::from_le_bytes(data[offset..offset + 16].try_into().unwrap())
+        };
+
+        // Layout: discriminator (8) | pool_id (32) | start_tick_index (4) | ticks (60 * 168) | initialized_tick_count (1) | ...
+        let pool_id = Pubkey::new_from_array(data[8..40].try_into().unwrap());
+        let start_tick_index = read_i32(40);
+
+        let ticks_offset = 44;
+        let ticks = (0..Self::TICK_COUNT)
+            .map(|i| {
+                let offset = ticks_offset + i * TickState::LEN;
+                TickState {
+                    tick: read_i32(offset),
+                    liquidity_net: read_u128(offset + 4) as i128,
+                    liquidity_gross: read_u128(offset + 20),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            pool_id,
+            start_tick_index,
+            ticks,
+            initialized_tick_count: data[ticks_offset + Self::TICK_COUNT * TickState::LEN],
+        })
+    }
+
+    /// Number of ticks covered by a single tick array
+    pub fn tick_count(tick_spacing: u16) -> i32 {
+        Self::TICK_COUNT as i32 * tick_spacing as i32
+    }
+
+    /// Start index of the tick array containing `tick`
+    pub fn get_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+        let ticks_in_array = Self::tick_count(tick_spacing);
+        tick.div_euclid(ticks_in_array) * ticks_in_array
+    }
+
+    /// Derive the tick array PDA for a pool and start index
+    pub fn find_address(pool_id: &Pubkey, start_tick_index: i32, program_id: &Pubkey) -> Pubkey {
+        let (address, _) = Pubkey::find_program_address(
+            &[Self::SEED, pool_id.as_ref(), &start_tick_index.to_be_bytes()],
+            program_id,
+        );
+        address
+    }
+
+    /// Initialized ticks in this array
+    pub fn initialized_ticks(&self) -> impl Iterator<Item = &TickState> {
+        self.ticks.iter().filter(|tick| tick.is_initialized())
+    }
+}
+
+/// Raydium Stable pool state layout (AMM V4 style, with the model data account)
+#[derive(Debug, Clone)]
 pub struct StablePoolState {
-    pub is_initialized: bool,
-    pub is_paused: bool,
-    pub nonce: u8,
-    pub initial_amp_factor: u64,
-    pub target_amp_factor: u64,
-    pub start_ramp_timestamp: i64,
-    pub stop_ramp_timestamp: i64,
-    pub future_admin_deadline: i64,
-    pub future_admin_account: Pubkey,
-    pub admin_account: Pubkey,
-    pub token_mint_a: Pubkey,
-    pub token_mint_b: Pubkey,
-    pub token_a_account: Pubkey,
-    pub token_b_account: Pubkey,
-    pub pool_mint: Pubkey,
-    pub token_a_fees: u64,
-    pub token_b_fees: u64,
-    pub admin_trade_fee_numerator: u64,
-    pub admin_trade_fee_denominator: u64,
+    pub account_type: u64,
+    pub status: u64,
+    pub nonce: u64,
+    pub order_num: u64,
+    pub depth: u64,
+    pub coin_decimals: u64,
+    pub pc_decimals: u64,
+    pub state: u64,
+    pub reset_flag: u64,
+    pub min_size: u64,
+    pub vol_max_cut_ratio: u64,
+    pub amount_wave: u64,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub min_price_multiplier: u64,
+    pub max_price_multiplier: u64,
+    pub sys_decimal_value: u64,
+    pub abort_trade_factor: u64,
+    pub price_tick_multiplier: u64,
+    pub price_tick: u64,
+    // Fees
+    pub min_separate_numerator: u64,
+    pub min_separate_denominator: u64,
     pub trade_fee_numerator: u64,
     pub trade_fee_denominator: u64,
+    pub pnl_numerator: u64,
+    pub pnl_denominator: u64,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+    // Output data
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+    pub total_pnl_pc: u64,
+    pub total_pnl_coin: u64,
+    pub pool_open_time: u64,
+    // Accounts
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub model_data_account: Pubkey,
+    pub open_orders: Pubkey,
+    pub market: Pubkey,
+    pub market_program: Pubkey,
+    pub target_orders: Pubkey,
+    pub owner: Pubkey,
 }
 
 impl StablePoolState {
-    /// Get current amplification factor
-    pub fn get_current_amp(&self, current_timestamp: i64) -> u64 {
-        if current_timestamp >= self.stop_ramp_timestamp {
-            self.target_amp_factor
-        } else if current_timestamp <= self.start_ramp_timestamp {
-            self.initial_amp_factor
-        } else {
-            // Linear interpolation during ramping
-            let time_range = self.stop_ramp_timestamp - self.start_ramp_timestamp;
-            let time_elapsed = current_timestamp - self.start_ramp_timestamp;
-            let amp_range = if self.target_amp_factor > self.initial_amp_factor {
-                self.target_amp_factor - self.initial_amp_factor
-            } else {
-                self.initial_amp_factor - self.target_amp_factor
-            };
-            
-            if self.target_amp_factor > self.initial_amp_factor {
-                self.initial_amp_factor + (amp_range * time_elapsed as u64 / time_range as u64)
-            } else {
-                self.initial_amp_factor - (amp_range * time_elapsed as u64 / time_range as u64)
-            }
+    pub const LEN: usize = 1232;
+    pub const COIN_MINT_OFFSET: usize = 432;
+    pub const PC_MINT_OFFSET: usize = 464;
+
+    /// Parse from raw bytes (packed layout)
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() != Self::LEN {
+            return Err(format!("Invalid Stable pool data length: {} (expected {})", data.len(), Self::LEN));
         }
+
+        let read_u64 = |offset: usize| -> u64 {
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+        };
+        let read_pubkey = |offset: usize| -> Pubkey {
+            Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap())
+        };
+
+        // Layout: 20 u64 params (0-159) | 8 u64 fees (160-223) | 8 u64 output data (224-287)
+        // | 4 u128 swap totals + 2 u64 swap fees (288-367) | 11 pubkeys (368-719) | padding
+        Ok(Self {
+            account_type: read_u64(0),
+            status: read_u64(8),
+            nonce: read_u64(16),
+            order_num: read_u64(24),
+            depth: read_u64(32),
+            coin_decimals: read_u64(40),
+            pc_decimals: read_u64(48),
+            state: read_u64(56),
+            reset_flag: read_u64(64),
+            min_size: read_u64(72),
+            vol_max_cut_ratio: read_u64(80),
+            amount_wave: read_u64(88),
+            coin_lot_size: read_u64(96),
+            pc_lot_size: read_u64(104),
+            min_price_multiplier: read_u64(112),
+            max_price_multiplier: read_u64(120),
+            sys_decimal_value: read_u64(128),
+            abort_trade_factor: read_u64(136),
+            price_tick_multiplier: read_u64(144),
+            price_tick: read_u64(152),
+            min_separate_numerator: read_u64(160),
+            min_separate_denominator: read_u64(168),
+            trade_fee_numerator: read_u64(176),
+            trade_fee_denominator: read_u64(184),
+            pnl_numerator: read_u64(192),
+            pnl_denominator: read_u64(200),
+            swap_fee_numerator: read_u64(208),
+            swap_fee_denominator: read_u64(216),
+            need_take_pnl_coin: read_u64(224),
+            need_take_pnl_pc: read_u64(232),
+            total_pnl_pc: read_u64(240),
+            total_pnl_coin: read_u64(248),
+            pool_open_time: read_u64(256),
+            coin_vault: read_pubkey(368),
+            pc_vault: read_pubkey(400),
+            coin_mint: read_pubkey(Self::COIN_MINT_OFFSET),
+            pc_mint: read_pubkey(Self::PC_MINT_OFFSET),
+            lp_mint: read_pubkey(496),
+            model_data_account: read_pubkey(528),
+            open_orders: read_pubkey(560),
+            market: read_pubkey(592),
+            market_program: read_pubkey(624),
+            target_orders: read_pubkey(656),
+            owner: read_pubkey(688),
+        })
+    }
+
+    /// Check if the pool accepts swaps
+    pub fn is_swappable(&self) -> bool {
+        // Status 1 = Initialized, 6 = Swap only, 7 = Waiting for open time
+        matches!(self.status, 1 | 6 | 7)
     }
 
-    /// Get trade fee rate
-    pub fn get_trade_fee_rate(&self) -> f64 {
-        if self.trade_fee_denominator == 0 {
+    /// Get swap fee rate
+    pub fn get_swap_fee_rate(&self) -> f64 {
+        if self.swap_fee_denominator == 0 {
             return 0.0;
         }
-        self.trade_fee_numerator as f64 / self.trade_fee_denominator as f64
+        self.swap_fee_numerator as f64 / self.swap_fee_denominator as f64
+    }
+}
+
+/// Point on the Stable program's pricing curve
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DataElement {
+    pub x: u64,
+    pub y: u64,
+    pub price: u64,
+}
+
+impl DataElement {
+    pub const LEN: usize = 24;
+}
+
+/// Raydium Stable model data account: the curve table every Stable pool
+/// prices against, with x ascending and y descending
+#[derive(Debug, Clone)]
+pub struct ModelDataInfo {
+    pub account_type: u16,
+    pub status: u16,
+    pub multiplier: u32,
+    pub valid_data_count: u64,
+    /// The first `valid_data_count` table entries
+    pub elements: Vec<DataElement>,
+}
+
+impl ModelDataInfo {
+    pub const ELEMENT_COUNT: usize = 50_000;
+    pub const HEADER_LEN: usize = 16;
+    pub const LEN: usize = Self::HEADER_LEN + Self::ELEMENT_COUNT * DataElement::LEN;
+
+    /// Parse from raw bytes
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() != Self::LEN {
+            return Err(format!("Invalid model data length: {} (expected {})", data.len(), Self::LEN));
+        }
+
+        let read_u64 = |offset: usize| -> u64 {
+            u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+        };
+
+        // Layout: account_type (2) | status (2) | multiplier (4) | valid_data_count (8) | elements (50000 * 24)
+        let valid_data_count = read_u64(8);
+        if valid_data_count < 2 || valid_data_count as usize > Self::ELEMENT_COUNT {
+            return Err(format!("Invalid model data point count: {}", valid_data_count));
+        }
+
+        let elements = (0..valid_data_count as usize)
+            .map(|i| {
+                let offset = Self::HEADER_LEN + i * DataElement::LEN;
+                DataElement {
+                    x: read_u64(offset),
+                    y: read_u64(offset + 8),
+                    price: read_u64(offset + 16),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            account_type: u16::from_le_bytes(data[0..2].try_into().unwrap()),
+            status: u16::from_le_bytes(data[2..4].try_into().unwrap()),
+            multiplier: u32::from_le_bytes(data[4..8].try_into().unwrap()),
+            valid_data_count,
+            elements,
+        })
     }
 }
 
@@ -271,15 +762,26 @@ pub struct CpSwapPoolState {
     pub fund_fees_token_0: u64,
     pub fund_fees_token_1: u64,
     pub open_time: u64,
-    pub padding: [u64; 32],
+    pub recent_epoch: u64,
+    pub padding: [u64; 31],
 }
 
 impl CpSwapPoolState {
-    pub const LEN: usize = 653;
-    
-    /// Check if pool is active
-    pub fn is_active(&self) -> bool {
-        self.status == 1
+    pub const LEN: usize = 637;
+    /// Status bit that disables swaps when set
+    const SWAP_DISABLED_BIT: u8 = 1 << 2;
+
+    /// Check if swaps are enabled (deposits and withdrawals have their own bits)
+    pub fn is_swap_enabled(&self) -> bool {
+        self.status & Self::SWAP_DISABLED_BIT == 0
+    }
+
+    /// Vault balances less the protocol and fund fees accrued in them
+    pub fn swappable_reserves(&self, vault_0_amount: u64, vault_1_amount: u64) -> (u64, u64) {
+        (
+            vault_0_amount.saturating_sub(self.protocol_fees_token_0 + self.fund_fees_token_0),
+            vault_1_amount.saturating_sub(self.protocol_fees_token_1 + self.fund_fees_token_1),
+        )
     }
     
     /// Parse from raw bytes
@@ -293,4 +795,84 @@ impl CpSwapPoolState {
         Self::try_from_slice(data)
             .map_err(|e| format!("Failed to deserialize CP pool state: {}", e))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_amm_v4_open_time_offset() {
+        let mut data = vec![0u8; AmmInfoLayoutV4::LEN];
+        data[216..224].copy_from_slice(&11u64.to_le_bytes());
+        data[224..232].copy_from_slice(&1_700_000_000u64.to_le_bytes());
+        data[248..256].copy_from_slice(&1_699_999_000u64.to_le_bytes());
+
+        let state = AmmInfoLayoutV4::from_bytes(&data).unwrap();
+        assert_eq!(state.total_pnl_coin, 11);
+        assert_eq!(state.pool_open_time, 1_700_000_000);
+        assert_eq!(state.orderbook_to_init_time, 1_699_999_000);
+    }
+
+    #[test]
+    fn test_stable_params_follow_account_type() {
+        let mut data = vec![0u8; StablePoolState::LEN];
+        for (offset, value) in [(0, 1u64), (8, 6), (16, 254), (152, 77), (208, 5), (216, 10_000), (256, 1_650_000_000)] {
+            data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        }
+
+        let state = StablePoolState::from_bytes(&data).unwrap();
+        assert_eq!(state.account_type, 1);
+        assert_eq!(state.status, 6);
+        assert_eq!(state.nonce, 254);
+        assert_eq!(state.price_tick, 77);
+        assert_eq!(state.get_swap_fee_rate(), 0.0005);
+        assert_eq!(state.pool_open_time, 1_650_000_000);
+        assert!(state.is_swappable());
+    }
+
+    fn clmm_pool_state(tick_spacing: u16) -> ClmmPoolState {
+        let mut pool_state = ClmmPoolState::try_from_slice(&vec![0u8; ClmmPoolState::LEN]).unwrap();
+        pool_state.tick_spacing = tick_spacing;
+        pool_state
+    }
+
+    #[test]
+    fn test_bitmap_extension_positions() {
+        let mut data = vec![0u8; TickArrayBitmapExtension::LEN];
+        // Positive bitmap 0, bit 3: array 515
+        data[40] = 1 << 3;
+        // Negative bitmap 1, bit 0: array -1536
+        data[40 + 14 * 64 + 64] = 1;
+        // Negative bitmap 0, bit 511: array -513
+        data[40 + 14 * 64 + 63] = 1 << 7;
+        let extension = TickArrayBitmapExtension::from_bytes(&data).unwrap();
+
+        // Spacing 10 puts 600 ticks in each array
+        assert_eq!(extension.is_tick_array_initialized(515 * 600, 10), Some(true));
+        assert_eq!(extension.is_tick_array_initialized(516 * 600, 10), Some(false));
+        assert_eq!(extension.is_tick_array_initialized(-1536 * 600, 10), Some(true));
+        assert_eq!(extension.is_tick_array_initialized(-513 * 600, 10), Some(true));
+        assert_eq!(extension.is_tick_array_initialized(-514 * 600, 10), Some(false));
+        // The pool's own bitmap covers these
+        assert_eq!(extension.is_tick_array_initialized(511 * 600, 10), None);
+        assert_eq!(extension.is_tick_array_initialized(-512 * 600, 10), None);
+    }
+
+    #[test]
+    fn test_tick_array_walk_skips_uninitialized_arrays() {
+        let mut pool_state = clmm_pool_state(10);
+        // Arrays 0 and 2 in the pool bitmap, 600 in the extension
+        pool_state.tick_array_bitmap[8] = 0b101;
+        let mut extension = TickArrayBitmapExtension::empty(Pubkey::default());
+        extension.positive_tick_array_bitmap[0][1] = 1 << (600 - 512 - 64);
+
+        let walk = pool_state.tick_array_walk(Some(&extension), 5, false, 3, 3);
+        assert_eq!(walk.start_indices, vec![0, 1200, 600 * 600]);
+        assert_eq!(walk.boundary_tick, 601 * 600);
+
+        // Nothing below array 0 is initialized, so the walk reaches the end of the tick range
+        let walk = pool_state.tick_array_walk(Some(&extension), 5, true, 3, 3);
+        assert_eq!(walk.start_indices, vec![0]);
+        assert_eq!(walk.boundary_tick, MIN_TICK);
+    }
+}
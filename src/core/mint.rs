@@ -0,0 +1,420 @@
+use crate::core::{SwapError, SwapResult, TOKEN_2022_PROGRAM};
+use dashmap::DashMap;
+use log::debug;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Size of the base SPL mint layout
+const MINT_BASE_LEN: usize = 82;
+/// Token-2022 pads mints to the token account size before the account type byte
+const ACCOUNT_TYPE_OFFSET: usize = 165;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+/// How long the current epoch is reused for transfer fee lookups
+const EPOCH_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Token-2022 mint extension tags
+const EXTENSION_TRANSFER_FEE_CONFIG: u16 = 1;
+const EXTENSION_MINT_CLOSE_AUTHORITY: u16 = 3;
+const EXTENSION_CONFIDENTIAL_TRANSFER_MINT: u16 = 4;
+const EXTENSION_DEFAULT_ACCOUNT_STATE: u16 = 6;
+const EXTENSION_NON_TRANSFERABLE: u16 = 9;
+const EXTENSION_INTEREST_BEARING_CONFIG: u16 = 10;
+const EXTENSION_PERMANENT_DELEGATE: u16 = 12;
+const EXTENSION_TRANSFER_HOOK: u16 = 14;
+const EXTENSION_CONFIDENTIAL_TRANSFER_FEE_CONFIG: u16 = 16;
+const EXTENSION_METADATA_POINTER: u16 = 18;
+const EXTENSION_TOKEN_METADATA: u16 = 19;
+const EXTENSION_GROUP_POINTER: u16 = 20;
+const EXTENSION_TOKEN_GROUP: u16 = 21;
+const EXTENSION_GROUP_MEMBER_POINTER: u16 = 22;
+const EXTENSION_TOKEN_GROUP_MEMBER: u16 = 23;
+const EXTENSION_CONFIDENTIAL_MINT_BURN: u16 = 24;
+const EXTENSION_SCALED_UI_AMOUNT: u16 = 25;
+const EXTENSION_PAUSABLE: u16 = 26;
+
+/// Extensions that leave ordinary transfers alone: they only affect how
+/// amounts are displayed or described, or add opt-in confidential balances
+const INFORMATIONAL_EXTENSIONS: [u16; 12] = [
+    EXTENSION_MINT_CLOSE_AUTHORITY,
+    EXTENSION_CONFIDENTIAL_TRANSFER_MINT,
+    EXTENSION_CONFIDENTIAL_TRANSFER_FEE_CONFIG,
+    EXTENSION_CONFIDENTIAL_MINT_BURN,
+    EXTENSION_INTEREST_BEARING_CONFIG,
+    EXTENSION_METADATA_POINTER,
+    EXTENSION_TOKEN_METADATA,
+    EXTENSION_GROUP_POINTER,
+    EXTENSION_TOKEN_GROUP,
+    EXTENSION_GROUP_MEMBER_POINTER,
+    EXTENSION_TOKEN_GROUP_MEMBER,
+    EXTENSION_SCALED_UI_AMOUNT,
+];
+
+/// Transfer fee in effect from `epoch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFee {
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub transfer_fee_basis_points: u16,
+}
+
+impl TransferFee {
+    const LEN: usize = 18;
+
+    fn from_bytes(data: &[u8]) -> Self {
+        Self {
+            epoch: u64::from_le_bytes(data[0..8].try_into().unwrap()),
+            maximum_fee: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+            transfer_fee_basis_points: u16::from_le_bytes(data[16..18].try_into().unwrap()),
+        }
+    }
+
+    /// Fee withheld when transferring `amount`, rounded up and capped at the maximum
+    pub fn calculate_fee(&self, amount: u64) -> u64 {
+        if self.transfer_fee_basis_points == 0 || amount == 0 {
+            return 0;
+        }
+        let fee = (amount as u128 * self.transfer_fee_basis_points as u128).div_ceil(MAX_FEE_BASIS_POINTS as u128);
+        fee.min(self.maximum_fee as u128) as u64
+    }
+
+    /// Amount to transfer so that `post_fee_amount` arrives
+    pub fn calculate_pre_fee_amount(&self, post_fee_amount: u64) -> SwapResult<u64> {
+        match (self.transfer_fee_basis_points, post_fee_amount) {
+            (0, _) => Ok(post_fee_amount),
+            (_, 0) => Ok(0),
+            (MAX_FEE_BASIS_POINTS, _) => post_fee_amount.checked_add(self.maximum_fee).ok_or(SwapError::MathOverflow),
+            (basis_points, _) => {
+                let pre_fee_amount = (post_fee_amount as u128 * MAX_FEE_BASIS_POINTS as u128)
+                    .div_ceil((MAX_FEE_BASIS_POINTS - basis_points) as u128);
+                if pre_fee_amount - post_fee_amount as u128 >= self.maximum_fee as u128 {
+                    post_fee_amount.checked_add(self.maximum_fee).ok_or(SwapError::MathOverflow)
+                } else {
+                    u64::try_from(pre_fee_amount).map_err(|_| SwapError::MathOverflow)
+                }
+            }
+        }
+    }
+}
+
+/// `TransferFeeConfig` extension: the fee switches from older to newer at newer's epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferFeeConfig {
+    pub older_transfer_fee: TransferFee,
+    pub newer_transfer_fee: TransferFee,
+}
+
+impl TransferFeeConfig {
+    /// Authorities (64) and withheld amount (8) precede the fees
+    const FEES_OFFSET: usize = 72;
+    const LEN: usize = Self::FEES_OFFSET + 2 * TransferFee::LEN;
+
+    fn from_bytes(data: &[u8]) -> Self {
+        let fees = &data[Self::FEES_OFFSET..];
+        Self {
+            older_transfer_fee: TransferFee::from_bytes(&fees[..TransferFee::LEN]),
+            newer_transfer_fee: TransferFee::from_bytes(&fees[TransferFee::LEN..]),
+        }
+    }
+
+    /// Fee in effect during `epoch`
+    pub fn get_epoch_fee(&self, epoch: u64) -> &TransferFee {
+        if epoch >= self.newer_transfer_fee.epoch {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+}
+
+/// A token mint with the Token-2022 extensions that matter for swapping
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MintInfo {
+    pub address: Pubkey,
+    /// Program owning the mint (SPL Token or Token-2022)
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub transfer_fee_config: Option<TransferFeeConfig>,
+    /// Program invoked on every transfer, if one is set
+    pub transfer_hook_program: Option<Pubkey>,
+    pub non_transferable: bool,
+    /// Account allowed to move or burn any holder's tokens, if one is set
+    pub permanent_delegate: Option<Pubkey>,
+    /// New token accounts start frozen
+    pub default_frozen: bool,
+    /// Transfers are paused by the mint's pause authority
+    pub paused: bool,
+    /// Other extensions swaps do not know how to handle
+    pub unknown_extensions: Vec<u16>,
+}
+
+impl MintInfo {
+    /// Parse a mint account owned by `token_program`
+    pub fn from_account(address: Pubkey, token_program: Pubkey, data: &[u8]) -> SwapResult<Self> {
+        if token_program != spl_token::ID && token_program != *TOKEN_2022_PROGRAM {
+            return Err(SwapError::InvalidTokenMint(format!(
+                "{} is owned by {}, not a token program",
+                address, token_program
+            )));
+        }
+        if data.len() < MINT_BASE_LEN {
+            return Err(SwapError::InvalidTokenMint(format!("{} is not a mint account", address)));
+        }
+
+        let mut mint = Self {
+            address,
+            token_program,
+            decimals: data[44],
+            transfer_fee_config: None,
+            transfer_hook_program: None,
+            non_transferable: false,
+            permanent_delegate: None,
+            default_frozen: false,
+            paused: false,
+            unknown_extensions: Vec::new(),
+        };
+        if data.len() <= ACCOUNT_TYPE_OFFSET {
+            return Ok(mint);
+        }
+        if data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_MINT {
+            return Err(SwapError::InvalidTokenMint(format!("{} is not a mint account", address)));
+        }
+
+        // Type-length-value entries follow the account type
+        let mut offset = ACCOUNT_TYPE_OFFSET + 1;
+        while offset + 4 <= data.len() {
+            let extension_type = u16::from_le_bytes([data[offset], data[offset + 1]]);
+            let length = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            let value = data.get(offset + 4..offset + 4 + length).ok_or_else(|| {
+                SwapError::ParseError(format!("Truncated extension {} in mint {}", extension_type, address))
+            })?;
+            offset += 4 + length;
+
+            let optional_pubkey = |start: usize| {
+                value
+                    .get(start..start + 32)
+                    .map(|bytes| Pubkey::new_from_array(bytes.try_into().unwrap()))
+                    .filter(|pubkey| *pubkey != Pubkey::default())
+            };
+            match extension_type {
+                // Uninitialized space ends the list
+                0 => break,
+                EXTENSION_TRANSFER_FEE_CONFIG if length >= TransferFeeConfig::LEN => {
+                    mint.transfer_fee_config = Some(TransferFeeConfig::from_bytes(value));
+                }
+                EXTENSION_TRANSFER_HOOK => mint.transfer_hook_program = optional_pubkey(32),
+                EXTENSION_NON_TRANSFERABLE => mint.non_transferable = true,
+                EXTENSION_PERMANENT_DELEGATE => mint.permanent_delegate = optional_pubkey(0),
+                // Account state 2 is frozen
+                EXTENSION_DEFAULT_ACCOUNT_STATE => mint.default_frozen = value.first() == Some(&2),
+                // Pause authority (32) then the paused flag
+                EXTENSION_PAUSABLE => mint.paused = value.get(32).is_some_and(|paused| *paused != 0),
+                other if INFORMATIONAL_EXTENSIONS.contains(&other) => {}
+                other => mint.unknown_extensions.push(other),
+            }
+        }
+
+        Ok(mint)
+    }
+
+    /// Refuse mints whose extensions would make a swap fail or put the user's tokens at risk
+    pub fn ensure_supported(&self) -> SwapResult<()> {
+        let reason = if self.non_transferable {
+            "is non-transferable".to_string()
+        } else if let Some(program) = self.transfer_hook_program {
+            format!("has a transfer hook ({})", program)
+        } else if let Some(delegate) = self.permanent_delegate {
+            format!("has a permanent delegate ({})", delegate)
+        } else if self.default_frozen {
+            "freezes new token accounts".to_string()
+        } else if self.paused {
+            "is paused".to_string()
+        } else if !self.unknown_extensions.is_empty() {
+            format!("has unsupported extensions {:?}", self.unknown_extensions)
+        } else {
+            return Ok(());
+        };
+        Err(SwapError::UnsupportedMint(format!("{} {}", self.address, reason)))
+    }
+
+    /// Transfer fee in effect during `epoch`, if the mint charges one
+    pub fn transfer_fee(&self, epoch: u64) -> Option<TransferFee> {
+        self.transfer_fee_config
+            .map(|config| *config.get_epoch_fee(epoch))
+            .filter(|fee| fee.transfer_fee_basis_points > 0)
+    }
+}
+
+/// Mint accounts fetched once per process, with the current epoch for transfer fees
+pub struct MintCache {
+    rpc_client: Arc<RpcClient>,
+    mints: DashMap<Pubkey, Arc<MintInfo>>,
+    epoch: Mutex<Option<(u64, Instant)>>,
+}
+
+impl MintCache {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client,
+            mints: DashMap::new(),
+            epoch: Mutex::new(None),
+        }
+    }
+
+    /// Mint info for `mint`
+    pub async fn get(&self, mint: &Pubkey) -> SwapResult<Arc<MintInfo>> {
+        Ok(self.get_many(std::slice::from_ref(mint)).await?.remove(0))
+    }
+
+    /// Mint info for each of `mints`, fetching the uncached ones in one request
+    pub async fn get_many(&self, mints: &[Pubkey]) -> SwapResult<Vec<Arc<MintInfo>>> {
+        let missing: Vec<Pubkey> = mints.iter().filter(|mint| !self.mints.contains_key(mint)).copied().collect();
+        if !missing.is_empty() {
+            let accounts = self
+                .rpc_client
+                .get_multiple_accounts(&missing)
+                .await
+                .map_err(SwapError::from)?;
+            for (address, account) in missing.into_iter().zip(accounts) {
+                let account = account.ok_or_else(|| SwapError::TokenNotFound(address.to_string()))?;
+                let mint = MintInfo::from_account(address, account.owner, &account.data)?;
+                debug!("Loaded mint {} (program {})", address, mint.token_program);
+                self.mints.insert(address, Arc::new(mint));
+            }
+        }
+
+        Ok(mints.iter().map(|mint| self.mints.get(mint).unwrap().clone()).collect())
+    }
+
+    /// Current epoch, refreshed every minute
+    pub async fn current_epoch(&self) -> SwapResult<u64> {
+        if let Some((epoch, fetched_at)) = *self.epoch.lock().unwrap() {
+            if fetched_at.elapsed() < EPOCH_CACHE_TTL {
+                return Ok(epoch);
+            }
+        }
+        let epoch = self.rpc_client.get_epoch_info().await.map_err(SwapError::from)?.epoch;
+        *self.epoch.lock().unwrap() = Some((epoch, Instant::now()));
+        Ok(epoch)
+    }
+
+    /// Transfer fees charged when moving `token_in` and `token_out` right
+    /// now, refusing mints swaps cannot handle
+    pub async fn swap_transfer_fees(
+        &self,
+        token_in: &Pubkey,
+        token_out: &Pubkey,
+    ) -> SwapResult<(Option<TransferFee>, Option<TransferFee>)> {
+        let mints = self.get_many(&[*token_in, *token_out]).await?;
+        for mint in &mints {
+            mint.ensure_supported()?;
+        }
+        if mints.iter().all(|mint| mint.transfer_fee_config.is_none()) {
+            return Ok((None, None));
+        }
+        let epoch = self.current_epoch().await?;
+        Ok((mints[0].transfer_fee(epoch), mints[1].transfer_fee(epoch)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Token-2022 mint account with the given (type, value) extensions
+    fn mint_account(decimals: u8, extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut data = vec![0u8; ACCOUNT_TYPE_OFFSET + 1];
+        data[44] = decimals;
+        data[45] = 1;
+        data[ACCOUNT_TYPE_OFFSET] = ACCOUNT_TYPE_MINT;
+        for (extension_type, value) in extensions {
+            data.extend_from_slice(&extension_type.to_le_bytes());
+            data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            data.extend_from_slice(value);
+        }
+        data
+    }
+
+    fn transfer_fee_config(older: (u64, u64, u16), newer: (u64, u64, u16)) -> Vec<u8> {
+        let mut value = vec![0u8; TransferFeeConfig::FEES_OFFSET];
+        for (epoch, maximum_fee, basis_points) in [older, newer] {
+            value.extend_from_slice(&epoch.to_le_bytes());
+            value.extend_from_slice(&maximum_fee.to_le_bytes());
+            value.extend_from_slice(&basis_points.to_le_bytes());
+        }
+        value
+    }
+
+    #[test]
+    fn test_parse_mint_extensions() {
+        let address = Pubkey::new_unique();
+
+        // Classic mints carry no extensions
+        let mint = MintInfo::from_account(address, spl_token::ID, &mint_account(6, &[])[..MINT_BASE_LEN]).unwrap();
+        assert_eq!(mint.decimals, 6);
+        assert!(mint.transfer_fee_config.is_none());
+        assert!(mint.ensure_supported().is_ok());
+
+        let data = mint_account(
+            9,
+            &[
+                (EXTENSION_TRANSFER_FEE_CONFIG, transfer_fee_config((0, 5_000, 100), (600, 1_000, 250))),
+                (EXTENSION_METADATA_POINTER, vec![0u8; 64]),
+                (EXTENSION_CONFIDENTIAL_TRANSFER_MINT, vec![0u8; 65]),
+                (EXTENSION_CONFIDENTIAL_TRANSFER_FEE_CONFIG, vec![0u8; 129]),
+                (EXTENSION_PAUSABLE, vec![0u8; 33]),
+            ],
+        );
+        let mint = MintInfo::from_account(address, *TOKEN_2022_PROGRAM, &data).unwrap();
+        assert_eq!(mint.decimals, 9);
+        assert!(mint.ensure_supported().is_ok());
+        assert_eq!(mint.transfer_fee(599).unwrap().transfer_fee_basis_points, 100);
+        assert_eq!(mint.transfer_fee(600).unwrap().maximum_fee, 1_000);
+
+        // Hooks without a program are inert; set ones are refused
+        let mut hook = vec![0u8; 64];
+        let data = mint_account(6, &[(EXTENSION_TRANSFER_HOOK, hook.clone())]);
+        assert!(MintInfo::from_account(address, *TOKEN_2022_PROGRAM, &data).unwrap().ensure_supported().is_ok());
+        hook[32..].copy_from_slice(&Pubkey::new_unique().to_bytes());
+        let data = mint_account(6, &[(EXTENSION_TRANSFER_HOOK, hook)]);
+        let error = MintInfo::from_account(address, *TOKEN_2022_PROGRAM, &data).unwrap().ensure_supported();
+        assert!(matches!(error, Err(SwapError::UnsupportedMint(_))));
+
+        for extension in [
+            (EXTENSION_NON_TRANSFERABLE, vec![]),
+            (EXTENSION_PERMANENT_DELEGATE, Pubkey::new_unique().to_bytes().to_vec()),
+            (EXTENSION_DEFAULT_ACCOUNT_STATE, vec![2]),
+            (EXTENSION_PAUSABLE, [vec![0u8; 32], vec![1]].concat()),
+            (99, vec![0u8; 8]),
+        ] {
+            let data = mint_account(6, &[extension]);
+            let mint = MintInfo::from_account(address, *TOKEN_2022_PROGRAM, &data).unwrap();
+            assert!(matches!(mint.ensure_supported(), Err(SwapError::UnsupportedMint(_))));
+        }
+
+        // Not a mint, or not a token program
+        assert!(MintInfo::from_account(address, *TOKEN_2022_PROGRAM, &[0u8; 40]).is_err());
+        assert!(MintInfo::from_account(address, Pubkey::new_unique(), &data).is_err());
+    }
+
+    #[test]
+    fn test_transfer_fee_math() {
+        let fee = TransferFee { epoch: 0, maximum_fee: 5_000, transfer_fee_basis_points: 100 };
+        assert_eq!(fee.calculate_fee(0), 0);
+        assert_eq!(fee.calculate_fee(1), 1);
+        assert_eq!(fee.calculate_fee(10_050), 101);
+        assert_eq!(fee.calculate_fee(1_000_000), 5_000);
+
+        // The inverse delivers at least the requested amount after the fee
+        for post_fee_amount in [1, 99, 10_000, 494_999, 495_000, 10_000_000] {
+            let pre_fee_amount = fee.calculate_pre_fee_amount(post_fee_amount).unwrap();
+            assert!(pre_fee_amount - fee.calculate_fee(pre_fee_amount) >= post_fee_amount);
+            assert!(pre_fee_amount - 1 - fee.calculate_fee(pre_fee_amount - 1) < post_fee_amount);
+        }
+
+        let all = TransferFee { epoch: 0, maximum_fee: 7, transfer_fee_basis_points: MAX_FEE_BASIS_POINTS };
+        assert_eq!(all.calculate_pre_fee_amount(100).unwrap(), 107);
+    }
+}
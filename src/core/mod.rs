@@ -1,19 +1,29 @@
 pub mod config;
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod layouts;
+pub mod mint;
 pub mod token_metadata;
 pub mod token_metadata_async;
 pub mod types;
 pub mod serum_market;
 pub mod price_calculator;
+pub mod price_service;
+pub mod scoring;
+pub mod clmm_math;
+#[cfg(test)]
+pub mod fixtures;
 
 pub use config::Config;
 pub use constants::*;
 pub use error::{SwapError, SwapResult};
 pub use layouts::*;
+pub use mint::{MintCache, MintInfo, TransferFee};
 pub use token_metadata::{TokenMetadata, get_token_metadata_cached, get_token_decimals};
 pub use token_metadata_async::AsyncTokenMetadataFetcher;
 pub use types::*;
 pub use serum_market::{MarketState, is_placeholder_market};
-pub use price_calculator::OnchainPriceCalculator;
\ No newline at end of file
+pub use price_calculator::OnchainPriceCalculator;
+pub use price_service::PriceService;
+pub use scoring::{RiskFlag, ScoringOverrides, ScoringStrategy};
\ No newline at end of file
@@ -88,7 +88,7 @@ impl OnchainPriceCalculator {
     fn calculate_price_from_pool(token_mint: &Pubkey, pool: &PoolInfo) -> Option<f64> {
         match &pool.pool_state {
             PoolState::AMM { reserve_a, reserve_b, .. } |
-            PoolState::Standard { reserve_a, reserve_b } => {
+            PoolState::Standard { reserve_a, reserve_b, .. } => {
                 Self::calculate_price_from_reserves(
                     token_mint,
                     &pool.token_a,
@@ -111,11 +111,106 @@ impl OnchainPriceCalculator {
                 }
             }
             PoolState::CLMM { .. } => {
-                // CLMM pools have dynamic prices based on tick
-                // For now, use the pool's reported liquidity
-                None // TODO: Implement CLMM price calculation
+                let other = if pool.token_a.mint == *token_mint {
+                    &pool.token_b
+                } else {
+                    &pool.token_a
+                };
+                let stable_price = STABLE_COINS.get(&other.mint)?;
+                Self::price_in_other_token(token_mint, pool).map(|price| price * stable_price)
+            }
+        }
+    }
+
+    /// Spot price of token A in units of token B (UI amounts) at the pool's
+    /// current state
+    pub fn spot_price(pool: &PoolInfo) -> Option<f64> {
+        let scale = 10f64.powi(pool.token_a.decimals as i32 - pool.token_b.decimals as i32);
+        let price = match &pool.pool_state {
+            PoolState::AMM {
+                reserve_a,
+                reserve_b,
+                need_take_pnl_coin,
+                need_take_pnl_pc,
+                ..
+            } => {
+                let coin = reserve_a.saturating_sub(*need_take_pnl_coin);
+                let pc = reserve_b.saturating_sub(*need_take_pnl_pc);
+                if coin == 0 || pc == 0 {
+                    return None;
+                }
+                pc as f64 / coin as f64 * scale
+            }
+            PoolState::Standard { reserve_a, reserve_b, .. } => {
+                if *reserve_a == 0 || *reserve_b == 0 {
+                    return None;
+                }
+                *reserve_b as f64 / *reserve_a as f64 * scale
+            }
+            // Stable pools pair pegged assets and trade close to 1:1 whatever the balance
+            PoolState::Stable { .. } => 1.0,
+            PoolState::CLMM { sqrt_price_x64, .. } => {
+                let sqrt_price = *sqrt_price_x64 as f64 / 2f64.powi(64);
+                sqrt_price * sqrt_price * scale
+            }
+        };
+        (price.is_finite() && price > 0.0).then_some(price)
+    }
+
+    /// Price of `token_mint` in units of the pool's other token
+    fn price_in_other_token(token_mint: &Pubkey, pool: &PoolInfo) -> Option<f64> {
+        let spot = Self::spot_price(pool)?;
+        if pool.token_a.mint == *token_mint {
+            Some(spot)
+        } else if pool.token_b.mint == *token_mint {
+            Some(1.0 / spot)
+        } else {
+            None
+        }
+    }
+
+    /// USD price of `token_mint` from pools pairing it with any token of
+    /// known price, weighted by pool liquidity
+    pub fn price_from_pools(
+        token_mint: &Pubkey,
+        pools: &[PoolInfo],
+        known_prices: &HashMap<Pubkey, f64>,
+    ) -> Option<f64> {
+        if let Some(&price) = STABLE_COINS.get(token_mint) {
+            return Some(price);
+        }
+
+        let mut total_liquidity = 0.0;
+        let mut weighted_price = 0.0;
+
+        for pool in pools {
+            let other = if pool.token_a.mint == *token_mint {
+                pool.token_b.mint
+            } else if pool.token_b.mint == *token_mint {
+                pool.token_a.mint
+            } else {
+                continue;
+            };
+            let Some(other_price) = known_prices
+                .get(&other)
+                .or_else(|| STABLE_COINS.get(&other))
+            else {
+                continue;
+            };
+
+            if let Some(price) = Self::price_in_other_token(token_mint, pool) {
+                let liquidity = pool.liquidity_usd.max(1.0);
+                weighted_price += price * other_price * liquidity;
+                total_liquidity += liquidity;
             }
         }
+
+        (total_liquidity > 0.0).then(|| weighted_price / total_liquidity)
+    }
+
+    /// Pegged USD price of a known stablecoin
+    pub fn stablecoin_price(token_mint: &Pubkey) -> Option<f64> {
+        STABLE_COINS.get(token_mint).copied()
     }
     
     /// Calculate price from reserves
@@ -206,7 +301,7 @@ impl OnchainPriceCalculator {
         
         match &pool.pool_state {
             PoolState::AMM { reserve_a, reserve_b, .. } |
-            PoolState::Standard { reserve_a, reserve_b } => {
+            PoolState::Standard { reserve_a, reserve_b, .. } => {
                 let amount_a = *reserve_a as f64 / 10f64.powi(pool.token_a.decimals as i32);
                 let amount_b = *reserve_b as f64 / 10f64.powi(pool.token_b.decimals as i32);
                 
@@ -234,7 +329,10 @@ impl OnchainPriceCalculator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::TokenInfo;
+    use crate::core::{
+        fixtures::{pool, token},
+        TokenInfo,
+    };
     
     #[test]
     fn test_stablecoin_price() {
@@ -270,4 +368,54 @@ mod tests {
         
         assert_eq!(price, Some(0.1));
     }
+
+    #[test]
+    fn test_clmm_spot_price() {
+        let usdc = Pubkey::from_str("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v").unwrap();
+        let sol = Pubkey::new_unique();
+        // 150 USDC per SOL: raw price 150e6 / 1e9 = 0.15
+        let sqrt_price_x64 = (0.15f64.sqrt() * 2f64.powi(64)) as u128;
+        let clmm = pool(
+            token(sol, 9),
+            token(usdc, 6),
+            PoolState::CLMM {
+                current_tick: 0,
+                tick_spacing: 64,
+                liquidity: 1,
+                sqrt_price_x64,
+                fee_tier: 500,
+            },
+        );
+
+        let spot = OnchainPriceCalculator::spot_price(&clmm).unwrap();
+        assert!((spot - 150.0).abs() < 1e-6);
+
+        let price = OnchainPriceCalculator::calculate_token_price(&sol, &[clmm]).unwrap();
+        assert!((price - 150.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_price_from_pools_through_known_token() {
+        let (sol, meme) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // 1 SOL = 2,000,000 MEME (6 decimals)
+        let meme_sol = pool(
+            token(meme, 6),
+            token(sol, 9),
+            PoolState::Standard {
+                reserve_a: 2_000_000_000_000,
+                reserve_b: 1_000_000_000,
+                trade_fee_rate: 2_500,
+            },
+        );
+        let known = HashMap::from([(sol, 150.0)]);
+
+        let price = OnchainPriceCalculator::price_from_pools(&meme, std::slice::from_ref(&meme_sol), &known).unwrap();
+        assert!((price - 0.000075).abs() < 1e-12);
+
+        // Nothing known about the other side
+        assert_eq!(
+            OnchainPriceCalculator::price_from_pools(&meme, &[meme_sol], &HashMap::new()),
+            None
+        );
+    }
 }
\ No newline at end of file
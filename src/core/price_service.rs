@@ -0,0 +1,198 @@
+use crate::core::{OnchainPriceCalculator, PoolInfo};
+use dashmap::DashMap;
+use log::debug;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Shared USD price cache used to value pool liquidity consistently across
+/// pool types
+///
+/// Prices are learned from pool reserves with [`OnchainPriceCalculator`]:
+/// stablecoins are pegged, other tokens are priced through pools pairing
+/// them with an already priced token (e.g. token -> SOL -> USDC).
+pub struct PriceService {
+    prices: DashMap<Pubkey, (f64, Instant)>,
+    /// Vault balances each pool's liquidity was last valued from
+    balances: DashMap<Pubkey, (u64, u64)>,
+    ttl: Duration,
+}
+
+impl PriceService {
+    pub fn new(ttl_secs: u64) -> Self {
+        Self {
+            prices: DashMap::new(),
+            balances: DashMap::new(),
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+
+    /// USD price of `mint`, if it is a stablecoin or was learned recently
+    pub fn price(&self, mint: &Pubkey) -> Option<f64> {
+        if let Some(price) = OnchainPriceCalculator::stablecoin_price(mint) {
+            return Some(price);
+        }
+
+        let entry = self.prices.get(mint)?;
+        let (price, learned_at) = *entry;
+        (learned_at.elapsed() < self.ttl).then_some(price)
+    }
+
+    /// Record the USD price of `mint`
+    pub fn set_price(&self, mint: Pubkey, price: f64) {
+        self.prices.insert(mint, (price, Instant::now()));
+    }
+
+    /// Learn prices for the unpriced tokens of `pools` from pools pairing
+    /// them with priced tokens, following chains of such pools
+    pub fn learn_from_pools(&self, pools: &[PoolInfo]) {
+        let mut tokens: Vec<Pubkey> = Vec::new();
+        for pool in pools {
+            for mint in [pool.token_a.mint, pool.token_b.mint] {
+                if !tokens.contains(&mint) {
+                    tokens.push(mint);
+                }
+            }
+        }
+
+        loop {
+            let known: HashMap<Pubkey, f64> = tokens
+                .iter()
+                .filter_map(|mint| self.price(mint).map(|price| (*mint, price)))
+                .collect();
+
+            let mut learned = false;
+            for mint in tokens.iter().filter(|mint| !known.contains_key(*mint)) {
+                if let Some(price) = OnchainPriceCalculator::price_from_pools(mint, pools, &known) {
+                    debug!("Learned price of {}: ${:.6}", mint, price);
+                    self.set_price(*mint, price);
+                    learned = true;
+                }
+            }
+
+            if !learned {
+                break;
+            }
+        }
+    }
+
+    /// Whether either token of `pool` has a known price
+    pub fn can_price(&self, pool: &PoolInfo) -> bool {
+        self.price(&pool.token_a.mint).is_some() || self.price(&pool.token_b.mint).is_some()
+    }
+
+    /// USD value of `amount_a` of the pool's token A plus `amount_b` of its
+    /// token B, pricing a side of unknown price through the pool's spot price
+    ///
+    /// Returns 0 when neither token can be priced.
+    pub fn liquidity_usd(&self, pool: &PoolInfo, amount_a: u64, amount_b: u64) -> f64 {
+        let spot = OnchainPriceCalculator::spot_price(pool);
+        let price_a = self
+            .price(&pool.token_a.mint)
+            .or_else(|| Some(spot? * self.price(&pool.token_b.mint)?));
+        let price_b = self
+            .price(&pool.token_b.mint)
+            .or_else(|| Some(self.price(&pool.token_a.mint)? / spot?));
+
+        let (Some(price_a), Some(price_b)) = (price_a, price_b) else {
+            return 0.0;
+        };
+
+        let ui_a = amount_a as f64 / 10f64.powi(pool.token_a.decimals as i32);
+        let ui_b = amount_b as f64 / 10f64.powi(pool.token_b.decimals as i32);
+        ui_a * price_a + ui_b * price_b
+    }
+
+    /// USD liquidity of `pool` holding `balance_a` and `balance_b`,
+    /// remembering the balances so [`Self::revalue`] can reprice the pool
+    pub fn pool_liquidity_usd(&self, pool: &PoolInfo, balance_a: u64, balance_b: u64) -> f64 {
+        self.balances.insert(pool.address, (balance_a, balance_b));
+        self.liquidity_usd(pool, balance_a, balance_b)
+    }
+
+    /// Revalue the liquidity of `pools` at the current prices, for pools
+    /// parsed before their tokens could be priced
+    pub fn revalue(&self, pools: &mut [PoolInfo]) {
+        for pool in pools {
+            if let Some(balances) = self.balances.get(&pool.address).map(|entry| *entry) {
+                pool.liquidity_usd = self.liquidity_usd(pool, balances.0, balances.1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        fixtures::{standard_pool as pool, token},
+        USDC_MINT,
+    };
+    use std::str::FromStr;
+
+    #[test]
+    fn test_learns_prices_through_hops() {
+        let usdc = Pubkey::from_str(USDC_MINT).unwrap();
+        let (sol, meme) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let service = PriceService::new(60);
+
+        // 1000 SOL / 150,000 USDC and 1 SOL = 2,000,000 MEME
+        let sol_usdc = pool(token(sol, 9), token(usdc, 6), 1_000_000_000_000, 150_000_000_000);
+        let meme_sol = pool(token(meme, 6), token(sol, 9), 2_000_000_000_000, 1_000_000_000);
+        service.learn_from_pools(&[meme_sol.clone(), sol_usdc]);
+
+        assert!((service.price(&sol).unwrap() - 150.0).abs() < 1e-9);
+        assert!((service.price(&meme).unwrap() - 0.000075).abs() < 1e-12);
+
+        // 2,000,000 MEME + 1 SOL is worth $300
+        let liquidity = service.liquidity_usd(&meme_sol, 2_000_000_000_000, 1_000_000_000);
+        assert!((liquidity - 300.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_liquidity_with_one_priced_side() {
+        let usdc = Pubkey::from_str(USDC_MINT).unwrap();
+        let unknown = Pubkey::new_unique();
+        let service = PriceService::new(60);
+
+        // 500 units against 1000 USDC: the unknown side is worth 1000 USDC too
+        let half_priced = pool(token(unknown, 6), token(usdc, 6), 500_000_000, 1_000_000_000);
+        assert!(service.can_price(&half_priced));
+        assert!((service.liquidity_usd(&half_priced, 500_000_000, 1_000_000_000) - 2000.0).abs() < 1e-6);
+
+        // Neither side priced
+        let other = Pubkey::new_unique();
+        let unpriced = pool(token(unknown, 6), token(other, 6), 1, 1);
+        assert!(!service.can_price(&unpriced));
+        assert_eq!(service.liquidity_usd(&unpriced, 1, 1), 0.0);
+    }
+
+    #[test]
+    fn test_revalue_after_learning_prices() {
+        let usdc = Pubkey::from_str(USDC_MINT).unwrap();
+        let (sol, meme) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let service = PriceService::new(60);
+
+        // Parsed before SOL had a price
+        let mut meme_sol = pool(token(meme, 6), token(sol, 9), 2_000_000_000_000, 1_000_000_000);
+        meme_sol.liquidity_usd = service.pool_liquidity_usd(&meme_sol, 2_000_000_000_000, 1_000_000_000);
+        assert_eq!(meme_sol.liquidity_usd, 0.0);
+
+        service.learn_from_pools(&[pool(token(sol, 9), token(usdc, 6), 1_000_000_000_000, 150_000_000_000)]);
+        let mut pools = vec![meme_sol];
+        service.revalue(&mut pools);
+        assert!((pools[0].liquidity_usd - 300.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_prices_expire() {
+        let mint = Pubkey::new_unique();
+        let service = PriceService::new(0);
+        service.set_price(mint, 1.5);
+        assert_eq!(service.price(&mint), None);
+
+        let service = PriceService::new(60);
+        service.set_price(mint, 1.5);
+        assert_eq!(service.price(&mint), Some(1.5));
+    }
+}
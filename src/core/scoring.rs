@@ -0,0 +1,403 @@
+use crate::core::{PoolInfo, PoolState, SwapError, SwapResult};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+/// Names of the built-in scoring presets
+pub const SCORING_PRESETS: [&str; 3] = ["balanced", "deepest", "cheapest-fee"];
+
+/// Score multipliers by pool type
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TypeBonuses {
+    pub amm: f64,
+    pub stable: f64,
+    /// Stable pools whose tokens are both stablecoins
+    pub stable_pair: f64,
+    pub clmm: f64,
+    pub standard: f64,
+}
+
+/// Conditions that make a pool riskier to route through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RiskFlag {
+    /// Opened for trading more recently than the strategy's minimum age
+    NewPool,
+    /// No swaps seen in the last 24 hours
+    NoRecentVolume,
+    /// Liquidity could not be valued in USD
+    Unpriced,
+}
+
+/// How pools are ranked in discovery and how near-equal quotes are decided
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoringStrategy {
+    pub name: String,
+    pub liquidity_weight: f64,
+    pub volume_weight: f64,
+    pub depth_weight: f64,
+    /// Price impact the depth metric is measured at (0.01 = 1%)
+    pub depth_impact: f64,
+    pub type_bonuses: TypeBonuses,
+    /// Share of the score lost per 1% of pool fee
+    pub fee_penalty: f64,
+    /// Share of the score lost per risk flag
+    pub risk_penalty: f64,
+    /// Pools open for less than this are flagged as new
+    pub min_pool_age_secs: u64,
+    /// Quotes within this many basis points of the best one are ranked by
+    /// pool score instead; 0 lets pool score decide exact ties only, so a
+    /// better-paying quote always wins unless a tolerance is opted into
+    pub tie_tolerance_bps: u16,
+}
+
+impl ScoringStrategy {
+    /// Weigh liquidity, volume and depth together, favouring stable and
+    /// CLMM pools slightly
+    pub fn balanced() -> Self {
+        Self {
+            name: "balanced".to_string(),
+            liquidity_weight: 0.5,
+            volume_weight: 0.3,
+            depth_weight: 0.2,
+            depth_impact: 0.01,
+            type_bonuses: TypeBonuses {
+                amm: 1.0,
+                stable: 1.2,
+                stable_pair: 1.5,
+                clmm: 1.1,
+                standard: 0.9,
+            },
+            fee_penalty: 0.1,
+            risk_penalty: 0.2,
+            min_pool_age_secs: 86_400,
+            tie_tolerance_bps: 0,
+        }
+    }
+
+    /// Prefer the pools that absorb the largest trades
+    pub fn deepest() -> Self {
+        Self {
+            name: "deepest".to_string(),
+            liquidity_weight: 0.3,
+            volume_weight: 0.1,
+            depth_weight: 0.6,
+            type_bonuses: TypeBonuses {
+                amm: 1.0,
+                stable: 1.0,
+                stable_pair: 1.2,
+                clmm: 1.0,
+                standard: 1.0,
+            },
+            fee_penalty: 0.05,
+            ..Self::balanced()
+        }
+    }
+
+    /// Prefer low-fee pools among those with usable liquidity
+    pub fn cheapest_fee() -> Self {
+        Self {
+            name: "cheapest-fee".to_string(),
+            liquidity_weight: 0.4,
+            volume_weight: 0.2,
+            depth_weight: 0.4,
+            type_bonuses: TypeBonuses {
+                amm: 1.0,
+                stable: 1.0,
+                stable_pair: 1.0,
+                clmm: 1.0,
+                standard: 1.0,
+            },
+            fee_penalty: 0.5,
+            ..Self::balanced()
+        }
+    }
+
+    /// Built-in preset by name
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "balanced" => Some(Self::balanced()),
+            "deepest" => Some(Self::deepest()),
+            "cheapest-fee" => Some(Self::cheapest_fee()),
+            _ => None,
+        }
+    }
+
+    /// Score multiplier for the pool's trade fee
+    pub fn fee_multiplier(&self, pool: &PoolInfo) -> f64 {
+        (1.0 - self.fee_penalty * pool.fee_rate * 100.0).max(0.0)
+    }
+
+    /// Score multiplier for the pool's risk flags
+    pub fn risk_multiplier(&self, flags: &[RiskFlag]) -> f64 {
+        (1.0 - self.risk_penalty).max(0.0).powi(flags.len() as i32)
+    }
+
+    /// Check that weights, bonuses and penalties are usable
+    pub fn validate(&self) -> SwapResult<()> {
+        let bonuses = &self.type_bonuses;
+        let non_negative = [
+            ("liquidity weight", self.liquidity_weight),
+            ("volume weight", self.volume_weight),
+            ("depth weight", self.depth_weight),
+            ("fee penalty", self.fee_penalty),
+            ("AMM bonus", bonuses.amm),
+            ("Stable bonus", bonuses.stable),
+            ("stable pair bonus", bonuses.stable_pair),
+            ("CLMM bonus", bonuses.clmm),
+            ("Standard bonus", bonuses.standard),
+        ];
+        for (name, value) in non_negative {
+            if !value.is_finite() || value < 0.0 {
+                return Err(SwapError::ConfigError(format!("Scoring {} must be 0 or more, got {}", name, value)));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.risk_penalty) {
+            return Err(SwapError::ConfigError(format!(
+                "Scoring risk penalty must be between 0 and 1, got {}",
+                self.risk_penalty
+            )));
+        }
+        if !(self.depth_impact > 0.0 && self.depth_impact < 1.0) {
+            return Err(SwapError::ConfigError(format!(
+                "Scoring depth impact must be between 0 and 1, got {}",
+                self.depth_impact
+            )));
+        }
+        if self.tie_tolerance_bps > 10_000 {
+            return Err(SwapError::ConfigError(format!(
+                "Scoring tie tolerance cannot exceed 10000 bps, got {}",
+                self.tie_tolerance_bps
+            )));
+        }
+        Ok(())
+    }
+
+    /// Risk flags raised by `pool` at unix time `now`
+    pub fn risk_flags(&self, pool: &PoolInfo, now: u64) -> Vec<RiskFlag> {
+        let mut flags = Vec::new();
+        if pool.open_time > 0 && now.saturating_sub(pool.open_time) < self.min_pool_age_secs {
+            flags.push(RiskFlag::NewPool);
+        }
+        if pool.volume_24h_usd <= 0.0 {
+            flags.push(RiskFlag::NoRecentVolume);
+        }
+        if pool.liquidity_usd <= 0.0 {
+            flags.push(RiskFlag::Unpriced);
+        }
+        flags
+    }
+}
+
+impl Default for ScoringStrategy {
+    fn default() -> Self {
+        Self::balanced()
+    }
+}
+
+impl FromStr for ScoringStrategy {
+    type Err = SwapError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::preset(name.trim()).ok_or_else(|| {
+            SwapError::ConfigError(format!(
+                "Unknown scoring strategy '{}' (expected one of: {})",
+                name,
+                SCORING_PRESETS.join(", ")
+            ))
+        })
+    }
+}
+
+impl fmt::Display for ScoringStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// Individual strategy fields set on top of a preset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoringOverrides {
+    pub liquidity_weight: Option<f64>,
+    pub volume_weight: Option<f64>,
+    pub depth_weight: Option<f64>,
+    pub depth_impact: Option<f64>,
+    pub fee_penalty: Option<f64>,
+    pub risk_penalty: Option<f64>,
+    pub amm_bonus: Option<f64>,
+    pub stable_bonus: Option<f64>,
+    pub stable_pair_bonus: Option<f64>,
+    pub clmm_bonus: Option<f64>,
+    pub standard_bonus: Option<f64>,
+    pub min_pool_age_secs: Option<u64>,
+    pub tie_tolerance_bps: Option<u16>,
+}
+
+impl ScoringOverrides {
+    /// Overrides from the `SCORING_*` environment variables
+    pub fn from_env() -> SwapResult<Self> {
+        Ok(Self {
+            liquidity_weight: env_override("SCORING_LIQUIDITY_WEIGHT")?,
+            volume_weight: env_override("SCORING_VOLUME_WEIGHT")?,
+            depth_weight: env_override("SCORING_DEPTH_WEIGHT")?,
+            depth_impact: env_override("SCORING_DEPTH_IMPACT")?,
+            fee_penalty: env_override("SCORING_FEE_PENALTY")?,
+            risk_penalty: env_override("SCORING_RISK_PENALTY")?,
+            amm_bonus: env_override("SCORING_AMM_BONUS")?,
+            stable_bonus: env_override("SCORING_STABLE_BONUS")?,
+            stable_pair_bonus: env_override("SCORING_STABLE_PAIR_BONUS")?,
+            clmm_bonus: env_override("SCORING_CLMM_BONUS")?,
+            standard_bonus: env_override("SCORING_STANDARD_BONUS")?,
+            min_pool_age_secs: env_override("SCORING_MIN_POOL_AGE_SECS")?,
+            tie_tolerance_bps: env_override("SCORING_TIE_TOLERANCE_BPS")?,
+        })
+    }
+
+    /// Set the overridden fields of `strategy`
+    pub fn apply(&self, strategy: &mut ScoringStrategy) {
+        let set = |field: &mut f64, value: Option<f64>| {
+            if let Some(value) = value {
+                *field = value;
+            }
+        };
+        set(&mut strategy.liquidity_weight, self.liquidity_weight);
+        set(&mut strategy.volume_weight, self.volume_weight);
+        set(&mut strategy.depth_weight, self.depth_weight);
+        set(&mut strategy.depth_impact, self.depth_impact);
+        set(&mut strategy.fee_penalty, self.fee_penalty);
+        set(&mut strategy.risk_penalty, self.risk_penalty);
+        set(&mut strategy.type_bonuses.amm, self.amm_bonus);
+        set(&mut strategy.type_bonuses.stable, self.stable_bonus);
+        set(&mut strategy.type_bonuses.stable_pair, self.stable_pair_bonus);
+        set(&mut strategy.type_bonuses.clmm, self.clmm_bonus);
+        set(&mut strategy.type_bonuses.standard, self.standard_bonus);
+        if let Some(min_pool_age_secs) = self.min_pool_age_secs {
+            strategy.min_pool_age_secs = min_pool_age_secs;
+        }
+        if let Some(tie_tolerance_bps) = self.tie_tolerance_bps {
+            strategy.tie_tolerance_bps = tie_tolerance_bps;
+        }
+    }
+}
+
+/// Value of environment variable `name`, if set
+fn env_override<T: FromStr>(name: &str) -> SwapResult<Option<T>>
+where
+    T::Err: fmt::Display,
+{
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| SwapError::ConfigError(format!("Invalid {} '{}': {}", name, value, e))),
+        Err(_) => Ok(None),
+    }
+}
+
+/// USD size of a trade that moves the pool price by `impact` (0.01 = 1%)
+///
+/// Reserves are treated as constant product; Stable pools scale that by
+/// their amplification, and CLMM liquidity is treated as full range.
+pub fn depth_usd(pool: &PoolInfo, impact: f64) -> f64 {
+    if pool.liquidity_usd <= 0.0 || !(0.0..1.0).contains(&impact) {
+        return 0.0;
+    }
+
+    // Selling x * (1/sqrt(1 - p) - 1) into a constant product pool moves
+    // its price by p; one side holds half the liquidity
+    let side_usd = pool.liquidity_usd / 2.0;
+    let depth = side_usd * (1.0 / (1.0 - impact).sqrt() - 1.0);
+
+    match &pool.pool_state {
+        PoolState::Stable { amp_factor, .. } => (depth * (*amp_factor).max(1) as f64).min(side_usd),
+        _ => depth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{PoolType, TokenInfo};
+
+    #[test]
+    fn test_presets_parse_by_name() {
+        for name in SCORING_PRESETS {
+            let strategy: ScoringStrategy = name.parse().unwrap();
+            assert_eq!(strategy.name, name);
+        }
+        assert!("fastest".parse::<ScoringStrategy>().is_err());
+        assert_eq!(ScoringStrategy::default(), ScoringStrategy::balanced());
+    }
+
+    #[test]
+    fn test_multipliers() {
+        let strategy = ScoringStrategy::cheapest_fee();
+        let mut pool = PoolInfo {
+            pool_type: PoolType::Standard,
+            address: Default::default(),
+            token_a: TokenInfo {
+                mint: Default::default(),
+                symbol: "SOL".to_string(),
+                decimals: 9,
+                name: "Solana".to_string(),
+            },
+            token_b: TokenInfo {
+                mint: Default::default(),
+                symbol: "USDC".to_string(),
+                decimals: 6,
+                name: "USD Coin".to_string(),
+            },
+            liquidity_usd: 1_000_000.0,
+            volume_24h_usd: 0.0,
+            fee_rate: 0.0025,
+            program_id: Default::default(),
+            context_slot: 0,
+            open_time: 1_000_000,
+            pool_state: PoolState::Standard { reserve_a: 1, reserve_b: 1, trade_fee_rate: 2_500 },
+        };
+
+        // 0.25% fee at half a score per 1%
+        assert!((strategy.fee_multiplier(&pool) - 0.875).abs() < 1e-12);
+
+        // Opened an hour ago and idle
+        let flags = strategy.risk_flags(&pool, 1_003_600);
+        assert_eq!(flags, vec![RiskFlag::NewPool, RiskFlag::NoRecentVolume]);
+        assert!((strategy.risk_multiplier(&flags) - 0.64).abs() < 1e-12);
+
+        // $500k a side moves 1% after ~$2.5k
+        assert!((depth_usd(&pool, 0.01) - 2_519.0).abs() < 1.0);
+        pool.pool_state = PoolState::Stable {
+            reserves: vec![1, 1],
+            amp_factor: 100,
+            swap_fee_numerator: 4,
+            swap_fee_denominator: 10_000,
+        };
+        assert!((depth_usd(&pool, 0.01) - 251_900.0).abs() < 100.0);
+    }
+
+    #[test]
+    fn test_overrides_apply_on_top_of_preset() {
+        let mut strategy = ScoringStrategy::deepest();
+        let overrides = ScoringOverrides {
+            volume_weight: Some(0.0),
+            clmm_bonus: Some(1.3),
+            tie_tolerance_bps: Some(5),
+            ..Default::default()
+        };
+        overrides.apply(&mut strategy);
+
+        assert_eq!(strategy.volume_weight, 0.0);
+        assert_eq!(strategy.type_bonuses.clmm, 1.3);
+        assert_eq!(strategy.tie_tolerance_bps, 5);
+        // Everything else stays the preset's
+        assert_eq!(strategy.depth_weight, ScoringStrategy::deepest().depth_weight);
+        assert_eq!(strategy.type_bonuses.amm, ScoringStrategy::deepest().type_bonuses.amm);
+        assert!(strategy.validate().is_ok());
+
+        ScoringOverrides { risk_penalty: Some(1.5), ..Default::default() }.apply(&mut strategy);
+        assert!(strategy.validate().is_err());
+        ScoringOverrides { risk_penalty: Some(0.2), depth_impact: Some(0.0), ..Default::default() }.apply(&mut strategy);
+        assert!(strategy.validate().is_err());
+    }
+}
@@ -31,20 +31,26 @@ pub struct MarketState {
 }
 
 impl MarketState {
+    /// Market account size: 5 bytes of head padding, the state, 7 bytes of tail padding
+    pub const LEN: usize = 388;
+    /// Serialized state without the account padding
+    const STATE_LEN: usize = 376;
+
     /// Parse market state from raw account data
     pub fn parse(data: &[u8]) -> SwapResult<Self> {
-        if data.len() < size_of::<MarketState>() + 5 {
+        if data.len() < Self::STATE_LEN + 5 {
             return Err(SwapError::Other(format!(
                 "Market account data too small: {} bytes, expected at least {}",
                 data.len(),
-                size_of::<MarketState>() + 5
+                Self::STATE_LEN + 5
             )));
         }
 
-        // Skip first 5 bytes (padding)
-        let market_data = &data[5..5 + size_of::<MarketState>()];
-        
-        // Safety: We've checked the size above
+        // Skip first 5 bytes (padding); the struct's own padding stays zeroed
+        let mut market_data = [0u8; size_of::<MarketState>()];
+        market_data[..Self::STATE_LEN].copy_from_slice(&data[5..5 + Self::STATE_LEN]);
+
+        // Safety: the buffer is exactly the size of the plain-data struct
         let market = unsafe { 
             std::ptr::read_unaligned(market_data.as_ptr() as *const MarketState)
         };
@@ -103,20 +109,18 @@ impl MarketState {
     }
 
     /// Get vault signer PDA
+    /// The market program seeds it with the market and its u64 nonce
     pub fn vault_signer(&self, market_address: &Pubkey, dex_program: &Pubkey) -> SwapResult<Pubkey> {
-        let (pda, nonce) = Pubkey::find_program_address(
-            &[&market_address.to_bytes()],
+        Pubkey::create_program_address(
+            &[&market_address.to_bytes(), &self.vault_signer_nonce.to_le_bytes()],
             dex_program,
-        );
-        
-        if nonce != self.vault_signer_nonce as u8 {
-            return Err(SwapError::Other(format!(
-                "Vault signer nonce mismatch: expected {}, got {}",
-                self.vault_signer_nonce, nonce
-            )));
-        }
-        
-        Ok(pda)
+        )
+        .map_err(|e| {
+            SwapError::Other(format!(
+                "Invalid vault signer nonce {} for market {}: {}",
+                self.vault_signer_nonce, market_address, e
+            ))
+        })
     }
 }
 
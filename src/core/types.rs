@@ -1,6 +1,8 @@
+use crate::core::scoring::RiskFlag;
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PoolType {
@@ -10,6 +12,23 @@ pub enum PoolType {
     Standard,
 }
 
+impl FromStr for PoolType {
+    type Err = crate::core::SwapError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "amm" => Ok(PoolType::AMM),
+            "stable" => Ok(PoolType::Stable),
+            "clmm" => Ok(PoolType::CLMM),
+            "standard" | "cp" => Ok(PoolType::Standard),
+            _ => Err(crate::core::SwapError::InvalidInput(format!(
+                "Invalid pool type '{}' (expected amm, stable, clmm or standard)",
+                value
+            ))),
+        }
+    }
+}
+
 impl fmt::Display for PoolType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -31,33 +50,58 @@ pub struct PoolInfo {
     pub volume_24h_usd: f64,
     pub fee_rate: f64,
     pub program_id: Pubkey,
+    /// Slot the pool state was read at or after (0 if unknown)
+    #[serde(default)]
+    pub context_slot: u64,
+    /// Unix time the pool opened for trading (0 if unknown)
+    #[serde(default)]
+    pub open_time: u64,
     pub pool_state: PoolState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PoolState {
     AMM {
+        /// Coin vault balance (token A)
         reserve_a: u64,
+        /// PC vault balance (token B)
         reserve_b: u64,
         nonce: u8,
+        /// Coin owed to the protocol, excluded from swappable reserves
+        need_take_pnl_coin: u64,
+        /// PC owed to the protocol, excluded from swappable reserves
+        need_take_pnl_pc: u64,
+        swap_fee_numerator: u64,
+        swap_fee_denominator: u64,
     },
     Stable {
         reserves: Vec<u64>,
+        /// Only used to estimate depth for scoring; the program prices
+        /// swaps from the model data account
         amp_factor: u64,
+        swap_fee_numerator: u64,
+        swap_fee_denominator: u64,
     },
     CLMM {
         current_tick: i32,
         tick_spacing: u16,
         liquidity: u128,
+        /// Q64.64 square root of the price (token B per token A)
+        sqrt_price_x64: u128,
+        /// Trade fee rate in hundredths of a basis point (500 = 0.05%)
         fee_tier: u32,
     },
     Standard {
+        /// Token 0 vault balance less accrued protocol and fund fees
         reserve_a: u64,
+        /// Token 1 vault balance less accrued protocol and fund fees
         reserve_b: u64,
+        /// Trade fee rate from the pool's AmmConfig (denominator 1_000_000)
+        trade_fee_rate: u64,
     },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TokenInfo {
     pub mint: Pubkey,
     pub symbol: String,
@@ -65,25 +109,387 @@ pub struct TokenInfo {
     pub name: String,
 }
 
+/// Which side of a swap the requested amount fixes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SwapMode {
+    /// Spend exactly `amount` of the input token (base in)
+    #[default]
+    ExactIn,
+    /// Receive exactly `amount` of the output token (base out)
+    ExactOut,
+}
+
+impl fmt::Display for SwapMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SwapMode::ExactIn => write!(f, "Exact In"),
+            SwapMode::ExactOut => write!(f, "Exact Out"),
+        }
+    }
+}
+
+/// How the compute unit price of a swap transaction is chosen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PriorityFee {
+    /// A percentile of the fees recently paid to write the swap's accounts
+    #[default]
+    Auto,
+    /// Fixed price in micro-lamports per compute unit
+    Fixed(u64),
+}
+
+impl FromStr for PriorityFee {
+    type Err = crate::core::SwapError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim() {
+            "auto" => Ok(PriorityFee::Auto),
+            price => price.parse().map(PriorityFee::Fixed).map_err(|_| {
+                crate::core::SwapError::ConfigError(format!(
+                    "Invalid priority fee '{}' (expected 'auto' or micro-lamports per compute unit)",
+                    value
+                ))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for PriorityFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityFee::Auto => write!(f, "auto"),
+            PriorityFee::Fixed(price) => write!(f, "{} micro-lamports/CU", price),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuoteRequest {
     pub token_in: Pubkey,
     pub token_out: Pubkey,
-    pub amount_in: u64,
+    /// Input amount for exact-in, desired output amount for exact-out
+    pub amount: u64,
+    #[serde(default)]
+    pub swap_mode: SwapMode,
     pub slippage_bps: u16,
 }
 
+impl QuoteRequest {
+    /// Request spending exactly `amount_in` of `token_in`
+    pub fn exact_in(token_in: Pubkey, token_out: Pubkey, amount_in: u64, slippage_bps: u16) -> Self {
+        Self {
+            token_in,
+            token_out,
+            amount: amount_in,
+            swap_mode: SwapMode::ExactIn,
+            slippage_bps,
+        }
+    }
+
+    /// Request receiving exactly `amount_out` of `token_out`
+    pub fn exact_out(token_in: Pubkey, token_out: Pubkey, amount_out: u64, slippage_bps: u16) -> Self {
+        Self {
+            token_in,
+            token_out,
+            amount: amount_out,
+            swap_mode: SwapMode::ExactOut,
+            slippage_bps,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuoteResult {
     pub pool_info: PoolInfo,
     pub amount_in: u64,
     pub amount_out: u64,
+    /// Minimum output accepted on chain (equals `amount_out` for exact-out)
     pub min_amount_out: u64,
+    /// Maximum input spent on chain (equals `amount_in` for exact-in)
+    pub max_amount_in: u64,
+    #[serde(default)]
+    pub swap_mode: SwapMode,
     pub price_impact: f64,
     pub fee: u64,
     pub route: Vec<Pubkey>,
     pub token_in: Pubkey,
     pub token_out: Pubkey,
+    /// Tick traversal details (CLMM quotes only)
+    pub tick_traversal: Option<TickTraversal>,
+    /// Costs of executing the quote outside the pool, once estimated
+    #[serde(default)]
+    pub execution_costs: Option<ExecutionCosts>,
+}
+
+impl QuoteResult {
+    /// Comparable measure of the trade itself, higher is better: the output
+    /// received for exact-in quotes, the negated input paid for exact-out quotes
+    pub fn execution_score(&self) -> i128 {
+        match self.swap_mode {
+            SwapMode::ExactIn => self.amount_out as i128,
+            SwapMode::ExactOut => -(self.amount_in as i128),
+        }
+    }
+
+    /// Slot of the pool state the quote was computed from (0 if unknown)
+    pub fn context_slot(&self) -> u64 {
+        self.pool_info.context_slot
+    }
+
+    /// Whether the pool state behind the quote is more than `max_age_slots`
+    /// behind `current_slot`; quotes of unknown age are always stale
+    pub fn is_stale(&self, current_slot: u64, max_age_slots: u64) -> bool {
+        self.context_slot() == 0 || current_slot.saturating_sub(self.context_slot()) > max_age_slots
+    }
+
+    /// How much worse `requote` is than this quote, in basis points of the
+    /// amount that varies (output for exact-in, input for exact-out); 0 if
+    /// it is no worse
+    pub fn drift_bps(&self, requote: &QuoteResult) -> u64 {
+        drift_bps(
+            self.swap_mode,
+            (self.amount_in, self.amount_out),
+            (requote.amount_in, requote.amount_out),
+        )
+    }
+
+    /// Execution costs in units of the ranked token, when they could be priced
+    fn cost_in_token(&self) -> Option<u64> {
+        self.execution_costs.as_ref().and_then(|costs| costs.total_in_token)
+    }
+
+    /// Output left after paying execution costs (exact-in quotes with priced costs)
+    pub fn net_amount_out(&self) -> Option<i128> {
+        match self.swap_mode {
+            SwapMode::ExactIn => self
+                .cost_in_token()
+                .map(|cost| self.amount_out as i128 - cost as i128),
+            SwapMode::ExactOut => None,
+        }
+    }
+
+    /// Input plus execution costs (exact-out quotes with priced costs)
+    pub fn net_amount_in(&self) -> Option<u128> {
+        match self.swap_mode {
+            SwapMode::ExactIn => None,
+            SwapMode::ExactOut => self
+                .cost_in_token()
+                .map(|cost| self.amount_in as u128 + cost as u128),
+        }
+    }
+
+    /// [`Self::execution_score`] after execution costs, falling back to the
+    /// gross score when costs are unknown
+    pub fn net_execution_score(&self) -> i128 {
+        self.execution_score() - self.cost_in_token().unwrap_or(0) as i128
+    }
+
+    /// Whether the trade still delivers something once costs are paid
+    pub fn is_worth_executing(&self) -> bool {
+        self.net_amount_out().is_none_or(|net| net > 0)
+    }
+}
+
+/// Estimated costs of landing a swap transaction, beyond the pool's own fee
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionCosts {
+    /// Base fee for the transaction signatures, in lamports
+    pub signature_fee: u64,
+    /// Compute unit limit times compute unit price, in lamports
+    pub priority_fee: u64,
+    /// Rent for token accounts the swap has to create and keep, in lamports
+    pub ata_rent: u64,
+    /// Rent for the wSOL account created to wrap native SOL input, in lamports
+    pub wsol_overhead: u64,
+    /// Total converted into the ranked token (output for exact-in, input
+    /// for exact-out), if a SOL price for it was available
+    pub total_in_token: Option<u64>,
+}
+
+impl ExecutionCosts {
+    /// Total cost in lamports
+    pub fn total_lamports(&self) -> u64 {
+        self.signature_fee + self.priority_fee + self.ata_rent + self.wsol_overhead
+    }
+}
+
+/// How much worse the `requoted` (input, output) is than the `original`
+/// one, in basis points of the amount that varies with `swap_mode`
+fn drift_bps(swap_mode: SwapMode, original: (u64, u64), requoted: (u64, u64)) -> u64 {
+    let (original, worse_by) = match swap_mode {
+        SwapMode::ExactIn => (original.1, original.1.saturating_sub(requoted.1)),
+        SwapMode::ExactOut => (original.0, requoted.0.saturating_sub(original.0)),
+    };
+    if worse_by == 0 {
+        return 0;
+    }
+    if original == 0 {
+        return u64::MAX;
+    }
+    (worse_by as u128 * 10_000).div_ceil(original as u128).min(u64::MAX as u128) as u64
+}
+
+/// An order split across several pools of the same pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitQuote {
+    /// Per-pool quotes, each for that pool's share of the order
+    pub legs: Vec<QuoteResult>,
+    pub token_in: Pubkey,
+    pub token_out: Pubkey,
+    pub swap_mode: SwapMode,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub min_amount_out: u64,
+    pub max_amount_in: u64,
+    /// Input-weighted average of the legs' price impact
+    pub price_impact: f64,
+    pub fee: u64,
+}
+
+impl SplitQuote {
+    /// Aggregate per-pool legs into a composite quote
+    pub fn from_legs(legs: Vec<QuoteResult>, token_in: Pubkey, token_out: Pubkey, swap_mode: SwapMode) -> Self {
+        let amount_in = legs.iter().map(|leg| leg.amount_in).sum::<u64>();
+        let weighted_impact = legs
+            .iter()
+            .map(|leg| leg.price_impact * leg.amount_in as f64)
+            .sum::<f64>();
+        let price_impact = if amount_in == 0 {
+            0.0
+        } else {
+            weighted_impact / amount_in as f64
+        };
+
+        Self {
+            token_in,
+            token_out,
+            swap_mode,
+            amount_in,
+            amount_out: legs.iter().map(|leg| leg.amount_out).sum(),
+            min_amount_out: legs.iter().map(|leg| leg.min_amount_out).sum(),
+            max_amount_in: legs.iter().map(|leg| leg.max_amount_in).sum(),
+            price_impact,
+            fee: legs.iter().map(|leg| leg.fee).sum(),
+            legs,
+        }
+    }
+
+    /// Same measure as [`QuoteResult::execution_score`], over the whole order
+    pub fn execution_score(&self) -> i128 {
+        match self.swap_mode {
+            SwapMode::ExactIn => self.amount_out as i128,
+            SwapMode::ExactOut => -(self.amount_in as i128),
+        }
+    }
+
+    /// Share of the order routed through `leg`, in percent
+    pub fn leg_share(&self, leg: &QuoteResult) -> f64 {
+        let (part, total) = match self.swap_mode {
+            SwapMode::ExactIn => (leg.amount_in, self.amount_in),
+            SwapMode::ExactOut => (leg.amount_out, self.amount_out),
+        };
+        if total == 0 {
+            return 0.0;
+        }
+        part as f64 / total as f64 * 100.0
+    }
+
+    /// Leg carrying the largest share of the order
+    pub fn primary_leg(&self) -> Option<&QuoteResult> {
+        self.legs.iter().max_by_key(|leg| leg.amount_in)
+    }
+
+    /// Same as [`QuoteResult::drift_bps`], over the whole order
+    pub fn drift_bps(&self, requote: &SplitQuote) -> u64 {
+        drift_bps(
+            self.swap_mode,
+            (self.amount_in, self.amount_out),
+            (requote.amount_in, requote.amount_out),
+        )
+    }
+}
+
+/// A swap chained through one or more intermediate tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteQuote {
+    /// Per-hop quotes in execution order; each hop's input is the previous
+    /// hop's guaranteed output
+    pub hops: Vec<QuoteResult>,
+    pub token_in: Pubkey,
+    pub token_out: Pubkey,
+    pub swap_mode: SwapMode,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub min_amount_out: u64,
+    pub max_amount_in: u64,
+    /// Compounded price impact of all hops
+    pub price_impact: f64,
+}
+
+impl RouteQuote {
+    /// Build a route from hop quotes given in execution order
+    pub fn from_hops(hops: Vec<QuoteResult>, swap_mode: SwapMode) -> Option<Self> {
+        let first = hops.first()?;
+        let last = hops.last()?;
+        let retained = hops
+            .iter()
+            .map(|hop| 1.0 - hop.price_impact / 100.0)
+            .product::<f64>();
+
+        Some(Self {
+            token_in: first.token_in,
+            token_out: last.token_out,
+            swap_mode,
+            amount_in: first.amount_in,
+            amount_out: last.amount_out,
+            min_amount_out: last.min_amount_out,
+            max_amount_in: first.max_amount_in,
+            price_impact: (1.0 - retained) * 100.0,
+            hops,
+        })
+    }
+
+    /// Same measure as [`QuoteResult::execution_score`], end to end
+    pub fn execution_score(&self) -> i128 {
+        match self.swap_mode {
+            SwapMode::ExactIn => self.amount_out as i128,
+            SwapMode::ExactOut => -(self.amount_in as i128),
+        }
+    }
+
+    /// Same as [`QuoteResult::drift_bps`], end to end
+    pub fn drift_bps(&self, requote: &RouteQuote) -> u64 {
+        drift_bps(
+            self.swap_mode,
+            (self.amount_in, self.amount_out),
+            (requote.amount_in, requote.amount_out),
+        )
+    }
+
+    /// Pools traversed, in order
+    pub fn route(&self) -> Vec<Pubkey> {
+        self.hops.iter().map(|hop| hop.pool_info.address).collect()
+    }
+
+    /// Tokens held between hops
+    pub fn intermediate_mints(&self) -> Vec<Pubkey> {
+        self.hops
+            .iter()
+            .skip(1)
+            .map(|hop| hop.token_in)
+            .collect()
+    }
+}
+
+/// How far a CLMM swap moves through the pool's ticks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TickTraversal {
+    /// Initialized ticks crossed (each one changes active liquidity)
+    pub ticks_crossed: u32,
+    /// Tick array boundaries crossed
+    pub tick_arrays_crossed: u32,
+    /// Current tick after the swap
+    pub end_tick: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,11 +501,31 @@ pub struct SwapParams {
     pub token_out: Pubkey,
 }
 
+/// Parameters for executing a [`SplitQuote`] in one transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitSwapParams {
+    pub quote: SplitQuote,
+    pub user_pubkey: Pubkey,
+    pub slippage_bps: u16,
+    pub token_in: Pubkey,
+    pub token_out: Pubkey,
+}
+
+/// Parameters for executing a [`RouteQuote`] atomically
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteSwapParams {
+    pub quote: RouteQuote,
+    pub user_pubkey: Pubkey,
+    pub slippage_bps: u16,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionResult {
     pub signature: String,
     pub pool_type: PoolType,
     pub pool_address: Pubkey,
+    pub token_in: Pubkey,
+    pub token_out: Pubkey,
     pub amount_in: u64,
     pub amount_out: u64,
     pub expected_amount_out: u64,
@@ -110,28 +536,45 @@ pub struct TransactionResult {
     pub confirmation_time_ms: u64,
     pub finalized: bool,
     pub transaction_fee: Option<u64>,
+    /// Compute units the swap used in simulation
+    pub compute_units_consumed: Option<u64>,
+    /// Compute unit limit the transaction was sent with
+    pub compute_unit_limit: u32,
+    /// Priority fee paid, in micro-lamports per compute unit
+    pub compute_unit_price: u64,
+    /// What each pool logged about its swap, in execution order
+    pub executions: Vec<SwapExecution>,
 }
 
+/// What a pool program logged about one swap of a transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapExecution {
+    pub pool_address: Pubkey,
+    pub amount_in: u64,
+    /// Before any output transfer fee
+    pub amount_out: u64,
+    /// Token-2022 transfer fees withheld on each side
+    pub input_transfer_fee: u64,
+    pub output_transfer_fee: u64,
+    /// Output tokens per input token at the pool's post-swap state
+    pub price_after: Option<f64>,
+    /// CLMM price, liquidity and tick after the swap
+    pub sqrt_price_x64: Option<u128>,
+    pub liquidity: Option<u128>,
+    pub tick: Option<i32>,
+}
+
+/// A pool's rank under a `ScoringStrategy`, with the components behind it
 #[derive(Debug, Clone)]
 pub struct PoolScore {
     pub pool: PoolInfo,
     pub score: f64,
     pub liquidity_score: f64,
     pub volume_score: f64,
+    pub depth_score: f64,
     pub type_bonus: f64,
-}
-
-impl PoolScore {
-    pub fn new(pool: PoolInfo, liquidity_score: f64, volume_score: f64, type_bonus: f64) -> Self {
-        let score = (liquidity_score * 0.6 + volume_score * 0.4) * type_bonus;
-        Self {
-            pool,
-            score,
-            liquidity_score,
-            volume_score,
-            type_bonus,
-        }
-    }
+    pub fee_multiplier: f64,
+    pub risk_flags: Vec<RiskFlag>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -1,6 +1,6 @@
 use crate::core::{
-    constants::*, layouts::AmmInfoLayoutV4, PoolInfo, PoolState, PoolType, SwapError, SwapResult,
-    TokenInfo, AsyncTokenMetadataFetcher,
+    constants::*, layouts::AmmInfoLayoutV4, PoolInfo, PoolState, PoolType, PriceService, SwapError,
+    SwapResult, TokenInfo, AsyncTokenMetadataFetcher,
 };
 use log::{debug, warn};
 use solana_account_decoder::UiAccountEncoding;
@@ -12,19 +12,19 @@ use solana_client::{
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 use std::sync::Arc;
 
-const TOKEN_2022_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
-
 pub struct AmmPoolParser {
     rpc_client: Arc<RpcClient>,
     metadata_fetcher: Arc<AsyncTokenMetadataFetcher>,
+    price_service: Arc<PriceService>,
 }
 
 impl AmmPoolParser {
-    pub fn new(rpc_client: Arc<RpcClient>, _rpc_url: String) -> Self {
+    pub fn new(rpc_client: Arc<RpcClient>, _rpc_url: String, price_service: Arc<PriceService>) -> Self {
         let metadata_fetcher = Arc::new(AsyncTokenMetadataFetcher::new(rpc_client.clone()));
         Self { 
             rpc_client,
             metadata_fetcher,
+            price_service,
         }
     }
 
@@ -106,14 +106,6 @@ impl AmmPoolParser {
         let (token_a_info, token_b_info, token_a_balance, token_b_balance) = 
             (coin_token_info, pc_token_info, coin_vault_balance, pc_vault_balance);
 
-        // Calculate liquidity in USD (simplified - would need price oracle in production)
-        let liquidity_usd = self.estimate_liquidity_usd(
-            token_a_balance,
-            token_b_balance,
-            &token_a_info,
-            &token_b_info,
-        );
-        
         // Sanity check reserves
         if token_a_balance == 0 || token_b_balance == 0 {
             debug!("AMM pool {} has zero reserves: coin={}, pc={}", address, token_a_balance, token_b_balance);
@@ -134,21 +126,30 @@ impl AmmPoolParser {
             }
         }
 
-        Ok(Some(PoolInfo {
+        let mut pool = PoolInfo {
             pool_type: PoolType::AMM,
             address,
             token_a: token_a_info,
             token_b: token_b_info,
-            liquidity_usd,
-            volume_24h_usd: 0.0, // Would need to track swaps or use API
+            liquidity_usd: 0.0,
+            volume_24h_usd: 0.0, // Estimated by discovery from recent swaps
             fee_rate: pool_state.get_swap_fee_rate(),
             program_id: *AMM_V4_PROGRAM,
+            context_slot: 0,
+            open_time: pool_state.pool_open_time,
             pool_state: PoolState::AMM {
                 reserve_a: token_a_balance,
                 reserve_b: token_b_balance,
                 nonce: pool_state.nonce as u8,
+                need_take_pnl_coin: pool_state.need_take_pnl_coin,
+                need_take_pnl_pc: pool_state.need_take_pnl_pc,
+                swap_fee_numerator: pool_state.swap_fee_numerator,
+                swap_fee_denominator: pool_state.swap_fee_denominator,
             },
-        }))
+        };
+        pool.liquidity_usd = self.price_service.pool_liquidity_usd(&pool, token_a_balance, token_b_balance);
+
+        Ok(Some(pool))
     }
 
     /// Find all AMM pools for a token pair
@@ -350,7 +351,7 @@ impl AmmPoolParser {
     async fn get_vault_mint(&self, vault_account: &Pubkey) -> SwapResult<Pubkey> {
         match self.rpc_client.get_account(vault_account).await {
             Ok(account) => {
-                if account.owner == spl_token::ID || account.owner.to_string() == TOKEN_2022_PROGRAM_ID {
+                if account.owner == spl_token::ID || account.owner == *TOKEN_2022_PROGRAM {
                     // Token account mint is at offset 0
                     if account.data.len() >= 32 {
                         Ok(Pubkey::new_from_array(account.data[0..32].try_into().unwrap()))
@@ -361,7 +362,7 @@ impl AmmPoolParser {
                     Err(SwapError::ParseError("Not a token account".to_string()))
                 }
             }
-            Err(e) => Err(SwapError::RpcError(e)),
+            Err(e) => Err(SwapError::from(e)),
         }
     }
     
@@ -420,7 +421,7 @@ impl AmmPoolParser {
             .rpc_client
             .get_account(mint)
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         // SPL Token mint layout: decimals at offset 44
         if account.data.len() > 44 {
@@ -429,43 +430,6 @@ impl AmmPoolParser {
             Ok(9) // Default to 9 decimals
         }
     }
-
-    /// Estimate liquidity in USD
-    fn estimate_liquidity_usd(
-        &self,
-        reserve_a: u64,
-        reserve_b: u64,
-        token_a: &TokenInfo,
-        token_b: &TokenInfo,
-    ) -> f64 {
-        // For AMM pools, derive price from reserves when one token is a stablecoin
-        let amount_a = reserve_a as f64 / 10f64.powi(token_a.decimals as i32);
-        let amount_b = reserve_b as f64 / 10f64.powi(token_b.decimals as i32);
-        
-        // Check if tokens are stablecoins
-        let is_stable_a = matches!(token_a.symbol.as_str(), "USDC" | "USDT" | "USDH" | "USDD" | "UXD");
-        let is_stable_b = matches!(token_b.symbol.as_str(), "USDC" | "USDT" | "USDH" | "USDD" | "UXD");
-        
-        if is_stable_a && is_stable_b {
-            // Both stablecoins
-            amount_a + amount_b
-        } else if is_stable_a {
-            // Token A is stable, derive token B price from AMM formula
-            // Price of B in terms of A = reserve_a / reserve_b
-            let price_b_in_usd = amount_a / amount_b;
-            amount_a + (amount_b * price_b_in_usd)
-        } else if is_stable_b {
-            // Token B is stable, derive token A price from AMM formula
-            // Price of A in terms of B = reserve_b / reserve_a
-            let price_a_in_usd = amount_b / amount_a;
-            (amount_a * price_a_in_usd) + amount_b
-        } else {
-            // Neither is a stablecoin
-            // For ranking purposes, use the geometric mean of reserves as a proxy
-            // This gives us a relative measure of pool size
-            2.0 * (amount_a * amount_b).sqrt()
-        }
-    }
     
     /// Get AMM vault addresses using PDA
     #[allow(dead_code)]
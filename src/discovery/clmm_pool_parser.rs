@@ -1,6 +1,6 @@
 use crate::core::{
-    constants::*, layouts::ClmmPoolState, PoolInfo, PoolState, PoolType, SwapError, SwapResult,
-    TokenInfo,
+    constants::*, layouts::{ClmmAmmConfig, ClmmPoolState}, PoolInfo, PoolState, PoolType, PriceService,
+    SwapError, SwapResult, TokenInfo,
 };
 use borsh::BorshDeserialize;
 use log::{debug, warn};
@@ -15,11 +15,12 @@ use std::sync::Arc;
 
 pub struct ClmmPoolParser {
     rpc_client: Arc<RpcClient>,
+    price_service: Arc<PriceService>,
 }
 
 impl ClmmPoolParser {
-    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
-        Self { rpc_client }
+    pub fn new(rpc_client: Arc<RpcClient>, price_service: Arc<PriceService>) -> Self {
+        Self { rpc_client, price_service }
     }
 
     /// Parse CLMM pool from account data
@@ -49,23 +50,14 @@ impl ClmmPoolParser {
             return Ok(None);
         }
 
+        if !pool_state.is_swap_enabled() {
+            debug!("CLMM pool {} has swaps disabled (status {})", address, pool_state.status);
+            return Ok(None);
+        }
+
         // Get token vault balances
-        let (token_vault_0, _) = Pubkey::find_program_address(
-            &[
-                b"pool_vault",
-                address.as_ref(),
-                pool_state.token_mint_0.as_ref(),
-            ],
-            &CLMM_PROGRAM,
-        );
-        let (token_vault_1, _) = Pubkey::find_program_address(
-            &[
-                b"pool_vault",
-                address.as_ref(),
-                pool_state.token_mint_1.as_ref(),
-            ],
-            &CLMM_PROGRAM,
-        );
+        let token_vault_0 = pool_state.token_vault_0;
+        let token_vault_1 = pool_state.token_vault_1;
 
         let token_0_balance = self.get_token_balance(&token_vault_0).await?;
         let token_1_balance = self.get_token_balance(&token_vault_1).await?;
@@ -74,39 +66,32 @@ impl ClmmPoolParser {
         let token_0_info = self.get_token_info(&pool_state.token_mint_0).await?;
         let token_1_info = self.get_token_info(&pool_state.token_mint_1).await?;
 
-        // Calculate liquidity in USD
-        let liquidity_usd = self.estimate_liquidity_usd(
-            token_0_balance,
-            token_1_balance,
-            &token_0_info,
-            &token_1_info,
-        );
+        // Fee rate lives in the pool's AmmConfig account
+        let amm_config = self.get_amm_config(&pool_state.amm_config).await?;
+        let fee_rate = amm_config.trade_fee_rate as f64 / 1_000_000.0;
 
-        // Filter out pools with very low liquidity
-        if liquidity_usd < 300.0 {
-            debug!("CLMM pool {} has low liquidity: ${}", address, liquidity_usd);
-            return Ok(None);
-        }
-
-        // Convert fee rate from CLMM format to percentage
-        let fee_rate = pool_state.fee_rate as f64 / 1_000_000.0;
-
-        Ok(Some(PoolInfo {
+        let mut pool = PoolInfo {
             pool_type: PoolType::CLMM,
             address,
             token_a: token_0_info,
             token_b: token_1_info,
-            liquidity_usd,
-            volume_24h_usd: 0.0, // Would need to track swaps or use API
+            liquidity_usd: 0.0,
+            volume_24h_usd: 0.0, // Estimated by discovery from recent swaps
             fee_rate,
             program_id: *CLMM_PROGRAM,
+            context_slot: 0,
+            open_time: pool_state.open_time,
             pool_state: PoolState::CLMM {
                 current_tick: pool_state.current_tick,
                 tick_spacing: pool_state.tick_spacing,
                 liquidity: pool_state.liquidity,
-                fee_tier: pool_state.get_fee_rate_bps(),
+                sqrt_price_x64: pool_state.current_price_sqrt,
+                fee_tier: amm_config.trade_fee_rate,
             },
-        }))
+        };
+        pool.liquidity_usd = self.price_service.pool_liquidity_usd(&pool, token_0_balance, token_1_balance);
+
+        Ok(Some(pool))
     }
 
     /// Find all CLMM pools for a token pair
@@ -119,20 +104,20 @@ impl ClmmPoolParser {
 
         let mut all_pools = Vec::new();
 
-        // CLMM pool state offsets (after Borsh deserialization):
-        // bump: 1 byte (offset 0)
-        // token_mint_0: 32 bytes (offset 1)
-        // token_mint_1: 32 bytes (offset 33)
+        // CLMM pool state offsets:
+        // discriminator: 8 bytes, bump: 1 byte, amm_config: 32 bytes, owner: 32 bytes
+        // token_mint_0: 32 bytes (offset 73)
+        // token_mint_1: 32 bytes (offset 105)
         
         // Search pattern 1: token_a as token_mint_0, token_b as token_mint_1
         let filters1 = vec![
             RpcFilterType::DataSize(ClmmPoolState::LEN as u64),
             RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-                1, // token_mint_0 offset (after bump byte)
+                73, // token_mint_0 offset
                 token_a.to_bytes().to_vec(),
             )),
             RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-                33, // token_mint_1 offset
+                105, // token_mint_1 offset
                 token_b.to_bytes().to_vec(),
             )),
         ];
@@ -175,11 +160,11 @@ impl ClmmPoolParser {
         let filters2 = vec![
             RpcFilterType::DataSize(ClmmPoolState::LEN as u64),
             RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-                1, // token_mint_0 offset
+                73, // token_mint_0 offset
                 token_b.to_bytes().to_vec(),
             )),
             RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
-                33, // token_mint_1 offset
+                105, // token_mint_1 offset
                 token_a.to_bytes().to_vec(),
             )),
         ];
@@ -219,6 +204,16 @@ impl ClmmPoolParser {
         Ok(all_pools)
     }
 
+    /// Get the AmmConfig account holding the pool's fee settings
+    async fn get_amm_config(&self, amm_config: &Pubkey) -> SwapResult<ClmmAmmConfig> {
+        let account = self
+            .rpc_client
+            .get_account(amm_config)
+            .await
+            .map_err(SwapError::from)?;
+        ClmmAmmConfig::from_bytes(&account.data).map_err(SwapError::InvalidPoolState)
+    }
+
     /// Get token balance for an account
     async fn get_token_balance(&self, token_account: &Pubkey) -> SwapResult<u64> {
         match self.rpc_client.get_account(token_account).await {
@@ -278,7 +273,7 @@ impl ClmmPoolParser {
             .rpc_client
             .get_account(mint)
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         // SPL Token mint layout: decimals at offset 44
         if account.data.len() > 44 {
@@ -287,33 +282,4 @@ impl ClmmPoolParser {
             Ok(9) // Default to 9 decimals
         }
     }
-
-    /// Estimate liquidity in USD (simplified)
-    fn estimate_liquidity_usd(
-        &self,
-        reserve_0: u64,
-        reserve_1: u64,
-        token_0: &TokenInfo,
-        token_1: &TokenInfo,
-    ) -> f64 {
-        // In production, use price oracle
-        // For now, use simple heuristics
-        // TODO: Implement proper price retrieval
-        
-        let value_0 = match token_0.symbol.as_str() {
-            "SOL" => (reserve_0 as f64 / 10f64.powi(token_0.decimals as i32)) * 140.0,
-            "USDC" | "USDT" => reserve_0 as f64 / 10f64.powi(token_0.decimals as i32),
-            "ETH" => (reserve_0 as f64 / 10f64.powi(token_0.decimals as i32)) * 3000.0,
-            _ => 0.0,
-        };
-
-        let value_1 = match token_1.symbol.as_str() {
-            "SOL" => (reserve_1 as f64 / 10f64.powi(token_1.decimals as i32)) * 140.0,
-            "USDC" | "USDT" => reserve_1 as f64 / 10f64.powi(token_1.decimals as i32),
-            "ETH" => (reserve_1 as f64 / 10f64.powi(token_1.decimals as i32)) * 3000.0,
-            _ => 0.0,
-        };
-
-        value_0 + value_1
-    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use crate::core::{
-    constants::*, layouts::ClmmPoolState, PoolInfo, PoolState, PoolType, SwapError, SwapResult,
-    TokenInfo,
+    constants::*, layouts::{ClmmAmmConfig, ClmmPoolState}, PoolInfo, PoolState, PoolType, PriceService,
+    SwapError, SwapResult, TokenInfo,
 };
 use borsh::BorshDeserialize;
 use dashmap::DashMap;
@@ -9,12 +9,17 @@ use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
     nonblocking::rpc_client::RpcClient,
     rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
-    rpc_filter::RpcFilterType,
+    rpc_filter::{Memcmp, RpcFilterType},
 };
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 use std::sync::Arc;
 use tokio::sync::Semaphore;
 
+/// Offsets of the token mints in the CLMM pool account
+/// (discriminator 8 + bump 1 + amm_config 32 + owner 32)
+const TOKEN_MINT_0_OFFSET: usize = 73;
+const TOKEN_MINT_1_OFFSET: usize = 105;
+
 /// Optimized CLMM pool parser with improved filtering and caching
 pub struct OptimizedClmmPoolParser {
     rpc_client: Arc<RpcClient>,
@@ -25,16 +30,22 @@ pub struct OptimizedClmmPoolParser {
     rpc_semaphore: Arc<Semaphore>,
     /// Cache for token metadata
     token_cache: Arc<DashMap<Pubkey, TokenInfo>>,
+    /// Cache for AmmConfig accounts (fee settings shared by many pools)
+    amm_config_cache: Arc<DashMap<Pubkey, ClmmAmmConfig>>,
+    /// Shared USD prices for valuing pool liquidity
+    price_service: Arc<PriceService>,
 }
 
 impl OptimizedClmmPoolParser {
-    pub fn new(rpc_client: Arc<RpcClient>, rpc_url: String) -> Self {
+    pub fn new(rpc_client: Arc<RpcClient>, rpc_url: String, price_service: Arc<PriceService>) -> Self {
         Self {
             rpc_client,
             rpc_url,
             pool_cache: Arc::new(DashMap::new()),
             rpc_semaphore: Arc::new(Semaphore::new(10)), // Limit concurrent RPC calls
             token_cache: Arc::new(DashMap::new()),
+            amm_config_cache: Arc::new(DashMap::new()),
+            price_service,
         }
     }
 
@@ -102,6 +113,55 @@ impl OptimizedClmmPoolParser {
         Ok(all_pools)
     }
 
+    /// Find all CLMM pools containing a specific token
+    pub async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        debug!("Searching for all CLMM pools containing token {}", token);
+
+        let context_slot = self.rpc_client.get_slot().await.unwrap_or(0);
+        let mut all_pools = Vec::new();
+
+        for (offset, side) in [(TOKEN_MINT_0_OFFSET, "token_0"), (TOKEN_MINT_1_OFFSET, "token_1")] {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::DataSize(ClmmPoolState::LEN as u64),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, token.to_bytes().to_vec())),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64Zstd),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    data_slice: None,
+                    min_context_slot: None,
+                },
+                with_context: Some(false),
+            };
+
+            let _permit = self.rpc_semaphore.acquire().await
+                .map_err(|_| SwapError::Other("Failed to acquire RPC semaphore".to_string()))?;
+
+            match self
+                .rpc_client
+                .get_program_accounts_with_config(&CLMM_PROGRAM, config)
+                .await
+            {
+                Ok(accounts) => {
+                    debug!("Found {} CLMM accounts with token as {}", accounts.len(), side);
+                    for (address, account) in accounts {
+                        if all_pools.iter().any(|p: &PoolInfo| p.address == address) {
+                            continue;
+                        }
+                        if let Ok(Some(pool)) = self.parse_pool_optimized(address, &account.data, context_slot).await {
+                            all_pools.push(pool);
+                        }
+                    }
+                }
+                Err(e) => warn!("Error searching CLMM pools with token as {}: {}", side, e),
+            }
+        }
+
+        debug!("Found {} total CLMM pools containing token", all_pools.len());
+        Ok(all_pools)
+    }
+
     /// Fetch pools with a specific token order pattern
     async fn fetch_pools_pattern(
         &self,
@@ -130,11 +190,14 @@ impl OptimizedClmmPoolParser {
         let _permit = self.rpc_semaphore.acquire().await
             .map_err(|_| SwapError::Other("Failed to acquire RPC semaphore".to_string()))?;
 
+        // The accounts below are at least as recent as this slot
+        let context_slot = self.rpc_client.get_slot().await.unwrap_or(0);
+
         let accounts = self
             .rpc_client
             .get_program_accounts_with_config(&CLMM_PROGRAM, config)
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         info!("Fetched {} CLMM accounts to check for tokens {}/{}", accounts.len(), token_0, token_1);
 
@@ -153,7 +216,7 @@ impl OptimizedClmmPoolParser {
                 let target_token_1 = token_1;
                 
                 chunk_futures.push(tokio::spawn(async move {
-                    match parser.parse_pool_optimized(address, &data).await {
+                    match parser.parse_pool_optimized(address, &data, context_slot).await {
                         Ok(Some(pool)) => {
                             // Filter by tokens after parsing
                             if (pool.token_a.mint == target_token_0 && pool.token_b.mint == target_token_1) ||
@@ -180,11 +243,23 @@ impl OptimizedClmmPoolParser {
         Ok(pools)
     }
 
+    /// Re-parse a pool from freshly fetched account data, bypassing the cache
+    pub async fn refresh_pool(
+        &self,
+        address: Pubkey,
+        data: &[u8],
+        context_slot: u64,
+    ) -> SwapResult<Option<PoolInfo>> {
+        self.pool_cache.remove(&address);
+        self.parse_pool_optimized(address, data, context_slot).await
+    }
+
     /// Parse CLMM pool with optimizations
     async fn parse_pool_optimized(
         &self,
         address: Pubkey,
         data: &[u8],
+        context_slot: u64,
     ) -> SwapResult<Option<PoolInfo>> {
         // Check cache first
         if let Some(pool) = self.pool_cache.get(&address) {
@@ -227,6 +302,11 @@ impl OptimizedClmmPoolParser {
             return Ok(None);
         }
 
+        if !pool_state.is_swap_enabled() {
+            debug!("CLMM pool {} has swaps disabled (status {})", address, pool_state.status);
+            return Ok(None);
+        }
+
         // Get token metadata (with caching)
         let (token_0_info, token_1_info) = tokio::join!(
             self.get_token_info_cached(&pool_state.token_mint_0),
@@ -235,23 +315,11 @@ impl OptimizedClmmPoolParser {
         let token_0_info = token_0_info?;
         let token_1_info = token_1_info?;
 
-        // Calculate vault addresses
-        let (token_vault_0, _) = Pubkey::find_program_address(
-            &[
-                b"pool_vault",
-                address.as_ref(),
-                pool_state.token_mint_0.as_ref(),
-            ],
-            &CLMM_PROGRAM,
-        );
-        let (token_vault_1, _) = Pubkey::find_program_address(
-            &[
-                b"pool_vault",
-                address.as_ref(),
-                pool_state.token_mint_1.as_ref(),
-            ],
-            &CLMM_PROGRAM,
-        );
+        // Fee rate lives in the pool's AmmConfig account
+        let amm_config = self.get_amm_config_cached(&pool_state.amm_config).await?;
+
+        let token_vault_0 = pool_state.token_vault_0;
+        let token_vault_1 = pool_state.token_vault_1;
 
         // Get vault balances in parallel
         let (balance_0, balance_1) = tokio::join!(
@@ -261,39 +329,29 @@ impl OptimizedClmmPoolParser {
         let token_0_balance = balance_0?;
         let token_1_balance = balance_1?;
 
-        // Estimate liquidity
-        let liquidity_usd = self.estimate_liquidity_usd(
-            token_0_balance,
-            token_1_balance,
-            &token_0_info,
-            &token_1_info,
-        );
-
-        // Filter out pools with very low liquidity
-        if liquidity_usd < 300.0 {
-            debug!("CLMM pool {} has low liquidity: ${}", address, liquidity_usd);
-            return Ok(None);
-        }
-
         // Convert fee rate
-        let fee_rate = pool_state.fee_rate as f64 / 1_000_000.0;
+        let fee_rate = amm_config.trade_fee_rate as f64 / 1_000_000.0;
 
-        let pool_info = PoolInfo {
+        let mut pool_info = PoolInfo {
             pool_type: PoolType::CLMM,
             address,
             token_a: token_0_info,
             token_b: token_1_info,
-            liquidity_usd,
-            volume_24h_usd: 0.0, // Would need indexer for this
+            liquidity_usd: 0.0,
+            volume_24h_usd: 0.0, // Estimated by discovery from recent swaps
             fee_rate,
             program_id: *CLMM_PROGRAM,
+            context_slot,
+            open_time: pool_state.open_time,
             pool_state: PoolState::CLMM {
                 current_tick: pool_state.current_tick,
                 tick_spacing: pool_state.tick_spacing,
                 liquidity: pool_state.liquidity,
-                fee_tier: pool_state.get_fee_rate_bps(),
+                sqrt_price_x64: pool_state.current_price_sqrt,
+                fee_tier: amm_config.trade_fee_rate,
             },
         };
+        pool_info.liquidity_usd = self.price_service.pool_liquidity_usd(&pool_info, token_0_balance, token_1_balance);
 
         // Cache the parsed pool
         self.pool_cache.insert(address, pool_info.clone());
@@ -322,6 +380,27 @@ impl OptimizedClmmPoolParser {
         }
     }
 
+    /// Get AmmConfig with caching
+    async fn get_amm_config_cached(&self, amm_config: &Pubkey) -> SwapResult<ClmmAmmConfig> {
+        if let Some(config) = self.amm_config_cache.get(amm_config) {
+            return Ok(config.clone());
+        }
+
+        let _permit = self.rpc_semaphore.acquire().await
+            .map_err(|_| SwapError::Other("Failed to acquire RPC semaphore".to_string()))?;
+
+        let account = self
+            .rpc_client
+            .get_account(amm_config)
+            .await
+            .map_err(SwapError::from)?;
+        let config = ClmmAmmConfig::from_bytes(&account.data)
+            .map_err(SwapError::InvalidPoolState)?;
+
+        self.amm_config_cache.insert(*amm_config, config.clone());
+        Ok(config)
+    }
+
     /// Fallback token info for known tokens
     async fn get_token_info_fallback(&self, mint: &Pubkey) -> SwapResult<TokenInfo> {
         let (symbol, name, decimals) = match mint.to_string().as_str() {
@@ -370,7 +449,7 @@ impl OptimizedClmmPoolParser {
             .rpc_client
             .get_account(mint)
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         if account.data.len() > 44 {
             Ok(account.data[44])
@@ -378,32 +457,6 @@ impl OptimizedClmmPoolParser {
             Ok(9)
         }
     }
-
-    /// Estimate liquidity in USD
-    fn estimate_liquidity_usd(
-        &self,
-        reserve_0: u64,
-        reserve_1: u64,
-        token_0: &TokenInfo,
-        token_1: &TokenInfo,
-    ) -> f64 {
-        // In production, use price oracle
-        let value_0 = match token_0.symbol.as_str() {
-            "SOL" => (reserve_0 as f64 / 10f64.powi(token_0.decimals as i32)) * 140.0,
-            "USDC" | "USDT" => reserve_0 as f64 / 10f64.powi(token_0.decimals as i32),
-            "ETH" => (reserve_0 as f64 / 10f64.powi(token_0.decimals as i32)) * 3000.0,
-            _ => 0.0,
-        };
-
-        let value_1 = match token_1.symbol.as_str() {
-            "SOL" => (reserve_1 as f64 / 10f64.powi(token_1.decimals as i32)) * 140.0,
-            "USDC" | "USDT" => reserve_1 as f64 / 10f64.powi(token_1.decimals as i32),
-            "ETH" => (reserve_1 as f64 / 10f64.powi(token_1.decimals as i32)) * 3000.0,
-            _ => 0.0,
-        };
-
-        value_0 + value_1
-    }
 }
 
 impl Clone for OptimizedClmmPoolParser {
@@ -414,6 +467,8 @@ impl Clone for OptimizedClmmPoolParser {
             pool_cache: self.pool_cache.clone(),
             rpc_semaphore: self.rpc_semaphore.clone(),
             token_cache: self.token_cache.clone(),
+            amm_config_cache: self.amm_config_cache.clone(),
+            price_service: self.price_service.clone(),
         }
     }
 }
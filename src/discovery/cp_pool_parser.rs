@@ -1,7 +1,8 @@
 use crate::core::{
-    constants::*, layouts::CpSwapPoolState, PoolInfo, PoolState, PoolType, SwapResult,
-    TokenInfo, AsyncTokenMetadataFetcher,
+    constants::*, layouts::{CpAmmConfig, CpSwapPoolState}, PoolInfo, PoolState, PoolType, PriceService,
+    SwapError, SwapResult, TokenInfo, AsyncTokenMetadataFetcher,
 };
+use dashmap::DashMap;
 use log::{debug, warn};
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
@@ -15,14 +16,18 @@ use std::sync::Arc;
 pub struct CpPoolParser {
     rpc_client: Arc<RpcClient>,
     metadata_fetcher: Arc<AsyncTokenMetadataFetcher>,
+    price_service: Arc<PriceService>,
+    amm_config_cache: DashMap<Pubkey, CpAmmConfig>,
 }
 
 impl CpPoolParser {
-    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+    pub fn new(rpc_client: Arc<RpcClient>, price_service: Arc<PriceService>) -> Self {
         let metadata_fetcher = Arc::new(AsyncTokenMetadataFetcher::new(rpc_client.clone()));
         Self { 
             rpc_client,
             metadata_fetcher,
+            price_service,
+            amm_config_cache: DashMap::new(),
         }
     }
 
@@ -72,45 +77,49 @@ impl CpPoolParser {
             fund_fees_token_0: u64::from_le_bytes(data[357..365].try_into().unwrap()),
             fund_fees_token_1: u64::from_le_bytes(data[365..373].try_into().unwrap()),
             open_time: u64::from_le_bytes(data[373..381].try_into().unwrap()),
-            padding: [0u64; 32], // Skip padding
+            recent_epoch: u64::from_le_bytes(data[381..389].try_into().unwrap()),
+            padding: [0u64; 31], // Skip padding
         };
 
-        // Check if pool is active
-        if !pool_state.is_active() {
-            debug!("CP pool {} is not active (status != 1)", address);
+        // Check if swaps are enabled
+        if !pool_state.is_swap_enabled() {
+            debug!("CP pool {} has swaps disabled (status {})", address, pool_state.status);
             return Ok(None);
         }
 
+        // Fee rates shared by every pool on the same config
+        let amm_config = self.get_amm_config_cached(&pool_state.amm_config).await?;
+
         // Get token metadata
         let token_0_info = self.get_token_info(&pool_state.token_0_mint).await?;
         let token_1_info = self.get_token_info(&pool_state.token_1_mint).await?;
 
-        // Get actual vault balances from token accounts
-        let token_0_balance = self.get_token_balance(&pool_state.token_0_vault).await?;
-        let token_1_balance = self.get_token_balance(&pool_state.token_1_vault).await?;
-
-        // Calculate liquidity in USD (simplified)
-        let liquidity_usd = self.estimate_liquidity_usd(
-            token_0_balance,
-            token_1_balance,
-            &token_0_info,
-            &token_1_info,
+        // Get actual vault balances from token accounts, less the fees accrued in them
+        let (token_0_balance, token_1_balance) = pool_state.swappable_reserves(
+            self.get_token_balance(&pool_state.token_0_vault).await?,
+            self.get_token_balance(&pool_state.token_1_vault).await?,
         );
 
-        Ok(Some(PoolInfo {
+        let mut pool = PoolInfo {
             pool_type: PoolType::Standard, // Use Standard type for CP pools
             address,
             token_a: token_0_info,
             token_b: token_1_info,
-            liquidity_usd,
-            volume_24h_usd: 0.0, // Would need to track swaps
-            fee_rate: 0.003, // Default 0.3% fee for CP pools
+            liquidity_usd: 0.0,
+            volume_24h_usd: 0.0, // Estimated by discovery from recent swaps
+            fee_rate: amm_config.trade_fee_rate as f64 / CpAmmConfig::FEE_RATE_DENOMINATOR as f64,
             program_id: *RAYDIUM_CP_SWAP_PROGRAM,
+            context_slot: 0,
+            open_time: pool_state.open_time,
             pool_state: PoolState::Standard {
                 reserve_a: token_0_balance,
                 reserve_b: token_1_balance,
+                trade_fee_rate: amm_config.trade_fee_rate,
             },
-        }))
+        };
+        pool.liquidity_usd = self.price_service.pool_liquidity_usd(&pool, token_0_balance, token_1_balance);
+
+        Ok(Some(pool))
     }
 
     /// Find all CP pools for a token pair
@@ -316,32 +325,30 @@ impl CpPoolParser {
         }
     }
 
-    /// Get token metadata
-    async fn get_token_info(&self, mint: &Pubkey) -> SwapResult<TokenInfo> {
-        self.metadata_fetcher.get_token_metadata(mint).await
-    }
+    /// Get AmmConfig with caching
+    async fn get_amm_config_cached(&self, amm_config: &Pubkey) -> SwapResult<CpAmmConfig> {
+        if let Some(config) = self.amm_config_cache.get(amm_config) {
+            return Ok(config.clone());
+        }
 
-    /// Estimate liquidity in USD (simplified)
-    fn estimate_liquidity_usd(
-        &self,
-        reserve_a: u64,
-        reserve_b: u64,
-        token_a: &TokenInfo,
-        token_b: &TokenInfo,
-    ) -> f64 {
-        // In production, use price oracle
-        let value_a = match token_a.symbol.as_str() {
-            "SOL" => (reserve_a as f64 / 10f64.powi(token_a.decimals as i32)) * 140.0,
-            "USDC" | "USDT" => reserve_a as f64 / 10f64.powi(token_a.decimals as i32),
-            _ => 0.0,
-        };
+        let account = self
+            .rpc_client
+            .get_account(amm_config)
+            .await
+            .map_err(SwapError::from)?;
+        let config = CpAmmConfig::from_bytes(&account.data)
+            .map_err(SwapError::InvalidPoolState)?;
+        debug!(
+            "CP config {}: trade fee {}, protocol fee {}, fund fee {}",
+            amm_config, config.trade_fee_rate, config.protocol_fee_rate, config.fund_fee_rate
+        );
 
-        let value_b = match token_b.symbol.as_str() {
-            "SOL" => (reserve_b as f64 / 10f64.powi(token_b.decimals as i32)) * 140.0,
-            "USDC" | "USDT" => reserve_b as f64 / 10f64.powi(token_b.decimals as i32),
-            _ => 0.0,
-        };
+        self.amm_config_cache.insert(*amm_config, config.clone());
+        Ok(config)
+    }
 
-        value_a + value_b
+    /// Get token metadata
+    async fn get_token_info(&self, mint: &Pubkey) -> SwapResult<TokenInfo> {
+        self.metadata_fetcher.get_token_metadata(mint).await
     }
 }
\ No newline at end of file
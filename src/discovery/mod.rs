@@ -6,6 +6,7 @@ pub mod cp_pool_parser;
 pub mod pool_cache;
 pub mod pool_finder;
 pub mod pool_scorer;
+pub mod volume;
 
 use crate::core::{Config, PoolInfo, PoolType, SwapResult};
 use solana_sdk::pubkey::Pubkey;
@@ -14,6 +15,7 @@ use std::sync::Arc;
 pub use pool_cache::PoolCache;
 pub use pool_finder::{PoolFinder, PoolDiscoveryService};
 pub use pool_scorer::PoolScorer;
+pub use volume::{TransactionRecord, VolumeEstimator, VolumeRpc};
 
 /// Main interface for pool discovery
 pub struct PoolDiscovery {
@@ -26,7 +28,7 @@ impl PoolDiscovery {
     pub fn new(config: Config) -> SwapResult<Self> {
         let finder = Arc::new(PoolDiscoveryService::new(config.clone())?);
         let cache = Arc::new(PoolCache::new(config.cache_ttl_secs));
-        let scorer = PoolScorer::new();
+        let scorer = PoolScorer::with_strategy(config.scoring_strategy.clone());
 
         Ok(Self {
             finder,
@@ -88,10 +90,33 @@ impl PoolDiscovery {
 
     /// Find all pools containing a specific token
     pub async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
-        // Use the comprehensive pool finder which already searches all pool types
         self.finder.find_pools_by_token(token).await
     }
 
+    /// Find all pools of the given types containing a specific token
+    pub async fn find_pools_by_token_and_type(
+        &self,
+        token: Pubkey,
+        pool_types: &[PoolType],
+    ) -> SwapResult<Vec<PoolInfo>> {
+        self.finder.find_pools_by_token_and_type(token, pool_types).await
+    }
+
+    /// Re-read a pool's on-chain state ahead of executing against it
+    pub async fn refresh_pool(&self, pool: &PoolInfo) -> SwapResult<PoolInfo> {
+        self.finder.refresh_pool(pool).await
+    }
+
+    /// Last known USD price of `mint`, learned while discovering its pools
+    pub fn price(&self, mint: &Pubkey) -> Option<f64> {
+        self.finder.price(mint)
+    }
+
+    /// Scorer ranking discovered pools
+    pub fn scorer(&self) -> &PoolScorer {
+        &self.scorer
+    }
+
     /// Invalidate cache for a token pair
     pub async fn invalidate_cache(&self, token_a: Pubkey, token_b: Pubkey) {
         self.cache.invalidate(&(token_a, token_b)).await;
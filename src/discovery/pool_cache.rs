@@ -105,10 +105,16 @@ mod tests {
             volume_24h_usd: 50000.0,
             fee_rate: 0.0025,
             program_id: Pubkey::new_unique(),
+            context_slot: 0,
+            open_time: 0,
             pool_state: PoolState::AMM {
                 reserve_a: 1000000,
                 reserve_b: 1000000,
                 nonce: 1,
+                need_take_pnl_coin: 0,
+                need_take_pnl_pc: 0,
+                swap_fee_numerator: 25,
+                swap_fee_denominator: 10000,
             },
         }
     }
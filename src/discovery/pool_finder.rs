@@ -1,17 +1,21 @@
 use crate::core::{
-    error::SwapResult, Config, PoolInfo,
+    error::SwapResult, Config, PoolInfo, PoolType, PriceService, SwapError, PRICE_CACHE_TTL,
+    USDC_MINT, USDT_MINT, VOLUME_POOL_CONCURRENCY, WSOL_MINT,
 };
 use crate::discovery::amm_pool_parser::AmmPoolParser;
 use crate::discovery::stable_pool_parser::StablePoolParser;
 use crate::discovery::clmm_pool_parser_optimized::OptimizedClmmPoolParser;
 use crate::discovery::cp_pool_parser::CpPoolParser;
-use futures::future::join_all;
+use crate::discovery::volume::VolumeEstimator;
+use futures::{future::join_all, stream, StreamExt};
 use log::{debug, info, warn};
 use solana_client::{
     nonblocking::rpc_client::RpcClient,
 };
 use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
-use std::sync::Arc;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Trait for pool-specific discovery
 #[async_trait::async_trait]
@@ -21,13 +25,31 @@ pub trait PoolFinder: Send + Sync {
         token_a: Pubkey,
         token_b: Pubkey,
     ) -> SwapResult<Vec<PoolInfo>>;
+
+    /// Find every pool of this type containing `token`
+    async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>>;
+
+    /// Pool type this finder discovers
+    fn pool_type(&self) -> PoolType;
+
+    /// Re-parse a known pool from freshly fetched account data
+    async fn refresh_pool(
+        &self,
+        address: Pubkey,
+        data: &[u8],
+        context_slot: u64,
+    ) -> SwapResult<Option<PoolInfo>>;
 }
 
 /// Main pool discovery service
 pub struct PoolDiscoveryService {
     rpc_client: Arc<RpcClient>,
-    config: Config,
     finders: Vec<Box<dyn PoolFinder>>,
+    prices: Arc<PriceService>,
+    min_liquidity_usd: f64,
+    volume: Option<VolumeEstimator>,
+    /// When SOL/USDC pools were last searched for SOL's price
+    sol_price_searched_at: Mutex<Option<Instant>>,
 }
 
 impl PoolDiscoveryService {
@@ -37,20 +59,34 @@ impl PoolDiscoveryService {
             CommitmentConfig::confirmed(),
         ));
 
+        let prices = Arc::new(PriceService::new(PRICE_CACHE_TTL));
+
         let finders: Vec<Box<dyn PoolFinder>> = vec![
-            Box::new(AmmPoolFinder::new(rpc_client.clone(), config.rpc_url.clone())),
-            Box::new(StablePoolFinder::new(rpc_client.clone())),
-            Box::new(ClmmPoolFinder::new(rpc_client.clone(), config.rpc_url.clone())),
-            Box::new(StandardPoolFinder::new(rpc_client.clone())), // This now handles CP pools
+            Box::new(AmmPoolFinder::new(rpc_client.clone(), config.rpc_url.clone(), prices.clone())),
+            Box::new(StablePoolFinder::new(rpc_client.clone(), prices.clone())),
+            Box::new(ClmmPoolFinder::new(rpc_client.clone(), config.rpc_url.clone(), prices.clone())),
+            Box::new(StandardPoolFinder::new(rpc_client.clone(), prices.clone())), // This now handles CP pools
         ];
 
+        let volume = (config.volume_sample_size > 0).then(|| {
+            VolumeEstimator::new(rpc_client.clone(), prices.clone(), config.volume_sample_size)
+        });
+
         Ok(Self {
             rpc_client,
-            config,
             finders,
+            prices,
+            min_liquidity_usd: config.min_liquidity_usd,
+            volume,
+            sol_price_searched_at: Mutex::new(None),
         })
     }
 
+    /// Last known USD price of `mint`
+    pub fn price(&self, mint: &Pubkey) -> Option<f64> {
+        self.prices.price(mint)
+    }
+
     /// Discover all pools in parallel
     pub async fn discover_all(
         &self,
@@ -59,6 +95,32 @@ impl PoolDiscoveryService {
     ) -> SwapResult<Vec<PoolInfo>> {
         info!("Discovering all pools for {}/{}", token_a, token_b);
 
+        // Pool state read from here on is at least as recent as this slot
+        let context_slot = self.current_slot().await;
+
+        let mut all_pools = self.find_pair_pools(token_a, token_b).await;
+
+        // The pair's own pools price one side from the other; other pools
+        // are only searched for tokens they leave unpriced
+        self.prices.learn_from_pools(&all_pools);
+        self.resolve_prices(&[token_a, token_b], &all_pools).await;
+        self.prices.revalue(&mut all_pools);
+
+        for pool in &mut all_pools {
+            if pool.context_slot == 0 {
+                pool.context_slot = context_slot;
+            }
+        }
+
+        self.retain_liquid(&mut all_pools);
+        self.attach_volumes(&mut all_pools).await;
+
+        info!("Found {} total pools after filtering", all_pools.len());
+        Ok(all_pools)
+    }
+
+    /// Query every finder for pools of a pair, logging finders that fail
+    async fn find_pair_pools(&self, token_a: Pubkey, token_b: Pubkey) -> Vec<PoolInfo> {
         let futures = self.finders.iter().map(|finder| {
             finder.find_pools(token_a, token_b)
         });
@@ -77,23 +139,186 @@ impl PoolDiscoveryService {
                 }
             }
         }
+        all_pools
+    }
 
-        // TODO: handle this
-        // Filter by minimum liquidity
-        // all_pools.retain(|pool| pool.liquidity_usd >= self.config.min_liquidity_usd);
+    /// Learn USD prices for `tokens` still unpriced from their pools
+    /// against USDC, USDT or SOL, in that order, skipping pairs `known`
+    /// already covers and re-learning from `known` after each search
+    async fn resolve_prices(&self, tokens: &[Pubkey], known: &[PoolInfo]) {
+        let sol = Pubkey::from_str(WSOL_MINT).unwrap();
+        let usdc = Pubkey::from_str(USDC_MINT).unwrap();
+        let usdt = Pubkey::from_str(USDT_MINT).unwrap();
 
-        info!("Found {} total pools after filtering", all_pools.len());
-        Ok(all_pools)
+        for token in tokens {
+            if *token == sol {
+                self.resolve_sol_price().await;
+                self.prices.learn_from_pools(known);
+                continue;
+            }
+
+            for quote_token in [usdc, usdt, sol] {
+                if self.prices.price(token).is_some() {
+                    break;
+                }
+                if *token == quote_token || pairs(known, token, &quote_token) {
+                    continue;
+                }
+                if quote_token == sol {
+                    self.resolve_sol_price().await;
+                }
+
+                let pools = self.find_pair_pools(*token, quote_token).await;
+                self.prices.learn_from_pools(&pools);
+                self.prices.learn_from_pools(known);
+            }
+
+            if self.prices.price(token).is_none() {
+                debug!("No USD price found for {}", token);
+            }
+        }
+    }
+
+    /// Learn SOL's price from the SOL/USDC pools, searching at most once
+    /// per price cache lifetime
+    async fn resolve_sol_price(&self) {
+        let sol = Pubkey::from_str(WSOL_MINT).unwrap();
+        let usdc = Pubkey::from_str(USDC_MINT).unwrap();
+        if self.prices.price(&sol).is_some() {
+            return;
+        }
+        {
+            let mut searched_at = self.sol_price_searched_at.lock().unwrap();
+            if searched_at.is_some_and(|at| at.elapsed() < Duration::from_secs(PRICE_CACHE_TTL)) {
+                return;
+            }
+            *searched_at = Some(Instant::now());
+        }
+
+        let pools = self.find_pair_pools(sol, usdc).await;
+        self.prices.learn_from_pools(&pools);
+    }
+
+    /// Fill in each pool's estimated 24h volume
+    async fn attach_volumes(&self, pools: &mut [PoolInfo]) {
+        let Some(volume) = &self.volume else {
+            return;
+        };
+
+        // Each pool costs up to `sample_size` + 1 RPC calls, so only a few
+        // pools are read at once
+        let volumes: Vec<f64> = stream::iter(pools.iter().map(|pool| volume.volume_24h_usd(pool)))
+            .buffered(VOLUME_POOL_CONCURRENCY)
+            .collect()
+            .await;
+        for (pool, volume_24h_usd) in pools.iter_mut().zip(volumes) {
+            pool.volume_24h_usd = volume_24h_usd;
+        }
+    }
+
+    /// Drop pools valued below the configured minimum liquidity; pools
+    /// whose tokens have no known price are kept rather than misjudged
+    fn retain_liquid(&self, pools: &mut Vec<PoolInfo>) {
+        let before = pools.len();
+        pools.retain(|pool| {
+            !self.prices.can_price(pool) || pool.liquidity_usd >= self.min_liquidity_usd
+        });
+        if pools.len() < before {
+            debug!(
+                "Dropped {} pools below ${} liquidity",
+                before - pools.len(),
+                self.min_liquidity_usd
+            );
+        }
+    }
+
+    /// Re-read a pool's on-chain state, stamping it with the slot it was read at
+    pub async fn refresh_pool(&self, pool: &PoolInfo) -> SwapResult<PoolInfo> {
+        let finder = self
+            .finders
+            .iter()
+            .find(|finder| finder.pool_type() == pool.pool_type)
+            .ok_or(SwapError::UnsupportedPoolType(pool.pool_type))?;
+
+        let context_slot = self.current_slot().await;
+        let account = self
+            .rpc_client
+            .get_account(&pool.address)
+            .await
+            .map_err(SwapError::from)?;
+
+        let mut refreshed = finder
+            .refresh_pool(pool.address, &account.data, context_slot)
+            .await?
+            .ok_or_else(|| SwapError::PoolNotFound(format!("{} is no longer usable", pool.address)))?;
+        refreshed.context_slot = context_slot;
+        refreshed.volume_24h_usd = pool.volume_24h_usd;
+        Ok(refreshed)
+    }
+
+    /// Current confirmed slot, or 0 if it cannot be fetched
+    async fn current_slot(&self) -> u64 {
+        match self.rpc_client.get_slot().await {
+            Ok(slot) => slot,
+            Err(e) => {
+                debug!("Failed to fetch current slot: {}", e);
+                0
+            }
+        }
     }
 
     /// Find all pools containing a specific token
     pub async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        self.find_pools_by_token_and_type(token, &[
+            PoolType::AMM,
+            PoolType::Stable,
+            PoolType::CLMM,
+            PoolType::Standard,
+        ])
+        .await
+    }
+
+    /// Find all pools of the given types containing a specific token
+    pub async fn find_pools_by_token_and_type(
+        &self,
+        token: Pubkey,
+        pool_types: &[PoolType],
+    ) -> SwapResult<Vec<PoolInfo>> {
         info!("Searching for all pools containing token {}", token);
 
-        // TODO: Implement token-based discovery across all finders
-        // For now, only use AMM finder - expand to other pool types later
-        let amm_finder = AmmPoolFinder::new(self.rpc_client.clone(), self.config.rpc_url.clone());
-        amm_finder.parser.find_pools_by_token(token).await
+        let context_slot = self.current_slot().await;
+        let futures = self
+            .finders
+            .iter()
+            .filter(|finder| pool_types.contains(&finder.pool_type()))
+            .map(|finder| finder.find_pools_by_token(token));
+
+        let mut all_pools = Vec::new();
+        for (i, result) in join_all(futures).await.into_iter().enumerate() {
+            match result {
+                Ok(pools) => {
+                    debug!("Found {} pools containing token from finder {}", pools.len(), i);
+                    merge_unique(&mut all_pools, pools);
+                }
+                Err(e) => {
+                    warn!("Error from finder {}: {}", i, e);
+                }
+            }
+        }
+
+        for pool in &mut all_pools {
+            if pool.context_slot == 0 {
+                pool.context_slot = context_slot;
+            }
+        }
+
+        self.prices.learn_from_pools(&all_pools);
+        self.resolve_prices(&[token], &all_pools).await;
+        self.prices.revalue(&mut all_pools);
+        self.retain_liquid(&mut all_pools);
+
+        info!("Found {} total pools containing token", all_pools.len());
+        Ok(all_pools)
     }
 }
 
@@ -103,9 +328,9 @@ struct AmmPoolFinder {
 }
 
 impl AmmPoolFinder {
-    fn new(rpc_client: Arc<RpcClient>, rpc_url: String) -> Self {
+    fn new(rpc_client: Arc<RpcClient>, rpc_url: String, prices: Arc<PriceService>) -> Self {
         Self {
-            parser: AmmPoolParser::new(rpc_client, rpc_url),
+            parser: AmmPoolParser::new(rpc_client, rpc_url, prices),
         }
     }
 }
@@ -119,6 +344,23 @@ impl PoolFinder for AmmPoolFinder {
     ) -> SwapResult<Vec<PoolInfo>> {
         self.parser.find_pools_for_pair(token_a, token_b).await
     }
+
+    async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        self.parser.find_pools_by_token(token).await
+    }
+
+    fn pool_type(&self) -> PoolType {
+        PoolType::AMM
+    }
+
+    async fn refresh_pool(
+        &self,
+        address: Pubkey,
+        data: &[u8],
+        _context_slot: u64,
+    ) -> SwapResult<Option<PoolInfo>> {
+        self.parser.parse_pool(address, data).await
+    }
 }
 
 /// Stable Pool Finder
@@ -127,9 +369,9 @@ struct StablePoolFinder {
 }
 
 impl StablePoolFinder {
-    fn new(rpc_client: Arc<RpcClient>) -> Self {
+    fn new(rpc_client: Arc<RpcClient>, prices: Arc<PriceService>) -> Self {
         Self {
-            parser: StablePoolParser::new(rpc_client),
+            parser: StablePoolParser::new(rpc_client, prices),
         }
     }
 }
@@ -143,6 +385,23 @@ impl PoolFinder for StablePoolFinder {
     ) -> SwapResult<Vec<PoolInfo>> {
         self.parser.find_pools_for_pair(token_a, token_b).await
     }
+
+    async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        self.parser.find_pools_by_token(token).await
+    }
+
+    fn pool_type(&self) -> PoolType {
+        PoolType::Stable
+    }
+
+    async fn refresh_pool(
+        &self,
+        address: Pubkey,
+        data: &[u8],
+        _context_slot: u64,
+    ) -> SwapResult<Option<PoolInfo>> {
+        self.parser.parse_pool(address, data).await
+    }
 }
 
 /// CLMM Pool Finder
@@ -151,9 +410,9 @@ struct ClmmPoolFinder {
 }
 
 impl ClmmPoolFinder {
-    fn new(rpc_client: Arc<RpcClient>, rpc_url: String) -> Self {
+    fn new(rpc_client: Arc<RpcClient>, rpc_url: String, prices: Arc<PriceService>) -> Self {
         Self {
-            parser: OptimizedClmmPoolParser::new(rpc_client, rpc_url),
+            parser: OptimizedClmmPoolParser::new(rpc_client, rpc_url, prices),
         }
     }
 }
@@ -167,6 +426,23 @@ impl PoolFinder for ClmmPoolFinder {
     ) -> SwapResult<Vec<PoolInfo>> {
         self.parser.find_pools_for_pair(token_a, token_b).await
     }
+
+    async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        self.parser.find_pools_by_token(token).await
+    }
+
+    fn pool_type(&self) -> PoolType {
+        PoolType::CLMM
+    }
+
+    async fn refresh_pool(
+        &self,
+        address: Pubkey,
+        data: &[u8],
+        context_slot: u64,
+    ) -> SwapResult<Option<PoolInfo>> {
+        self.parser.refresh_pool(address, data, context_slot).await
+    }
 }
 
 /// Standard Pool Finder (delegates to CP pool finder since they're the same)
@@ -175,9 +451,9 @@ struct StandardPoolFinder {
 }
 
 impl StandardPoolFinder {
-    fn new(rpc_client: Arc<RpcClient>) -> Self {
+    fn new(rpc_client: Arc<RpcClient>, prices: Arc<PriceService>) -> Self {
         Self { 
-            cp_finder: CpPoolFinder::new(rpc_client)
+            cp_finder: CpPoolFinder::new(rpc_client, prices)
         }
     }
 }
@@ -194,6 +470,23 @@ impl PoolFinder for StandardPoolFinder {
         // Standard pools are actually CP pools
         self.cp_finder.find_pools(token_a, token_b).await
     }
+
+    async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        self.cp_finder.find_pools_by_token(token).await
+    }
+
+    fn pool_type(&self) -> PoolType {
+        PoolType::Standard
+    }
+
+    async fn refresh_pool(
+        &self,
+        address: Pubkey,
+        data: &[u8],
+        context_slot: u64,
+    ) -> SwapResult<Option<PoolInfo>> {
+        self.cp_finder.refresh_pool(address, data, context_slot).await
+    }
 }
 
 /// CP Pool Finder
@@ -202,9 +495,9 @@ struct CpPoolFinder {
 }
 
 impl CpPoolFinder {
-    fn new(rpc_client: Arc<RpcClient>) -> Self {
+    fn new(rpc_client: Arc<RpcClient>, prices: Arc<PriceService>) -> Self {
         Self {
-            parser: CpPoolParser::new(rpc_client),
+            parser: CpPoolParser::new(rpc_client, prices),
         }
     }
 }
@@ -218,4 +511,152 @@ impl PoolFinder for CpPoolFinder {
     ) -> SwapResult<Vec<PoolInfo>> {
         self.parser.find_pools_for_pair(token_a, token_b).await
     }
-}
\ No newline at end of file
+
+    async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        self.parser.find_pools_by_token(token).await
+    }
+
+    fn pool_type(&self) -> PoolType {
+        PoolType::Standard
+    }
+
+    async fn refresh_pool(
+        &self,
+        address: Pubkey,
+        data: &[u8],
+        _context_slot: u64,
+    ) -> SwapResult<Option<PoolInfo>> {
+        self.parser.parse_pool(address, data).await
+    }
+}
+
+/// Whether any of `pools` pairs `token_a` with `token_b`
+fn pairs(pools: &[PoolInfo], token_a: &Pubkey, token_b: &Pubkey) -> bool {
+    pools.iter().any(|pool| {
+        let mints = [pool.token_a.mint, pool.token_b.mint];
+        mints.contains(token_a) && mints.contains(token_b)
+    })
+}
+
+/// Append pools not already present, keeping the first copy of each address
+fn merge_unique(pools: &mut Vec<PoolInfo>, found: Vec<PoolInfo>) {
+    for pool in found {
+        if !pools.iter().any(|p| p.address == pool.address) {
+            pools.push(pool);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fixtures::{standard_pool, token};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Finder serving a fixed set of pools, counting pair searches
+    struct MockFinder {
+        pools: Vec<PoolInfo>,
+        searches: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl PoolFinder for MockFinder {
+        async fn find_pools(&self, token_a: Pubkey, token_b: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+            self.searches.fetch_add(1, Ordering::SeqCst);
+            Ok(self
+                .pools
+                .iter()
+                .filter(|pool| pairs(std::slice::from_ref(pool), &token_a, &token_b))
+                .cloned()
+                .collect())
+        }
+
+        async fn find_pools_by_token(&self, _token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+            Ok(Vec::new())
+        }
+
+        fn pool_type(&self) -> PoolType {
+            PoolType::Standard
+        }
+
+        async fn refresh_pool(&self, _address: Pubkey, _data: &[u8], _context_slot: u64) -> SwapResult<Option<PoolInfo>> {
+            Ok(None)
+        }
+    }
+
+    fn service(pools: Vec<PoolInfo>) -> (PoolDiscoveryService, Arc<AtomicUsize>) {
+        let searches = Arc::new(AtomicUsize::new(0));
+        let service = PoolDiscoveryService {
+            rpc_client: Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string())),
+            finders: vec![Box::new(MockFinder { pools, searches: searches.clone() })],
+            prices: Arc::new(PriceService::new(PRICE_CACHE_TTL)),
+            min_liquidity_usd: 0.0,
+            volume: None,
+            sol_price_searched_at: Mutex::new(None),
+        };
+        (service, searches)
+    }
+
+    fn pool(address: Pubkey, pool_type: PoolType) -> PoolInfo {
+        let (token_a, token_b) = (token(Pubkey::new_unique(), 6), token(Pubkey::new_unique(), 6));
+        PoolInfo {
+            pool_type,
+            address,
+            ..standard_pool(token_a, token_b, 1_000_000, 1_000_000)
+        }
+    }
+
+    #[test]
+    fn test_merge_unique_deduplicates_by_address() {
+        let (shared, amm_only, cp_only) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pools = Vec::new();
+
+        merge_unique(&mut pools, vec![pool(shared, PoolType::AMM), pool(amm_only, PoolType::AMM)]);
+        merge_unique(&mut pools, vec![pool(shared, PoolType::Standard), pool(cp_only, PoolType::Standard)]);
+
+        let addresses: Vec<Pubkey> = pools.iter().map(|p| p.address).collect();
+        assert_eq!(addresses, vec![shared, amm_only, cp_only]);
+        assert_eq!(pools[0].pool_type, PoolType::AMM);
+    }
+
+    #[tokio::test]
+    async fn test_prices_learned_from_pair_pools_first() {
+        let sol = Pubkey::from_str(WSOL_MINT).unwrap();
+        let usdc = Pubkey::from_str(USDC_MINT).unwrap();
+        let meme = Pubkey::new_unique();
+        // 1000 SOL / 150,000 USDC and 1 SOL = 2,000,000 MEME
+        let sol_usdc = standard_pool(token(sol, 9), token(usdc, 6), 1_000_000_000_000, 150_000_000_000);
+        let meme_sol = standard_pool(token(meme, 6), token(sol, 9), 2_000_000_000_000, 1_000_000_000);
+        let (service, searches) = service(vec![sol_usdc]);
+
+        // MEME is searched against USDC and USDT but not SOL, whose pools
+        // are the pair's own; SOL/USDC is searched once and prices both
+        service.resolve_prices(&[meme, sol], std::slice::from_ref(&meme_sol)).await;
+        assert_eq!(searches.load(Ordering::SeqCst), 3);
+        assert!((service.prices.price(&sol).unwrap() - 150.0).abs() < 1e-9);
+        assert!((service.prices.price(&meme).unwrap() - 0.000075).abs() < 1e-12);
+
+        // Priced tokens need no further searches
+        service.resolve_prices(&[meme, sol], &[]).await;
+        assert_eq!(searches.load(Ordering::SeqCst), 3);
+
+        // A pair with a priced side needs none either
+        let other = Pubkey::new_unique();
+        let other_usdc = standard_pool(token(other, 6), token(usdc, 6), 1_000_000, 2_000_000);
+        service.prices.learn_from_pools(std::slice::from_ref(&other_usdc));
+        service.resolve_prices(&[other, usdc], &[other_usdc]).await;
+        assert_eq!(searches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_sol_usdc_search_is_cached() {
+        let sol = Pubkey::from_str(WSOL_MINT).unwrap();
+        let (service, searches) = service(Vec::new());
+
+        // Without SOL/USDC pools SOL stays unpriced, but is not searched again
+        service.resolve_prices(&[sol], &[]).await;
+        service.resolve_prices(&[sol], &[]).await;
+        assert_eq!(searches.load(Ordering::SeqCst), 1);
+        assert_eq!(service.prices.price(&sol), None);
+    }
+}
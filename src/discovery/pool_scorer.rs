@@ -1,18 +1,23 @@
-use crate::core::{PoolInfo, PoolScore, PoolType};
+use crate::core::{scoring::depth_usd, PoolInfo, PoolScore, PoolType, ScoringStrategy};
 use log::debug;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-/// Pool scoring service
+/// Pool scoring service, ranking pools by a `ScoringStrategy`
 pub struct PoolScorer {
-    _liquidity_weight: f64,
-    _volume_weight: f64,
+    strategy: ScoringStrategy,
 }
 
 impl PoolScorer {
     pub fn new() -> Self {
-        Self {
-            _liquidity_weight: 0.6,
-            _volume_weight: 0.4,
-        }
+        Self::with_strategy(ScoringStrategy::default())
+    }
+
+    pub fn with_strategy(strategy: ScoringStrategy) -> Self {
+        Self { strategy }
+    }
+
+    pub fn strategy(&self) -> &ScoringStrategy {
+        &self.strategy
     }
 
     /// Score all pools
@@ -25,16 +30,39 @@ impl PoolScorer {
 
     /// Score a single pool
     pub fn score_pool(&self, pool: PoolInfo) -> PoolScore {
+        let strategy = &self.strategy;
         let liquidity_score = self.calculate_liquidity_score(pool.liquidity_usd);
         let volume_score = self.calculate_volume_score(pool.volume_24h_usd);
+        let depth_score = self.calculate_depth_score(&pool);
         let type_bonus = self.get_pool_type_bonus(&pool);
+        let fee_multiplier = strategy.fee_multiplier(&pool);
+        let risk_flags = strategy.risk_flags(&pool, unix_now());
+
+        let weighted = liquidity_score * strategy.liquidity_weight
+            + volume_score * strategy.volume_weight
+            + depth_score * strategy.depth_weight;
+        let score = weighted * type_bonus * fee_multiplier * strategy.risk_multiplier(&risk_flags);
 
         debug!(
-            "Pool {} scoring: liquidity={:.2}, volume={:.2}, type_bonus={:.2}",
-            pool.address, liquidity_score, volume_score, type_bonus
+            "Pool {} scoring ({}): liquidity={:.2}, volume={:.2}, depth={:.2}, type_bonus={:.2}, fee={:.2}, risks={:?}",
+            pool.address, strategy, liquidity_score, volume_score, depth_score, type_bonus, fee_multiplier, risk_flags
         );
 
-        PoolScore::new(pool, liquidity_score, volume_score, type_bonus)
+        PoolScore {
+            pool,
+            score,
+            liquidity_score,
+            volume_score,
+            depth_score,
+            type_bonus,
+            fee_multiplier,
+            risk_flags,
+        }
+    }
+
+    /// Score of a pool the caller keeps
+    pub fn score(&self, pool: &PoolInfo) -> f64 {
+        self.score_pool(pool.clone()).score
     }
 
     /// Calculate liquidity score (logarithmic scale)
@@ -59,34 +87,26 @@ impl PoolScorer {
         score * 100.0
     }
 
+    /// Calculate depth score from the trade size that moves the price by
+    /// the strategy's impact (logarithmic scale)
+    fn calculate_depth_score(&self, pool: &PoolInfo) -> f64 {
+        let depth = depth_usd(pool, self.strategy.depth_impact);
+        if depth <= 0.0 {
+            return 0.0;
+        }
+
+        (depth.ln() / 100000.0_f64.ln()).clamp(0.0, 1.0) * 100.0
+    }
+
     /// Get pool type bonus
     fn get_pool_type_bonus(&self, pool: &PoolInfo) -> f64 {
+        let bonuses = &self.strategy.type_bonuses;
         match pool.pool_type {
-            PoolType::Stable => {
-                // Extra bonus for stable pairs
-                if self.is_stable_pair(pool) {
-                    1.5
-                } else {
-                    1.2
-                }
-            }
-            PoolType::CLMM => {
-                // CLMM pools get bonus for capital efficiency
-                // Higher bonus for pools with tighter fee tiers
-                if let crate::core::PoolState::CLMM { fee_tier, .. } = &pool.pool_state {
-                    match fee_tier {
-                        1 => 1.3,    // 0.01% fee - highest capital efficiency
-                        5 => 1.25,   // 0.05% fee
-                        30 => 1.2,   // 0.3% fee
-                        100 => 1.15, // 1% fee
-                        _ => 1.1,    // Other fee tiers
-                    }
-                } else {
-                    1.1
-                }
-            }
-            PoolType::AMM => 1.0,
-            PoolType::Standard => 0.9, // Slight penalty for legacy pools
+            PoolType::Stable if self.is_stable_pair(pool) => bonuses.stable_pair,
+            PoolType::Stable => bonuses.stable,
+            PoolType::CLMM => bonuses.clmm,
+            PoolType::AMM => bonuses.amm,
+            PoolType::Standard => bonuses.standard,
         }
     }
 
@@ -99,6 +119,12 @@ impl PoolScorer {
     }
 }
 
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
 impl Default for PoolScorer {
     fn default() -> Self {
         Self::new()
@@ -131,10 +157,16 @@ mod tests {
             volume_24h_usd: volume,
             fee_rate: 0.0025,
             program_id: Pubkey::new_unique(),
+            context_slot: 0,
+            open_time: 0,
             pool_state: PoolState::AMM {
                 reserve_a: 1000000,
                 reserve_b: 1000000,
                 nonce: 1,
+                need_take_pnl_coin: 0,
+                need_take_pnl_pc: 0,
+                swap_fee_numerator: 25,
+                swap_fee_denominator: 10000,
             },
         }
     }
@@ -1,8 +1,7 @@
 use crate::core::{
-    constants::*, layouts::StablePoolState, PoolInfo, PoolState, PoolType, SwapError, SwapResult,
-    TokenInfo,
+    constants::*, layouts::StablePoolState, PoolInfo, PoolState, PoolType, PriceService, SwapError,
+    SwapResult, TokenInfo,
 };
-use borsh::BorshDeserialize;
 use log::{debug, warn};
 use solana_account_decoder::UiAccountEncoding;
 use solana_client::{
@@ -10,16 +9,21 @@ use solana_client::{
     rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
     rpc_filter::{Memcmp, RpcFilterType},
 };
-use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, clock::Clock, sysvar};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
 use std::sync::Arc;
 
+/// Offsets of the token mints in the Stable pool account
+const TOKEN_MINT_A_OFFSET: usize = StablePoolState::COIN_MINT_OFFSET;
+const TOKEN_MINT_B_OFFSET: usize = StablePoolState::PC_MINT_OFFSET;
+
 pub struct StablePoolParser {
     rpc_client: Arc<RpcClient>,
+    price_service: Arc<PriceService>,
 }
 
 impl StablePoolParser {
-    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
-        Self { rpc_client }
+    pub fn new(rpc_client: Arc<RpcClient>, price_service: Arc<PriceService>) -> Self {
+        Self { rpc_client, price_service }
     }
 
     /// Parse Stable pool from account data
@@ -34,8 +38,7 @@ impl StablePoolParser {
             data.len()
         );
 
-        // Parse pool state using Borsh deserialization
-        let pool_state = match StablePoolState::try_from_slice(data) {
+        let pool_state = match StablePoolState::from_bytes(data) {
             Ok(state) => state,
             Err(e) => {
                 debug!("Failed to parse Stable pool {}: {}", address, e);
@@ -43,21 +46,20 @@ impl StablePoolParser {
             }
         };
 
-        // Check if pool is initialized
-        if !pool_state.is_initialized {
-            debug!("Stable pool {} is not initialized", address);
-            return Ok(None);
-        }
-
-        // Check if pool is not paused
-        if pool_state.is_paused {
-            debug!("Stable pool {} is paused", address);
+        if !pool_state.is_swappable() {
+            debug!("Stable pool {} is not open for swaps (status {})", address, pool_state.status);
             return Ok(None);
         }
 
-        // Get token reserves from token accounts
-        let token_a_balance = self.get_token_balance(&pool_state.token_a_account).await?;
-        let token_b_balance = self.get_token_balance(&pool_state.token_b_account).await?;
+        // Vault balances less the PnL owed to the protocol
+        let token_a_balance = self
+            .get_token_balance(&pool_state.coin_vault)
+            .await?
+            .saturating_sub(pool_state.need_take_pnl_coin);
+        let token_b_balance = self
+            .get_token_balance(&pool_state.pc_vault)
+            .await?
+            .saturating_sub(pool_state.need_take_pnl_pc);
 
         // Skip pools with no liquidity
         if token_a_balance == 0 || token_b_balance == 0 {
@@ -66,35 +68,30 @@ impl StablePoolParser {
         }
 
         // Get token metadata
-        let token_a_info = self.get_token_info(&pool_state.token_mint_a).await?;
-        let token_b_info = self.get_token_info(&pool_state.token_mint_b).await?;
-
-        // Get current timestamp for amp calculation
-        let current_timestamp = self.get_current_timestamp().await?;
-        let current_amp = pool_state.get_current_amp(current_timestamp);
+        let token_a_info = self.get_token_info(&pool_state.coin_mint).await?;
+        let token_b_info = self.get_token_info(&pool_state.pc_mint).await?;
 
-        // Calculate liquidity in USD (simplified)
-        let liquidity_usd = self.estimate_liquidity_usd(
-            token_a_balance,
-            token_b_balance,
-            &token_a_info,
-            &token_b_info,
-        );
-
-        Ok(Some(PoolInfo {
+        let mut pool = PoolInfo {
             pool_type: PoolType::Stable,
             address,
             token_a: token_a_info,
             token_b: token_b_info,
-            liquidity_usd,
-            volume_24h_usd: 0.0, // Would need to track swaps or use API
-            fee_rate: pool_state.get_trade_fee_rate(),
+            liquidity_usd: 0.0,
+            volume_24h_usd: 0.0, // Estimated by discovery from recent swaps
+            fee_rate: pool_state.get_swap_fee_rate(),
             program_id: *STABLE_PROGRAM,
+            context_slot: 0,
+            open_time: pool_state.pool_open_time,
             pool_state: PoolState::Stable {
                 reserves: vec![token_a_balance, token_b_balance],
-                amp_factor: current_amp,
+                amp_factor: STABLE_AMP_FACTOR,
+                swap_fee_numerator: pool_state.swap_fee_numerator,
+                swap_fee_denominator: pool_state.swap_fee_denominator,
             },
-        }))
+        };
+        pool.liquidity_usd = self.price_service.pool_liquidity_usd(&pool, token_a_balance, token_b_balance);
+
+        Ok(Some(pool))
     }
 
     /// Find all Stable pools for a token pair
@@ -105,28 +102,11 @@ impl StablePoolParser {
     ) -> SwapResult<Vec<PoolInfo>> {
         debug!("Searching for Stable pools: {}/{}", token_a, token_b);
 
-        // Calculate offsets for token_mint_a and token_mint_b in StablePoolState
-        // StablePoolState fields (Borsh serialized):
-        // - is_initialized: bool (1 byte)
-        // - is_paused: bool (1 byte)
-        // - nonce: u8 (1 byte)
-        // - initial_amp_factor: u64 (8 bytes)
-        // - target_amp_factor: u64 (8 bytes)
-        // - start_ramp_timestamp: i64 (8 bytes)
-        // - stop_ramp_timestamp: i64 (8 bytes)
-        // - future_admin_deadline: i64 (8 bytes)
-        // - future_admin_account: Pubkey (32 bytes)
-        // - admin_account: Pubkey (32 bytes)
-        // - token_mint_a: Pubkey (32 bytes) - offset: 107
-        // - token_mint_b: Pubkey (32 bytes) - offset: 139
-        
-        const TOKEN_MINT_A_OFFSET: usize = 107;
-        const TOKEN_MINT_B_OFFSET: usize = 139;
-        
         let mut all_pools = Vec::new();
 
         // Search pattern 1: token_a as mint_a, token_b as mint_b
         let filters1 = vec![
+            RpcFilterType::DataSize(StablePoolState::LEN as u64),
             RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
                 TOKEN_MINT_A_OFFSET,
                 token_a.to_bytes().to_vec(),
@@ -173,6 +153,7 @@ impl StablePoolParser {
 
         // Search pattern 2: token_b as mint_a, token_a as mint_b (reversed)
         let filters2 = vec![
+            RpcFilterType::DataSize(StablePoolState::LEN as u64),
             RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
                 TOKEN_MINT_A_OFFSET,
                 token_b.to_bytes().to_vec(),
@@ -218,6 +199,55 @@ impl StablePoolParser {
         Ok(all_pools)
     }
 
+    /// Find all Stable pools containing a specific token
+    pub async fn find_pools_by_token(&self, token: Pubkey) -> SwapResult<Vec<PoolInfo>> {
+        debug!("Searching for all Stable pools containing token {}", token);
+
+        let mut all_pools = Vec::new();
+
+        for (offset, side) in [(TOKEN_MINT_A_OFFSET, "mint_a"), (TOKEN_MINT_B_OFFSET, "mint_b")] {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::DataSize(StablePoolState::LEN as u64),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, token.to_bytes().to_vec())),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64Zstd),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+
+            match self
+                .rpc_client
+                .get_program_accounts_with_config(&STABLE_PROGRAM, config)
+                .await
+            {
+                Ok(accounts) => {
+                    debug!("Found {} Stable accounts with token as {}", accounts.len(), side);
+                    for (address, account) in accounts {
+                        if all_pools.iter().any(|p: &PoolInfo| p.address == address) {
+                            continue;
+                        }
+                        // One unreadable pool must not hide the others
+                        match self.parse_pool(address, &account.data).await {
+                            Ok(Some(pool)) => all_pools.push(pool),
+                            Ok(None) => {}
+                            Err(e) => debug!("Skipping Stable pool {}: {}", address, e),
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Error searching Stable pools with token as {}: {}", side, e);
+                }
+            }
+        }
+
+        debug!("Found {} total Stable pools containing token", all_pools.len());
+        Ok(all_pools)
+    }
+
     /// Get token balance for an account
     async fn get_token_balance(&self, token_account: &Pubkey) -> SwapResult<u64> {
         match self.rpc_client.get_account(token_account).await {
@@ -275,7 +305,7 @@ impl StablePoolParser {
             .rpc_client
             .get_account(mint)
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         // SPL Token mint layout: decimals at offset 44
         if account.data.len() > 44 {
@@ -284,65 +314,4 @@ impl StablePoolParser {
             Ok(9) // Default to 9 decimals
         }
     }
-
-    /// Get current timestamp from the clock sysvar
-    async fn get_current_timestamp(&self) -> SwapResult<i64> {
-        match self.rpc_client.get_account(&sysvar::clock::id()).await {
-            Ok(account) => {
-                let clock: Clock = bincode::deserialize(&account.data)
-                    .map_err(|e| SwapError::SerializationError(e.to_string()))?;
-                Ok(clock.unix_timestamp)
-            }
-            Err(e) => {
-                warn!("Failed to get clock sysvar: {}, using system time", e);
-                // Fallback to system time
-                Ok(std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs() as i64)
-            }
-        }
-    }
-
-    /// Estimate liquidity in USD
-    fn estimate_liquidity_usd(
-        &self,
-        reserve_a: u64,
-        reserve_b: u64,
-        token_a: &TokenInfo,
-        token_b: &TokenInfo,
-    ) -> f64 {
-        // For stable pools, we can make reasonable assumptions:
-        // 1. If both tokens are stablecoins, liquidity = sum of reserves
-        // 2. If one token is a stablecoin, we can derive the other token's implied price
-        // 3. Otherwise, we return a conservative estimate
-        
-        let amount_a = reserve_a as f64 / 10f64.powi(token_a.decimals as i32);
-        let amount_b = reserve_b as f64 / 10f64.powi(token_b.decimals as i32);
-        
-        // Check if tokens are known stablecoins
-        let is_stable_a = matches!(token_a.symbol.as_str(), "USDC" | "USDT" | "USDH" | "USDD" | "UXD");
-        let is_stable_b = matches!(token_b.symbol.as_str(), "USDC" | "USDT" | "USDH" | "USDD" | "UXD");
-        
-        if is_stable_a && is_stable_b {
-            // Both stablecoins - liquidity is simply sum of amounts
-            amount_a + amount_b
-        } else if is_stable_a {
-            // Token A is stable, derive token B price from pool ratio
-            // In a stable pool with high amp, price ratio should be close to reserve ratio
-            amount_a * 2.0 // Conservative estimate: assume symmetric liquidity
-        } else if is_stable_b {
-            // Token B is stable, derive token A price from pool ratio
-            amount_b * 2.0 // Conservative estimate: assume symmetric liquidity
-        } else {
-            // Neither is a stablecoin - use conservative estimate based on pool size
-            // For stable pools, tokens are usually correlated (e.g., SOL/mSOL, ETH/stETH)
-            // We can't determine USD value without external reference
-            let total_units = amount_a + amount_b;
-            
-            // Return a conservative non-zero estimate to allow pool ranking
-            // Actual USD value will be determined when we have more pool data
-            total_units * 0.1 // Very conservative multiplier
-        }
-    }
 }
\ No newline at end of file
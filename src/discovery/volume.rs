@@ -0,0 +1,538 @@
+use crate::core::{
+    constants::*,
+    events::{executed_instructions, invocation_logs, ClmmSwapEvent, CpSwapEvent, ExecutedInstruction, RayLog},
+    OnchainPriceCalculator, PoolInfo, PoolType, PriceService, SwapError, SwapResult,
+};
+use dashmap::DashMap;
+use futures::future::join_all;
+use log::{debug, warn};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Most signatures `getSignaturesForAddress` returns per call
+const SIGNATURE_PAGE_LIMIT: usize = 1_000;
+/// Transactions fetched concurrently per pool
+const TRANSACTION_FETCH_CONCURRENCY: usize = 10;
+
+/// A transaction that touched an address
+#[derive(Debug, Clone)]
+pub struct SignatureEntry {
+    pub signature: Signature,
+    pub block_time: Option<i64>,
+    pub failed: bool,
+}
+
+/// What volume estimation reads from a confirmed transaction
+#[derive(Debug, Clone, Default)]
+pub struct TransactionRecord {
+    pub logs: Vec<String>,
+    /// Instructions in execution order, inner instructions included
+    pub instructions: Vec<ExecutedInstruction>,
+}
+
+/// RPC calls needed to estimate volume, abstracted so they can be mocked
+#[async_trait::async_trait]
+pub trait VolumeRpc: Send + Sync {
+    /// Up to `limit` signatures for `address`, newest first, older than
+    /// `before` and newer than `until`
+    async fn signatures(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> SwapResult<Vec<SignatureEntry>>;
+
+    /// Logs and executed instructions of a confirmed transaction
+    async fn transaction(&self, signature: &Signature) -> SwapResult<TransactionRecord>;
+}
+
+#[async_trait::async_trait]
+impl VolumeRpc for RpcClient {
+    async fn signatures(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> SwapResult<Vec<SignatureEntry>> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(limit),
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let statuses = self
+            .get_signatures_for_address_with_config(address, config)
+            .await
+            .map_err(SwapError::from)?;
+
+        Ok(statuses
+            .into_iter()
+            .filter_map(|status| {
+                Some(SignatureEntry {
+                    signature: Signature::from_str(&status.signature).ok()?,
+                    block_time: status.block_time,
+                    failed: status.err.is_some(),
+                })
+            })
+            .collect())
+    }
+
+    async fn transaction(&self, signature: &Signature) -> SwapResult<TransactionRecord> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+        let transaction = self
+            .get_transaction_with_config(signature, config)
+            .await
+            .map_err(SwapError::from)?;
+
+        let logs = match transaction.transaction.meta.as_ref().map(|meta| &meta.log_messages) {
+            Some(OptionSerializer::Some(logs)) => logs.clone(),
+            _ => Vec::new(),
+        };
+        Ok(TransactionRecord {
+            logs,
+            instructions: executed_instructions(&transaction),
+        })
+    }
+}
+
+/// Swaps seen in a pool's trailing 24 hours
+struct VolumeWindow {
+    /// Block time and USD value of each swap, newest first
+    swaps: VecDeque<(i64, f64)>,
+    newest_signature: Option<Signature>,
+    /// Every swap since this time is recorded
+    complete_since: i64,
+    refreshed_at: Instant,
+}
+
+impl VolumeWindow {
+    /// USD volume over the 24 hours before `now`, extrapolated from the
+    /// recorded span when older swaps were not sampled
+    fn total_usd(&self, now: i64) -> f64 {
+        let cutoff = now - VOLUME_WINDOW_SECS;
+        let total: f64 = self
+            .swaps
+            .iter()
+            .filter(|(time, _)| *time >= cutoff)
+            .map(|(_, usd)| usd)
+            .sum();
+
+        let covered = (now - self.complete_since.max(cutoff)).max(1);
+        total * VOLUME_WINDOW_SECS as f64 / covered as f64
+    }
+}
+
+/// Estimates pools' 24h USD volume from the swap events in their recent
+/// transactions, keeping a rolling window per pool
+///
+/// At most `sample_size` new transactions are read per refresh; when they
+/// do not reach back 24 hours the sampled span is extrapolated.
+pub struct VolumeEstimator {
+    rpc: Arc<dyn VolumeRpc>,
+    prices: Arc<PriceService>,
+    windows: DashMap<Pubkey, VolumeWindow>,
+    sample_size: usize,
+    refresh_interval: Duration,
+}
+
+impl VolumeEstimator {
+    pub fn new(rpc: Arc<dyn VolumeRpc>, prices: Arc<PriceService>, sample_size: usize) -> Self {
+        Self {
+            rpc,
+            prices,
+            windows: DashMap::new(),
+            sample_size,
+            refresh_interval: Duration::from_secs(VOLUME_CACHE_TTL),
+        }
+    }
+
+    /// Estimated USD volume of `pool` over the last 24 hours
+    pub async fn volume_24h_usd(&self, pool: &PoolInfo) -> f64 {
+        let now = unix_now();
+        let fresh = self
+            .windows
+            .get(&pool.address)
+            .is_some_and(|window| window.refreshed_at.elapsed() < self.refresh_interval);
+
+        if !fresh {
+            if let Err(e) = self.refresh(pool, now).await {
+                warn!("Failed to estimate volume of pool {}: {}", pool.address, e);
+            }
+        }
+
+        self.windows
+            .get(&pool.address)
+            .map_or(0.0, |window| window.total_usd(now))
+    }
+
+    /// Read transactions since the last refresh into the pool's window
+    async fn refresh(&self, pool: &PoolInfo, now: i64) -> SwapResult<()> {
+        let cutoff = now - VOLUME_WINDOW_SECS;
+        let until = self
+            .windows
+            .get(&pool.address)
+            .and_then(|window| window.newest_signature);
+
+        let mut entries: Vec<SignatureEntry> = Vec::new();
+        let mut before = None;
+        // Whether paging reached the previous refresh or the 24h cutoff
+        let mut contiguous = false;
+        'paging: while entries.len() < self.sample_size {
+            let limit = (self.sample_size - entries.len()).min(SIGNATURE_PAGE_LIMIT);
+            let page = self.rpc.signatures(&pool.address, before, until, limit).await?;
+            let exhausted = page.len() < limit;
+
+            for entry in page {
+                if entry.block_time.is_some_and(|time| time < cutoff) {
+                    contiguous = true;
+                    break 'paging;
+                }
+                before = Some(entry.signature);
+                entries.push(entry);
+            }
+
+            if exhausted {
+                contiguous = true;
+                break;
+            }
+        }
+
+        let successful: Vec<&SignatureEntry> = entries.iter().filter(|entry| !entry.failed).collect();
+        let mut swaps = VecDeque::new();
+        for chunk in successful.chunks(TRANSACTION_FETCH_CONCURRENCY) {
+            let results = join_all(chunk.iter().map(|entry| self.rpc.transaction(&entry.signature))).await;
+            for (entry, result) in chunk.iter().zip(results) {
+                let transaction = match result {
+                    Ok(transaction) => transaction,
+                    Err(e) => {
+                        debug!("Skipping transaction {}: {}", entry.signature, e);
+                        continue;
+                    }
+                };
+                let time = entry.block_time.unwrap_or(now);
+                for (amount_a, amount_b) in swap_inputs(pool, &transaction) {
+                    swaps.push_back((time, self.prices.liquidity_usd(pool, amount_a, amount_b)));
+                }
+            }
+        }
+
+        debug!(
+            "Read {} transactions with {} swaps for pool {}",
+            entries.len(),
+            swaps.len(),
+            pool.address
+        );
+
+        let previous = self.windows.remove(&pool.address).map(|(_, window)| window);
+        let oldest_time = entries.last().and_then(|entry| entry.block_time).unwrap_or(now);
+        let complete_since = match (&previous, contiguous) {
+            (Some(previous), true) => previous.complete_since,
+            (None, true) => cutoff,
+            (_, false) => oldest_time,
+        };
+        let mut newest_signature = entries.first().map(|entry| entry.signature);
+        if contiguous {
+            if let Some(previous) = previous {
+                swaps.extend(previous.swaps);
+                newest_signature = newest_signature.or(previous.newest_signature);
+            }
+        }
+        swaps.retain(|(time, _)| *time >= cutoff);
+
+        self.windows.insert(
+            pool.address,
+            VolumeWindow {
+                swaps,
+                newest_signature,
+                complete_since: complete_since.max(cutoff),
+                refreshed_at: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Amounts of token A and token B paid into `pool` by each swap in `transaction`
+pub fn swap_inputs(pool: &PoolInfo, transaction: &TransactionRecord) -> Vec<(u64, u64)> {
+    let split = |amount: u64, is_a: bool| if is_a { (amount, 0) } else { (0, amount) };
+    let logs = &transaction.logs;
+
+    match pool.pool_type {
+        // AMM V4 and Stable report swaps in `ray_log`, which does not name
+        // the pool; token A is the coin side
+        PoolType::AMM | PoolType::Stable => pool_invocation_logs(pool, transaction)
+            .into_iter()
+            .flatten()
+            .filter_map(|line| RayLog::decode(line)?.swap_input())
+            .map(|(amount, coin_in)| split(amount, coin_in))
+            .collect(),
+        PoolType::CLMM => logs
+            .iter()
+            .filter_map(|line| ClmmSwapEvent::decode(line))
+            .filter(|event| event.pool_state == pool.address)
+            .map(|event| {
+                if event.zero_for_one {
+                    (event.amount_0, 0)
+                } else {
+                    (0, event.amount_1)
+                }
+            })
+            .collect(),
+        PoolType::Standard => logs
+            .iter()
+            .filter_map(|line| CpSwapEvent::decode(line))
+            .filter(|(event, _)| event.pool_id == pool.address)
+            .map(|(event, mints)| {
+                let input_is_a = match mints {
+                    Some(mints) => mints.input_mint == pool.token_a.mint,
+                    None => cp_input_is_a(pool, &event),
+                };
+                split(event.input_amount, input_is_a)
+            })
+            .collect(),
+    }
+}
+
+/// Log lines of each invocation of the pool's program that was given
+/// `pool` as its pool account (account 1)
+///
+/// The k-th invocation in the logs is the k-th instruction of the program
+/// in execution order; transactions whose logs do not line up with their
+/// instructions (e.g. truncated logs) are skipped.
+fn pool_invocation_logs<'a>(pool: &PoolInfo, transaction: &'a TransactionRecord) -> Vec<Vec<&'a str>> {
+    let invocations = invocation_logs(&transaction.logs, &pool.program_id);
+    let instructions: Vec<&ExecutedInstruction> = transaction
+        .instructions
+        .iter()
+        .filter(|instruction| instruction.program_id == pool.program_id)
+        .collect();
+    if invocations.len() != instructions.len() {
+        debug!(
+            "Skipping transaction with {} invocations of {} but {} instructions",
+            invocations.len(),
+            pool.program_id,
+            instructions.len()
+        );
+        return Vec::new();
+    }
+
+    instructions
+        .into_iter()
+        .zip(invocations)
+        .filter(|(instruction, _)| instruction.accounts.get(1) == Some(&pool.address))
+        .map(|(_, lines)| lines)
+        .collect()
+}
+
+/// Whether a CP-Swap event without mints swapped token A in, judged by
+/// which direction matches the pool price
+fn cp_input_is_a(pool: &PoolInfo, event: &CpSwapEvent) -> bool {
+    let Some(spot) = OnchainPriceCalculator::spot_price(pool) else {
+        return true;
+    };
+    if event.input_amount == 0 || event.output_amount == 0 {
+        return true;
+    }
+
+    let ui = |amount: u64, decimals: u8| amount as f64 / 10f64.powi(decimals as i32);
+    let (decimals_a, decimals_b) = (pool.token_a.decimals, pool.token_b.decimals);
+    // Price of A in B implied by each reading of the event
+    let a_in = ui(event.output_amount, decimals_b) / ui(event.input_amount, decimals_a);
+    let b_in = ui(event.input_amount, decimals_b) / ui(event.output_amount, decimals_a);
+    (a_in / spot).ln().abs() <= (b_in / spot).ln().abs()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{events::RAY_LOG_PREFIX, PoolState, TokenInfo, USDC_MINT};
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// In-memory chain history for one address, newest first
+    #[derive(Default)]
+    struct MockRpc {
+        history: Vec<SignatureEntry>,
+        transactions: HashMap<Signature, TransactionRecord>,
+        transaction_calls: AtomicUsize,
+    }
+
+    impl MockRpc {
+        fn push(&mut self, block_time: i64, failed: bool, transaction: TransactionRecord) {
+            let signature = Signature::new_unique();
+            self.history.push(SignatureEntry { signature, block_time: Some(block_time), failed });
+            self.transactions.insert(signature, transaction);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl VolumeRpc for MockRpc {
+        async fn signatures(
+            &self,
+            _address: &Pubkey,
+            before: Option<Signature>,
+            until: Option<Signature>,
+            limit: usize,
+        ) -> SwapResult<Vec<SignatureEntry>> {
+            let start = before
+                .and_then(|before| self.history.iter().position(|e| e.signature == before))
+                .map_or(0, |index| index + 1);
+            Ok(self.history[start..]
+                .iter()
+                .take_while(|entry| Some(entry.signature) != until)
+                .take(limit)
+                .cloned()
+                .collect())
+        }
+
+        async fn transaction(&self, signature: &Signature) -> SwapResult<TransactionRecord> {
+            self.transaction_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.transactions.get(signature).cloned().unwrap_or_default())
+        }
+    }
+
+    /// `ray_log` line for a SwapBaseIn of `amount_in`
+    fn swap_log(amount_in: u64, coin_to_pc: bool) -> String {
+        let direction = if coin_to_pc { 2u64 } else { 1 };
+        let mut data = vec![3u8];
+        for value in [amount_in, 0, direction, 0, 0, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        format!("{}{}", RAY_LOG_PREFIX, STANDARD.encode(data))
+    }
+
+    /// Transaction making AMM V4 swaps of `(pool, amount_in, coin_to_pc)`
+    /// in order, each as a top-level instruction
+    fn amm_swaps(swaps: &[(Pubkey, u64, bool)]) -> TransactionRecord {
+        let program = *AMM_V4_PROGRAM;
+        let mut transaction = TransactionRecord::default();
+        for (pool, amount_in, coin_to_pc) in swaps {
+            transaction.logs.extend([
+                format!("Program {} invoke [1]", program),
+                "Program log: Instruction: SwapBaseIn".to_string(),
+                swap_log(*amount_in, *coin_to_pc),
+                format!("Program {} success", program),
+            ]);
+            transaction.instructions.push(ExecutedInstruction {
+                program_id: program,
+                accounts: vec![spl_token::ID, *pool],
+                data: vec![9],
+            });
+        }
+        transaction
+    }
+
+    /// SOL/USDC AMM pool at $150
+    fn amm_pool() -> PoolInfo {
+        let token = |mint: Pubkey, decimals: u8| TokenInfo {
+            mint,
+            symbol: "TKN".to_string(),
+            decimals,
+            name: "Token".to_string(),
+        };
+        PoolInfo {
+            pool_type: PoolType::AMM,
+            address: Pubkey::new_unique(),
+            token_a: token(Pubkey::new_unique(), 9),
+            token_b: token(Pubkey::from_str(USDC_MINT).unwrap(), 6),
+            liquidity_usd: 0.0,
+            volume_24h_usd: 0.0,
+            fee_rate: 0.0025,
+            program_id: *AMM_V4_PROGRAM,
+            context_slot: 0,
+            open_time: 0,
+            pool_state: PoolState::AMM {
+                reserve_a: 1_000_000_000_000,
+                reserve_b: 150_000_000_000,
+                nonce: 0,
+                need_take_pnl_coin: 0,
+                need_take_pnl_pc: 0,
+                swap_fee_numerator: 25,
+                swap_fee_denominator: 10_000,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_volume_from_mocked_history() {
+        let now = unix_now();
+        let mut rpc = MockRpc::default();
+        let pool = amm_pool();
+        rpc.push(now - 60, false, amm_swaps(&[(pool.address, 2_000_000_000, true)])); // 2 SOL = $300
+        rpc.push(now - 120, true, amm_swaps(&[(pool.address, 1_000_000_000, true)])); // failed
+        rpc.push(now - 3_600, false, amm_swaps(&[(pool.address, 100_000_000, false)])); // $100
+        rpc.push(now - 2 * VOLUME_WINDOW_SECS, false, amm_swaps(&[(pool.address, 50_000_000, false)]));
+        let rpc = Arc::new(rpc);
+
+        let estimator = VolumeEstimator::new(rpc.clone(), Arc::new(PriceService::new(60)), 100);
+        let volume = estimator.volume_24h_usd(&pool).await;
+        assert!((volume - 400.0).abs() < 1e-6, "volume was {}", volume);
+        assert_eq!(rpc.transaction_calls.load(Ordering::SeqCst), 2);
+
+        // Served from the window until the refresh interval passes
+        assert!((estimator.volume_24h_usd(&pool).await - 400.0).abs() < 1e-6);
+        assert_eq!(rpc.transaction_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_volume_extrapolates_partial_sample() {
+        let now = unix_now();
+        let mut rpc = MockRpc::default();
+        let pool = amm_pool();
+        // $100 every hour for the last day, but only two transactions are sampled
+        for hour in 1..=24 {
+            rpc.push(now - hour * 3_600 + 1, false, amm_swaps(&[(pool.address, 100_000_000, false)]));
+        }
+
+        let estimator = VolumeEstimator::new(Arc::new(rpc), Arc::new(PriceService::new(60)), 2);
+        let volume = estimator.volume_24h_usd(&pool).await;
+        // $200 over the ~2 sampled hours
+        assert!((volume - 2_400.0).abs() < 1.0, "volume was {}", volume);
+    }
+
+    #[test]
+    fn test_ray_logs_attributed_to_their_pool() {
+        let pool = amm_pool();
+        let other = Pubkey::new_unique();
+
+        // Multi-hop route through another AMM pool first
+        let route = amm_swaps(&[(other, 5_000_000_000, true), (pool.address, 100_000_000, false)]);
+        assert_eq!(swap_inputs(&pool, &route), vec![(0, 100_000_000)]);
+
+        // Swap made by an aggregator through a CPI into the AMM
+        let aggregator = Pubkey::new_unique();
+        let mut cpi = amm_swaps(&[(pool.address, 7, true)]);
+        cpi.logs.insert(0, format!("Program {} invoke [1]", aggregator));
+        cpi.logs.iter_mut().skip(1).for_each(|line| *line = line.replace("invoke [1]", "invoke [2]"));
+        cpi.logs.push(format!("Program {} success", aggregator));
+        cpi.instructions.insert(0, ExecutedInstruction { program_id: aggregator, accounts: vec![], data: vec![] });
+        assert_eq!(swap_inputs(&pool, &cpi), vec![(7, 0)]);
+
+        // Logs that do not line up with the instructions are not guessed at
+        let mut truncated = route.clone();
+        truncated.logs.truncate(4);
+        assert!(swap_inputs(&pool, &truncated).is_empty());
+        assert!(swap_inputs(&pool, &amm_swaps(&[(other, 1, true)])).is_empty());
+    }
+}
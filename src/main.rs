@@ -36,6 +36,15 @@ async fn main() -> Result<(), SwapError> {
             };
             wrap_cmd.execute(config).await?;
         }
+        Commands::History(args) => {
+            raydium_multipool_swap::cli::commands::history::execute(args, config).await?;
+        }
+        Commands::Pnl(args) => {
+            raydium_multipool_swap::cli::commands::pnl::execute(args, config).await?;
+        }
+        Commands::Alt(args) => {
+            raydium_multipool_swap::cli::commands::alt::execute(args, config).await?;
+        }
     }
 
     Ok(())
@@ -1,125 +1,291 @@
 use crate::core::{
-    PoolInfo, PoolState, QuoteRequest, QuoteResult, SwapError, SwapResult,
+    clmm_math::{
+        add_liquidity_delta, compute_swap_step, get_sqrt_price_at_tick, get_tick_at_sqrt_price,
+        sqrt_price_x64_to_price,
+    },
+    layouts::{ClmmPoolState, TickArrayBitmapExtension, TickArrayState}, PoolInfo, PoolState, PoolType, QuoteRequest, QuoteResult, SwapError,
+    SwapMode, SwapResult, TickTraversal,
 };
+use crate::quotes::max_amount_in_with_slippage;
+use borsh::BorshDeserialize;
 use log::debug;
-use rust_decimal::prelude::*;
-use rust_decimal::Decimal;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+
+pub use crate::core::clmm_math::{MAX_TICK, MIN_TICK};
+
+/// Initialized tick arrays fetched in the swap direction, including the current one
+const MAX_TICK_ARRAYS: usize = 10;
+
+/// Outcome of walking a swap through the pool's initialized ticks
+#[derive(Debug, Clone)]
+pub struct ClmmSwapSimulation {
+    /// Input consumed, fee included
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub traversal: TickTraversal,
+}
+
+/// Tick arrays loaded for one pool and swap direction, reused across quotes
+#[derive(Debug, Clone)]
+pub struct ClmmTickArrays {
+    is_token_a_to_b: bool,
+    tick_arrays: Vec<TickArrayState>,
+    /// Furthest tick the arrays cover in the swap direction
+    boundary_tick: i32,
+}
+
+/// Whether selling `token_in` to `pool` swaps token A for token B
+fn swap_direction(pool: &PoolInfo, token_in: &Pubkey) -> SwapResult<bool> {
+    if pool.token_a.mint == *token_in {
+        Ok(true)
+    } else if pool.token_b.mint == *token_in {
+        Ok(false)
+    } else {
+        Err(SwapError::InvalidTokenMint("Input token not found in pool".to_string()))
+    }
+}
 
 /// CLMM (Concentrated Liquidity Market Maker) quote calculator
 /// Uses tick-based pricing with concentrated liquidity
-pub struct ClmmQuoteCalculator;
+pub struct ClmmQuoteCalculator {
+    /// Used to load the pool's tick arrays; without it the swap is simulated
+    /// against the current liquidity only
+    rpc_client: Option<Arc<RpcClient>>,
+}
 
 impl ClmmQuoteCalculator {
     pub fn new() -> Self {
-        Self
+        Self { rpc_client: None }
     }
 
-    /// Calculate price from tick
-    /// price = 1.0001^tick
-    fn tick_to_price(&self, tick: i32) -> SwapResult<Decimal> {
-        let base = Decimal::from_str("1.0001").unwrap();
-        let _tick_dec = Decimal::from(tick.abs());
-        
-        // Calculate 1.0001^|tick|
-        let mut price = Decimal::ONE;
-        let mut temp_base = base;
-        let mut temp_tick = tick.abs() as u32;
-        
-        // Binary exponentiation for efficiency
-        while temp_tick > 0 {
-            if temp_tick & 1 == 1 {
-                price *= temp_base;
-            }
-            temp_base *= temp_base;
-            temp_tick >>= 1;
-        }
-        
-        // If tick is negative, invert the price
-        if tick < 0 {
-            if price.is_zero() {
-                return Err(SwapError::MathOverflow);
-            }
-            price = Decimal::ONE / price;
+    /// Create a calculator that loads tick arrays from chain
+    pub fn with_rpc_client(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            rpc_client: Some(rpc_client),
         }
-        
-        Ok(price)
     }
 
-    /// Calculate sqrt price from tick
-    fn tick_to_sqrt_price(&self, tick: i32) -> SwapResult<Decimal> {
-        let price = self.tick_to_price(tick)?;
-        
-        // Calculate square root using Newton's method
-        let mut sqrt_price = price / Decimal::TWO;
-        for _ in 0..10 {
-            if sqrt_price.is_zero() {
-                return Err(SwapError::MathOverflow);
+    /// Load the initialized tick arrays a swap may traverse
+    /// Follows the pool's tick array bitmap and its extension so only initialized
+    /// arrays are fetched. Returns the arrays found and the furthest tick they
+    /// cover in the swap direction.
+    async fn load_tick_arrays(
+        &self,
+        rpc_client: &RpcClient,
+        pool: &PoolInfo,
+        current_tick: i32,
+        zero_for_one: bool,
+    ) -> SwapResult<(Vec<TickArrayState>, i32)> {
+        let extension_address = ClmmPoolState::find_bitmap_extension_address(&pool.address, &pool.program_id);
+        let accounts = rpc_client
+            .get_multiple_accounts(&[pool.address, extension_address])
+            .await
+            .map_err(SwapError::from)?;
+
+        let pool_state = accounts[0]
+            .as_ref()
+            .ok_or_else(|| SwapError::PoolNotFound(pool.address.to_string()))
+            .and_then(|account| {
+                ClmmPoolState::try_from_slice(&account.data)
+                    .map_err(|e| SwapError::ParseError(format!("Failed to parse CLMM pool {}: {}", pool.address, e)))
+            })?;
+        // Without an extension account no array past the pool's own bitmap is initialized
+        let extension = match &accounts[1] {
+            Some(account) => TickArrayBitmapExtension::from_bytes(&account.data).map_err(SwapError::ParseError)?,
+            None => TickArrayBitmapExtension::empty(pool.address),
+        };
+
+        let walk = pool_state.tick_array_walk(
+            Some(&extension),
+            current_tick,
+            zero_for_one,
+            MAX_TICK_ARRAYS,
+            MAX_TICK_ARRAYS,
+        );
+        let addresses: Vec<_> = walk
+            .start_indices
+            .iter()
+            .map(|index| TickArrayState::find_address(&pool.address, *index, &pool.program_id))
+            .collect();
+
+        let accounts = rpc_client
+            .get_multiple_accounts(&addresses)
+            .await
+            .map_err(SwapError::from)?;
+
+        // The bitmap can lag an array closed in the same slot
+        let mut tick_arrays = Vec::new();
+        for (address, account) in addresses.iter().zip(accounts) {
+            let Some(account) = account else {
+                continue;
+            };
+            match TickArrayState::from_bytes(&account.data) {
+                Ok(tick_array) if tick_array.pool_id == pool.address => tick_arrays.push(tick_array),
+                Ok(_) => debug!("Tick array {} belongs to another pool", address),
+                Err(e) => debug!("Failed to parse tick array {}: {}", address, e),
             }
-            sqrt_price = (sqrt_price + price / sqrt_price) / Decimal::TWO;
         }
-        
-        Ok(sqrt_price)
+
+        debug!(
+            "Loaded {} initialized tick arrays for pool {} (boundary tick {})",
+            tick_arrays.len(),
+            pool.address,
+            walk.boundary_tick
+        );
+
+        Ok((tick_arrays, walk.boundary_tick))
     }
 
-    /// Calculate output amount for CLMM swap
-    fn calculate_clmm_output(
+    /// Simulate a CLMM swap, crossing initialized ticks until `amount` is used up
+    /// (exact input) or paid out (exact output), or `limit_tick` is reached
+    /// Running out before a `limit_tick` short of the tick range means the swap
+    /// needs more tick arrays than were loaded.
+    ///
+    /// Steps through the pool with the program's own fixed-point math, so the
+    /// result matches what the swap instruction pays out.
+    #[allow(clippy::too_many_arguments)]
+    fn simulate_swap(
         &self,
-        amount_in: u64,
+        amount: u64,
+        is_base_input: bool,
         current_tick: i32,
-        _tick_spacing: u16,
+        sqrt_price_x64: u128,
+        tick_spacing: u16,
         liquidity: u128,
         fee_tier: u32,
         is_token_a_to_b: bool,
-    ) -> SwapResult<u64> {
-        if liquidity == 0 {
+        tick_arrays: &[TickArrayState],
+        limit_tick: i32,
+    ) -> SwapResult<ClmmSwapSimulation> {
+        let zero_for_one = is_token_a_to_b;
+
+        // Initialized ticks ahead of the current price, in the order they will be crossed
+        let mut ticks: Vec<(i32, i128)> = tick_arrays
+            .iter()
+            .flat_map(|array| array.initialized_ticks())
+            .filter(|tick| {
+                if zero_for_one {
+                    tick.tick <= current_tick && tick.tick > limit_tick
+                } else {
+                    tick.tick > current_tick && tick.tick < limit_tick
+                }
+            })
+            .map(|tick| (tick.tick, tick.liquidity_net))
+            .collect();
+        if zero_for_one {
+            ticks.sort_by_key(|tick| std::cmp::Reverse(tick.0));
+        } else {
+            ticks.sort_by_key(|tick| tick.0);
+        }
+        ticks.dedup_by_key(|tick| tick.0);
+
+        let sqrt_price_limit_x64 = get_sqrt_price_at_tick(limit_tick)?;
+        let mut liquidity = liquidity;
+        let mut sqrt_price_x64 = sqrt_price_x64;
+        // Input left to spend (exact input) or output still owed (exact output)
+        let mut remaining = amount;
+        let mut amount_in = 0u64;
+        let mut amount_out = 0u64;
+        let mut fee_amount = 0u64;
+        let mut tick = current_tick;
+        let mut ticks_crossed = 0u32;
+
+        // Each step moves the price to the next initialized tick (or the limit)
+        let targets = ticks.iter().map(|(tick, net)| (*tick, Some(*net)));
+        for (target_tick, liquidity_net) in targets.chain(std::iter::once((limit_tick, None))) {
+            if remaining == 0 || sqrt_price_x64 == sqrt_price_limit_x64 {
+                break;
+            }
+
+            let sqrt_price_start_x64 = sqrt_price_x64;
+            let sqrt_price_target_x64 = get_sqrt_price_at_tick(target_tick)?;
+            let step = compute_swap_step(
+                sqrt_price_x64,
+                sqrt_price_target_x64,
+                liquidity,
+                remaining,
+                fee_tier,
+                is_base_input,
+                zero_for_one,
+            )?;
+            sqrt_price_x64 = step.sqrt_price_next_x64;
+            let step_amount_in = step
+                .amount_in
+                .checked_add(step.fee_amount)
+                .ok_or(SwapError::MathOverflow)?;
+            remaining = remaining
+                .checked_sub(if is_base_input { step_amount_in } else { step.amount_out })
+                .ok_or(SwapError::MathOverflow)?;
+            amount_in = amount_in
+                .checked_add(step_amount_in)
+                .ok_or(SwapError::MathOverflow)?;
+            amount_out = amount_out
+                .checked_add(step.amount_out)
+                .ok_or(SwapError::MathOverflow)?;
+            fee_amount += step.fee_amount;
+
+            if sqrt_price_x64 != sqrt_price_target_x64 {
+                // Swap finished inside this range
+                if sqrt_price_x64 != sqrt_price_start_x64 {
+                    tick = get_tick_at_sqrt_price(sqrt_price_x64)?;
+                }
+                break;
+            }
+
+            match liquidity_net {
+                Some(liquidity_net) => {
+                    // Crossing a tick adds or removes the liquidity of positions bounded by it
+                    let liquidity_delta = if zero_for_one { -liquidity_net } else { liquidity_net };
+                    liquidity = add_liquidity_delta(liquidity, liquidity_delta).map_err(|_| {
+                        SwapError::InvalidPoolState(format!(
+                            "Negative liquidity after crossing tick {}",
+                            target_tick
+                        ))
+                    })?;
+                    ticks_crossed += 1;
+                    tick = if zero_for_one { target_tick - 1 } else { target_tick };
+                }
+                None => {
+                    // Price limit reached
+                    tick = target_tick;
+                }
+            }
+        }
+
+        if remaining > 0 && limit_tick != MIN_TICK && limit_tick != MAX_TICK {
+            // The pool may have liquidity past the arrays that were loaded
+            return Err(SwapError::TickRangeExceeded {
+                boundary_tick: limit_tick,
+                remaining,
+            });
+        }
+        if remaining > 0 {
             return Err(SwapError::InsufficientLiquidity {
-                pool_type: crate::core::PoolType::CLMM,
-                available: 0,
-                required: amount_in,
+                pool_type: PoolType::CLMM,
+                available: amount_out,
+                required: amount,
             });
         }
 
-        let fee_rate = fee_tier as f64 / 1_000_000.0;
-        let amount_in_after_fee = (amount_in as f64 * (1.0 - fee_rate)) as u64;
-        
-        // Get current sqrt price
-        let sqrt_price_current = self.tick_to_sqrt_price(current_tick)?;
-        
-        // For simplicity, assume swap happens within current tick range
-        // we would need to handle crossing multiple ticks
-        // TODO: Implement tick crossing logic if needed
-        let liquidity_dec = Decimal::from(liquidity);
-        let amount_in_dec = Decimal::from(amount_in_after_fee);
-        
-        let amount_out = if is_token_a_to_b {
-            // Token A to Token B: price decreases
-            // amount_out = liquidity * (1/sqrt_price_new - 1/sqrt_price_current)
-            // Simplified: assume small swap doesn't cross ticks
-            let delta_sqrt_price = amount_in_dec / liquidity_dec;
-            let new_sqrt_price = sqrt_price_current - delta_sqrt_price;
-            
-            if new_sqrt_price <= Decimal::ZERO {
-                return Err(SwapError::InsufficientLiquidity {
-                    pool_type: crate::core::PoolType::CLMM,
-                    available: liquidity as u64,
-                    required: amount_in,
-                });
-            }
-            
-            let amount_out_dec = liquidity_dec * (Decimal::ONE / new_sqrt_price - Decimal::ONE / sqrt_price_current);
-            amount_out_dec.abs()
-        } else {
-            // Token B to Token A: price increases
-            let delta_sqrt_price = amount_in_dec / liquidity_dec;
-            let new_sqrt_price = sqrt_price_current + delta_sqrt_price;
-            
-            let amount_out_dec = liquidity_dec * (new_sqrt_price - sqrt_price_current);
-            amount_out_dec
-        };
-        
-        amount_out
-            .to_u64()
-            .ok_or(SwapError::MathOverflow)
+        let ticks_in_array = TickArrayState::tick_count(tick_spacing);
+        let tick_arrays_crossed = (TickArrayState::get_array_start_index(current_tick, tick_spacing)
+            - TickArrayState::get_array_start_index(tick, tick_spacing))
+        .abs()
+            / ticks_in_array;
+
+        Ok(ClmmSwapSimulation {
+            amount_in,
+            amount_out,
+            fee_amount,
+            traversal: TickTraversal {
+                ticks_crossed,
+                tick_arrays_crossed: tick_arrays_crossed as u32,
+                end_tick: tick,
+            },
+        })
     }
 
     /// Calculate price impact for CLMM
@@ -127,39 +293,63 @@ impl ClmmQuoteCalculator {
         &self,
         amount_in: u64,
         amount_out: u64,
-        current_tick: i32,
+        sqrt_price_x64: u128,
+        is_token_a_to_b: bool,
     ) -> SwapResult<f64> {
-        let current_price = self.tick_to_price(current_tick)?;
-        let execution_price = Decimal::from(amount_out) / Decimal::from(amount_in);
-        
-        let current_price_f64 = current_price
-            .to_f64()
-            .ok_or(SwapError::MathOverflow)?;
-        let execution_price_f64 = execution_price
-            .to_f64()
-            .ok_or(SwapError::MathOverflow)?;
-        
-        let impact = ((current_price_f64 - execution_price_f64) / current_price_f64).abs() * 100.0;
-        
+        if amount_in == 0 {
+            return Err(SwapError::InvalidAmount("Amount in must be positive".to_string()));
+        }
+
+        // Pool price is token B per token A; invert it when selling token B
+        let mut current_price = sqrt_price_x64_to_price(sqrt_price_x64);
+        if !is_token_a_to_b {
+            current_price = 1.0 / current_price;
+        }
+        if !current_price.is_finite() || current_price <= 0.0 {
+            return Err(SwapError::MathOverflow);
+        }
+        let execution_price = amount_out as f64 / amount_in as f64;
+
+        let impact = ((current_price - execution_price) / current_price).abs() * 100.0;
+
         Ok(impact)
     }
-}
 
-#[async_trait::async_trait]
-impl crate::quotes::QuoteCalculator for ClmmQuoteCalculator {
-    async fn calculate_quote(
+    /// Load the tick arrays a swap of `token_in` through `pool` walks through,
+    /// for quoting any number of amounts with `quote_with_tick_arrays`
+    pub async fn load_swap_tick_arrays(&self, pool: &PoolInfo, token_in: &Pubkey) -> SwapResult<ClmmTickArrays> {
+        let PoolState::CLMM { current_tick, .. } = pool.pool_state else {
+            return Err(SwapError::InvalidPoolState("Expected CLMM pool state".to_string()));
+        };
+        let is_token_a_to_b = swap_direction(pool, token_in)?;
+
+        let (tick_arrays, boundary_tick) = match &self.rpc_client {
+            Some(rpc_client) => self.load_tick_arrays(rpc_client, pool, current_tick, is_token_a_to_b).await?,
+            None => (Vec::new(), if is_token_a_to_b { MIN_TICK } else { MAX_TICK }),
+        };
+        Ok(ClmmTickArrays {
+            is_token_a_to_b,
+            tick_arrays,
+            boundary_tick,
+        })
+    }
+
+    /// Quote `request` against tick arrays loaded for its direction
+    pub fn quote_with_tick_arrays(
         &self,
         pool: &PoolInfo,
         request: &QuoteRequest,
+        tick_arrays: &ClmmTickArrays,
     ) -> SwapResult<QuoteResult> {
         // Extract CLMM state
-        let (current_tick, tick_spacing, liquidity, fee_tier) = match &pool.pool_state {
+        let (current_tick, tick_spacing, liquidity, sqrt_price_x64, fee_tier) = match &pool.pool_state {
             PoolState::CLMM {
                 current_tick,
                 tick_spacing,
                 liquidity,
+                sqrt_price_x64,
                 fee_tier,
-            } => (*current_tick, *tick_spacing, *liquidity, *fee_tier),
+            } => (*current_tick, *tick_spacing, *liquidity, *sqrt_price_x64, *fee_tier),
             _ => {
                 return Err(SwapError::InvalidPoolState(
                     "Expected CLMM pool state".to_string(),
@@ -168,57 +358,90 @@ impl crate::quotes::QuoteCalculator for ClmmQuoteCalculator {
         };
 
         // Determine swap direction
-        let is_token_a_to_b = pool.token_a.mint == request.token_in;
-        if !is_token_a_to_b && pool.token_b.mint != request.token_in {
-            return Err(SwapError::InvalidTokenMint(
-                "Input token not found in pool".to_string(),
+        let is_token_a_to_b = swap_direction(pool, &request.token_in)?;
+        if is_token_a_to_b != tick_arrays.is_token_a_to_b {
+            return Err(SwapError::InvalidInput(
+                "Tick arrays were loaded for the other swap direction".to_string(),
             ));
         }
 
         debug!(
-            "CLMM Quote: amount_in={}, tick={}, liquidity={}, fee_tier={}",
-            request.amount_in, current_tick, liquidity, fee_tier
+            "CLMM Quote: amount={} ({}), tick={}, liquidity={}, fee_tier={}",
+            request.amount, request.swap_mode, current_tick, liquidity, fee_tier
         );
 
-        // Calculate output amount
-        let amount_out = self.calculate_clmm_output(
-            request.amount_in,
+        // Simulate the swap
+        let simulation = self.simulate_swap(
+            request.amount,
+            request.swap_mode == SwapMode::ExactIn,
             current_tick,
+            sqrt_price_x64,
             tick_spacing,
             liquidity,
             fee_tier,
             is_token_a_to_b,
+            &tick_arrays.tick_arrays,
+            tick_arrays.boundary_tick,
         )?;
+        let amount_in = simulation.amount_in;
+        let amount_out = simulation.amount_out;
+
+        debug!(
+            "CLMM simulation: in={}, out={}, ticks crossed={}, tick arrays crossed={}, end tick={}",
+            amount_in,
+            amount_out,
+            simulation.traversal.ticks_crossed,
+            simulation.traversal.tick_arrays_crossed,
+            simulation.traversal.end_tick
+        );
 
         // Calculate price impact
         let price_impact = self.calculate_price_impact(
-            request.amount_in,
+            amount_in,
             amount_out,
-            current_tick,
+            sqrt_price_x64,
+            is_token_a_to_b,
         )?;
 
-        // Calculate minimum output with slippage
-        let slippage_multiplier = 1.0 - (request.slippage_bps as f64 / 10000.0);
-        let min_amount_out = (amount_out as f64 * slippage_multiplier) as u64;
-
-        // Calculate fee (round to nearest)
-        let fee_rate = fee_tier as f64 / 1_000_000.0;
-        let fee = (request.amount_in as f64 * fee_rate).round() as u64;
+        // Apply slippage to the side that is not fixed
+        let (min_amount_out, max_amount_in) = match request.swap_mode {
+            SwapMode::ExactIn => {
+                let slippage_multiplier = 1.0 - (request.slippage_bps as f64 / 10000.0);
+                ((amount_out as f64 * slippage_multiplier) as u64, amount_in)
+            }
+            SwapMode::ExactOut => (amount_out, max_amount_in_with_slippage(amount_in, request.slippage_bps)),
+        };
 
         Ok(QuoteResult {
             pool_info: pool.clone(),
-            amount_in: request.amount_in,
+            amount_in,
             amount_out,
             min_amount_out,
+            max_amount_in,
+            swap_mode: request.swap_mode,
             price_impact,
-            fee,
+            fee: simulation.fee_amount,
             route: vec![pool.address],
             token_in: request.token_in,
             token_out: request.token_out,
+            tick_traversal: Some(simulation.traversal),
+            execution_costs: None,
         })
     }
 }
 
+#[async_trait::async_trait]
+impl crate::quotes::QuoteCalculator for ClmmQuoteCalculator {
+    async fn calculate_quote(
+        &self,
+        pool: &PoolInfo,
+        request: &QuoteRequest,
+    ) -> SwapResult<QuoteResult> {
+        let tick_arrays = self.load_swap_tick_arrays(pool, &request.token_in).await?;
+        self.quote_with_tick_arrays(pool, request, &tick_arrays)
+    }
+}
+
 impl Default for ClmmQuoteCalculator {
     fn default() -> Self {
         Self::new()
@@ -228,9 +451,27 @@ impl Default for ClmmQuoteCalculator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{PoolType, TokenInfo};
+    use crate::core::{layouts::TickState, PoolType, TokenInfo};
     use crate::quotes::QuoteCalculator;
-    use solana_sdk::pubkey::Pubkey;
+
+    fn create_test_tick_array(start_tick_index: i32, tick_spacing: u16, initialized: &[(i32, i128)]) -> TickArrayState {
+        let mut ticks = vec![TickState::default(); TickArrayState::TICK_COUNT];
+        for (tick, liquidity_net) in initialized {
+            let slot = ((tick - start_tick_index) / tick_spacing as i32) as usize;
+            ticks[slot] = TickState {
+                tick: *tick,
+                liquidity_net: *liquidity_net,
+                liquidity_gross: liquidity_net.unsigned_abs(),
+            };
+        }
+
+        TickArrayState {
+            pool_id: Pubkey::new_unique(),
+            start_tick_index,
+            ticks,
+            initialized_tick_count: initialized.len() as u8,
+        }
+    }
 
     fn create_test_clmm_pool(
         current_tick: i32,
@@ -261,10 +502,13 @@ mod tests {
             volume_24h_usd: 250000.0,
             fee_rate: fee_tier as f64 / 1_000_000.0,
             program_id: Pubkey::new_unique(),
+            context_slot: 0,
+            open_time: 0,
             pool_state: PoolState::CLMM {
                 current_tick,
                 tick_spacing,
                 liquidity,
+                sqrt_price_x64: get_sqrt_price_at_tick(current_tick).unwrap(),
                 fee_tier,
             },
         }
@@ -272,37 +516,26 @@ mod tests {
 
     #[test]
     fn test_tick_to_price() {
-        let calculator = ClmmQuoteCalculator::new();
-        
         // Test tick 0 = price 1
-        let price = calculator.tick_to_price(0).unwrap();
-        assert_eq!(price, Decimal::ONE);
-        
+        let price = sqrt_price_x64_to_price(get_sqrt_price_at_tick(0).unwrap());
+        assert_eq!(price, 1.0);
+
         // Test positive tick
-        let price = calculator.tick_to_price(1000).unwrap();
-        assert!(price > Decimal::ONE);
-        
+        let price = sqrt_price_x64_to_price(get_sqrt_price_at_tick(1000).unwrap());
+        assert!((price - 1.0001f64.powi(1000)).abs() < 1e-9);
+
         // Test negative tick
-        let price = calculator.tick_to_price(-1000).unwrap();
-        assert!(price < Decimal::ONE);
+        let price = sqrt_price_x64_to_price(get_sqrt_price_at_tick(-1000).unwrap());
+        assert!((price - 1.0001f64.powi(-1000)).abs() < 1e-9);
     }
 
     #[test]
     fn test_tick_to_sqrt_price() {
-        let calculator = ClmmQuoteCalculator::new();
-        
-        // Test tick 0
-        let sqrt_price = calculator.tick_to_sqrt_price(0).unwrap();
-        assert!((sqrt_price - Decimal::ONE).abs() < Decimal::from_str("0.001").unwrap());
-        
-        // Test that sqrt(price) * sqrt(price) ≈ price
-        let tick = 1000;
-        let price = calculator.tick_to_price(tick).unwrap();
-        let sqrt_price = calculator.tick_to_sqrt_price(tick).unwrap();
-        let price_check = sqrt_price * sqrt_price;
-        
-        let diff = (price - price_check).abs() / price;
-        assert!(diff < Decimal::from_str("0.01").unwrap()); // Less than 1% error
+        // Sqrt price round-trips to the tick it was computed from
+        for tick in [-1000, -1, 0, 1, 1000] {
+            let sqrt_price_x64 = get_sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_price(sqrt_price_x64).unwrap(), tick);
+        }
     }
 
     #[tokio::test]
@@ -315,12 +548,12 @@ mod tests {
             500,      // 0.05% fee tier
         );
         
-        let request = QuoteRequest {
-            token_in: pool.token_a.mint,
-            token_out: pool.token_b.mint,
-            amount_in: 1000000, // 1M units
-            slippage_bps: 50,
-        };
+        let request = QuoteRequest::exact_in(
+            pool.token_a.mint,
+            pool.token_b.mint,
+            1000000, // 1M units
+            50,
+        );
 
         let quote = calculator.calculate_quote(&pool, &request).await.unwrap();
         
@@ -328,6 +561,25 @@ mod tests {
         assert!(quote.amount_out > 0);
         assert!(quote.price_impact >= 0.0);
         assert_eq!(quote.fee, 500); // 0.05% of 1M
+        assert_eq!(quote.amount_out, 999_499); // Exact program output
+    }
+
+    #[tokio::test]
+    async fn test_calculate_clmm_quote_exact_out() {
+        let calculator = ClmmQuoteCalculator::new();
+        let pool = create_test_clmm_pool(0, 1, 1_000_000_000_000, 500);
+
+        let request = QuoteRequest::exact_out(pool.token_a.mint, pool.token_b.mint, 999_499, 50);
+
+        let quote = calculator.calculate_quote(&pool, &request).await.unwrap();
+
+        assert_eq!(quote.swap_mode, SwapMode::ExactOut);
+        assert_eq!(quote.amount_out, 999_499);
+        assert_eq!(quote.min_amount_out, 999_499);
+        // The exact-in quote for 1M pays this output, so no more than 1M is needed
+        assert!(quote.amount_in > 999_499 && quote.amount_in <= 1_000_000);
+        assert!(quote.fee > 0);
+        assert_eq!(quote.max_amount_in, (quote.amount_in * 1005).div_ceil(1000));
     }
 
     #[test]
@@ -339,15 +591,20 @@ mod tests {
         
         for fee_tier in fee_tiers {
             let output = calculator
-                .calculate_clmm_output(
+                .simulate_swap(
                     1_000_000,
+                    true,
                     0,
+                    1u128 << 64,
                     1,
                     1_000_000_000_000,
                     fee_tier,
                     true,
+                    &[],
+                    MIN_TICK,
                 )
-                .unwrap();
+                .unwrap()
+                .amount_out;
             
             // Higher fee tier should result in less output
             let expected_fee = (1_000_000u64 as u128 * fee_tier as u128 / 1_000_000) as u64;
@@ -362,15 +619,110 @@ mod tests {
         let calculator = ClmmQuoteCalculator::new();
         
         // Test with zero liquidity
-        let result = calculator.calculate_clmm_output(
+        let result = calculator.simulate_swap(
             1_000_000,
+            true,
             0,
+            1u128 << 64,
             1,
             0, // No liquidity
             500,
             true,
+            &[],
+            MIN_TICK,
         );
         
         assert!(matches!(result, Err(SwapError::InsufficientLiquidity { .. })));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_tick_array_start_index() {
+        assert_eq!(TickArrayState::get_array_start_index(0, 1), 0);
+        assert_eq!(TickArrayState::get_array_start_index(59, 1), 0);
+        assert_eq!(TickArrayState::get_array_start_index(60, 1), 60);
+        assert_eq!(TickArrayState::get_array_start_index(-1, 1), -60);
+        assert_eq!(TickArrayState::get_array_start_index(-1234, 10), -1800);
+    }
+
+    #[test]
+    fn test_tick_crossing_reduces_output() {
+        let calculator = ClmmQuoteCalculator::new();
+        // A position ending at tick -10 provides 90% of the liquidity around the current price
+        let tick_arrays = vec![create_test_tick_array(-60, 1, &[(-10, 900_000_000_000)])];
+
+        let crossing = calculator
+            .simulate_swap(600_000_000, true, 0, 1u128 << 64, 1, 1_000_000_000_000, 500, true, &tick_arrays, -60)
+            .unwrap();
+        let single_range = calculator
+            .simulate_swap(600_000_000, true, 0, 1u128 << 64, 1, 1_000_000_000_000, 500, true, &[], MIN_TICK)
+            .unwrap();
+
+        assert_eq!(crossing.traversal.ticks_crossed, 1);
+        assert_eq!(crossing.traversal.tick_arrays_crossed, 1);
+        assert!(crossing.traversal.end_tick < -10 && crossing.traversal.end_tick > -60);
+        assert_eq!(crossing.amount_in, 600_000_000);
+        assert!(crossing.amount_out < single_range.amount_out);
+        assert_eq!(single_range.traversal.ticks_crossed, 0);
+    }
+
+    #[test]
+    fn test_tick_crossing_one_for_zero() {
+        let calculator = ClmmQuoteCalculator::new();
+        // Liquidity doubles above tick 5 and the position below ends at tick 70
+        let tick_arrays = vec![
+            create_test_tick_array(0, 1, &[(5, 1_000_000_000_000)]),
+            create_test_tick_array(60, 1, &[(70, -1_000_000_000_000)]),
+        ];
+
+        let result = calculator
+            .simulate_swap(1_000_000_000, true, 0, 1u128 << 64, 1, 1_000_000_000_000, 500, false, &tick_arrays, 120)
+            .unwrap();
+
+        assert_eq!(result.traversal.ticks_crossed, 1);
+        assert_eq!(result.traversal.tick_arrays_crossed, 0);
+        assert!(result.traversal.end_tick >= 5 && result.traversal.end_tick < 60);
+        assert!(result.amount_out > 0);
+    }
+
+    #[test]
+    fn test_exact_out_crossing_matches_exact_in() {
+        let calculator = ClmmQuoteCalculator::new();
+        let tick_arrays = vec![create_test_tick_array(-60, 1, &[(-10, 900_000_000_000)])];
+
+        let exact_in = calculator
+            .simulate_swap(600_000_000, true, 0, 1u128 << 64, 1, 1_000_000_000_000, 500, true, &tick_arrays, -60)
+            .unwrap();
+        let exact_out = calculator
+            .simulate_swap(exact_in.amount_out, false, 0, 1u128 << 64, 1, 1_000_000_000_000, 500, true, &tick_arrays, -60)
+            .unwrap();
+
+        assert_eq!(exact_out.amount_out, exact_in.amount_out);
+        assert_eq!(exact_out.traversal.ticks_crossed, 1);
+        // Exact input gives its rounding dust to the fee, so exact output never needs more
+        assert!(exact_out.amount_in <= exact_in.amount_in);
+        assert!(exact_in.amount_in - exact_out.amount_in < 10);
+    }
+
+    #[test]
+    fn test_price_limit_stops_swap() {
+        let calculator = ClmmQuoteCalculator::new();
+        let tick_arrays = vec![create_test_tick_array(-60, 1, &[(-10, 900_000_000_000)])];
+
+        // Not enough liquidity before the loaded tick arrays run out; the pool may
+        // still fill it from arrays that were not loaded
+        let result = calculator.simulate_swap(
+            1_000_000_000,
+            true,
+            0,
+            1u128 << 64,
+            1,
+            1_000_000_000_000,
+            500,
+            true,
+            &tick_arrays,
+            -60,
+        );
+
+        assert!(matches!(result, Err(SwapError::TickRangeExceeded { boundary_tick: -60, .. })));
+    }
+}
@@ -3,7 +3,14 @@ pub mod clmm_calculator;
 pub mod stable_calculator;
 pub mod standard_calculator;
 
-use crate::core::{PoolInfo, QuoteRequest, QuoteResult, SwapResult};
+use crate::core::{
+    MintCache, PoolInfo, QuoteRequest, QuoteResult, RouteQuote, SplitQuote, SwapError, SwapMode, SwapResult,
+    TransferFee,
+};
+use clmm_calculator::ClmmTickArrays;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
 
 pub use amm_calculator::AmmQuoteCalculator;
 pub use clmm_calculator::ClmmQuoteCalculator;
@@ -20,12 +27,81 @@ pub trait QuoteCalculator: Send + Sync {
     ) -> SwapResult<QuoteResult>;
 }
 
+/// Maximum input accepted for an exact-out swap, rounded up
+pub(crate) fn max_amount_in_with_slippage(amount_in: u64, slippage_bps: u16) -> u64 {
+    let max_amount_in = (amount_in as u128 * (10_000 + slippage_bps as u128)).div_ceil(10_000);
+    max_amount_in.min(u64::MAX as u128) as u64
+}
+
+/// Minimum output accepted for an exact-in swap, rounded down
+pub(crate) fn min_amount_out_with_slippage(amount_out: u64, slippage_bps: u16) -> u64 {
+    (amount_out as u128 * 10_000u128.saturating_sub(slippage_bps as u128) / 10_000) as u64
+}
+
+/// Request for the amounts that reach the pool once transfer fees are
+/// taken from the user's input and added to the pool's output
+fn pool_request(
+    request: &QuoteRequest,
+    input_fee: Option<&TransferFee>,
+    output_fee: Option<&TransferFee>,
+) -> SwapResult<QuoteRequest> {
+    let amount = match request.swap_mode {
+        SwapMode::ExactIn => request.amount - input_fee.map_or(0, |fee| fee.calculate_fee(request.amount)),
+        SwapMode::ExactOut => match output_fee {
+            Some(fee) => fee.calculate_pre_fee_amount(request.amount)?,
+            None => request.amount,
+        },
+    };
+    if amount == 0 {
+        return Err(SwapError::InvalidAmount(format!(
+            "{} leaves nothing to swap after transfer fees",
+            request.amount
+        )));
+    }
+    Ok(QuoteRequest { amount, ..request.clone() })
+}
+
+/// Restate a pool quote in the amounts the user sends and receives
+fn with_transfer_fees(
+    mut quote: QuoteResult,
+    request: &QuoteRequest,
+    input_fee: Option<&TransferFee>,
+    output_fee: Option<&TransferFee>,
+) -> SwapResult<QuoteResult> {
+    match request.swap_mode {
+        SwapMode::ExactIn => {
+            quote.amount_in = request.amount;
+            quote.max_amount_in = request.amount;
+            quote.amount_out -= output_fee.map_or(0, |fee| fee.calculate_fee(quote.amount_out));
+            quote.min_amount_out = min_amount_out_with_slippage(quote.amount_out, request.slippage_bps);
+        }
+        SwapMode::ExactOut => {
+            if let Some(fee) = input_fee {
+                quote.amount_in = fee.calculate_pre_fee_amount(quote.amount_in)?;
+            }
+            quote.max_amount_in = max_amount_in_with_slippage(quote.amount_in, request.slippage_bps);
+            quote.amount_out = request.amount;
+            quote.min_amount_out = request.amount;
+        }
+    }
+    Ok(quote)
+}
+
+/// A pool with the on-chain state its quotes read, loaded once to quote
+/// several amounts in the same direction
+pub struct LoadedPool<'a> {
+    pub pool: &'a PoolInfo,
+    clmm_tick_arrays: Option<ClmmTickArrays>,
+}
+
 /// Main quote engine that delegates to pool-specific calculators
 pub struct QuoteEngine {
     amm_calculator: AmmQuoteCalculator,
     stable_calculator: StableQuoteCalculator,
     clmm_calculator: ClmmQuoteCalculator,
     standard_calculator: StandardQuoteCalculator,
+    /// Token-2022 mints, for transfer fees and unsupported extensions
+    mints: Option<Arc<MintCache>>,
 }
 
 impl QuoteEngine {
@@ -35,15 +111,68 @@ impl QuoteEngine {
             stable_calculator: StableQuoteCalculator::new(),
             clmm_calculator: ClmmQuoteCalculator::new(),
             standard_calculator: StandardQuoteCalculator::new(),
+            mints: None,
         }
     }
 
-    /// Calculate quote for a specific pool
+    /// Create an engine that reads extra on-chain state while quoting
+    /// (CLMM tick arrays, the Stable model data, token mints)
+    pub fn with_rpc_client(rpc_client: Arc<RpcClient>) -> Self {
+        Self {
+            stable_calculator: StableQuoteCalculator::with_rpc_client(rpc_client.clone()),
+            clmm_calculator: ClmmQuoteCalculator::with_rpc_client(rpc_client.clone()),
+            mints: Some(Arc::new(MintCache::new(rpc_client))),
+            ..Self::new()
+        }
+    }
+
+    /// Calculate quote for a specific pool, in the amounts the user sends
+    /// and receives after Token-2022 transfer fees
     pub async fn calculate_quote(
         &self,
         pool: &PoolInfo,
         request: &QuoteRequest,
     ) -> SwapResult<QuoteResult> {
+        let loaded = self.load_pool(pool, &request.token_in).await?;
+        self.calculate_loaded_quote(&loaded, request).await
+    }
+
+    /// Load the on-chain state quoting `pool` from `token_in` reads (CLMM tick
+    /// arrays), so many amounts can be quoted without fetching it again
+    pub async fn load_pool<'a>(&self, pool: &'a PoolInfo, token_in: &Pubkey) -> SwapResult<LoadedPool<'a>> {
+        let clmm_tick_arrays = match pool.pool_type {
+            crate::core::PoolType::CLMM => Some(self.clmm_calculator.load_swap_tick_arrays(pool, token_in).await?),
+            _ => None,
+        };
+        Ok(LoadedPool { pool, clmm_tick_arrays })
+    }
+
+    /// Calculate quote for a pool loaded with `load_pool`
+    pub async fn calculate_loaded_quote(
+        &self,
+        loaded: &LoadedPool<'_>,
+        request: &QuoteRequest,
+    ) -> SwapResult<QuoteResult> {
+        let Some(mints) = &self.mints else {
+            return self.calculate_pool_quote(loaded, request).await;
+        };
+        let (input_fee, output_fee) = mints.swap_transfer_fees(&request.token_in, &request.token_out).await?;
+        if input_fee.is_none() && output_fee.is_none() {
+            return self.calculate_pool_quote(loaded, request).await;
+        }
+
+        let pool_request = pool_request(request, input_fee.as_ref(), output_fee.as_ref())?;
+        let quote = self.calculate_pool_quote(loaded, &pool_request).await?;
+        with_transfer_fees(quote, request, input_fee.as_ref(), output_fee.as_ref())
+    }
+
+    /// Quote the amounts moving in and out of the pool itself
+    async fn calculate_pool_quote(
+        &self,
+        loaded: &LoadedPool<'_>,
+        request: &QuoteRequest,
+    ) -> SwapResult<QuoteResult> {
+        let pool = loaded.pool;
         match pool.pool_type {
             crate::core::PoolType::AMM => {
                 self.amm_calculator.calculate_quote(pool, request).await
@@ -51,15 +180,85 @@ impl QuoteEngine {
             crate::core::PoolType::Stable => {
                 self.stable_calculator.calculate_quote(pool, request).await
             }
-            crate::core::PoolType::CLMM => {
-                self.clmm_calculator.calculate_quote(pool, request).await
-            }
+            crate::core::PoolType::CLMM => match &loaded.clmm_tick_arrays {
+                Some(tick_arrays) => self.clmm_calculator.quote_with_tick_arrays(pool, request, tick_arrays),
+                None => self.clmm_calculator.calculate_quote(pool, request).await,
+            },
             crate::core::PoolType::Standard => {
                 self.standard_calculator.calculate_quote(pool, request).await
             }
         }
     }
 
+    /// Quote the trade behind `quote` again against `pool`, keeping the
+    /// amount it fixed and its execution costs
+    pub async fn requote(&self, quote: &QuoteResult, pool: &PoolInfo, slippage_bps: u16) -> SwapResult<QuoteResult> {
+        let request = match quote.swap_mode {
+            SwapMode::ExactIn => {
+                QuoteRequest::exact_in(quote.token_in, quote.token_out, quote.amount_in, slippage_bps)
+            }
+            SwapMode::ExactOut => {
+                QuoteRequest::exact_out(quote.token_in, quote.token_out, quote.amount_out, slippage_bps)
+            }
+        };
+
+        let mut requote = self.calculate_quote(pool, &request).await?;
+        requote.execution_costs = quote.execution_costs;
+        Ok(requote)
+    }
+
+    /// Quote every leg of `split` again against `pools`, one per leg
+    pub async fn requote_split(
+        &self,
+        split: &SplitQuote,
+        pools: &[PoolInfo],
+        slippage_bps: u16,
+    ) -> SwapResult<SplitQuote> {
+        let mut legs = Vec::with_capacity(split.legs.len());
+        for (leg, pool) in split.legs.iter().zip(pools) {
+            legs.push(self.requote(leg, pool, slippage_bps).await?);
+        }
+        Ok(SplitQuote::from_legs(legs, split.token_in, split.token_out, split.swap_mode))
+    }
+
+    /// Quote `route` again against `pools`, one per hop, chaining the hops
+    /// the way the route was found: each exact-in hop takes the previous
+    /// hop's minimum output, each exact-out hop pays the next hop's maximum input
+    pub async fn requote_route(
+        &self,
+        route: &RouteQuote,
+        pools: &[PoolInfo],
+        slippage_bps: u16,
+    ) -> SwapResult<RouteQuote> {
+        let mut hops = Vec::with_capacity(route.hops.len());
+        match route.swap_mode {
+            SwapMode::ExactIn => {
+                let mut amount = route.amount_in;
+                for (hop, pool) in route.hops.iter().zip(pools) {
+                    let request = QuoteRequest::exact_in(hop.token_in, hop.token_out, amount, slippage_bps);
+                    let mut requote = self.calculate_quote(pool, &request).await?;
+                    requote.execution_costs = hop.execution_costs;
+                    amount = requote.min_amount_out;
+                    hops.push(requote);
+                }
+            }
+            SwapMode::ExactOut => {
+                let mut amount = route.amount_out;
+                for (hop, pool) in route.hops.iter().zip(pools).rev() {
+                    let request = QuoteRequest::exact_out(hop.token_in, hop.token_out, amount, slippage_bps);
+                    let mut requote = self.calculate_quote(pool, &request).await?;
+                    requote.execution_costs = hop.execution_costs;
+                    amount = requote.max_amount_in;
+                    hops.push(requote);
+                }
+                hops.reverse();
+            }
+        }
+
+        RouteQuote::from_hops(hops, route.swap_mode)
+            .ok_or_else(|| SwapError::InvalidAmount("Route has no hops".to_string()))
+    }
+
     /// Calculate quotes for multiple pools
     pub async fn calculate_quotes(
         &self,
@@ -80,4 +279,163 @@ impl Default for QuoteEngine {
     fn default() -> Self {
         Self::new()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fixtures::{standard_pool, token};
+    use crate::core::PoolState;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn pool(token_in: Pubkey, token_out: Pubkey) -> PoolInfo {
+        standard_pool(token(token_in, 6), token(token_out, 6), 1_000_000_000, 1_000_000_000)
+    }
+
+    #[tokio::test]
+    async fn test_transfer_fees_on_both_legs() {
+        let (token_in, token_out) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pool = pool(token_in, token_out);
+        let engine = QuoteEngine::new();
+        let input_fee = TransferFee { epoch: 0, maximum_fee: u64::MAX, transfer_fee_basis_points: 100 };
+        let output_fee = TransferFee { epoch: 0, maximum_fee: u64::MAX, transfer_fee_basis_points: 50 };
+
+        // Exact in: 1% of the input never reaches the pool, 0.5% of its output never reaches the user
+        let request = QuoteRequest::exact_in(token_in, token_out, 1_000_000, 100);
+        let pool_quote = engine
+            .calculate_quote(&pool, &QuoteRequest { amount: 990_000, ..request.clone() })
+            .await
+            .unwrap();
+        let quote = with_transfer_fees(
+            engine
+                .calculate_quote(&pool, &pool_request(&request, Some(&input_fee), Some(&output_fee)).unwrap())
+                .await
+                .unwrap(),
+            &request,
+            Some(&input_fee),
+            Some(&output_fee),
+        )
+        .unwrap();
+        assert_eq!(quote.amount_in, 1_000_000);
+        assert_eq!(quote.amount_out, pool_quote.amount_out - output_fee.calculate_fee(pool_quote.amount_out));
+        assert_eq!(quote.min_amount_out, quote.amount_out * 99 / 100);
+
+        // Exact out: the pool sends enough to cover the output fee, the user covers the input fee
+        let request = QuoteRequest { amount: 500_000, swap_mode: SwapMode::ExactOut, ..request };
+        let request_to_pool = pool_request(&request, Some(&input_fee), Some(&output_fee)).unwrap();
+        assert_eq!(request_to_pool.amount, output_fee.calculate_pre_fee_amount(500_000).unwrap());
+        let pool_quote = engine.calculate_quote(&pool, &request_to_pool).await.unwrap();
+        let quote = with_transfer_fees(pool_quote.clone(), &request, Some(&input_fee), Some(&output_fee)).unwrap();
+        assert_eq!(quote.amount_out, 500_000);
+        assert!(quote.amount_in - input_fee.calculate_fee(quote.amount_in) >= pool_quote.amount_in);
+        assert_eq!(quote.max_amount_in, max_amount_in_with_slippage(quote.amount_in, 100));
+
+        // An input swallowed by its fee is refused
+        let fee_all = TransferFee { epoch: 0, maximum_fee: u64::MAX, transfer_fee_basis_points: 10_000 };
+        let request = QuoteRequest::exact_in(token_in, token_out, 10, 100);
+        assert!(pool_request(&request, Some(&fee_all), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_loaded_pool_quotes_match() {
+        let (token_in, token_out) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let clmm = crate::core::fixtures::pool(
+            token(token_in, 6),
+            token(token_out, 6),
+            PoolState::CLMM {
+                current_tick: 0,
+                tick_spacing: 1,
+                liquidity: 1_000_000_000_000,
+                sqrt_price_x64: 1 << 64,
+                fee_tier: 500,
+            },
+        );
+        let engine = QuoteEngine::new();
+
+        let loaded = engine.load_pool(&clmm, &token_in).await.unwrap();
+        for amount in [1_000, 1_000_000, 50_000_000] {
+            let request = QuoteRequest::exact_in(token_in, token_out, amount, 50);
+            let quote = engine.calculate_loaded_quote(&loaded, &request).await.unwrap();
+            assert_eq!(quote.amount_out, engine.calculate_quote(&clmm, &request).await.unwrap().amount_out);
+        }
+
+        // Tick arrays only cover the direction they were loaded for
+        let reverse = QuoteRequest::exact_in(token_out, token_in, 1_000, 50);
+        assert!(engine.calculate_loaded_quote(&loaded, &reverse).await.is_err());
+    }
+
+    /// Pool state read again after the price moved against the order
+    fn moved(pool: &PoolInfo, reserve_out: u64) -> PoolInfo {
+        let mut moved = pool.clone();
+        moved.pool_state = PoolState::Standard { reserve_a: 1_000_000_000, reserve_b: reserve_out, trade_fee_rate: 2_500 };
+        moved
+    }
+
+    #[tokio::test]
+    async fn test_requote_stale_split() {
+        let (token_in, token_out) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = [pool(token_in, token_out), pool(token_in, token_out)];
+        let engine = QuoteEngine::new();
+
+        let mut legs = Vec::new();
+        for (pool, amount) in pools.iter().zip([400_000, 600_000]) {
+            let request = QuoteRequest::exact_in(token_in, token_out, amount, 50);
+            legs.push(engine.calculate_quote(pool, &request).await.unwrap());
+        }
+        let split = SplitQuote::from_legs(legs, token_in, token_out, SwapMode::ExactIn);
+
+        // Only the second pool moved; each leg keeps its share of the input
+        let current = [pools[0].clone(), moved(&pools[1], 990_000_000)];
+        let requote = engine.requote_split(&split, &current, 50).await.unwrap();
+        assert_eq!(requote.legs[0].amount_out, split.legs[0].amount_out);
+        assert_eq!(requote.legs[1].amount_in, 600_000);
+        assert!(requote.legs[1].amount_out < split.legs[1].amount_out);
+        assert_eq!(requote.amount_in, 1_000_000);
+        assert_eq!(requote.amount_out, requote.legs[0].amount_out + requote.legs[1].amount_out);
+        // 1% less on 60% of the order, rounded up
+        assert_eq!(split.drift_bps(&requote), 61);
+    }
+
+    #[tokio::test]
+    async fn test_requote_stale_route() {
+        let (token_in, hub, token_out) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = [pool(token_in, hub), pool(hub, token_out)];
+        let current = [moved(&pools[0], 990_000_000), pools[1].clone()];
+        let engine = QuoteEngine::new();
+
+        // Exact in: the second hop sells whatever the moved first hop now guarantees
+        let first = engine
+            .calculate_quote(&pools[0], &QuoteRequest::exact_in(token_in, hub, 1_000_000, 50))
+            .await
+            .unwrap();
+        let second = engine
+            .calculate_quote(&pools[1], &QuoteRequest::exact_in(hub, token_out, first.min_amount_out, 50))
+            .await
+            .unwrap();
+        let route = RouteQuote::from_hops(vec![first, second], SwapMode::ExactIn).unwrap();
+
+        let requote = engine.requote_route(&route, &current, 50).await.unwrap();
+        assert_eq!(requote.amount_in, 1_000_000);
+        assert!(requote.hops[0].amount_out < route.hops[0].amount_out);
+        assert_eq!(requote.hops[1].amount_in, requote.hops[0].min_amount_out);
+        assert!(requote.amount_out < route.amount_out);
+        assert!(route.drift_bps(&requote) > 0);
+
+        // Exact out: the first hop buys whatever the second hop may now charge
+        let second = engine
+            .calculate_quote(&pools[1], &QuoteRequest::exact_out(hub, token_out, 1_000_000, 50))
+            .await
+            .unwrap();
+        let first = engine
+            .calculate_quote(&pools[0], &QuoteRequest::exact_out(token_in, hub, second.max_amount_in, 50))
+            .await
+            .unwrap();
+        let route = RouteQuote::from_hops(vec![first, second], SwapMode::ExactOut).unwrap();
+
+        let requote = engine.requote_route(&route, &current, 50).await.unwrap();
+        assert_eq!(requote.amount_out, 1_000_000);
+        assert_eq!(requote.hops[0].amount_out, requote.hops[1].max_amount_in);
+        assert!(requote.amount_in > route.amount_in);
+        assert!(route.drift_bps(&requote) > 0);
+    }
 }
\ No newline at end of file
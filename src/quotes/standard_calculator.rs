@@ -1,28 +1,35 @@
 use crate::core::{
-    PoolInfo, PoolState, QuoteRequest, QuoteResult, SwapError,
+    layouts::CpAmmConfig, PoolInfo, PoolState, PoolType, QuoteRequest, QuoteResult, SwapError, SwapMode,
     SwapResult,
 };
+use crate::quotes::max_amount_in_with_slippage;
 use log::debug;
-use rust_decimal::prelude::*;
-use rust_decimal::Decimal;
 
 /// Standard pool quote calculator
-/// Legacy pool type with simple constant product formula
+/// Raydium CP-Swap constant product pools, priced with the program's integer curve
 pub struct StandardQuoteCalculator;
 
+/// Fee rate denominator used by CP-Swap
+const FEE_RATE_DENOMINATOR: u128 = CpAmmConfig::FEE_RATE_DENOMINATOR as u128;
+
 impl StandardQuoteCalculator {
     pub fn new() -> Self {
         Self
     }
 
-    /// Calculate output amount using constant product formula
-    /// Similar to AMM but with potentially different fee structure
+    /// Trade fee charged on `amount_in`, rounded up
+    fn trade_fee(amount_in: u64, trade_fee_rate: u64) -> u64 {
+        (amount_in as u128 * trade_fee_rate as u128).div_ceil(FEE_RATE_DENOMINATOR) as u64
+    }
+
+    /// Calculate output amount: the trade fee comes off the input, then the
+    /// constant product output rounds down
     fn calculate_output_amount(
         &self,
         amount_in: u64,
         reserve_in: u64,
         reserve_out: u64,
-        fee_rate: f64,
+        trade_fee_rate: u64,
     ) -> SwapResult<u64> {
         // Validate inputs
         if reserve_in == 0 || reserve_out == 0 {
@@ -35,30 +42,49 @@ impl StandardQuoteCalculator {
             return Ok(0);
         }
 
-        // Use Decimal for precise calculation
-        let amount_in_dec = Decimal::from(amount_in);
-        let reserve_in_dec = Decimal::from(reserve_in);
-        let reserve_out_dec = Decimal::from(reserve_out);
-        let fee_multiplier = Decimal::from_f64(1.0 - fee_rate)
-            .ok_or(SwapError::MathOverflow)?;
+        let amount_in_less_fees = (amount_in - Self::trade_fee(amount_in, trade_fee_rate)) as u128;
+        let amount_out = amount_in_less_fees * reserve_out as u128 / (reserve_in as u128 + amount_in_less_fees);
+
+        u64::try_from(amount_out).map_err(|_| SwapError::MathOverflow)
+    }
+
+    /// Calculate the input needed to receive `amount_out` (rounded up)
+    fn calculate_input_amount(
+        &self,
+        amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        trade_fee_rate: u64,
+    ) -> SwapResult<u64> {
+        if reserve_in == 0 || reserve_out == 0 {
+            return Err(SwapError::InvalidPoolState(
+                "Pool has zero reserves".to_string(),
+            ));
+        }
 
-        // Apply fee to input amount
-        let amount_in_with_fee = amount_in_dec * fee_multiplier;
+        if amount_out == 0 {
+            return Ok(0);
+        }
 
-        // Constant product formula
-        let numerator = reserve_out_dec * amount_in_with_fee;
-        let denominator = reserve_in_dec + amount_in_with_fee;
+        if amount_out >= reserve_out {
+            return Err(SwapError::InsufficientLiquidity {
+                pool_type: PoolType::Standard,
+                available: reserve_out,
+                required: amount_out,
+            });
+        }
 
-        if denominator.is_zero() {
-            return Err(SwapError::MathOverflow);
+        if trade_fee_rate as u128 >= FEE_RATE_DENOMINATOR {
+            return Err(SwapError::InvalidPoolState(format!("Invalid trade fee rate: {}", trade_fee_rate)));
         }
 
-        let amount_out = numerator / denominator;
+        // Inverse of the constant product curve, grossed up for the trade fee
+        let amount_in_less_fees =
+            (amount_out as u128 * reserve_in as u128).div_ceil((reserve_out - amount_out) as u128);
+        let amount_in = (amount_in_less_fees * FEE_RATE_DENOMINATOR)
+            .div_ceil(FEE_RATE_DENOMINATOR - trade_fee_rate as u128);
 
-        // Convert back to u64
-        amount_out
-            .to_u64()
-            .ok_or(SwapError::MathOverflow)
+        u64::try_from(amount_in).map_err(|_| SwapError::MathOverflow)
     }
 
     /// Calculate price impact
@@ -94,13 +120,19 @@ impl crate::quotes::QuoteCalculator for StandardQuoteCalculator {
         pool: &PoolInfo,
         request: &QuoteRequest,
     ) -> SwapResult<QuoteResult> {
+        // The program refuses swaps until the pool opens
+        if pool.open_time >= chrono::Utc::now().timestamp() as u64 {
+            debug!("CP pool {} opens at {}", pool.address, pool.open_time);
+            return Err(SwapError::PoolNotActive);
+        }
+
         // Extract reserves from pool state
-        let (reserve_in, reserve_out) = match &pool.pool_state {
-            PoolState::Standard { reserve_a, reserve_b } => {
+        let (reserve_in, reserve_out, trade_fee_rate) = match &pool.pool_state {
+            PoolState::Standard { reserve_a, reserve_b, trade_fee_rate } => {
                 if pool.token_a.mint == request.token_in {
-                    (*reserve_a, *reserve_b)
+                    (*reserve_a, *reserve_b, *trade_fee_rate)
                 } else if pool.token_b.mint == request.token_in {
-                    (*reserve_b, *reserve_a)
+                    (*reserve_b, *reserve_a, *trade_fee_rate)
                 } else {
                     return Err(SwapError::InvalidTokenMint(
                         "Input token not found in pool".to_string(),
@@ -115,43 +147,66 @@ impl crate::quotes::QuoteCalculator for StandardQuoteCalculator {
         };
 
         debug!(
-            "Standard Quote: amount_in={}, reserve_in={}, reserve_out={}, fee={}",
-            request.amount_in, reserve_in, reserve_out, pool.fee_rate
+            "Standard Quote: amount={} ({}), reserve_in={}, reserve_out={}, trade fee rate={}",
+            request.amount, request.swap_mode, reserve_in, reserve_out, trade_fee_rate
         );
 
-        // Calculate output amount
-        let amount_out = self.calculate_output_amount(
-            request.amount_in,
-            reserve_in,
-            reserve_out,
-            pool.fee_rate,
-        )?;
+        // Calculate the side of the swap not fixed by the request
+        let (amount_in, amount_out) = match request.swap_mode {
+            SwapMode::ExactIn => {
+                let amount_out = self.calculate_output_amount(
+                    request.amount,
+                    reserve_in,
+                    reserve_out,
+                    trade_fee_rate,
+                )?;
+                (request.amount, amount_out)
+            }
+            SwapMode::ExactOut => {
+                let amount_in = self.calculate_input_amount(
+                    request.amount,
+                    reserve_in,
+                    reserve_out,
+                    trade_fee_rate,
+                )?;
+                (amount_in, request.amount)
+            }
+        };
 
         // Calculate price impact
         let price_impact = self.calculate_price_impact(
-            request.amount_in,
+            amount_in,
             amount_out,
             reserve_in,
             reserve_out,
         );
 
-        // Calculate minimum output with slippage
-        let slippage_multiplier = 1.0 - (request.slippage_bps as f64 / 10000.0);
-        let min_amount_out = (amount_out as f64 * slippage_multiplier) as u64;
+        // Apply slippage to the side that is not fixed
+        let (min_amount_out, max_amount_in) = match request.swap_mode {
+            SwapMode::ExactIn => {
+                let slippage_multiplier = 1.0 - (request.slippage_bps as f64 / 10000.0);
+                ((amount_out as f64 * slippage_multiplier) as u64, amount_in)
+            }
+            SwapMode::ExactOut => (amount_out, max_amount_in_with_slippage(amount_in, request.slippage_bps)),
+        };
 
-        // Calculate fee (round to nearest)
-        let fee = (request.amount_in as f64 * pool.fee_rate).round() as u64;
+        // Trade fee charged by the program, rounded up
+        let fee = Self::trade_fee(amount_in, trade_fee_rate);
 
         Ok(QuoteResult {
             pool_info: pool.clone(),
-            amount_in: request.amount_in,
+            amount_in,
             amount_out,
             min_amount_out,
+            max_amount_in,
+            swap_mode: request.swap_mode,
             price_impact,
             fee,
             route: vec![pool.address],
             token_in: request.token_in,
             token_out: request.token_out,
+            tick_traversal: None,
+            execution_costs: None,
         })
     }
 }
@@ -165,7 +220,7 @@ impl Default for StandardQuoteCalculator {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{PoolType, TokenInfo, STANDARD_FEE_RATE};
+    use crate::core::{PoolType, TokenInfo};
     use crate::quotes::QuoteCalculator;
     use solana_sdk::pubkey::Pubkey;
 
@@ -191,12 +246,14 @@ mod tests {
             token_b,
             liquidity_usd: 50000.0,
             volume_24h_usd: 10000.0,
-            // TODO: fix this
-            fee_rate: STANDARD_FEE_RATE,
+            fee_rate: 0.003,
             program_id: Pubkey::new_unique(),
+            context_slot: 0,
+            open_time: 0,
             pool_state: PoolState::Standard {
                 reserve_a,
                 reserve_b,
+                trade_fee_rate: 3_000,
             },
         }
     }
@@ -207,7 +264,7 @@ mod tests {
 
         // Test basic calculation with standard fee
         let amount_out = calculator
-            .calculate_output_amount(1000, 1_000_000, 1_000_000, 0.003)
+            .calculate_output_amount(1000, 1_000_000, 1_000_000, 3_000)
             .unwrap();
         
         // With 0.3% fee and equal reserves, output should be slightly less than input
@@ -233,12 +290,12 @@ mod tests {
         let calculator = StandardQuoteCalculator::new();
         let pool = create_test_standard_pool(1_000_000, 2_000_000);
         
-        let request = QuoteRequest {
-            token_in: pool.token_a.mint,
-            token_out: pool.token_b.mint,
-            amount_in: 1000,
-            slippage_bps: 100, // 1% slippage
-        };
+        let request = QuoteRequest::exact_in(
+            pool.token_a.mint,
+            pool.token_b.mint,
+            1000,
+            100, // 1% slippage
+        );
 
         let quote = calculator.calculate_quote(&pool, &request).await.unwrap();
         
@@ -250,15 +307,63 @@ mod tests {
         assert_eq!(quote.fee, 3); // 0.3% of 1000
     }
 
+    #[tokio::test]
+    async fn test_calculate_standard_quote_exact_out() {
+        let calculator = StandardQuoteCalculator::new();
+        let pool = create_test_standard_pool(1_000_000, 2_000_000);
+
+        let request = QuoteRequest::exact_out(pool.token_a.mint, pool.token_b.mint, 2000, 100);
+
+        let quote = calculator.calculate_quote(&pool, &request).await.unwrap();
+
+        assert_eq!(quote.amount_out, 2000);
+        assert_eq!(quote.min_amount_out, 2000);
+        assert!(quote.amount_in > 1000); // More than the spot price due to fee and curve
+        assert_eq!(quote.max_amount_in, (quote.amount_in * 101).div_ceil(100)); // 1% slippage
+
+        let amount_out = calculator
+            .calculate_output_amount(quote.amount_in, 1_000_000, 2_000_000, 3_000)
+            .unwrap();
+        assert!(amount_out >= 2000);
+    }
+
+    #[tokio::test]
+    async fn test_cp_curve_matches_program_rounding() {
+        let calculator = StandardQuoteCalculator::new();
+        let mut pool = create_test_standard_pool(1_000_000_000, 2_000_000_000);
+        pool.pool_state = PoolState::Standard {
+            reserve_a: 1_000_000_000,
+            reserve_b: 2_000_000_000,
+            trade_fee_rate: 2_500,
+        };
+
+        // 2,500 fee on 1,000,000 in; 997,500 * 2e9 / (1e9 + 997,500) rounds down
+        let request = QuoteRequest::exact_in(pool.token_a.mint, pool.token_b.mint, 1_000_000, 0);
+        let quote = calculator.calculate_quote(&pool, &request).await.unwrap();
+        assert_eq!((quote.amount_out, quote.fee), (1_993_011, 2_500));
+
+        // Exact out pays the ceiling of the inverse, then grosses up for the fee
+        let request = QuoteRequest::exact_out(pool.token_a.mint, pool.token_b.mint, 1_993_011, 0);
+        let quote = calculator.calculate_quote(&pool, &request).await.unwrap();
+        assert_eq!(quote.amount_in, 1_000_000);
+
+        // Not open yet
+        pool.open_time = chrono::Utc::now().timestamp() as u64 + 3_600;
+        assert!(matches!(
+            calculator.calculate_quote(&pool, &request).await,
+            Err(SwapError::PoolNotActive)
+        ));
+    }
+
     #[test]
     fn test_zero_reserves() {
         let calculator = StandardQuoteCalculator::new();
 
         // Zero reserves should error
-        let result = calculator.calculate_output_amount(1000, 0, 1_000_000, 0.003);
+        let result = calculator.calculate_output_amount(1000, 0, 1_000_000, 3_000);
         assert!(result.is_err());
 
-        let result = calculator.calculate_output_amount(1000, 1_000_000, 0, 0.003);
+        let result = calculator.calculate_output_amount(1000, 1_000_000, 0, 3_000);
         assert!(result.is_err());
     }
 
@@ -268,17 +373,17 @@ mod tests {
 
         // 1:1 ratio
         let amount_out_1_1 = calculator
-            .calculate_output_amount(1000, 1_000_000, 1_000_000, 0.003)
+            .calculate_output_amount(1000, 1_000_000, 1_000_000, 3_000)
             .unwrap();
 
         // 1:2 ratio (more output token)
         let amount_out_1_2 = calculator
-            .calculate_output_amount(1000, 1_000_000, 2_000_000, 0.003)
+            .calculate_output_amount(1000, 1_000_000, 2_000_000, 3_000)
             .unwrap();
 
         // 2:1 ratio (less output token)
         let amount_out_2_1 = calculator
-            .calculate_output_amount(1000, 2_000_000, 1_000_000, 0.003)
+            .calculate_output_amount(1000, 2_000_000, 1_000_000, 3_000)
             .unwrap();
 
         // Should get more output when output reserve is higher
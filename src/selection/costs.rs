@@ -0,0 +1,255 @@
+use crate::core::{
+    ExecutionCosts, MintCache, PoolType, PriorityFee, QuoteResult, ACCOUNT_CREATION_COMPUTE_UNITS,
+    AMM_SWAP_COMPUTE_UNITS, CLMM_SWAP_COMPUTE_UNITS, CLMM_TICK_ARRAY_COMPUTE_UNITS, CP_SWAP_COMPUTE_UNITS,
+    DEFAULT_COMPUTE_UNIT_PRICE, DEFAULT_MAX_PRIORITY_FEE_LAMPORTS, DEFAULT_PRIORITY_FEE_PERCENTILE,
+    LAMPORTS_PER_SIGNATURE, STABLE_SWAP_COMPUTE_UNITS, TOKEN_ACCOUNT_RENT_LAMPORTS,
+};
+use crate::transaction::priority_fee::{compute_unit_limit_with_margin, PriorityFeeEstimator};
+use dashmap::DashMap;
+use log::debug;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use std::sync::Arc;
+
+/// Estimates what landing a swap costs outside the pool: signature and
+/// priority fees, rent for token accounts the swap creates, and wSOL wrapping
+///
+/// Mirrors what `TransactionExecutor` adds around the swap instructions,
+/// which requests the compute units the swap simulates to plus a margin.
+pub struct CostModel {
+    rpc_client: Option<Arc<RpcClient>>,
+    /// Token program of each mint, for the owner's associated token accounts
+    mints: Option<MintCache>,
+    owner: Option<Pubkey>,
+    priority_fee: PriorityFeeEstimator,
+    /// Whether the owner holds each mint's associated token account
+    accounts: DashMap<Pubkey, bool>,
+}
+
+/// Token accounts a swap needs that the owner does not hold yet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct AccountNeeds {
+    /// Accounts created and kept open after the swap
+    kept: u64,
+    /// Whether a wSOL account is created to wrap native SOL input
+    wsol_input: bool,
+}
+
+impl CostModel {
+    /// Model that checks `owner`'s existing token accounts; without an owner
+    /// every account is assumed to need creating
+    pub fn new(rpc_client: Arc<RpcClient>, owner: Option<Pubkey>) -> Self {
+        Self {
+            mints: Some(MintCache::new(rpc_client.clone())),
+            rpc_client: Some(rpc_client),
+            owner,
+            priority_fee: default_priority_fee(),
+            accounts: DashMap::new(),
+        }
+    }
+
+    /// Model without RPC access, assuming every token account must be created
+    pub fn offline() -> Self {
+        Self {
+            rpc_client: None,
+            mints: None,
+            owner: None,
+            priority_fee: default_priority_fee(),
+            accounts: DashMap::new(),
+        }
+    }
+
+    /// Price compute units the way the transaction executor will
+    pub fn with_priority_fee(mut self, priority_fee: PriorityFeeEstimator) -> Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    /// Priority fee in lamports for requesting `compute_unit_limit` units in
+    /// a transaction writing `accounts`
+    pub async fn priority_fee(&self, accounts: &[Pubkey], compute_unit_limit: u32) -> u64 {
+        let compute_unit_price = self
+            .priority_fee
+            .compute_unit_price_for_accounts(self.rpc_client.as_deref(), accounts, compute_unit_limit)
+            .await;
+        (compute_unit_limit as u128 * compute_unit_price as u128).div_ceil(1_000_000) as u64
+    }
+
+    /// Estimate the costs of executing `quotes` in one transaction: the hops
+    /// of a route in order, the legs of a split, or a single pool's quote
+    pub async fn estimate(&self, quotes: &[&QuoteResult]) -> ExecutionCosts {
+        let (Some(first), Some(last)) = (quotes.first(), quotes.last()) else {
+            return ExecutionCosts::default();
+        };
+        let (token_in, token_out) = (first.token_in, last.token_out);
+
+        // Tokens a route holds between hops; split legs share their ends
+        let mut intermediate_mints: Vec<Pubkey> = Vec::new();
+        for quote in quotes {
+            let mint = quote.token_out;
+            if mint != token_in && mint != token_out && !intermediate_mints.contains(&mint) {
+                intermediate_mints.push(mint);
+            }
+        }
+
+        let needs = self.account_needs(token_in, token_out, &intermediate_mints).await;
+        let created = needs.kept + needs.wsol_input as u64;
+        let compute_units: u64 = quotes.iter().map(|quote| swap_compute_units(quote)).sum::<u64>()
+            + created * ACCOUNT_CREATION_COMPUTE_UNITS;
+        // Every swap writes its pool account, the most contended one it touches
+        let pools: Vec<Pubkey> = quotes.iter().map(|quote| quote.pool_info.address).collect();
+        let priority_fee = self
+            .priority_fee(&pools, compute_unit_limit_with_margin(compute_units))
+            .await;
+
+        ExecutionCosts {
+            signature_fee: LAMPORTS_PER_SIGNATURE,
+            priority_fee,
+            ata_rent: needs.kept * TOKEN_ACCOUNT_RENT_LAMPORTS,
+            wsol_overhead: if needs.wsol_input { TOKEN_ACCOUNT_RENT_LAMPORTS } else { 0 },
+            total_in_token: None,
+        }
+    }
+
+    /// Accounts a swap from `token_in` to `token_out`, holding
+    /// `intermediate_mints` between hops, has to create
+    async fn account_needs(
+        &self,
+        token_in: Pubkey,
+        token_out: Pubkey,
+        intermediate_mints: &[Pubkey],
+    ) -> AccountNeeds {
+        let native_mint = spl_token::native_mint::ID;
+        let mut needs = AccountNeeds::default();
+
+        for mint in intermediate_mints.iter().chain(std::iter::once(&token_out)) {
+            // A wSOL output account is closed by the unwrap, refunding its rent
+            if *mint == token_out && *mint == native_mint {
+                continue;
+            }
+            if !self.account_exists(mint).await {
+                needs.kept += 1;
+            }
+        }
+
+        // Wrapping native SOL input leaves a wSOL account open
+        needs.wsol_input = token_in == native_mint && !self.account_exists(&native_mint).await;
+        needs
+    }
+
+    /// Whether the owner already holds the associated token account for
+    /// `mint`, derived under the mint's own token program
+    async fn account_exists(&self, mint: &Pubkey) -> bool {
+        let (Some(rpc_client), Some(mints), Some(owner)) = (&self.rpc_client, &self.mints, &self.owner) else {
+            return false;
+        };
+        if let Some(exists) = self.accounts.get(mint) {
+            return *exists;
+        }
+
+        let token_program = match mints.get(mint).await {
+            Ok(info) => info.token_program,
+            Err(e) => {
+                debug!("Failed to load mint {}: {}, assuming SPL Token", mint, e);
+                spl_token::ID
+            }
+        };
+        let ata = get_associated_token_address_with_program_id(owner, mint, &token_program);
+        let exists = match rpc_client.get_account(&ata).await {
+            Ok(_) => true,
+            Err(e) => {
+                debug!("Token account {} for {} not found: {}", ata, mint, e);
+                false
+            }
+        };
+        self.accounts.insert(*mint, exists);
+        exists
+    }
+}
+
+/// Default compute unit price, for models not given the configured one
+fn default_priority_fee() -> PriorityFeeEstimator {
+    PriorityFeeEstimator::new(
+        PriorityFee::Fixed(DEFAULT_COMPUTE_UNIT_PRICE),
+        DEFAULT_PRIORITY_FEE_PERCENTILE,
+        DEFAULT_MAX_PRIORITY_FEE_LAMPORTS,
+    )
+}
+
+/// Compute units the swap instruction of `quote` typically consumes
+fn swap_compute_units(quote: &QuoteResult) -> u64 {
+    match quote.pool_info.pool_type {
+        PoolType::AMM => AMM_SWAP_COMPUTE_UNITS,
+        PoolType::Stable => STABLE_SWAP_COMPUTE_UNITS,
+        PoolType::CLMM => {
+            let tick_arrays = quote
+                .tick_traversal
+                .as_ref()
+                .map_or(0, |traversal| traversal.tick_arrays_crossed as u64);
+            CLMM_SWAP_COMPUTE_UNITS + tick_arrays * CLMM_TICK_ARRAY_COMPUTE_UNITS
+        }
+        PoolType::Standard => CP_SWAP_COMPUTE_UNITS,
+    }
+}
+
+/// Convert lamports into units of a token, given how many units
+/// `probe_lamports` of SOL buys
+pub fn lamports_to_token(lamports: u64, probe_lamports: u64, probe_amount_out: u64) -> u64 {
+    if probe_lamports == 0 {
+        return 0;
+    }
+    let amount = (lamports as u128 * probe_amount_out as u128).div_ceil(probe_lamports as u128);
+    amount.min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_offline_estimate_assumes_new_accounts() {
+        let model = CostModel::offline();
+        let usdc = Pubkey::new_unique();
+        let sol = spl_token::native_mint::ID;
+
+        // SOL -> USDC: new USDC account plus the wSOL account for wrapping
+        let needs = model.account_needs(sol, usdc, &[]).await;
+        assert_eq!(needs, AccountNeeds { kept: 1, wsol_input: true });
+
+        // USDC -> SOL: the wSOL output account is closed again
+        let needs = model.account_needs(usdc, sol, &[]).await;
+        assert_eq!(needs, AccountNeeds::default());
+
+        // Route through an intermediate token keeps its account open
+        let needs = model.account_needs(usdc, sol, &[Pubkey::new_unique()]).await;
+        assert_eq!(needs.kept, 1);
+    }
+
+    #[tokio::test]
+    async fn test_priority_fee_rounds_up() {
+        let fixed = |price| PriorityFeeEstimator::new(PriorityFee::Fixed(price), 75, u64::MAX);
+        let model = CostModel::offline().with_priority_fee(fixed(1));
+        assert_eq!(model.priority_fee(&[], 200_001).await, 1);
+        let model = CostModel::offline().with_priority_fee(fixed(50_000));
+        assert_eq!(model.priority_fee(&[], 1_400_000).await, 70_000);
+
+        // The executor's lamport cap applies to estimates too
+        let capped = PriorityFeeEstimator::new(PriorityFee::Fixed(50_000), 75, 10_000);
+        let model = CostModel::offline().with_priority_fee(capped);
+        assert_eq!(model.priority_fee(&[], 1_000_000).await, 10_000);
+
+        // Automatic fees need an RPC client; offline models use the default
+        let auto = PriorityFeeEstimator::new(PriorityFee::Auto, 75, u64::MAX);
+        let model = CostModel::offline().with_priority_fee(auto);
+        assert_eq!(model.priority_fee(&[], 1_000_000).await, DEFAULT_COMPUTE_UNIT_PRICE);
+    }
+
+    #[test]
+    fn test_lamports_to_token() {
+        // 0.01 SOL buys 1.5 USDC (6 decimals)
+        assert_eq!(lamports_to_token(2_044_680, 10_000_000, 1_500_000), 306_702);
+        assert_eq!(lamports_to_token(1, 10_000_000, 1_500_000), 1);
+        assert_eq!(lamports_to_token(5_000, 0, 1_500_000), 0);
+    }
+}
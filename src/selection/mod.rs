@@ -1,13 +1,29 @@
-use crate::core::{PoolInfo, PoolType, QuoteRequest, QuoteResult, SwapResult};
+pub mod costs;
+pub mod route;
+pub mod split;
+
+use crate::core::{
+    PoolInfo, PoolType, QuoteRequest, QuoteResult, RouteQuote, SplitQuote, SwapMode, SwapResult,
+    TOKEN_ACCOUNT_RENT_LAMPORTS,
+};
 use crate::discovery::PoolDiscovery;
 use crate::quotes::QuoteEngine;
 use log::{debug, info};
+use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
 
+pub use costs::CostModel;
+pub use route::RouteFinder;
+pub use split::{SplitConfig, SplitOptimizer};
+
+/// Amount of SOL quoted to price execution costs in another token (0.01 SOL)
+const COST_PRICE_PROBE_LAMPORTS: u64 = 10_000_000;
+
 /// Smart pool selector that finds the best pool for a swap
 pub struct PoolSelector {
     discovery: Arc<PoolDiscovery>,
     quote_engine: Arc<QuoteEngine>,
+    cost_model: Option<CostModel>,
 }
 
 impl PoolSelector {
@@ -15,9 +31,16 @@ impl PoolSelector {
         Self {
             discovery,
             quote_engine,
+            cost_model: None,
         }
     }
 
+    /// Rank quotes net of the execution costs estimated by `cost_model`
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = Some(cost_model);
+        self
+    }
+
     /// Select the best pool for a swap request
     pub async fn select_best_pool(
         &self,
@@ -49,6 +72,50 @@ impl PoolSelector {
         Ok(best_quote)
     }
 
+    /// Split a swap request across the pools of its pair for the best overall fill
+    pub async fn select_best_split(
+        &self,
+        request: &QuoteRequest,
+        config: SplitConfig,
+    ) -> SwapResult<Option<SplitQuote>> {
+        let pools = self
+            .discovery
+            .discover_all_pools(request.token_in, request.token_out)
+            .await?;
+
+        info!(
+            "Optimizing split across {} pools for {}/{}",
+            pools.len(),
+            request.token_in,
+            request.token_out
+        );
+
+        SplitOptimizer::new(&self.quote_engine, config)
+            .optimize(&pools, request)
+            .await
+    }
+
+    /// Find the best route for a swap request, chaining pools through `hub_tokens`
+    pub async fn select_best_route(
+        &self,
+        request: &QuoteRequest,
+        hub_tokens: Vec<Pubkey>,
+    ) -> SwapResult<Option<RouteQuote>> {
+        let mut finder = RouteFinder::new(&self.discovery, &self.quote_engine, hub_tokens);
+
+        // Every intermediate token needs an account of its own
+        if self.cost_model.is_some() {
+            if let Some(account_cost) = self
+                .lamports_in_token(TOKEN_ACCOUNT_RENT_LAMPORTS, ranked_token(request))
+                .await
+            {
+                finder = finder.with_account_cost(account_cost);
+            }
+        }
+
+        finder.find_best_route(request).await
+    }
+
     /// Get quotes from all pools
     async fn get_quotes_from_pools(
         &self,
@@ -76,33 +143,87 @@ impl PoolSelector {
             }
         }
 
+        self.attach_costs(&mut valid_quotes, request).await;
         valid_quotes
     }
 
-    /// Select the best quote based on output amount and other factors
+    /// Attach the cost model's estimate for each quote of `request`,
+    /// priced in the token each quote is ranked by
+    async fn attach_costs(&self, quotes: &mut [QuoteResult], request: &QuoteRequest) {
+        let Some(cost_model) = &self.cost_model else {
+            return;
+        };
+        if quotes.is_empty() {
+            return;
+        }
+
+        let probe_out = self.cost_probe_out(ranked_token(request)).await;
+        for quote in quotes.iter_mut() {
+            let mut costs = cost_model.estimate(&[&*quote]).await;
+            costs.total_in_token = probe_out.map(|amount_out| {
+                costs::lamports_to_token(costs.total_lamports(), COST_PRICE_PROBE_LAMPORTS, amount_out)
+            });
+            debug!(
+                "Execution costs via pool {}: {} lamports ({:?} in ranked token)",
+                quote.pool_info.address,
+                costs.total_lamports(),
+                costs.total_in_token
+            );
+            quote.execution_costs = Some(costs);
+        }
+    }
+
+    /// Convert lamports into units of `token` at the best SOL pool's price
+    async fn lamports_in_token(&self, lamports: u64, token: Pubkey) -> Option<u64> {
+        self.cost_probe_out(token)
+            .await
+            .map(|amount_out| costs::lamports_to_token(lamports, COST_PRICE_PROBE_LAMPORTS, amount_out))
+    }
+
+    /// Units of `token` the best SOL pool pays for `COST_PRICE_PROBE_LAMPORTS`
+    async fn cost_probe_out(&self, token: Pubkey) -> Option<u64> {
+        let sol = spl_token::native_mint::ID;
+        if token == sol {
+            return Some(COST_PRICE_PROBE_LAMPORTS);
+        }
+
+        let pools = match self.discovery.discover_all_pools(sol, token).await {
+            Ok(pools) => pools,
+            Err(e) => {
+                debug!("Cannot price execution costs in {}: {}", token, e);
+                return None;
+            }
+        };
+
+        let probe = QuoteRequest::exact_in(sol, token, COST_PRICE_PROBE_LAMPORTS, 0);
+        let mut best_out: Option<u64> = None;
+        for pool in &pools {
+            if let Ok(quote) = self.quote_engine.calculate_quote(pool, &probe).await {
+                best_out = best_out.max(Some(quote.amount_out));
+            }
+        }
+        best_out
+    }
+
+    /// Select the best quote by output amount (or input cost for exact-out
+    /// quotes) net of execution costs; ties, or quotes within the scoring
+    /// strategy's tie tolerance of the best if it sets one, are decided by
+    /// pool score
     fn select_best_quote(&self, quotes: Vec<QuoteResult>) -> Option<QuoteResult> {
+        let scorer = self.discovery.scorer();
+        let best = quotes.iter().map(|quote| quote.net_execution_score()).max()?;
+        let tolerance = best.abs() * scorer.strategy().tie_tolerance_bps as i128 / 10_000;
+
         quotes
             .into_iter()
-            .max_by_key(|quote| {
-                // Primary criterion: maximum output amount
-                let output_score = quote.amount_out;
-
-                // Secondary criterion: prefer certain pool types for stability
-                let type_bonus = match quote.pool_info.pool_type {
-                    PoolType::Stable => {
-                        // Bonus for stable pools if dealing with stablecoins
-                        if self.is_stable_pair(&quote.pool_info) {
-                            1000 // Small bonus
-                        } else {
-                            0
-                        }
-                    }
-                    PoolType::CLMM => 100, // Small bonus for capital efficiency
-                    _ => 0,
-                };
-
-                output_score + type_bonus
+            .filter(|quote| quote.net_execution_score() >= best - tolerance)
+            .map(|quote| (scorer.score(&quote.pool_info), quote))
+            .max_by(|(score_a, a), (score_b, b)| {
+                score_a
+                    .total_cmp(score_b)
+                    .then_with(|| a.net_execution_score().cmp(&b.net_execution_score()))
             })
+            .map(|(_, quote)| quote)
     }
 
     /// Get quotes from all pools with details
@@ -138,13 +259,13 @@ impl PoolSelector {
 
         Ok(quotes_by_type)
     }
+}
 
-    /// Check if tokens form a stable pair
-    fn is_stable_pair(&self, pool: &PoolInfo) -> bool {
-        let stable_symbols = ["USDC", "USDT", "DAI", "BUSD", "TUSD", "USDP"];
-
-        stable_symbols.contains(&pool.token_a.symbol.as_str())
-            && stable_symbols.contains(&pool.token_b.symbol.as_str())
+/// Token whose amount a quote for `request` is ranked by
+fn ranked_token(request: &QuoteRequest) -> Pubkey {
+    match request.swap_mode {
+        SwapMode::ExactIn => request.token_out,
+        SwapMode::ExactOut => request.token_in,
     }
 }
 
@@ -175,7 +296,7 @@ impl QuotesByType {
 
         all_quotes
             .into_iter()
-            .max_by_key(|quote| quote.amount_out)
+            .max_by_key(|quote| quote.net_execution_score())
     }
 
     /// Get summary statistics
@@ -185,10 +306,10 @@ impl QuotesByType {
             stable_count: self.stable.len(),
             clmm_count: self.clmm.len(),
             standard_count: self.standard.len(),
-            best_amm: self.amm.iter().max_by_key(|q| q.amount_out).cloned(),
-            best_stable: self.stable.iter().max_by_key(|q| q.amount_out).cloned(),
-            best_clmm: self.clmm.iter().max_by_key(|q| q.amount_out).cloned(),
-            best_standard: self.standard.iter().max_by_key(|q| q.amount_out).cloned(),
+            best_amm: self.amm.iter().max_by_key(|q| q.net_execution_score()).cloned(),
+            best_stable: self.stable.iter().max_by_key(|q| q.net_execution_score()).cloned(),
+            best_clmm: self.clmm.iter().max_by_key(|q| q.net_execution_score()).cloned(),
+            best_standard: self.standard.iter().max_by_key(|q| q.net_execution_score()).cloned(),
         }
     }
 }
@@ -208,7 +329,7 @@ pub struct PoolTypeSummary {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{Config, PoolState, TokenInfo};
+    use crate::core::{Config, ExecutionCosts, PoolState, TickTraversal, TokenInfo};
     use solana_sdk::pubkey::Pubkey;
 
 
@@ -232,25 +353,35 @@ mod tests {
             volume_24h_usd: 50000.0,
             fee_rate: 0.0025,
             program_id: Pubkey::new_unique(),
+            context_slot: 0,
+            open_time: 0,
             pool_state: match pool_type {
                 PoolType::AMM => PoolState::AMM {
                     reserve_a: 1000000,
                     reserve_b: 1000000,
                     nonce: 1,
+                    need_take_pnl_coin: 0,
+                    need_take_pnl_pc: 0,
+                    swap_fee_numerator: 25,
+                    swap_fee_denominator: 10000,
                 },
                 PoolType::Stable => PoolState::Stable {
                     reserves: vec![1000000, 1000000],
                     amp_factor: 1000,
+                    swap_fee_numerator: 4,
+                    swap_fee_denominator: 10000,
                 },
                 PoolType::CLMM => PoolState::CLMM {
                     current_tick: 0,
                     tick_spacing: 64,
                     liquidity: 1000000,
+                    sqrt_price_x64: 1u128 << 64,
                     fee_tier: 250,
                 },
                 PoolType::Standard => PoolState::Standard {
                     reserve_a: 1000000,
                     reserve_b: 1000000,
+                    trade_fee_rate: 2500,
                 },
             },
         };
@@ -260,11 +391,15 @@ mod tests {
             amount_in: 1000,
             amount_out,
             min_amount_out: amount_out * 99 / 100,
+            max_amount_in: 1000,
+            swap_mode: SwapMode::ExactIn,
             price_impact: 0.1,
             fee: 2,
             route: vec![Pubkey::new_unique()],
             token_in: pool_info.token_a.mint,
             token_out: pool_info.token_b.mint,
+            tick_traversal: None,
+            execution_costs: None,
         }
     }
 
@@ -273,6 +408,7 @@ mod tests {
         let selector = PoolSelector {
             discovery: Arc::new(PoolDiscovery::new(Config::default()).unwrap()),
             quote_engine: Arc::new(QuoteEngine::new()),
+            cost_model: None,
         };
 
         let quotes = vec![
@@ -283,7 +419,55 @@ mod tests {
         ];
 
         let best = selector.select_best_quote(quotes).unwrap();
-        assert_eq!(best.amount_out, 1003); // CLMM pool wins due to 100 point bonus
+        assert_eq!(best.amount_out, 1005);
+
+        // A better-paying quote wins however much better the other pool scores
+        let quotes = vec![
+            create_test_quote(PoolType::AMM, 10_000),
+            create_test_quote(PoolType::Stable, 9_995),
+        ];
+        let best = selector.select_best_quote(quotes.clone()).unwrap();
+        assert_eq!(best.pool_info.pool_type, PoolType::AMM);
+
+        // Exact ties go to the better scoring pool
+        let tied = vec![create_test_quote(PoolType::AMM, 10_000), create_test_quote(PoolType::Stable, 10_000)];
+        let best = selector.select_best_quote(tied).unwrap();
+        assert_eq!(best.pool_info.pool_type, PoolType::Stable);
+
+        // Within an opted-in 10 bps tolerance the stable pool's score decides
+        let mut config = Config::default();
+        config.scoring_strategy.tie_tolerance_bps = 10;
+        let selector = PoolSelector {
+            discovery: Arc::new(PoolDiscovery::new(config).unwrap()),
+            ..selector
+        };
+        let best = selector.select_best_quote(quotes).unwrap();
+        assert_eq!(best.pool_info.pool_type, PoolType::Stable);
+    }
+
+    #[test]
+    fn test_select_best_quote_exact_out() {
+        let selector = PoolSelector {
+            discovery: Arc::new(PoolDiscovery::new(Config::default()).unwrap()),
+            quote_engine: Arc::new(QuoteEngine::new()),
+            cost_model: None,
+        };
+
+        let exact_out = |pool_type, amount_in| QuoteResult {
+            amount_in,
+            max_amount_in: amount_in,
+            swap_mode: SwapMode::ExactOut,
+            ..create_test_quote(pool_type, 1000)
+        };
+        let quotes = vec![
+            exact_out(PoolType::AMM, 1010),
+            exact_out(PoolType::Standard, 1004),
+            exact_out(PoolType::CLMM, 1200),
+        ];
+
+        // Cheapest input wins when the output is fixed
+        let best = selector.select_best_quote(quotes).unwrap();
+        assert_eq!(best.amount_in, 1004);
     }
 
     #[test]
@@ -306,11 +490,147 @@ mod tests {
         assert_eq!(summary.standard_count, 0);
     }
 
+    #[test]
+    fn test_execution_costs_net_out_of_quotes() {
+        let costs = |total_in_token| ExecutionCosts {
+            signature_fee: 5_000,
+            priority_fee: 400,
+            ata_rent: 2_039_280,
+            wsol_overhead: 0,
+            total_in_token,
+        };
+        let mut quotes_by_type = QuotesByType::default();
+        quotes_by_type.amm.push(QuoteResult {
+            execution_costs: Some(costs(Some(300))),
+            ..create_test_quote(PoolType::AMM, 1000)
+        });
+        quotes_by_type.stable.push(QuoteResult {
+            execution_costs: Some(costs(Some(10))),
+            ..create_test_quote(PoolType::Stable, 995)
+        });
+
+        // The cheaper-to-execute quote wins despite a lower gross output
+        let best = quotes_by_type.best_quote().unwrap();
+        assert_eq!(best.amount_out, 995);
+        assert_eq!(best.net_amount_out(), Some(985));
+        assert!(best.is_worth_executing());
+
+        // Costs exceeding the output make the trade pointless
+        let dust = QuoteResult {
+            execution_costs: Some(costs(Some(2_000))),
+            ..create_test_quote(PoolType::AMM, 1000)
+        };
+        assert_eq!(dust.net_amount_out(), Some(-1000));
+        assert!(!dust.is_worth_executing());
+
+        // Unpriced costs leave the ranking gross
+        let unpriced = QuoteResult {
+            execution_costs: Some(costs(None)),
+            ..create_test_quote(PoolType::AMM, 1000)
+        };
+        assert_eq!(unpriced.net_execution_score(), unpriced.execution_score());
+        assert!(unpriced.is_worth_executing());
+    }
+
+    /// Quote of `pool_type` swapping `token_in` into `token_out`
+    fn leg(pool_type: PoolType, token_in: Pubkey, token_out: Pubkey) -> QuoteResult {
+        QuoteResult {
+            token_in,
+            token_out,
+            ..create_test_quote(pool_type, 1000)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attach_costs_per_quote() {
+        let selector = PoolSelector {
+            discovery: Arc::new(PoolDiscovery::new(Config::default()).unwrap()),
+            quote_engine: Arc::new(QuoteEngine::new()),
+            cost_model: Some(CostModel::offline()),
+        };
+        let (usdc, sol) = (Pubkey::new_unique(), spl_token::native_mint::ID);
+
+        let mut clmm = leg(PoolType::CLMM, usdc, sol);
+        clmm.tick_traversal = Some(TickTraversal { ticks_crossed: 5, tick_arrays_crossed: 3, end_tick: 0 });
+        let mut quotes = vec![leg(PoolType::AMM, usdc, sol), leg(PoolType::Stable, usdc, sol), clmm];
+        selector
+            .attach_costs(&mut quotes, &QuoteRequest::exact_in(usdc, sol, 1000, 50))
+            .await;
+
+        // Compute units by pool type plus the 15% margin, at 1,000 micro-lamports:
+        // 40k for AMM, 90k for Stable, 60k + 3 x 20k for CLMM crossing 3 tick arrays
+        let priority_fees: Vec<u64> = quotes
+            .iter()
+            .map(|quote| quote.execution_costs.unwrap().priority_fee)
+            .collect();
+        assert_eq!(priority_fees, vec![46, 104, 138]);
+
+        for quote in &quotes {
+            let costs = quote.execution_costs.unwrap();
+            // The wSOL output account is closed by the unwrap
+            assert_eq!(costs.ata_rent, 0);
+            assert_eq!(costs.wsol_overhead, 0);
+            // Ranked in SOL, so no conversion
+            assert_eq!(costs.total_in_token, Some(costs.total_lamports()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cost_estimate_for_splits_and_routes() {
+        let model = CostModel::offline();
+        let (usdc, ray, sol) = (Pubkey::new_unique(), Pubkey::new_unique(), spl_token::native_mint::ID);
+
+        // Split legs share one output account and one wSOL wrap
+        let (first, second) = (leg(PoolType::AMM, sol, usdc), leg(PoolType::AMM, sol, usdc));
+        let costs = model.estimate(&[&first, &second]).await;
+        assert_eq!(costs.ata_rent, TOKEN_ACCOUNT_RENT_LAMPORTS);
+        assert_eq!(costs.wsol_overhead, TOKEN_ACCOUNT_RENT_LAMPORTS);
+        // 2 x 40k for the swaps and 2 x 25k for the accounts, plus 15%
+        assert_eq!(costs.priority_fee, 150);
+
+        // A route keeps the token it holds between hops
+        let (first, second) = (leg(PoolType::AMM, usdc, ray), leg(PoolType::CLMM, ray, sol));
+        let costs = model.estimate(&[&first, &second]).await;
+        assert_eq!(costs.ata_rent, TOKEN_ACCOUNT_RENT_LAMPORTS);
+        assert_eq!(costs.wsol_overhead, 0);
+        // 40k + 60k for the swaps and 25k for the intermediate account, plus 15%
+        assert_eq!(costs.priority_fee, 144);
+    }
+
+    #[test]
+    fn test_quote_staleness_and_drift() {
+        let mut quote = create_test_quote(PoolType::AMM, 1000);
+
+        // Unknown age is always stale
+        assert!(quote.is_stale(100, 25));
+
+        quote.pool_info.context_slot = 100;
+        assert!(!quote.is_stale(125, 25));
+        assert!(quote.is_stale(126, 25));
+        assert!(!quote.is_stale(90, 25));
+
+        let better = QuoteResult { amount_out: 1010, ..quote.clone() };
+        let worse = QuoteResult { amount_out: 994, ..quote.clone() };
+        assert_eq!(quote.drift_bps(&better), 0);
+        assert_eq!(quote.drift_bps(&worse), 60);
+
+        // Exact-out quotes drift when the input grows
+        let exact_out = QuoteResult {
+            swap_mode: SwapMode::ExactOut,
+            amount_in: 1000,
+            ..quote.clone()
+        };
+        let pricier = QuoteResult { amount_in: 1003, ..exact_out.clone() };
+        assert_eq!(exact_out.drift_bps(&pricier), 30);
+        assert_eq!(pricier.drift_bps(&exact_out), 0);
+    }
+
     #[test]
     fn test_stable_pair_detection() {
         let selector = PoolSelector {
             discovery: Arc::new(PoolDiscovery::new(Config::default()).unwrap()),
             quote_engine: Arc::new(QuoteEngine::new()),
+            cost_model: None,
         };
 
         let mut pool = PoolInfo {
@@ -332,16 +652,21 @@ mod tests {
             volume_24h_usd: 500000.0,
             fee_rate: 0.0004,
             program_id: Pubkey::new_unique(),
+            context_slot: 0,
+            open_time: 0,
             pool_state: PoolState::Stable {
                 reserves: vec![1000000, 1000000],
                 amp_factor: 1000,
+                swap_fee_numerator: 4,
+                swap_fee_denominator: 10000,
             },
         };
 
-        assert!(selector.is_stable_pair(&pool));
+        let scorer = selector.discovery.scorer();
+        let stable_pair_score = scorer.score(&pool);
 
         // Change one token to non-stable
         pool.token_a.symbol = "SOL".to_string();
-        assert!(!selector.is_stable_pair(&pool));
+        assert!(scorer.score(&pool) < stable_pair_score);
     }
 }
\ No newline at end of file
@@ -0,0 +1,368 @@
+use crate::core::{PoolInfo, QuoteRequest, QuoteResult, RouteQuote, SwapMode, SwapResult};
+use crate::discovery::PoolDiscovery;
+use crate::quotes::QuoteEngine;
+use log::{debug, info};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+
+/// Pools available for each token pair, keyed by [`pair_key`]
+pub(crate) type PairPools = HashMap<(Pubkey, Pubkey), Vec<PoolInfo>>;
+
+/// Order-independent key for a token pair
+fn pair_key(token_a: Pubkey, token_b: Pubkey) -> (Pubkey, Pubkey) {
+    if token_a <= token_b {
+        (token_a, token_b)
+    } else {
+        (token_b, token_a)
+    }
+}
+
+/// Finds the best multi-hop route between two tokens through hub tokens
+///
+/// Exact-in routes feed each hop the previous hop's minimum output, and
+/// exact-out routes ask each hop for the next hop's maximum input, so every
+/// hop can execute even when the one before it fills at the slippage limit.
+pub struct RouteFinder<'a> {
+    discovery: &'a PoolDiscovery,
+    quote_engine: &'a QuoteEngine,
+    hub_tokens: Vec<Pubkey>,
+    max_hops: usize,
+    account_cost: u64,
+}
+
+impl<'a> RouteFinder<'a> {
+    pub fn new(discovery: &'a PoolDiscovery, quote_engine: &'a QuoteEngine, hub_tokens: Vec<Pubkey>) -> Self {
+        Self {
+            discovery,
+            quote_engine,
+            hub_tokens,
+            max_hops: 3,
+            account_cost: 0,
+        }
+    }
+
+    /// Charge each intermediate token `account_cost`, in units of the ranked
+    /// token, for the account the route has to create for it
+    pub fn with_account_cost(mut self, account_cost: u64) -> Self {
+        self.account_cost = account_cost;
+        self
+    }
+
+    /// Limit routes to `max_hops` swaps (1 = direct only)
+    pub fn with_max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = max_hops.clamp(1, 3);
+        self
+    }
+
+    /// Find the best end-to-end route for `request`, direct pools included
+    pub async fn find_best_route(&self, request: &QuoteRequest) -> SwapResult<Option<RouteQuote>> {
+        let hubs = self.hubs_for(request);
+        let mut pools = PairPools::new();
+
+        self.load_pair(&mut pools, request.token_in, request.token_out).await;
+        if self.max_hops >= 2 {
+            for hub in &hubs {
+                self.load_pair(&mut pools, request.token_in, *hub).await;
+                self.load_pair(&mut pools, *hub, request.token_out).await;
+            }
+        }
+        if self.max_hops >= 3 {
+            // Only connect hubs that are reachable from both ends
+            for first in &hubs {
+                if !has_pools(&pools, request.token_in, *first) {
+                    continue;
+                }
+                for second in &hubs {
+                    if first != second && has_pools(&pools, *second, request.token_out) {
+                        self.load_pair(&mut pools, *first, *second).await;
+                    }
+                }
+            }
+        }
+
+        let paths = candidate_paths(request.token_in, request.token_out, &hubs, self.max_hops);
+        info!(
+            "Evaluating {} candidate routes for {} -> {}",
+            paths.len(),
+            request.token_in,
+            request.token_out
+        );
+
+        Ok(self.best_route_from_pools(&paths, &pools, request).await)
+    }
+
+    /// Hub tokens usable for this request, excluding its own tokens
+    fn hubs_for(&self, request: &QuoteRequest) -> Vec<Pubkey> {
+        let mut hubs: Vec<Pubkey> = Vec::new();
+        for hub in &self.hub_tokens {
+            if *hub != request.token_in && *hub != request.token_out && !hubs.contains(hub) {
+                hubs.push(*hub);
+            }
+        }
+        hubs
+    }
+
+    /// Discover the pools of a pair once, treating failures as no pools
+    async fn load_pair(&self, pools: &mut PairPools, token_a: Pubkey, token_b: Pubkey) {
+        let key = pair_key(token_a, token_b);
+        if pools.contains_key(&key) {
+            return;
+        }
+
+        let found = match self.discovery.discover_all_pools(token_a, token_b).await {
+            Ok(found) => found,
+            Err(e) => {
+                debug!("Pool discovery failed for {}/{}: {}", token_a, token_b, e);
+                Vec::new()
+            }
+        };
+        pools.insert(key, found);
+    }
+
+    /// Quote every path against known pools and keep the best one
+    pub(crate) async fn best_route_from_pools(
+        &self,
+        paths: &[Vec<Pubkey>],
+        pools: &PairPools,
+        request: &QuoteRequest,
+    ) -> Option<RouteQuote> {
+        let mut best: Option<RouteQuote> = None;
+
+        for path in paths {
+            let Some(route) = self.quote_path(path, pools, request).await else {
+                continue;
+            };
+            debug!(
+                "Route {:?}: {} -> {}",
+                path, route.amount_in, route.amount_out
+            );
+            if best
+                .as_ref()
+                .is_none_or(|best| self.net_score(&route) > self.net_score(best))
+            {
+                best = Some(route);
+            }
+        }
+
+        best
+    }
+
+    /// Route score after paying for its intermediate token accounts
+    fn net_score(&self, route: &RouteQuote) -> i128 {
+        let accounts = route.hops.len().saturating_sub(1) as i128;
+        route.execution_score() - accounts * self.account_cost as i128
+    }
+
+    /// Chain the best per-hop quotes along `path`
+    async fn quote_path(
+        &self,
+        path: &[Pubkey],
+        pools: &PairPools,
+        request: &QuoteRequest,
+    ) -> Option<RouteQuote> {
+        let hops: Vec<(Pubkey, Pubkey)> = path.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        let mut quotes = Vec::with_capacity(hops.len());
+        let mut amount = request.amount;
+
+        match request.swap_mode {
+            SwapMode::ExactIn => {
+                for (token_in, token_out) in hops {
+                    let hop_request = QuoteRequest::exact_in(token_in, token_out, amount, request.slippage_bps);
+                    let quote = self.best_hop(pools, &hop_request).await?;
+                    amount = quote.min_amount_out;
+                    quotes.push(quote);
+                }
+            }
+            SwapMode::ExactOut => {
+                for (token_in, token_out) in hops.into_iter().rev() {
+                    let hop_request = QuoteRequest::exact_out(token_in, token_out, amount, request.slippage_bps);
+                    let quote = self.best_hop(pools, &hop_request).await?;
+                    amount = quote.max_amount_in;
+                    quotes.push(quote);
+                }
+                quotes.reverse();
+            }
+        }
+
+        RouteQuote::from_hops(quotes, request.swap_mode)
+    }
+
+    /// Best single-pool quote for one hop
+    async fn best_hop(&self, pools: &PairPools, request: &QuoteRequest) -> Option<QuoteResult> {
+        if request.amount == 0 {
+            return None;
+        }
+
+        let mut best: Option<QuoteResult> = None;
+        for pool in pools.get(&pair_key(request.token_in, request.token_out))? {
+            match self.quote_engine.calculate_quote(pool, request).await {
+                Ok(quote) => {
+                    if best
+                        .as_ref()
+                        .is_none_or(|best| quote.execution_score() > best.execution_score())
+                    {
+                        best = Some(quote);
+                    }
+                }
+                Err(e) => debug!("Failed to get quote from pool {}: {}", pool.address, e),
+            }
+        }
+        best
+    }
+}
+
+fn has_pools(pools: &PairPools, token_a: Pubkey, token_b: Pubkey) -> bool {
+    pools
+        .get(&pair_key(token_a, token_b))
+        .is_some_and(|found| !found.is_empty())
+}
+
+/// Direct, two-hop and three-hop token paths through distinct hubs
+fn candidate_paths(token_in: Pubkey, token_out: Pubkey, hubs: &[Pubkey], max_hops: usize) -> Vec<Vec<Pubkey>> {
+    let mut paths = vec![vec![token_in, token_out]];
+
+    if max_hops >= 2 {
+        for hub in hubs {
+            paths.push(vec![token_in, *hub, token_out]);
+        }
+    }
+    if max_hops >= 3 {
+        for first in hubs {
+            for second in hubs {
+                if first != second {
+                    paths.push(vec![token_in, *first, *second, token_out]);
+                }
+            }
+        }
+    }
+
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fixtures::{standard_pool, token};
+    use crate::core::Config;
+
+    fn pool(token_a: Pubkey, token_b: Pubkey, reserve_a: u64, reserve_b: u64) -> PoolInfo {
+        standard_pool(token(token_a, 6), token(token_b, 6), reserve_a, reserve_b)
+    }
+
+    fn pair_pools(pools: Vec<PoolInfo>) -> PairPools {
+        let mut map = PairPools::new();
+        for pool in pools {
+            map.entry(pair_key(pool.token_a.mint, pool.token_b.mint))
+                .or_default()
+                .push(pool);
+        }
+        map
+    }
+
+    #[test]
+    fn test_candidate_paths() {
+        let (token_in, token_out) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let hubs = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        assert_eq!(candidate_paths(token_in, token_out, &hubs, 1).len(), 1);
+        assert_eq!(candidate_paths(token_in, token_out, &hubs, 2).len(), 3);
+
+        let paths = candidate_paths(token_in, token_out, &hubs, 3);
+        assert_eq!(paths.len(), 5);
+        assert!(paths.contains(&vec![token_in, hubs[1], hubs[0], token_out]));
+    }
+
+    #[tokio::test]
+    async fn test_route_through_hub_when_no_direct_pool() {
+        let discovery = PoolDiscovery::new(Config::default()).unwrap();
+        let engine = QuoteEngine::new();
+        let (long_tail, sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = pair_pools(vec![
+            pool(long_tail, sol, 1_000_000_000_000, 10_000_000_000),
+            pool(sol, usdc, 10_000_000_000_000, 1_500_000_000_000),
+        ]);
+        let finder = RouteFinder::new(&discovery, &engine, vec![sol, usdc]);
+        let request = QuoteRequest::exact_in(long_tail, usdc, 1_000_000_000, 50);
+
+        let paths = candidate_paths(long_tail, usdc, &finder.hubs_for(&request), 3);
+        let route = finder.best_route_from_pools(&paths, &pools, &request).await.unwrap();
+
+        assert_eq!(route.hops.len(), 2);
+        assert_eq!(route.intermediate_mints(), vec![sol]);
+        assert_eq!(route.token_in, long_tail);
+        assert_eq!(route.token_out, usdc);
+        assert_eq!(route.amount_in, 1_000_000_000);
+        // The second hop only spends what the first is guaranteed to deliver
+        assert_eq!(route.hops[1].amount_in, route.hops[0].min_amount_out);
+        assert_eq!(route.amount_out, route.hops[1].amount_out);
+        assert_eq!(route.route().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_exact_out_route_chains_backwards() {
+        let discovery = PoolDiscovery::new(Config::default()).unwrap();
+        let engine = QuoteEngine::new();
+        let (long_tail, sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = pair_pools(vec![
+            pool(long_tail, sol, 1_000_000_000_000, 10_000_000_000),
+            pool(sol, usdc, 10_000_000_000_000, 1_500_000_000_000),
+        ]);
+        let finder = RouteFinder::new(&discovery, &engine, vec![sol]);
+        let request = QuoteRequest::exact_out(long_tail, usdc, 1_000_000, 50);
+
+        let paths = candidate_paths(long_tail, usdc, &finder.hubs_for(&request), 3);
+        let route = finder.best_route_from_pools(&paths, &pools, &request).await.unwrap();
+
+        assert_eq!(route.hops.len(), 2);
+        assert_eq!(route.amount_out, 1_000_000);
+        // The first hop delivers the most the second can spend
+        assert_eq!(route.hops[0].amount_out, route.hops[1].max_amount_in);
+        assert_eq!(route.max_amount_in, route.hops[0].max_amount_in);
+    }
+
+    #[tokio::test]
+    async fn test_direct_pool_beats_shallow_hops() {
+        let discovery = PoolDiscovery::new(Config::default()).unwrap();
+        let engine = QuoteEngine::new();
+        let (token_in, hub, token_out) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let direct = pool(token_in, token_out, 1_000_000_000_000, 1_000_000_000_000);
+        let direct_address = direct.address;
+        let pools = pair_pools(vec![
+            direct,
+            pool(token_in, hub, 1_000_000_000, 1_000_000_000),
+            pool(hub, token_out, 1_000_000_000, 1_000_000_000),
+        ]);
+        let finder = RouteFinder::new(&discovery, &engine, vec![hub]);
+        let request = QuoteRequest::exact_in(token_in, token_out, 10_000_000, 50);
+
+        let paths = candidate_paths(token_in, token_out, &finder.hubs_for(&request), 3);
+        let route = finder.best_route_from_pools(&paths, &pools, &request).await.unwrap();
+
+        assert_eq!(route.route(), vec![direct_address]);
+        assert!(route.intermediate_mints().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_account_cost_favours_direct_pool() {
+        let discovery = PoolDiscovery::new(Config::default()).unwrap();
+        let engine = QuoteEngine::new();
+        let (token_in, hub, token_out) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = pair_pools(vec![
+            pool(token_in, token_out, 100_000_000, 100_000_000),
+            pool(token_in, hub, 1_000_000_000_000, 1_000_000_000_000),
+            pool(hub, token_out, 1_000_000_000_000, 1_000_000_000_000),
+        ]);
+        let request = QuoteRequest::exact_in(token_in, token_out, 1_000_000, 50);
+
+        // The deep two-hop route wins gross...
+        let finder = RouteFinder::new(&discovery, &engine, vec![hub]);
+        let paths = candidate_paths(token_in, token_out, &finder.hubs_for(&request), 2);
+        let gross = finder.best_route_from_pools(&paths, &pools, &request).await.unwrap();
+        assert_eq!(gross.hops.len(), 2);
+
+        // ...but not once its intermediate account has to be paid for
+        let finder = finder.with_account_cost(10_000);
+        let net = finder.best_route_from_pools(&paths, &pools, &request).await.unwrap();
+        assert_eq!(net.hops.len(), 1);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::core::{PoolInfo, QuoteRequest, QuoteResult, SplitQuote, SwapMode, SwapResult};
+use crate::quotes::{LoadedPool, QuoteEngine};
+use futures::future::join_all;
+use log::debug;
+
+/// Settings for splitting one order across several pools
+#[derive(Debug, Clone, Copy)]
+pub struct SplitConfig {
+    /// Number of equal chunks the order is divided into (granularity of the split)
+    pub steps: u32,
+    /// Maximum number of pools used, bounded by what fits in one transaction
+    pub max_legs: usize,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        Self {
+            steps: 20,
+            max_legs: 3,
+        }
+    }
+}
+
+/// Finds the split of an order across pools that maximises total output
+/// (or minimises total input for exact-out orders)
+///
+/// The order is handed out chunk by chunk, each chunk going to the pool whose
+/// quote improves the most by taking it. Every pool's output is concave in its
+/// input, so this greedy allocation is optimal at the chosen granularity.
+pub struct SplitOptimizer<'a> {
+    quote_engine: &'a QuoteEngine,
+    config: SplitConfig,
+}
+
+/// A pool's quote for a given allocation, cached between steps
+struct Candidate {
+    amount: u64,
+    quote: Option<QuoteResult>,
+}
+
+impl<'a> SplitOptimizer<'a> {
+    pub fn new(quote_engine: &'a QuoteEngine, config: SplitConfig) -> Self {
+        Self {
+            quote_engine,
+            config,
+        }
+    }
+
+    /// Optimise the split of `request` across `pools`
+    ///
+    /// Each pool's on-chain state is loaded once up front, and every step
+    /// quotes the pools whose allocation changed concurrently.
+    pub async fn optimize(
+        &self,
+        pools: &[PoolInfo],
+        request: &QuoteRequest,
+    ) -> SwapResult<Option<SplitQuote>> {
+        if pools.is_empty() || request.amount == 0 {
+            return Ok(None);
+        }
+
+        let pools: Vec<LoadedPool> = join_all(pools.iter().map(|pool| self.load(pool, request)))
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let steps = (self.config.steps.max(1) as u64).min(request.amount);
+        let chunk = request.amount / steps;
+        let max_legs = self.config.max_legs.max(1);
+
+        let mut allocations = vec![0u64; pools.len()];
+        let mut current: Vec<Option<QuoteResult>> = vec![None; pools.len()];
+        let mut candidates: Vec<Option<Candidate>> = (0..pools.len()).map(|_| None).collect();
+
+        for step in 0..steps {
+            // The last chunk also carries the rounding remainder
+            let size = if step == steps - 1 {
+                request.amount - chunk * (steps - 1)
+            } else {
+                chunk
+            };
+            let legs_used = allocations.iter().filter(|amount| **amount > 0).count();
+            let open: Vec<usize> = (0..pools.len())
+                .filter(|index| allocations[*index] > 0 || legs_used < max_legs)
+                .collect();
+
+            // Only pools whose candidate allocation changed need a new quote
+            let stale: Vec<(usize, u64)> = open
+                .iter()
+                .map(|index| (*index, allocations[*index] + size))
+                .filter(|(index, amount)| {
+                    candidates[*index]
+                        .as_ref()
+                        .is_none_or(|candidate| candidate.amount != *amount)
+                })
+                .collect();
+            let quotes = join_all(
+                stale
+                    .iter()
+                    .map(|(index, amount)| self.quote(&pools[*index], request, *amount)),
+            )
+            .await;
+            for ((index, amount), quote) in stale.into_iter().zip(quotes) {
+                candidates[index] = Some(Candidate { amount, quote });
+            }
+
+            let mut best: Option<(usize, i128)> = None;
+            for index in open {
+                let Some(quote) = candidates[index].as_ref().and_then(|c| c.quote.as_ref()) else {
+                    continue;
+                };
+                let gain = quote.execution_score()
+                    - current[index].as_ref().map_or(0, |q| q.execution_score());
+                if best.is_none_or(|(_, best_gain)| gain > best_gain) {
+                    best = Some((index, gain));
+                }
+            }
+
+            let Some((index, _)) = best else {
+                debug!("No pool can take another {} of the order", size);
+                return Ok(None);
+            };
+            allocations[index] += size;
+            current[index] = candidates[index].take().and_then(|c| c.quote);
+        }
+
+        let legs: Vec<QuoteResult> = current.into_iter().flatten().collect();
+        let split = SplitQuote::from_legs(legs, request.token_in, request.token_out, request.swap_mode);
+
+        // Never do worse than routing the whole order through the best single pool
+        let best_single = join_all(pools.iter().map(|pool| self.quote(pool, request, request.amount)))
+            .await
+            .into_iter()
+            .flatten()
+            .reduce(|best, quote| if quote.execution_score() > best.execution_score() { quote } else { best });
+
+        match best_single {
+            Some(single) if single.execution_score() > split.execution_score() => {
+                debug!("Single pool {} beats the split", single.pool_info.address);
+                Ok(Some(SplitQuote::from_legs(
+                    vec![single],
+                    request.token_in,
+                    request.token_out,
+                    request.swap_mode,
+                )))
+            }
+            _ => Ok(Some(split)),
+        }
+    }
+
+    /// Load the state quoting one pool reads, treating failures as unavailable
+    async fn load<'p>(&self, pool: &'p PoolInfo, request: &QuoteRequest) -> Option<LoadedPool<'p>> {
+        match self.quote_engine.load_pool(pool, &request.token_in).await {
+            Ok(loaded) => Some(loaded),
+            Err(e) => {
+                debug!("Pool {} cannot be loaded for a split: {}", pool.address, e);
+                None
+            }
+        }
+    }
+
+    /// Quote `amount` through one pool, treating failures as unavailable
+    async fn quote(&self, pool: &LoadedPool<'_>, request: &QuoteRequest, amount: u64) -> Option<QuoteResult> {
+        let leg_request = match request.swap_mode {
+            SwapMode::ExactIn => {
+                QuoteRequest::exact_in(request.token_in, request.token_out, amount, request.slippage_bps)
+            }
+            SwapMode::ExactOut => {
+                QuoteRequest::exact_out(request.token_in, request.token_out, amount, request.slippage_bps)
+            }
+        };
+
+        match self.quote_engine.calculate_loaded_quote(pool, &leg_request).await {
+            Ok(quote) => Some(quote),
+            Err(e) => {
+                debug!("Pool {} cannot quote {}: {}", pool.pool.address, amount, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::fixtures::{standard_pool, token};
+    use solana_sdk::pubkey::Pubkey;
+
+    /// CP-Swap pool holding `reserve` of each token
+    fn balanced_pool(token_a: Pubkey, token_b: Pubkey, reserve: u64) -> PoolInfo {
+        standard_pool(token(token_a, 6), token(token_b, 6), reserve, reserve)
+    }
+
+    #[tokio::test]
+    async fn test_split_between_equal_pools() {
+        let engine = QuoteEngine::new();
+        let (token_a, token_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = vec![
+            balanced_pool(token_a, token_b, 1_000_000_000),
+            balanced_pool(token_a, token_b, 1_000_000_000),
+        ];
+        let request = QuoteRequest::exact_in(token_a, token_b, 100_000_000, 50);
+
+        let split = SplitOptimizer::new(&engine, SplitConfig::default())
+            .optimize(&pools, &request)
+            .await
+            .unwrap()
+            .unwrap();
+        let single = engine.calculate_quote(&pools[0], &request).await.unwrap();
+
+        assert_eq!(split.legs.len(), 2);
+        assert_eq!(split.amount_in, 100_000_000);
+        assert_eq!(split.amount_out, split.legs.iter().map(|leg| leg.amount_out).sum::<u64>());
+        assert!(split.amount_out > single.amount_out);
+        assert!(split.price_impact < single.price_impact);
+        for leg in &split.legs {
+            assert_eq!(split.leg_share(leg), 50.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_split_favours_deeper_pool() {
+        let engine = QuoteEngine::new();
+        let (token_a, token_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = vec![
+            balanced_pool(token_a, token_b, 100_000_000),
+            balanced_pool(token_a, token_b, 10_000_000_000),
+        ];
+        let request = QuoteRequest::exact_in(token_a, token_b, 200_000_000, 50);
+
+        let split = SplitOptimizer::new(&engine, SplitConfig::default())
+            .optimize(&pools, &request)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let primary = split.primary_leg().unwrap();
+        assert_eq!(primary.pool_info.address, pools[1].address);
+        assert!(split.leg_share(primary) > 80.0);
+    }
+
+    #[tokio::test]
+    async fn test_split_exact_out() {
+        let engine = QuoteEngine::new();
+        let (token_a, token_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = vec![
+            balanced_pool(token_a, token_b, 1_000_000_000),
+            balanced_pool(token_a, token_b, 500_000_000),
+        ];
+        let request = QuoteRequest::exact_out(token_a, token_b, 100_000_000, 50);
+
+        let split = SplitOptimizer::new(&engine, SplitConfig::default())
+            .optimize(&pools, &request)
+            .await
+            .unwrap()
+            .unwrap();
+        let single = engine.calculate_quote(&pools[0], &request).await.unwrap();
+
+        assert_eq!(split.swap_mode, SwapMode::ExactOut);
+        assert_eq!(split.amount_out, 100_000_000);
+        assert_eq!(split.legs.len(), 2);
+        assert!(split.amount_in < single.amount_in);
+        assert!(split.max_amount_in >= split.amount_in);
+    }
+
+    #[tokio::test]
+    async fn test_split_respects_max_legs() {
+        let engine = QuoteEngine::new();
+        let (token_a, token_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = vec![
+            balanced_pool(token_a, token_b, 1_000_000_000),
+            balanced_pool(token_a, token_b, 2_000_000_000),
+            balanced_pool(token_a, token_b, 1_000_000_000),
+        ];
+        let request = QuoteRequest::exact_in(token_a, token_b, 100_000_000, 50);
+        let config = SplitConfig {
+            max_legs: 1,
+            ..SplitConfig::default()
+        };
+
+        let split = SplitOptimizer::new(&engine, config)
+            .optimize(&pools, &request)
+            .await
+            .unwrap()
+            .unwrap();
+        let best_single = engine.calculate_quote(&pools[1], &request).await.unwrap();
+
+        assert_eq!(split.legs.len(), 1);
+        assert_eq!(split.legs[0].pool_info.address, pools[1].address);
+        assert_eq!(split.amount_out, best_single.amount_out);
+    }
+}
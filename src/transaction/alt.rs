@@ -1,162 +1,219 @@
-use crate::core::{SwapError, SwapResult};
+use crate::core::{
+    is_placeholder_market,
+    layouts::{AmmInfoLayoutV4, ClmmPoolState, CpSwapPoolState, StablePoolState, TickArrayState},
+    SwapError, SwapResult, AMM_V4_PROGRAM, CLMM_PROGRAM, MEMO_PROGRAM, RAYDIUM_CP_SWAP_PROGRAM, STABLE_PROGRAM,
+};
+use crate::transaction::{
+    amm_swap::{amm_authority, parse_serum_market_accounts},
+    clmm_swap::{swap_tick_array_candidates, MIN_SWAP_TICK_ARRAYS},
+    cp_swap::CP_AUTHORITY_SEED,
+    journal::JournalEntry,
+    stable_swap::derive_stable_pool_authority,
+};
+use borsh::BorshDeserialize;
 use log::{debug, info, warn};
-use solana_address_lookup_table_program::state::AddressLookupTable;
-use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
 use solana_sdk::{
-    account::Account,
-    address_lookup_table::AddressLookupTableAccount,
-    pubkey::Pubkey,
+    address_lookup_table::{
+        self,
+        instruction::{close_lookup_table, create_lookup_table, deactivate_lookup_table, extend_lookup_table},
+        state::{AddressLookupTable, LOOKUP_TABLE_MAX_ADDRESSES},
+        AddressLookupTableAccount,
+    },
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
     message::v0::MessageAddressTableLookup,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
 };
+use solana_account_decoder::UiAccountEncoding;
 use std::collections::HashMap;
-use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-/// Popular ALT addresses on Solana mainnet
-const POPULAR_ALTS: &[(&str, &str)] = &[
-    ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", "Ea3r8VWC3hgaXjH7NM8RrbMCudTX8zgpzEarrDNPWpEd"), // SPL Token ALT
-    ("11111111111111111111111111111111", "1g11BmFKKKBP4xKUQh27F8Qz3bdC23ECrZFdxCCnsT9"), // System Program ALT
-    ("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL", "9mBcHPcLhkzmbjRqUxvb9n1vPNJPJaHv6PrTpDpBMuam"), // AToken ALT
-];
-
-/// ALT cache entry
-#[derive(Clone, Debug)]
-struct AltCacheEntry {
-    lookup_table: AddressLookupTableAccount,
-    addresses: Vec<Pubkey>,
+/// Offset of the authority in a lookup table account, after the program
+/// state tag, deactivation slot, last extended slot, its start index and
+/// the authority's option tag
+const AUTHORITY_OFFSET: usize = 22;
+/// Addresses added per extend transaction, keeping it under the size limit
+const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+/// A table costs its 32 byte key plus two index counts and saves 31 bytes
+/// per account it replaces, so it only pays for itself from two accounts
+const MIN_ACCOUNTS_PER_TABLE: usize = 2;
+
+/// An on-chain Address Lookup Table
+#[derive(Clone, Debug, PartialEq)]
+pub struct LookupTable {
+    pub address: Pubkey,
+    pub authority: Option<Pubkey>,
+    /// Slot the table was deactivated in, `u64::MAX` while active
+    pub deactivation_slot: u64,
+    pub addresses: Vec<Pubkey>,
+}
+
+impl LookupTable {
+    /// Parse the lookup table account at `address`
+    pub fn from_account(address: Pubkey, data: &[u8]) -> SwapResult<Self> {
+        let table = AddressLookupTable::deserialize(data)
+            .map_err(|e| SwapError::ParseError(format!("Failed to parse ALT {}: {}", address, e)))?;
+        Ok(Self {
+            address,
+            authority: table.meta.authority,
+            deactivation_slot: table.meta.deactivation_slot,
+            addresses: table.addresses.to_vec(),
+        })
+    }
+
+    /// Whether transactions can still look addresses up in the table
+    pub fn is_active(&self) -> bool {
+        self.deactivation_slot == u64::MAX
+    }
+
+    /// Room left for more addresses
+    pub fn remaining_capacity(&self) -> usize {
+        LOOKUP_TABLE_MAX_ADDRESSES.saturating_sub(self.addresses.len())
+    }
+
+    pub fn account(&self) -> AddressLookupTableAccount {
+        AddressLookupTableAccount {
+            key: self.address,
+            addresses: self.addresses.clone(),
+        }
+    }
 }
 
 /// Address Lookup Table manager
+///
+/// Swaps draw on the user-supplied tables and on the tables owned by
+/// `authority`, which this manager creates and extends with the accounts of
+/// the pools traded through.
 pub struct AltManager {
     rpc_client: Arc<RpcClient>,
-    cache: Arc<RwLock<HashMap<Pubkey, AltCacheEntry>>>,
+    /// Tables passed in by the user, used but never modified
+    user_tables: Vec<Pubkey>,
+    /// Owner of the managed tables
+    authority: Option<Pubkey>,
+    cache: Arc<RwLock<HashMap<Pubkey, LookupTable>>>,
+    loaded: AtomicBool,
 }
 
 impl AltManager {
     /// Create new ALT manager
-    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+    pub fn new(rpc_client: Arc<RpcClient>, user_tables: Vec<Pubkey>, authority: Option<Pubkey>) -> Self {
         Self {
             rpc_client,
+            user_tables,
+            authority,
             cache: Arc::new(RwLock::new(HashMap::new())),
+            loaded: AtomicBool::new(false),
         }
     }
 
-    /// Load popular ALTs into cache
-    pub async fn load_popular_alts(&self) -> SwapResult<()> {
-        info!("Loading popular Address Lookup Tables");
-        
-        for (name, alt_address) in POPULAR_ALTS {
-            match Pubkey::from_str(alt_address) {
-                Ok(pubkey) => {
-                    match self.fetch_alt(&pubkey).await {
-                        Ok(_) => debug!("Loaded ALT for {}: {}", name, alt_address),
-                        Err(e) => warn!("Failed to load ALT for {}: {}", name, e),
-                    }
-                }
-                Err(e) => warn!("Invalid ALT address for {}: {}", name, e),
+    /// Load the user-supplied and managed tables into the cache
+    pub async fn load_tables(&self) -> SwapResult<()> {
+        info!("Loading Address Lookup Tables");
+
+        for table in &self.user_tables {
+            if let Err(e) = self.fetch_table(table).await {
+                warn!("Failed to load ALT {}: {}", table, e);
+            }
+        }
+        if let Some(authority) = self.authority {
+            match self.tables_owned_by(&authority).await {
+                Ok(tables) => debug!("Loaded {} ALTs owned by {}", tables.len(), authority),
+                Err(e) => warn!("Failed to load ALTs owned by {}: {}", authority, e),
             }
         }
-        
+
+        self.loaded.store(true, Ordering::Relaxed);
         Ok(())
     }
 
     /// Fetch and cache an ALT
-    async fn fetch_alt(&self, alt_address: &Pubkey) -> SwapResult<AltCacheEntry> {
+    async fn fetch_table(&self, alt_address: &Pubkey) -> SwapResult<LookupTable> {
         // Check cache first
-        {
-            let cache = self.cache.read().await;
-            if let Some(entry) = cache.get(alt_address) {
-                debug!("ALT cache hit for {}", alt_address);
-                return Ok(entry.clone());
-            }
+        if let Some(table) = self.cache.read().await.get(alt_address) {
+            debug!("ALT cache hit for {}", alt_address);
+            return Ok(table.clone());
         }
 
-        // Fetch from RPC
         debug!("Fetching ALT from RPC: {}", alt_address);
-        let account = self.rpc_client
+        let account = self
+            .rpc_client
             .get_account(alt_address)
             .await
             .map_err(|e| SwapError::Other(format!("Failed to fetch ALT {}: {}", alt_address, e)))?;
+        let table = LookupTable::from_account(*alt_address, &account.data)?;
+
+        self.cache.write().await.insert(*alt_address, table.clone());
+        info!("Loaded ALT {} with {} addresses", alt_address, table.addresses.len());
+        Ok(table)
+    }
 
-        // Parse ALT
-        let lookup_table = self.parse_alt_account(&account)?;
-        let addresses = lookup_table.addresses.to_vec();
-        
-        let entry = AltCacheEntry {
-            lookup_table: lookup_table.clone(),
-            addresses: addresses.clone(),
+    /// Every table `authority` owns, active or not, read fresh from chain
+    pub async fn tables_owned_by(&self, authority: &Pubkey) -> SwapResult<Vec<LookupTable>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                AUTHORITY_OFFSET,
+                authority.to_bytes().to_vec(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..Default::default()
+            },
+            ..Default::default()
         };
+        let accounts = self
+            .rpc_client
+            .get_program_accounts_with_config(&address_lookup_table::program::id(), config)
+            .await
+            .map_err(SwapError::from)?;
 
-        // Cache the result
-        {
-            let mut cache = self.cache.write().await;
-            cache.insert(*alt_address, entry.clone());
+        let mut tables = Vec::new();
+        for (address, account) in accounts {
+            match LookupTable::from_account(address, &account.data) {
+                Ok(table) => tables.push(table),
+                Err(e) => warn!("Skipping ALT {}: {}", address, e),
+            }
         }
+        tables.sort_by_key(|table| table.address);
 
-        info!("Loaded ALT {} with {} addresses", alt_address, addresses.len());
-        Ok(entry)
-    }
-
-    /// Parse ALT account data
-    fn parse_alt_account(&self, account: &Account) -> SwapResult<AddressLookupTableAccount> {
-        let table = AddressLookupTable::deserialize(&account.data)
-            .map_err(|e| SwapError::Other(format!("Failed to parse ALT account: {}", e)))?;
-        
-        // Convert AddressLookupTable to AddressLookupTableAccount
-        Ok(AddressLookupTableAccount {
-            key: table.meta.authority.unwrap_or_default(), // Using authority as a placeholder for the key
-            addresses: table.addresses.to_vec(),
-        })
+        let mut cache = self.cache.write().await;
+        for table in &tables {
+            cache.insert(table.address, table.clone());
+        }
+        Ok(tables)
     }
 
     /// Find optimal ALTs for a set of accounts
-    /// Returns ALT accounts that contain the requested accounts
+    /// Returns the tables worth referencing, most useful first
     pub async fn find_optimal_alts(&self, accounts: &[Pubkey]) -> SwapResult<Vec<AddressLookupTableAccount>> {
         if accounts.is_empty() {
             return Ok(vec![]);
         }
 
         debug!("Finding optimal ALTs for {} accounts", accounts.len());
-        
-        // Load popular ALTs if not already loaded
-        if self.cache.read().await.is_empty() {
-            self.load_popular_alts().await?;
+        if !self.loaded.load(Ordering::Relaxed) {
+            self.load_tables().await?;
         }
 
-        let mut selected_alts = Vec::new();
-        let mut remaining_accounts: Vec<Pubkey> = accounts.to_vec();
-        
-        // Check each cached ALT
-        let cache = self.cache.read().await;
-        for (_alt_pubkey, entry) in cache.iter() {
-            if remaining_accounts.is_empty() {
-                break;
-            }
-
-            // Find accounts that exist in this ALT
-            let mut found_accounts = Vec::new();
-
-            for account in &remaining_accounts {
-                if entry.addresses.contains(account) {
-                    found_accounts.push(*account);
-                }
-            }
-
-            if !found_accounts.is_empty() {
-                debug!("Found {} accounts in ALT", found_accounts.len());
-                selected_alts.push(entry.lookup_table.clone());
-
-                // Remove found accounts from remaining list
-                remaining_accounts.retain(|a| !found_accounts.contains(a));
-            }
-        }
+        let mut tables: Vec<LookupTable> = self.cache.read().await.values().cloned().collect();
+        tables.sort_by_key(|table| table.address);
+        let selected = select_tables(&tables, accounts);
 
-        if !remaining_accounts.is_empty() {
-            debug!("{} accounts not found in any ALT", remaining_accounts.len());
-        }
+        let covered: usize = selected
+            .iter()
+            .map(|table| accounts.iter().filter(|account| table.addresses.contains(account)).count())
+            .sum();
+        debug!("{} of {} accounts found in {} ALTs", covered, accounts.len(), selected.len());
 
-        Ok(selected_alts)
+        Ok(selected)
     }
 
     /// Check if using ALTs would be beneficial
@@ -168,14 +225,14 @@ impl AltManager {
 
     /// Get specific ALT by address
     pub async fn get_alt(&self, alt_address: &Pubkey) -> SwapResult<AddressLookupTableAccount> {
-        let entry = self.fetch_alt(alt_address).await?;
-        Ok(entry.lookup_table)
+        Ok(self.fetch_table(alt_address).await?.account())
     }
 
     /// Clear ALT cache
     pub async fn clear_cache(&self) {
         let mut cache = self.cache.write().await;
         cache.clear();
+        self.loaded.store(false, Ordering::Relaxed);
         debug!("ALT cache cleared");
     }
 
@@ -184,29 +241,315 @@ impl AltManager {
         let cache = self.cache.read().await;
         let total_alts = cache.len();
         let total_addresses = cache.values()
-            .map(|entry| entry.addresses.len())
+            .map(|table| table.addresses.len())
             .sum();
         (total_alts, total_addresses)
     }
+
+    /// Create a table owned and paid for by `payer`
+    pub async fn create_table(&self, payer: &Keypair) -> SwapResult<(Pubkey, Signature)> {
+        // The derivation slot must be one the network still has a hash for
+        let recent_slot = self
+            .rpc_client
+            .get_slot_with_commitment(CommitmentConfig::finalized())
+            .await
+            .map_err(SwapError::from)?;
+        let (instruction, table) = create_lookup_table(payer.pubkey(), payer.pubkey(), recent_slot);
+
+        let signature = self.send(payer, &[instruction]).await?;
+        info!("Created ALT {}", table);
+        Ok((table, signature))
+    }
+
+    /// Add the `addresses` the table does not hold yet
+    pub async fn extend_table(&self, payer: &Keypair, table: &Pubkey, addresses: &[Pubkey]) -> SwapResult<Vec<Signature>> {
+        let current = self.refresh_table(table).await?;
+        if !current.is_active() {
+            return Err(SwapError::InvalidInput(format!("ALT {} is deactivated", table)));
+        }
+
+        let mut missing: Vec<Pubkey> = Vec::new();
+        for address in addresses {
+            if !current.addresses.contains(address) && !missing.contains(address) {
+                missing.push(*address);
+            }
+        }
+        if missing.len() > current.remaining_capacity() {
+            return Err(SwapError::InvalidInput(format!(
+                "ALT {} has room for {} more addresses, {} requested",
+                table,
+                current.remaining_capacity(),
+                missing.len()
+            )));
+        }
+
+        let mut signatures = Vec::new();
+        for chunk in missing.chunks(MAX_ADDRESSES_PER_EXTEND) {
+            let instruction = extend_lookup_table(*table, payer.pubkey(), Some(payer.pubkey()), chunk.to_vec());
+            signatures.push(self.send(payer, &[instruction]).await?);
+        }
+        self.cache.write().await.remove(table);
+
+        info!("Added {} addresses to ALT {}", missing.len(), table);
+        Ok(signatures)
+    }
+
+    /// Add `addresses` to the `payer`'s tables, preferring the one already
+    /// holding most of them so a swap needs few tables, and creating a
+    /// table when none has room
+    pub async fn add_addresses(&self, payer: &Keypair, addresses: &[Pubkey]) -> SwapResult<Vec<Signature>> {
+        let tables: Vec<LookupTable> = self
+            .tables_owned_by(&payer.pubkey())
+            .await?
+            .into_iter()
+            .filter(LookupTable::is_active)
+            .collect();
+
+        let missing: Vec<Pubkey> = addresses
+            .iter()
+            .filter(|address| !tables.iter().any(|table| table.addresses.contains(address)))
+            .copied()
+            .collect();
+        if missing.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let target = tables
+            .iter()
+            .filter(|table| table.remaining_capacity() >= missing.len())
+            .max_by_key(|table| addresses.iter().filter(|address| table.addresses.contains(address)).count())
+            .map(|table| table.address);
+
+        let mut signatures = Vec::new();
+        let target = match target {
+            Some(table) => table,
+            None => {
+                let (table, signature) = self.create_table(payer).await?;
+                signatures.push(signature);
+                table
+            }
+        };
+        signatures.extend(self.extend_table(payer, &target, &missing).await?);
+        Ok(signatures)
+    }
+
+    /// Stop new transactions from using a table, the first step to closing it
+    pub async fn deactivate_table(&self, payer: &Keypair, table: &Pubkey) -> SwapResult<Signature> {
+        let current = self.refresh_table(table).await?;
+        if !current.is_active() {
+            return Err(SwapError::InvalidInput(format!(
+                "ALT {} was already deactivated in slot {}",
+                table, current.deactivation_slot
+            )));
+        }
+
+        let signature = self.send(payer, &[deactivate_lookup_table(*table, payer.pubkey())]).await?;
+        self.cache.write().await.remove(table);
+        info!("Deactivated ALT {}", table);
+        Ok(signature)
+    }
+
+    /// Close a deactivated table and reclaim its rent
+    ///
+    /// Fails until the deactivation slot has left the slot hashes, about
+    /// 513 slots after deactivating.
+    pub async fn close_table(&self, payer: &Keypair, table: &Pubkey) -> SwapResult<Signature> {
+        if self.refresh_table(table).await?.is_active() {
+            return Err(SwapError::InvalidInput(format!("ALT {} must be deactivated before closing", table)));
+        }
+
+        let signature = self
+            .send(payer, &[close_lookup_table(*table, payer.pubkey(), payer.pubkey())])
+            .await?;
+        self.cache.write().await.remove(table);
+        info!("Closed ALT {}", table);
+        Ok(signature)
+    }
+
+    /// Accounts a swap through `pool` passes that lookups can replace: the
+    /// pool, its authority, vaults and mints, its market or tick arrays, and
+    /// the programs it calls into
+    pub async fn pool_accounts(&self, pool: &Pubkey) -> SwapResult<Vec<Pubkey>> {
+        let account = self.rpc_client.get_account(pool).await.map_err(SwapError::from)?;
+        let program = account.owner;
+        let mut addresses = vec![*pool, spl_token::ID];
+
+        if program == *AMM_V4_PROGRAM {
+            let state = AmmInfoLayoutV4::from_bytes(&account.data).map_err(SwapError::ParseError)?;
+            addresses.extend([
+                amm_authority()?,
+                state.amm_open_orders,
+                state.amm_target_orders,
+                state.pool_coin_token_account,
+                state.pool_pc_token_account,
+                state.coin_mint_address,
+                state.pc_mint_address,
+            ]);
+            if !is_placeholder_market(&state.serum_market) {
+                addresses.extend(self.market_accounts(&state.serum_market, &state.serum_program_id).await?);
+            }
+        } else if program == *STABLE_PROGRAM {
+            let state = StablePoolState::from_bytes(&account.data).map_err(SwapError::ParseError)?;
+            addresses.extend([
+                derive_stable_pool_authority(&program, state.nonce)?,
+                state.open_orders,
+                state.coin_vault,
+                state.pc_vault,
+                state.coin_mint,
+                state.pc_mint,
+                state.model_data_account,
+            ]);
+            addresses.extend(self.market_accounts(&state.market, &state.market_program).await?);
+        } else if program == *CLMM_PROGRAM {
+            let state = ClmmPoolState::try_from_slice(&account.data)
+                .map_err(|e| SwapError::ParseError(format!("Failed to parse CLMM pool {}: {}", pool, e)))?;
+            addresses.extend([
+                state.amm_config,
+                state.token_vault_0,
+                state.token_vault_1,
+                state.token_mint_0,
+                state.token_mint_1,
+                state.observation_key,
+                *crate::core::TOKEN_2022_PROGRAM,
+                *MEMO_PROGRAM,
+            ]);
+            addresses.extend(self.clmm_existing_accounts(pool, &program, &state).await?);
+        } else if program == *RAYDIUM_CP_SWAP_PROGRAM {
+            let state = CpSwapPoolState::from_bytes(&account.data).map_err(SwapError::ParseError)?;
+            addresses.extend([
+                Pubkey::find_program_address(&[CP_AUTHORITY_SEED], &program).0,
+                state.amm_config,
+                state.token_0_vault,
+                state.token_1_vault,
+                state.token_0_mint,
+                state.token_1_mint,
+                state.token_0_program,
+                state.token_1_program,
+                state.observation_key,
+            ]);
+        } else {
+            return Err(SwapError::InvalidPoolType(format!(
+                "{} is owned by {}, not a Raydium pool program",
+                pool, program
+            )));
+        }
+
+        let mut unique = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            if !unique.contains(&address) {
+                unique.push(address);
+            }
+        }
+        Ok(unique)
+    }
+
+    /// OpenBook program, market and the market accounts a swap passes
+    async fn market_accounts(&self, market: &Pubkey, market_program: &Pubkey) -> SwapResult<Vec<Pubkey>> {
+        let data = self.rpc_client.get_account_data(market).await.map_err(SwapError::from)?;
+        let (bids, asks, event_queue, coin_vault, pc_vault, vault_signer) =
+            parse_serum_market_accounts(&data, market, market_program)?;
+        Ok(vec![*market_program, *market, bids, asks, event_queue, coin_vault, pc_vault, vault_signer])
+    }
+
+    /// Bitmap extension and the tick arrays around the current price in
+    /// both directions, leaving out the ones not created yet
+    async fn clmm_existing_accounts(
+        &self,
+        pool: &Pubkey,
+        program: &Pubkey,
+        state: &ClmmPoolState,
+    ) -> SwapResult<Vec<Pubkey>> {
+        let mut candidates = vec![ClmmPoolState::find_bitmap_extension_address(pool, program)];
+        for zero_for_one in [true, false] {
+            for start_index in swap_tick_array_candidates(state, zero_for_one, MIN_SWAP_TICK_ARRAYS) {
+                let address = TickArrayState::find_address(pool, start_index, program);
+                if !candidates.contains(&address) {
+                    candidates.push(address);
+                }
+            }
+        }
+
+        let accounts = self
+            .rpc_client
+            .get_multiple_accounts(&candidates)
+            .await
+            .map_err(SwapError::from)?;
+        Ok(candidates
+            .into_iter()
+            .zip(accounts)
+            .filter(|(_, account)| account.is_some())
+            .map(|(address, _)| address)
+            .collect())
+    }
+
+    /// Read a table from chain, replacing its cached copy
+    async fn refresh_table(&self, table: &Pubkey) -> SwapResult<LookupTable> {
+        self.cache.write().await.remove(table);
+        self.fetch_table(table).await
+    }
+
+    /// Sign, send and confirm a table management transaction
+    async fn send(&self, payer: &Keypair, instructions: &[Instruction]) -> SwapResult<Signature> {
+        let recent_blockhash = self.rpc_client.get_latest_blockhash().await.map_err(SwapError::from)?;
+        let transaction =
+            Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &[payer], recent_blockhash);
+        self.rpc_client
+            .send_and_confirm_transaction(&transaction)
+            .await
+            .map_err(SwapError::from)
+    }
+}
+
+/// Greedily pick the tables covering the most of `accounts` that are still
+/// uncovered, while a table replaces enough accounts to pay for itself
+fn select_tables(tables: &[LookupTable], accounts: &[Pubkey]) -> Vec<AddressLookupTableAccount> {
+    let mut remaining: Vec<Pubkey> = accounts.to_vec();
+    let mut selected: Vec<AddressLookupTableAccount> = Vec::new();
+
+    loop {
+        let best = tables
+            .iter()
+            .filter(|table| table.is_active() && !selected.iter().any(|chosen| chosen.key == table.address))
+            .map(|table| (table, remaining.iter().filter(|account| table.addresses.contains(account)).count()))
+            .fold(None, |best: Option<(&LookupTable, usize)>, candidate| match best {
+                Some(best) if best.1 >= candidate.1 => Some(best),
+                _ => Some(candidate),
+            });
+
+        match best {
+            Some((table, covered)) if covered >= MIN_ACCOUNTS_PER_TABLE => {
+                remaining.retain(|account| !table.addresses.contains(account));
+                selected.push(table.account());
+            }
+            _ => return selected,
+        }
+    }
+}
+
+/// Pools swapped through at least `min_trades` times, most traded first
+pub fn frequent_pools(entries: &[JournalEntry], min_trades: usize) -> Vec<(Pubkey, usize)> {
+    let mut counts: Vec<(Pubkey, usize)> = Vec::new();
+    for pool in entries.iter().flat_map(JournalEntry::traded_pools) {
+        match counts.iter_mut().find(|(address, _)| *address == pool) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((pool, 1)),
+        }
+    }
+    counts.retain(|(_, count)| *count >= min_trades.max(1));
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    counts
 }
 
 /// Helper functions for ALT operations
 pub mod helpers {
     use super::*;
 
-    /// Create lookups for common Raydium accounts
-    pub fn create_raydium_lookups(_pool_type: &str) -> Vec<MessageAddressTableLookup> {
-        // This would return pre-configured lookups for different pool types
-        // For now, return empty as we'd need specific ALT addresses
-        vec![]
-    }
-
     /// Estimate transaction size reduction from using ALTs
     pub fn estimate_size_reduction(_accounts: &[Pubkey], lookups: &[MessageAddressTableLookup]) -> usize {
         let accounts_in_alts: usize = lookups.iter()
             .map(|l| l.writable_indexes.len() + l.readonly_indexes.len())
             .sum();
-        
+
         // Each account saves ~32 bytes when using ALT (replaced by 1-2 byte index)
         accounts_in_alts * 30
     }
@@ -225,6 +568,8 @@ pub mod helpers {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use solana_sdk::address_lookup_table::state::LookupTableMeta;
+    use std::borrow::Cow;
 
     #[test]
     fn test_should_use_alts() {
@@ -237,7 +582,7 @@ mod tests {
     #[test]
     fn test_size_reduction_estimation() {
         use helpers::*;
-        
+
         let lookups = vec![
             MessageAddressTableLookup {
                 account_key: Pubkey::new_unique(),
@@ -245,8 +590,54 @@ mod tests {
                 readonly_indexes: vec![2, 3, 4],
             },
         ];
-        
+
         let reduction = estimate_size_reduction(&[], &lookups);
         assert_eq!(reduction, 150); // 5 accounts * 30 bytes
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_table_and_authority_offset() {
+        let (address, authority) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let addresses = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let data = AddressLookupTable {
+            meta: LookupTableMeta::new(authority),
+            addresses: Cow::Owned(addresses.clone()),
+        }
+        .serialize_for_tests()
+        .unwrap();
+
+        // Managed tables are found by filtering on these bytes
+        assert_eq!(&data[AUTHORITY_OFFSET..AUTHORITY_OFFSET + 32], authority.as_ref());
+
+        let table = LookupTable::from_account(address, &data).unwrap();
+        assert_eq!(table.authority, Some(authority));
+        assert_eq!(table.addresses, addresses);
+        assert!(table.is_active());
+        assert_eq!(table.remaining_capacity(), LOOKUP_TABLE_MAX_ADDRESSES - 2);
+        assert_eq!(table.account().key, address);
+    }
+
+    #[test]
+    fn test_select_tables() {
+        let accounts: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        let table = |seed: u8, addresses: &[Pubkey], deactivation_slot: u64| LookupTable {
+            address: Pubkey::new_from_array([seed; 32]),
+            authority: None,
+            deactivation_slot,
+            addresses: addresses.to_vec(),
+        };
+        let tables = vec![
+            table(1, &accounts[0..3], u64::MAX),
+            table(2, &accounts[0..6], u64::MAX),
+            table(3, &accounts[5..8], u64::MAX),
+            // Would cover the rest, but can no longer be used
+            table(4, &accounts[6..8], 100),
+            // A single account costs more than it saves
+            table(5, &[accounts[7], Pubkey::new_unique()], u64::MAX),
+        ];
+
+        let selected: Vec<Pubkey> = select_tables(&tables, &accounts).iter().map(|table| table.key).collect();
+        assert_eq!(selected, vec![tables[1].address, tables[2].address]);
+        assert!(select_tables(&tables[3..], &accounts).is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::core::{SwapError, SwapParams, SwapResult};
+use crate::core::{SwapError, SwapMode, SwapParams, SwapResult};
 use borsh::{BorshDeserialize, BorshSerialize};
 use log::{debug, info, warn};
 use solana_sdk::{
@@ -9,18 +9,25 @@ use std::str::FromStr;
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::ID as TOKEN_PROGRAM_ID;
 
-/// AMM Swap instruction discriminator
+/// AMM Swap instruction discriminators
 const AMM_SWAP_INSTRUCTION: u8 = 9;
+const AMM_SWAP_BASE_OUT_INSTRUCTION: u8 = 11;
+/// Swap instructions that take no OpenBook accounts
+const AMM_SWAP_BASE_IN_V2_INSTRUCTION: u8 = 16;
+const AMM_SWAP_BASE_OUT_V2_INSTRUCTION: u8 = 17;
+
+/// Authority owning the vaults of every AMM V4 pool
+const AMM_AUTHORITY: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
 
 /// AMM Swap instruction data layout
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct AmmSwapInstructionData {
     /// Instruction discriminator
     pub instruction: u8,
-    /// Amount of tokens to swap in
+    /// Amount of tokens to swap in (maximum amount in for base out)
     pub amount_in: u64,
-    /// Minimum amount of tokens to receive
-    pub min_amount_out: u64,
+    /// Minimum amount of tokens to receive (exact amount out for base out)
+    pub amount_out: u64,
 }
 
 impl AmmSwapInstructionData {
@@ -28,9 +35,54 @@ impl AmmSwapInstructionData {
         Self {
             instruction: AMM_SWAP_INSTRUCTION,
             amount_in,
-            min_amount_out,
+            amount_out: min_amount_out,
+        }
+    }
+
+    pub fn new_base_out(max_amount_in: u64, amount_out: u64) -> Self {
+        Self {
+            instruction: AMM_SWAP_BASE_OUT_INSTRUCTION,
+            amount_in: max_amount_in,
+            amount_out,
+        }
+    }
+
+    /// Instruction data matching the quote's swap mode
+    pub fn from_quote(quote: &crate::core::QuoteResult) -> Self {
+        match quote.swap_mode {
+            SwapMode::ExactIn => Self::new(quote.amount_in, quote.min_amount_out),
+            SwapMode::ExactOut => Self::new_base_out(quote.max_amount_in, quote.amount_out),
         }
     }
+
+    /// `swap_base_in_v2` / `swap_base_out_v2` data matching the quote's swap mode
+    pub fn from_quote_v2(quote: &crate::core::QuoteResult) -> Self {
+        let data = Self::from_quote(quote);
+        let instruction = match quote.swap_mode {
+            SwapMode::ExactIn => AMM_SWAP_BASE_IN_V2_INSTRUCTION,
+            SwapMode::ExactOut => AMM_SWAP_BASE_OUT_V2_INSTRUCTION,
+        };
+        Self { instruction, ..data }
+    }
+}
+
+/// AMM V4 pool authority
+pub(crate) fn amm_authority() -> SwapResult<Pubkey> {
+    Pubkey::from_str(AMM_AUTHORITY).map_err(|_| SwapError::ParseError("Invalid authority pubkey".to_string()))
+}
+
+/// User token accounts for the swap direction: (source, destination)
+fn user_token_accounts(params: &SwapParams, user_pubkey: &Pubkey) -> (Pubkey, Pubkey) {
+    let pool_info = &params.quote.pool_info;
+    let (source_mint, destination_mint) = if params.token_in == pool_info.token_a.mint {
+        (&pool_info.token_a.mint, &pool_info.token_b.mint)
+    } else {
+        (&pool_info.token_b.mint, &pool_info.token_a.mint)
+    };
+    (
+        get_associated_token_address(user_pubkey, source_mint),
+        get_associated_token_address(user_pubkey, destination_mint),
+    )
 }
 
 /// Build AMM swap instruction for Raydium AMM V4
@@ -48,59 +100,40 @@ pub async fn build_amm_swap_instruction(
     // Parse pool state to get all accounts
     let pool_state = crate::core::layouts::AmmInfoLayoutV4::from_bytes(pool_account_data)
         .map_err(|e| SwapError::ParseError(e))?;
+
+    // Pools without an OpenBook market can only be swapped through v2
+    if crate::core::is_placeholder_market(&pool_state.serum_market) {
+        info!("Pool has placeholder serum market, using swap v2");
+        return build_amm_swap_v2_instruction(params, user_pubkey, amm_program_id, pool_account_data).await;
+    }
     
     // Raydium AMM V4 uses a hardcoded authority for all pools
     // This is the actual authority that owns all pool vaults
-    let authority = Pubkey::from_str("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1")
-        .map_err(|_| SwapError::ParseError("Invalid authority pubkey".to_string()))?;
-
-    // Determine swap direction based on token_in
-    let (source_mint, destination_mint) = if params.token_in == params.quote.pool_info.token_a.mint {
-        (
-            &params.quote.pool_info.token_a.mint,
-            &params.quote.pool_info.token_b.mint,
-        )
-    } else {
-        (
-            &params.quote.pool_info.token_b.mint,
-            &params.quote.pool_info.token_a.mint,
-        )
-    };
-    
-    info!("Swap direction: {} -> {}", source_mint, destination_mint);
+    let authority = amm_authority()?;
+
+    info!("Swap direction: {} -> {}", params.token_in, params.token_out);
     info!("Pool mints - coin: {}, pc: {}", pool_state.coin_mint_address, pool_state.pc_mint_address);
 
     // Get user token accounts
-    let user_source_token = get_associated_token_address(user_pubkey, source_mint);
-    let user_destination_token = get_associated_token_address(user_pubkey, destination_mint);
+    let (user_source_token, user_destination_token) = user_token_accounts(params, user_pubkey);
     
     info!("User source token account: {}", user_source_token);
     info!("User destination token account: {}", user_destination_token);
 
     // Create instruction data
-    let data = AmmSwapInstructionData::new(
-        params.quote.amount_in,
-        params.quote.min_amount_out,
-    );
+    let data = AmmSwapInstructionData::from_quote(&params.quote);
 
     // Serialize instruction data
     let mut instruction_data = Vec::new();
     data.serialize(&mut instruction_data)?;
 
-    // Check if serum market is a placeholder
     // Parse Serum market accounts using proper parser
-    let (serum_bids, serum_asks, serum_event_queue, serum_coin_vault, serum_pc_vault, serum_vault_signer) = 
-        if crate::core::is_placeholder_market(&pool_state.serum_market) {
-            info!("Pool has placeholder serum market, using dummy accounts");
-            let dummy = Pubkey::default();
-            (dummy, dummy, dummy, dummy, dummy, dummy)
-        } else {
-            parse_serum_market_accounts(
-                market_account_data,
-                &pool_state.serum_market,
-                &pool_state.serum_program_id
-            )?
-        };
+    let (serum_bids, serum_asks, serum_event_queue, serum_coin_vault, serum_pc_vault, serum_vault_signer) =
+        parse_serum_market_accounts(
+            market_account_data,
+            &pool_state.serum_market,
+            &pool_state.serum_program_id
+        )?;
 
     info!("Building AMM swap with {} accounts", 18);
     info!("Authority: {}", authority);
@@ -154,85 +187,44 @@ pub async fn build_amm_swap_instruction(
     })
 }
 
-/// Enhanced version that uses actual pool state data
-pub async fn build_amm_swap_instruction_with_state(
+/// Build AMM V4 `swap_base_in_v2` / `swap_base_out_v2` instruction
+/// These swap against the pool alone, without the OpenBook market accounts
+pub async fn build_amm_swap_v2_instruction(
     params: &SwapParams,
     user_pubkey: &Pubkey,
     amm_program_id: &Pubkey,
-    pool_state_data: &[u8],
+    pool_account_data: &[u8],
 ) -> SwapResult<Instruction> {
-    use crate::core::layouts::AmmInfoLayoutV4;
-    
-    // Parse pool state
-    let pool_state = AmmInfoLayoutV4::from_bytes(pool_state_data)
-        .map_err(|e| SwapError::ParseError(e))?;
-    
-    // Check if pool is enabled
-    if !pool_state.is_enabled() {
-        return Err(SwapError::PoolNotActive);
-    }
-    
-    // Determine swap direction based on mints
-    let (_is_coin_to_pc, source_mint, destination_mint) = 
-        if params.quote.pool_info.token_a.mint == pool_state.coin_mint_address {
-            (true, pool_state.coin_mint_address, pool_state.pc_mint_address)
-        } else {
-            (false, pool_state.pc_mint_address, pool_state.coin_mint_address)
-        };
-
-    // Get pool authority (PDA)
-    let (authority, _nonce) = Pubkey::find_program_address(
-        &[&params.quote.pool_info.address.to_bytes()],
-        amm_program_id,
-    );
+    let pool_state = crate::core::layouts::AmmInfoLayoutV4::from_bytes(pool_account_data)
+        .map_err(SwapError::ParseError)?;
+    let authority = amm_authority()?;
+    let (user_source_token, user_destination_token) = user_token_accounts(params, user_pubkey);
 
-    // Get user token accounts
-    let user_source_token = get_associated_token_address(user_pubkey, &source_mint);
-    let user_destination_token = get_associated_token_address(user_pubkey, &destination_mint);
+    let mut instruction_data = Vec::new();
+    AmmSwapInstructionData::from_quote_v2(&params.quote).serialize(&mut instruction_data)?;
 
-    // Create instruction data
-    let data = AmmSwapInstructionData::new(
-        params.quote.amount_in,
-        params.quote.min_amount_out,
+    debug!(
+        "Building AMM swap v2 for pool {} (vaults coin: {}, pc: {})",
+        params.quote.pool_info.address, pool_state.pool_coin_token_account, pool_state.pool_pc_token_account
     );
 
-    // Serialize instruction data
-    let mut instruction_data = Vec::new();
-    data.serialize(&mut instruction_data)?;
-
-    // Build accounts list using actual pool state data
     let accounts = vec![
         // 0. Token program
         AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
-        // 1. AMM pool account
+        // 1. AMM pool account (writable)
         AccountMeta::new(params.quote.pool_info.address, false),
-        // 2. AMM authority
+        // 2. AMM authority (read-only)
         AccountMeta::new_readonly(authority, false),
-        // 3. AMM open orders account
-        AccountMeta::new(pool_state.amm_open_orders, false),
-        // 4. AMM target orders account
-        AccountMeta::new(pool_state.amm_target_orders, false),
-        // 5. Pool coin vault
+        // 3. Pool coin vault (writable)
         AccountMeta::new(pool_state.pool_coin_token_account, false),
-        // 6. Pool pc vault
+        // 4. Pool pc vault (writable)
         AccountMeta::new(pool_state.pool_pc_token_account, false),
-        // 7. Serum market account
-        AccountMeta::new(pool_state.serum_market, false),
-        // 8-13. Serum market accounts (placeholders for pools without real Serum market)
-        AccountMeta::new(Pubkey::default(), false), // bids
-        AccountMeta::new(Pubkey::default(), false), // asks
-        AccountMeta::new(Pubkey::default(), false), // event queue
-        AccountMeta::new(Pubkey::default(), false), // coin vault
-        AccountMeta::new(Pubkey::default(), false), // pc vault
-        AccountMeta::new_readonly(Pubkey::default(), false), // vault signer
-        // 14. User source token account
+        // 5. User source token account (writable)
         AccountMeta::new(user_source_token, false),
-        // 15. User destination token account
+        // 6. User destination token account (writable)
         AccountMeta::new(user_destination_token, false),
-        // 16. User owner (signer)
+        // 7. User owner (signer)
         AccountMeta::new_readonly(*user_pubkey, true),
-        // 17. Serum DEX program
-        AccountMeta::new_readonly(pool_state.serum_program_id, false),
     ];
 
     Ok(Instruction {
@@ -243,7 +235,7 @@ pub async fn build_amm_swap_instruction_with_state(
 }
 
 // Serum market account helpers
-fn parse_serum_market_accounts(
+pub(crate) fn parse_serum_market_accounts(
     market_data: &[u8], 
     market_address: &Pubkey,
     dex_program: &Pubkey
@@ -251,7 +243,7 @@ fn parse_serum_market_accounts(
     use crate::core::MarketState;
     
     // If market data is too small, it's likely a placeholder
-    if market_data.len() < 400 {
+    if market_data.len() < MarketState::LEN {
         debug!("Market data too small, using placeholder accounts");
         // Return dummy accounts for placeholder market
         let dummy = Pubkey::default();
@@ -281,29 +273,98 @@ fn parse_serum_market_accounts(
     }
 }
 
-/// Structure to hold Serum market accounts
-#[derive(Debug, Clone)]
-pub struct SerumMarketAccounts {
-    pub market: Pubkey,
-    pub bids: Pubkey,
-    pub asks: Pubkey,
-    pub event_queue: Pubkey,
-    pub coin_vault: Pubkey,
-    pub pc_vault: Pubkey,
-    pub vault_signer: Pubkey,
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl SerumMarketAccounts {
-    /// Create placeholder accounts for markets without real Serum integration
-    pub fn placeholder(market: &Pubkey) -> Self {
-        Self {
-            market: *market,
-            bids: Pubkey::default(),
-            asks: Pubkey::default(),
-            event_queue: Pubkey::default(),
-            coin_vault: Pubkey::default(),
-            pc_vault: Pubkey::default(),
-            vault_signer: Pubkey::default(),
-        }
+    #[test]
+    fn test_amm_swap_instruction_data_layout() {
+        let mut base_in = Vec::new();
+        AmmSwapInstructionData::new(1_000, 990).serialize(&mut base_in).unwrap();
+        assert_eq!(base_in.len(), 17);
+        assert_eq!(base_in[0], 9);
+        assert_eq!(u64::from_le_bytes(base_in[1..9].try_into().unwrap()), 1_000);
+        assert_eq!(u64::from_le_bytes(base_in[9..17].try_into().unwrap()), 990);
+
+        // Base out: max amount in, then exact amount out
+        let mut base_out = Vec::new();
+        AmmSwapInstructionData::new_base_out(1_010, 1_000).serialize(&mut base_out).unwrap();
+        assert_eq!(base_out[0], 11);
+        assert_eq!(u64::from_le_bytes(base_out[1..9].try_into().unwrap()), 1_010);
+        assert_eq!(u64::from_le_bytes(base_out[9..17].try_into().unwrap()), 1_000);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_amm_swap_v2_instruction() {
+        use crate::core::{PoolInfo, PoolState, PoolType, QuoteResult, TokenInfo, AMM_V4_PROGRAM};
+
+        let key = |seed: u8| Pubkey::new_from_array([seed; 32]);
+        let token = |mint| TokenInfo { mint, symbol: String::new(), decimals: 6, name: String::new() };
+        let mut pool_data = vec![0u8; crate::core::layouts::AmmInfoLayoutV4::LEN];
+        pool_data[336..368].copy_from_slice(&key(1).to_bytes());
+        pool_data[368..400].copy_from_slice(&key(2).to_bytes());
+
+        let user = key(30);
+        let params = SwapParams {
+            quote: QuoteResult {
+                pool_info: PoolInfo {
+                    pool_type: PoolType::AMM,
+                    address: key(20),
+                    token_a: token(key(3)),
+                    token_b: token(key(4)),
+                    liquidity_usd: 0.0,
+                    volume_24h_usd: 0.0,
+                    fee_rate: 0.0025,
+                    program_id: *AMM_V4_PROGRAM,
+                    context_slot: 0,
+                    open_time: 0,
+                    pool_state: PoolState::AMM {
+                        reserve_a: 1,
+                        reserve_b: 1,
+                        nonce: 0,
+                        need_take_pnl_coin: 0,
+                        need_take_pnl_pc: 0,
+                        swap_fee_numerator: 25,
+                        swap_fee_denominator: 10_000,
+                    },
+                },
+                amount_in: 1_010,
+                amount_out: 1_000,
+                min_amount_out: 1_000,
+                max_amount_in: 1_015,
+                swap_mode: SwapMode::ExactOut,
+                price_impact: 0.0,
+                fee: 3,
+                route: vec![key(20)],
+                token_in: key(4),
+                token_out: key(3),
+                tick_traversal: None,
+                execution_costs: None,
+            },
+            user_pubkey: user,
+            slippage_bps: 50,
+            token_in: key(4),
+            token_out: key(3),
+        };
+
+        let instruction = build_amm_swap_v2_instruction(&params, &user, &AMM_V4_PROGRAM, &pool_data)
+            .await
+            .unwrap();
+        let expected = vec![
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new(key(20), false),
+            AccountMeta::new_readonly(amm_authority().unwrap(), false),
+            AccountMeta::new(key(1), false),
+            AccountMeta::new(key(2), false),
+            AccountMeta::new(get_associated_token_address(&user, &key(4)), false),
+            AccountMeta::new(get_associated_token_address(&user, &key(3)), false),
+            AccountMeta::new_readonly(user, true),
+        ];
+        assert_eq!(instruction.accounts, expected);
+
+        // Base out v2: max amount in, then exact amount out
+        assert_eq!(instruction.data[0], AMM_SWAP_BASE_OUT_V2_INSTRUCTION);
+        assert_eq!(u64::from_le_bytes(instruction.data[1..9].try_into().unwrap()), 1_015);
+        assert_eq!(u64::from_le_bytes(instruction.data[9..17].try_into().unwrap()), 1_000);
+    }
+}
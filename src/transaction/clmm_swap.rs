@@ -1,119 +1,266 @@
-use crate::core::{error::SwapError, SwapParams, SwapResult, PoolState, constants::CLMM_PROGRAM};
+use crate::core::{
+    clmm_math::{get_sqrt_price_at_tick, MAX_SQRT_PRICE_X64, MAX_TICK, MIN_SQRT_PRICE_X64, MIN_TICK},
+    constants::{MEMO_PROGRAM, TOKEN_2022_PROGRAM},
+    error::SwapError,
+    layouts::{ClmmPoolState, TickArrayState},
+    QuoteResult, SwapMode, SwapParams, SwapResult,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
-    sysvar,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use log::debug;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+/// Tick arrays passed to a swap when the quote crosses none
+pub(crate) const MIN_SWAP_TICK_ARRAYS: usize = 3;
+/// Most tick arrays passed to a single swap
+const MAX_SWAP_TICK_ARRAYS: usize = 8;
+/// Arrays beyond the pool bitmap checked on chain while looking for initialized ones
+const MAX_UNTRACKED_TICK_ARRAYS: usize = 16;
 
 /// Raydium CLMM swap instruction data
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
 struct ClmmSwapInstructionData {
     /// Instruction discriminator for CLMM swap
     instruction: [u8; 8], // Anchor discriminator
-    /// Amount of input token
+    /// Exact input amount (base input) or exact output amount (base output)
     amount: u64,
-    /// Minimum amount of output token  
+    /// Minimum output (base input) or maximum input (base output)
     other_amount_threshold: u64,
     /// Square root price limit
     sqrt_price_limit_x64: u128,
-    /// Is base input (true if swapping base to quote)
+    /// True when `amount` is the input side of the swap
     is_base_input: bool,
 }
 
-/// Build Raydium CLMM swap instruction
+/// Anchor discriminator of `swap_v2`
+fn swap_v2_discriminator() -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(b"global:swap_v2");
+    hash.to_bytes()[..8].try_into().unwrap()
+}
+
+/// Accounts a CLMM swap needs besides the pool and its vaults
+#[derive(Debug, Clone)]
+pub struct ClmmSwapAccounts {
+    /// Token program owning each pool mint
+    pub token_program_0: Pubkey,
+    pub token_program_1: Pubkey,
+    /// Passed only when the pool has one
+    pub bitmap_extension: Option<Pubkey>,
+    /// Initialized tick arrays in the swap direction, current one first
+    pub tick_arrays: Vec<Pubkey>,
+}
+
+/// Number of tick arrays to pass for a quote: the ones it crossed plus one spare
+pub fn swap_tick_array_count(quote: &QuoteResult) -> usize {
+    quote
+        .tick_traversal
+        .as_ref()
+        .map_or(MIN_SWAP_TICK_ARRAYS, |traversal| traversal.tick_arrays_crossed as usize + 2)
+        .clamp(MIN_SWAP_TICK_ARRAYS, MAX_SWAP_TICK_ARRAYS)
+}
+
+/// Start indices of the tick arrays a swap may walk through, from the array
+/// holding the current tick in the swap direction
+/// Arrays the pool bitmap marks uninitialized are skipped; arrays outside it
+/// are returned for the caller to check on chain.
+pub fn swap_tick_array_candidates(pool_state: &ClmmPoolState, zero_for_one: bool, count: usize) -> Vec<i32> {
+    pool_state
+        .tick_array_walk(None, pool_state.current_tick, zero_for_one, count, MAX_UNTRACKED_TICK_ARRAYS)
+        .start_indices
+}
+
+/// Load the token programs, bitmap extension and tick arrays for a swap
+pub async fn load_clmm_swap_accounts(
+    rpc_client: &RpcClient,
+    pool_address: &Pubkey,
+    pool_program: &Pubkey,
+    pool_state: &ClmmPoolState,
+    zero_for_one: bool,
+    tick_array_count: usize,
+) -> SwapResult<ClmmSwapAccounts> {
+    let bitmap_extension = ClmmPoolState::find_bitmap_extension_address(pool_address, pool_program);
+    let tick_arrays: Vec<Pubkey> = swap_tick_array_candidates(pool_state, zero_for_one, tick_array_count)
+        .into_iter()
+        .map(|start_index| TickArrayState::find_address(pool_address, start_index, pool_program))
+        .collect();
+
+    let mut addresses = vec![pool_state.token_mint_0, pool_state.token_mint_1, bitmap_extension];
+    addresses.extend(&tick_arrays);
+    let accounts = rpc_client
+        .get_multiple_accounts(&addresses)
+        .await
+        .map_err(SwapError::from)?;
+
+    let token_program = |index: usize| {
+        accounts[index]
+            .as_ref()
+            .map(|account| account.owner)
+            .ok_or_else(|| SwapError::InvalidTokenMint(format!("Mint {} not found", addresses[index])))
+    };
+
+    Ok(ClmmSwapAccounts {
+        token_program_0: token_program(0)?,
+        token_program_1: token_program(1)?,
+        bitmap_extension: accounts[2].as_ref().map(|_| bitmap_extension),
+        // Missing accounts are uninitialized arrays
+        tick_arrays: tick_arrays
+            .into_iter()
+            .zip(&accounts[3..])
+            .filter(|(_, account)| account.is_some())
+            .map(|(address, _)| address)
+            .take(tick_array_count)
+            .collect(),
+    })
+}
+
+/// Square root price the swap may not cross: the quoted end price moved
+/// against the user by the slippage tolerance
+fn sqrt_price_limit_x64(
+    quote: &QuoteResult,
+    current_sqrt_price_x64: u128,
+    zero_for_one: bool,
+    slippage_bps: u16,
+) -> SwapResult<u128> {
+    // The end tick rounds down, so its upper edge bounds a rising price
+    let end_sqrt_price_x64 = match &quote.tick_traversal {
+        Some(traversal) if zero_for_one => get_sqrt_price_at_tick(traversal.end_tick.max(MIN_TICK))?,
+        Some(traversal) => get_sqrt_price_at_tick((traversal.end_tick + 1).min(MAX_TICK))?,
+        None => current_sqrt_price_x64,
+    };
+
+    let slippage = slippage_bps as f64 / 10_000.0;
+    if zero_for_one {
+        let limit = (end_sqrt_price_x64 as f64 * (1.0 - slippage).max(0.0).sqrt()) as u128;
+        Ok(limit.min(current_sqrt_price_x64.saturating_sub(1)).max(MIN_SQRT_PRICE_X64 + 1))
+    } else {
+        let limit = (end_sqrt_price_x64 as f64 * (1.0 + slippage).sqrt()) as u128;
+        Ok(limit.max(current_sqrt_price_x64 + 1).min(MAX_SQRT_PRICE_X64 - 1))
+    }
+}
+
+/// Build Raydium CLMM `swap_v2` instruction
 pub async fn build_clmm_swap_instruction(
     params: &SwapParams,
     user_pubkey: &Pubkey,
     pool_program: &Pubkey,
-    _pool_data: &[u8],
+    pool_data: &[u8],
+    swap_accounts: &ClmmSwapAccounts,
 ) -> SwapResult<Instruction> {
     debug!("Building CLMM swap instruction");
-    
-    let pool_info = &params.quote.pool_info;
-    
-    // Extract pool state
-    let (current_tick, tick_spacing, _liquidity) = match &pool_info.pool_state {
-        PoolState::CLMM { current_tick, tick_spacing, liquidity, .. } => 
-            (*current_tick, *tick_spacing, *liquidity),
-        _ => return Err(SwapError::InvalidPoolState("Expected CLMM pool state".to_string())),
+
+    let pool_address = params.quote.pool_info.address;
+    let pool_state = ClmmPoolState::try_from_slice(pool_data)
+        .map_err(|e| SwapError::ParseError(format!("Failed to parse CLMM pool {}: {}", pool_address, e)))?;
+
+    // Determine swap direction and which side the amount fixes
+    let zero_for_one = if params.token_in == pool_state.token_mint_0 {
+        true
+    } else if params.token_in == pool_state.token_mint_1 {
+        false
+    } else {
+        return Err(SwapError::InvalidTokenMint(format!(
+            "{} is not traded by CLMM pool {}",
+            params.token_in, pool_address
+        )));
+    };
+    let is_base_input = params.quote.swap_mode == SwapMode::ExactIn;
+    let (amount, other_amount_threshold) = match params.quote.swap_mode {
+        SwapMode::ExactIn => (params.quote.amount_in, params.quote.min_amount_out),
+        SwapMode::ExactOut => (params.quote.amount_out, params.quote.max_amount_in),
     };
-    
-    // Determine swap direction
-    let is_base_input = pool_info.token_a.mint == params.token_in;
-    
+    let sqrt_price_limit_x64 = sqrt_price_limit_x64(
+        &params.quote,
+        pool_state.current_price_sqrt,
+        zero_for_one,
+        params.slippage_bps,
+    )?;
+
+    let (input_vault, output_vault, input_mint, output_mint, input_program, output_program) = if zero_for_one {
+        (
+            pool_state.token_vault_0,
+            pool_state.token_vault_1,
+            pool_state.token_mint_0,
+            pool_state.token_mint_1,
+            swap_accounts.token_program_0,
+            swap_accounts.token_program_1,
+        )
+    } else {
+        (
+            pool_state.token_vault_1,
+            pool_state.token_vault_0,
+            pool_state.token_mint_1,
+            pool_state.token_mint_0,
+            swap_accounts.token_program_1,
+            swap_accounts.token_program_0,
+        )
+    };
+
     debug!(
-        "CLMM swap: {} -> {}, amount: {}, min_out: {}, is_base_input: {}",
-        if is_base_input { &pool_info.token_a.symbol } else { &pool_info.token_b.symbol },
-        if is_base_input { &pool_info.token_b.symbol } else { &pool_info.token_a.symbol },
-        params.quote.amount_in,
-        params.quote.min_amount_out,
-        is_base_input
+        "CLMM swap: {} -> {}, amount: {}, threshold: {}, is_base_input: {}, sqrt price limit: {}, tick arrays: {}",
+        input_mint,
+        output_mint,
+        amount,
+        other_amount_threshold,
+        is_base_input,
+        sqrt_price_limit_x64,
+        swap_accounts.tick_arrays.len()
     );
-    
+
     // Get user token accounts
-    let user_token_in = get_associated_token_address(user_pubkey, &params.token_in);
-    let user_token_out = get_associated_token_address(user_pubkey, &params.token_out);
-    
-    // Derive pool accounts
-    let pool_token_vault_0 = derive_clmm_token_vault(&pool_info.address, 0)?;
-    let pool_token_vault_1 = derive_clmm_token_vault(&pool_info.address, 1)?;
-    let tick_array_0 = derive_tick_array_address(&pool_info.address, current_tick, tick_spacing)?;
-    let tick_array_1 = derive_tick_array_address(&pool_info.address, current_tick + tick_spacing as i32, tick_spacing)?;
-    let tick_array_2 = derive_tick_array_address(&pool_info.address, current_tick - tick_spacing as i32, tick_spacing)?;
-    
-    // CLMM swap instruction discriminator
-    // This is the Anchor discriminator for "swap" instruction
-    let discriminator: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200]; // Anchor IDL hash for "swap"
-    
-    // No sqrt price limit (0 means no limit)
-    let sqrt_price_limit_x64 = 0u128;
-    
+    let user_token_in = get_associated_token_address_with_program_id(user_pubkey, &input_mint, &input_program);
+    let user_token_out = get_associated_token_address_with_program_id(user_pubkey, &output_mint, &output_program);
+
     let instruction_data = ClmmSwapInstructionData {
-        instruction: discriminator,
-        amount: params.quote.amount_in,
-        other_amount_threshold: params.quote.min_amount_out,
+        instruction: swap_v2_discriminator(),
+        amount,
+        other_amount_threshold,
         sqrt_price_limit_x64,
         is_base_input,
     };
-    
+
     let mut data = Vec::new();
     instruction_data.serialize(&mut data)
         .map_err(|e| SwapError::SerializationError(e.to_string()))?;
-    
-    // Build accounts for CLMM swap
-    // Account order for Raydium CLMM:
-    // 0. [signer] Payer (user)
-    // 1. [] Pool state
-    // 2. [writable] Token vault 0
-    // 3. [writable] Token vault 1  
-    // 4. [writable] Tick array 0
-    // 5. [writable] Tick array 1
-    // 6. [writable] Tick array 2
-    // 7. [writable] User token account A
-    // 8. [writable] User token account B
-    // 9. [] Token program
-    // 10. [] Associated token program
-    // 11. [] System program
-    // 12. [] Rent sysvar
-    
-    let accounts = vec![
-        AccountMeta::new(*user_pubkey, true), // Payer
-        AccountMeta::new_readonly(pool_info.address, false), // Pool state
-        AccountMeta::new(pool_token_vault_0, false), // Token vault 0
-        AccountMeta::new(pool_token_vault_1, false), // Token vault 1
-        AccountMeta::new(tick_array_0, false), // Tick array 0
-        AccountMeta::new(tick_array_1, false), // Tick array 1
-        AccountMeta::new(tick_array_2, false), // Tick array 2
-        AccountMeta::new(user_token_in, false), // User token in
-        AccountMeta::new(user_token_out, false), // User token out
-        AccountMeta::new_readonly(spl_token::id(), false), // Token program
-        AccountMeta::new_readonly(spl_associated_token_account::id(), false), // Associated token program
-        AccountMeta::new_readonly(solana_sdk::system_program::id(), false), // System program
-        AccountMeta::new_readonly(sysvar::rent::id(), false), // Rent sysvar
+
+    let mut accounts = vec![
+        // 0. Payer (signer)
+        AccountMeta::new_readonly(*user_pubkey, true),
+        // 1. AMM config
+        AccountMeta::new_readonly(pool_state.amm_config, false),
+        // 2. Pool state (writable)
+        AccountMeta::new(pool_address, false),
+        // 3. User input token account (writable)
+        AccountMeta::new(user_token_in, false),
+        // 4. User output token account (writable)
+        AccountMeta::new(user_token_out, false),
+        // 5. Input vault (writable)
+        AccountMeta::new(input_vault, false),
+        // 6. Output vault (writable)
+        AccountMeta::new(output_vault, false),
+        // 7. Observation state (writable)
+        AccountMeta::new(pool_state.observation_key, false),
+        // 8. Token program
+        AccountMeta::new_readonly(spl_token::id(), false),
+        // 9. Token-2022 program
+        AccountMeta::new_readonly(*TOKEN_2022_PROGRAM, false),
+        // 10. Memo program
+        AccountMeta::new_readonly(*MEMO_PROGRAM, false),
+        // 11. Input mint
+        AccountMeta::new_readonly(input_mint, false),
+        // 12. Output mint
+        AccountMeta::new_readonly(output_mint, false),
     ];
-    
+
+    // Remaining accounts: bitmap extension first, then tick arrays
+    if let Some(bitmap_extension) = swap_accounts.bitmap_extension {
+        accounts.push(AccountMeta::new(bitmap_extension, false));
+    }
+    accounts.extend(swap_accounts.tick_arrays.iter().map(|tick_array| AccountMeta::new(*tick_array, false)));
+
     Ok(Instruction {
         program_id: *pool_program,
         accounts,
@@ -121,72 +268,186 @@ pub async fn build_clmm_swap_instruction(
     })
 }
 
-/// Derive CLMM token vault
-fn derive_clmm_token_vault(pool_address: &Pubkey, index: u8) -> SwapResult<Pubkey> {
-    let (vault, _) = Pubkey::find_program_address(
-        &[b"pool_vault", pool_address.as_ref(), &[index]],
-        &CLMM_PROGRAM,
-    );
-    Ok(vault)
-}
-
-/// Derive tick array address
-fn derive_tick_array_address(pool_address: &Pubkey, tick: i32, tick_spacing: u16) -> SwapResult<Pubkey> {
-    // Calculate start index for tick array
-    let ticks_per_array = 60; // CLMM uses 60 ticks per array
-    let array_index = tick / (ticks_per_array * tick_spacing as i32);
-    let start_tick = array_index * ticks_per_array * tick_spacing as i32;
-    
-    let (tick_array, _) = Pubkey::find_program_address(
-        &[
-            b"tick_array",
-            pool_address.as_ref(),
-            &start_tick.to_le_bytes(),
-        ],
-        &CLMM_PROGRAM,
-    );
-    
-    Ok(tick_array)
-}
-
-/// Get associated token address
-fn get_associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
-    spl_associated_token_account::get_associated_token_address(wallet, mint)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use crate::core::{constants::CLMM_PROGRAM, PoolInfo, PoolState, PoolType, TickTraversal, TokenInfo};
+
+    fn pool_state(current_tick: i32, tick_spacing: u16) -> ClmmPoolState {
+        let mut pool_state = ClmmPoolState::try_from_slice(&vec![0u8; ClmmPoolState::LEN]).unwrap();
+        pool_state.current_tick = current_tick;
+        pool_state.tick_spacing = tick_spacing;
+        pool_state.current_price_sqrt = get_sqrt_price_at_tick(current_tick).unwrap();
+        pool_state
+    }
+
+    /// Mark the tick array with `array_index` (start tick / ticks per array) initialized
+    fn set_initialized(pool_state: &mut ClmmPoolState, array_index: i32) {
+        let position = (array_index + ClmmPoolState::TICK_ARRAY_BITMAP_HALF) as usize;
+        pool_state.tick_array_bitmap[position / 64] |= 1 << (position % 64);
+    }
+
+    fn quote(zero_for_one: bool, end_tick: i32) -> QuoteResult {
+        let token = |seed| TokenInfo {
+            mint: Pubkey::new_from_array([seed; 32]),
+            symbol: String::new(),
+            decimals: 6,
+            name: String::new(),
+        };
+        let (token_in, token_out) = if zero_for_one { (token(3), token(4)) } else { (token(4), token(3)) };
+        QuoteResult {
+            pool_info: PoolInfo {
+                pool_type: PoolType::CLMM,
+                address: Pubkey::new_from_array([20; 32]),
+                token_a: token(3),
+                token_b: token(4),
+                liquidity_usd: 0.0,
+                volume_24h_usd: 0.0,
+                fee_rate: 0.0005,
+                program_id: *CLMM_PROGRAM,
+                context_slot: 0,
+                open_time: 0,
+                pool_state: PoolState::CLMM {
+                    current_tick: 0,
+                    tick_spacing: 10,
+                    liquidity: 0,
+                    sqrt_price_x64: 0,
+                    fee_tier: 500,
+                },
+            },
+            amount_in: 1_000_000,
+            amount_out: 990_000,
+            min_amount_out: 985_000,
+            max_amount_in: 1_000_000,
+            swap_mode: SwapMode::ExactIn,
+            price_impact: 0.0,
+            fee: 500,
+            route: vec![],
+            token_in: token_in.mint,
+            token_out: token_out.mint,
+            tick_traversal: Some(TickTraversal { ticks_crossed: 1, tick_arrays_crossed: 3, end_tick }),
+            execution_costs: None,
+        }
+    }
+
     #[test]
     fn test_clmm_swap_instruction_serialization() {
         let instruction_data = ClmmSwapInstructionData {
-            instruction: [248, 198, 158, 145, 225, 117, 135, 200],
+            instruction: swap_v2_discriminator(),
             amount: 1_000_000_000,
             other_amount_threshold: 900_000_000,
             sqrt_price_limit_x64: 0,
             is_base_input: true,
         };
-        
+
         let mut serialized = Vec::new();
         instruction_data.serialize(&mut serialized).unwrap();
-        assert!(!serialized.is_empty());
-        
+        assert_eq!(serialized.len(), 41);
+
         // Verify discriminator is at the beginning
-        assert_eq!(&serialized[0..8], &[248, 198, 158, 145, 225, 117, 135, 200]);
+        assert_eq!(&serialized[0..8], &[43, 4, 237, 11, 26, 201, 30, 98]);
+    }
+
+    #[test]
+    fn test_tick_array_candidates_follow_direction_and_bitmap() {
+        // Arrays span 600 ticks at spacing 10; tick -1234 sits in array -3
+        let mut pool_state = pool_state(-1234, 10);
+        for array_index in [-5, -3, -1, 0, 4] {
+            set_initialized(&mut pool_state, array_index);
+        }
+
+        assert_eq!(swap_tick_array_candidates(&pool_state, true, 2), vec![-1800, -3000]);
+        assert_eq!(swap_tick_array_candidates(&pool_state, false, 3), vec![-1800, -600, 0]);
+
+        // Arrays past the pool bitmap are left to the on-chain check
+        let pool_state = pool_state_at_bitmap_edge();
+        let candidates = swap_tick_array_candidates(&pool_state, false, 3);
+        assert_eq!(candidates.len(), 1 + MAX_UNTRACKED_TICK_ARRAYS);
+        assert_eq!(candidates[1], 512 * 600);
+    }
+
+    fn pool_state_at_bitmap_edge() -> ClmmPoolState {
+        let mut pool_state = pool_state(511 * 600 + 5, 10);
+        set_initialized(&mut pool_state, 511);
+        pool_state
     }
-    
+
     #[test]
-    fn test_tick_array_calculation() {
-        let pool = Pubkey::new_unique();
-        let tick = 1234;
-        let tick_spacing = 10;
-        
-        let tick_array = derive_tick_array_address(&pool, tick, tick_spacing).unwrap();
-        assert_ne!(tick_array, Pubkey::default());
-        
-        // Test negative tick
-        let tick_array_neg = derive_tick_array_address(&pool, -1234, tick_spacing).unwrap();
-        assert_ne!(tick_array_neg, Pubkey::default());
+    fn test_sqrt_price_limit_from_slippage() {
+        let current = get_sqrt_price_at_tick(0).unwrap();
+
+        // Selling token 0 lowers the price: the limit sits below the end price
+        let limit = sqrt_price_limit_x64(&quote(true, -100), current, true, 100).unwrap();
+        let end = get_sqrt_price_at_tick(-100).unwrap();
+        assert!(limit < end);
+        assert!((limit as f64 / end as f64 - 0.99f64.sqrt()).abs() < 1e-9);
+
+        // Buying token 0 raises it
+        let limit = sqrt_price_limit_x64(&quote(false, 100), current, false, 100).unwrap();
+        assert!(limit > get_sqrt_price_at_tick(101).unwrap());
+
+        // Never on the wrong side of the current price
+        assert!(sqrt_price_limit_x64(&quote(true, 0), current, true, 0).unwrap() < current);
+        assert!(sqrt_price_limit_x64(&quote(false, -1), current, false, 0).unwrap() > current);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_swap_v2_account_layout() {
+        let key = |seed: u8| Pubkey::new_from_array([seed; 32]);
+        let mut pool_state = pool_state(0, 10);
+        pool_state.amm_config = key(1);
+        pool_state.token_mint_0 = key(3);
+        pool_state.token_mint_1 = key(4);
+        pool_state.token_vault_0 = key(5);
+        pool_state.token_vault_1 = key(6);
+        pool_state.observation_key = key(7);
+        let pool_data = borsh::to_vec(&pool_state).unwrap();
+
+        let user = key(30);
+        let params = SwapParams {
+            quote: quote(false, 100),
+            user_pubkey: user,
+            slippage_bps: 50,
+            token_in: key(4),
+            token_out: key(3),
+        };
+        let swap_accounts = ClmmSwapAccounts {
+            token_program_0: spl_token::id(),
+            token_program_1: *TOKEN_2022_PROGRAM,
+            bitmap_extension: Some(key(8)),
+            tick_arrays: vec![key(9), key(10)],
+        };
+
+        let instruction = build_clmm_swap_instruction(&params, &user, &CLMM_PROGRAM, &pool_data, &swap_accounts)
+            .await
+            .unwrap();
+
+        let expected = [
+            (user, false),
+            (key(1), false),
+            (key(20), true),
+            (get_associated_token_address_with_program_id(&user, &key(4), &TOKEN_2022_PROGRAM), true),
+            (get_associated_token_address_with_program_id(&user, &key(3), &spl_token::id()), true),
+            (key(6), true),
+            (key(5), true),
+            (key(7), true),
+            (spl_token::id(), false),
+            (*TOKEN_2022_PROGRAM, false),
+            (*MEMO_PROGRAM, false),
+            (key(4), false),
+            (key(3), false),
+            (key(8), true),
+            (key(9), true),
+            (key(10), true),
+        ];
+        assert_eq!(instruction.accounts.len(), expected.len());
+        for (i, (meta, (pubkey, is_writable))) in instruction.accounts.iter().zip(expected).enumerate() {
+            assert_eq!((meta.pubkey, meta.is_writable), (pubkey, is_writable), "account {}", i);
+        }
+        assert!(instruction.accounts[0].is_signer);
+
+        let data = ClmmSwapInstructionData::try_from_slice(&instruction.data).unwrap();
+        assert_eq!((data.amount, data.other_amount_threshold, data.is_base_input), (1_000_000, 985_000, true));
+        assert!(data.sqrt_price_limit_x64 > pool_state.current_price_sqrt);
+    }
+}
@@ -1,33 +1,36 @@
-use crate::core::{SwapError, SwapParams, SwapResult};
+use crate::core::{SwapError, SwapMode, SwapParams, SwapResult};
 use borsh::{BorshDeserialize, BorshSerialize};
 use log::info;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
-    sysvar,
 };
-use spl_associated_token_account::get_associated_token_address;
-use spl_token::ID as TOKEN_PROGRAM_ID;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 
-/// CP Swap instruction discriminator
-const CP_SWAP_BASE_IN: u8 = 0;
-const CP_SWAP_BASE_OUT: u8 = 1;
+/// Seed of the authority owning every CP pool vault
+pub(crate) const CP_AUTHORITY_SEED: &[u8] = b"vault_and_lp_mint_auth_seed";
+
+/// Anchor discriminator of a CP Swap instruction
+fn cp_swap_discriminator(name: &str) -> [u8; 8] {
+    let hash = solana_sdk::hash::hash(format!("global:{}", name).as_bytes());
+    hash.to_bytes()[..8].try_into().unwrap()
+}
 
 /// CP Swap instruction data layout
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
 pub struct CpSwapInstructionData {
-    /// Instruction discriminator
-    pub instruction: u8,
-    /// Amount (input or output depending on instruction)
+    /// Anchor discriminator
+    pub instruction: [u8; 8],
+    /// Amount in (base input) or maximum amount in (base output)
     pub amount: u64,
-    /// Other side amount (minimum out or maximum in)
+    /// Minimum amount out (base input) or exact amount out (base output)
     pub other_amount: u64,
 }
 
 impl CpSwapInstructionData {
     pub fn new_base_in(amount_in: u64, min_amount_out: u64) -> Self {
         Self {
-            instruction: CP_SWAP_BASE_IN,
+            instruction: cp_swap_discriminator("swap_base_input"),
             amount: amount_in,
             other_amount: min_amount_out,
         }
@@ -35,9 +38,9 @@ impl CpSwapInstructionData {
 
     pub fn new_base_out(amount_out: u64, max_amount_in: u64) -> Self {
         Self {
-            instruction: CP_SWAP_BASE_OUT,
-            amount: amount_out,
-            other_amount: max_amount_in,
+            instruction: cp_swap_discriminator("swap_base_output"),
+            amount: max_amount_in,
+            other_amount: amount_out,
         }
     }
 }
@@ -51,76 +54,183 @@ pub async fn build_cp_swap_instruction(
 ) -> SwapResult<Instruction> {
     // Extract pool info
     let pool_address = params.quote.pool_info.address;
-    
+
     // Parse pool state to get vault addresses
     let pool_state = crate::core::layouts::CpSwapPoolState::from_bytes(pool_account_data)
         .map_err(|e| SwapError::ParseError(e))?;
-    
+
     info!("Building CP swap instruction for pool {}", pool_address);
     info!("Pool token mints - 0: {}, 1: {}", pool_state.token_0_mint, pool_state.token_1_mint);
-    
-    // Determine swap direction
-    let (_, token_0_to_1) = if params.token_in == pool_state.token_0_mint {
-        (true, true)
-    } else if params.token_in == pool_state.token_1_mint {
-        (true, false)
-    } else {
-        return Err(SwapError::InvalidTokenMint("Input token not found in pool".to_string()));
-    };
-    
-    info!("Swap direction: token_0_to_1 = {}", token_0_to_1);
-    
+
+    // Determine swap direction; each side keeps its own token program
+    let (input_vault, output_vault, input_mint, output_mint, input_program, output_program) =
+        if params.token_in == pool_state.token_0_mint {
+            (
+                pool_state.token_0_vault,
+                pool_state.token_1_vault,
+                pool_state.token_0_mint,
+                pool_state.token_1_mint,
+                pool_state.token_0_program,
+                pool_state.token_1_program,
+            )
+        } else if params.token_in == pool_state.token_1_mint {
+            (
+                pool_state.token_1_vault,
+                pool_state.token_0_vault,
+                pool_state.token_1_mint,
+                pool_state.token_0_mint,
+                pool_state.token_1_program,
+                pool_state.token_0_program,
+            )
+        } else {
+            return Err(SwapError::InvalidTokenMint("Input token not found in pool".to_string()));
+        };
+
+    info!("Swap direction: {} -> {}", input_mint, output_mint);
+
     // Get user token accounts
-    let user_input_token = get_associated_token_address(user_pubkey, &params.token_in);
-    let user_output_token = get_associated_token_address(user_pubkey, &params.token_out);
-    
+    let user_input_token = get_associated_token_address_with_program_id(user_pubkey, &input_mint, &input_program);
+    let user_output_token = get_associated_token_address_with_program_id(user_pubkey, &output_mint, &output_program);
+
     info!("User input token account: {}", user_input_token);
     info!("User output token account: {}", user_output_token);
-    
-    // Create instruction data (always use base_in for simplicity)
-    let data = CpSwapInstructionData::new_base_in(
-        params.quote.amount_in,
-        params.quote.min_amount_out,
-    );
-    
+
+    // Create instruction data for the quote's swap mode
+    let data = match params.quote.swap_mode {
+        SwapMode::ExactIn => CpSwapInstructionData::new_base_in(
+            params.quote.amount_in,
+            params.quote.min_amount_out,
+        ),
+        SwapMode::ExactOut => CpSwapInstructionData::new_base_out(
+            params.quote.amount_out,
+            params.quote.max_amount_in,
+        ),
+    };
+
     // Serialize instruction data
     let mut instruction_data = Vec::new();
     data.serialize(&mut instruction_data)?;
-    
+
     // Derive authority PDA
-    let (authority, _bump) = Pubkey::find_program_address(
-        &[b"cp_swap_program", &pool_address.to_bytes()],
-        cp_program_id,
-    );
-    
+    let (authority, _bump) = Pubkey::find_program_address(&[CP_AUTHORITY_SEED], cp_program_id);
+
     info!("Pool authority: {}", authority);
-    
+
     // Build accounts list
     // The order is critical for CP swap instruction
     let accounts = vec![
-        // 0. Pool config account (if exists, otherwise system program)
-        AccountMeta::new_readonly(sysvar::ID, false), // Using system program as placeholder
-        // 1. Pool state account
-        AccountMeta::new(pool_address, false),
-        // 2. Authority
+        // 0. Payer (signer)
+        AccountMeta::new_readonly(*user_pubkey, true),
+        // 1. Authority
         AccountMeta::new_readonly(authority, false),
-        // 3. User input token account
+        // 2. AMM config
+        AccountMeta::new_readonly(pool_state.amm_config, false),
+        // 3. Pool state account
+        AccountMeta::new(pool_address, false),
+        // 4. User input token account
         AccountMeta::new(user_input_token, false),
-        // 4. User output token account  
+        // 5. User output token account
         AccountMeta::new(user_output_token, false),
-        // 5. Pool vault 0
-        AccountMeta::new(pool_state.token_0_vault, false),
-        // 6. Pool vault 1
-        AccountMeta::new(pool_state.token_1_vault, false),
-        // 7. Token program
-        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
-        // 8. User owner (signer)
-        AccountMeta::new_readonly(*user_pubkey, true),
+        // 6. Input vault
+        AccountMeta::new(input_vault, false),
+        // 7. Output vault
+        AccountMeta::new(output_vault, false),
+        // 8. Input token program
+        AccountMeta::new_readonly(input_program, false),
+        // 9. Output token program
+        AccountMeta::new_readonly(output_program, false),
+        // 10. Input mint
+        AccountMeta::new_readonly(input_mint, false),
+        // 11. Output mint
+        AccountMeta::new_readonly(output_mint, false),
+        // 12. Observation state
+        AccountMeta::new(pool_state.observation_key, false),
     ];
-    
+
     Ok(Instruction {
         program_id: *cp_program_id,
         accounts,
         data: instruction_data,
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        layouts::CpSwapPoolState, PoolInfo, PoolState, PoolType, QuoteResult, TokenInfo, RAYDIUM_CP_SWAP_PROGRAM,
+        TOKEN_2022_PROGRAM,
+    };
+
+    #[tokio::test]
+    async fn test_cp_swap_uses_each_mints_token_program() {
+        let key = |seed: u8| Pubkey::new_from_array([seed; 32]);
+        let mut pool_data = vec![0u8; CpSwapPoolState::LEN];
+        for (offset, seed) in [(8, 1), (72, 5), (104, 6), (168, 3), (200, 4), (296, 7)] {
+            pool_data[offset..offset + 32].copy_from_slice(&key(seed).to_bytes());
+        }
+        pool_data[232..264].copy_from_slice(&spl_token::ID.to_bytes());
+        pool_data[264..296].copy_from_slice(&TOKEN_2022_PROGRAM.to_bytes());
+
+        let token = |mint| TokenInfo { mint, symbol: String::new(), decimals: 6, name: String::new() };
+        let user = key(30);
+        let params = SwapParams {
+            quote: QuoteResult {
+                pool_info: PoolInfo {
+                    pool_type: PoolType::Standard,
+                    address: key(20),
+                    token_a: token(key(3)),
+                    token_b: token(key(4)),
+                    liquidity_usd: 0.0,
+                    volume_24h_usd: 0.0,
+                    fee_rate: 0.0025,
+                    program_id: *RAYDIUM_CP_SWAP_PROGRAM,
+                    context_slot: 0,
+                    open_time: 0,
+                    pool_state: PoolState::Standard { reserve_a: 1, reserve_b: 1, trade_fee_rate: 2_500 },
+                },
+                amount_in: 1_000,
+                amount_out: 990,
+                min_amount_out: 985,
+                max_amount_in: 1_000,
+                swap_mode: SwapMode::ExactIn,
+                price_impact: 0.0,
+                fee: 3,
+                route: vec![key(20)],
+                token_in: key(4),
+                token_out: key(3),
+                tick_traversal: None,
+                execution_costs: None,
+            },
+            user_pubkey: user,
+            slippage_bps: 50,
+            token_in: key(4),
+            token_out: key(3),
+        };
+
+        let instruction = build_cp_swap_instruction(&params, &user, &RAYDIUM_CP_SWAP_PROGRAM, &pool_data)
+            .await
+            .unwrap();
+        let (authority, _) = Pubkey::find_program_address(&[CP_AUTHORITY_SEED], &RAYDIUM_CP_SWAP_PROGRAM);
+        let expected = vec![
+            AccountMeta::new_readonly(user, true),
+            AccountMeta::new_readonly(authority, false),
+            AccountMeta::new_readonly(key(1), false),
+            AccountMeta::new(key(20), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(&user, &key(4), &TOKEN_2022_PROGRAM), false),
+            AccountMeta::new(get_associated_token_address_with_program_id(&user, &key(3), &spl_token::ID), false),
+            AccountMeta::new(key(6), false),
+            AccountMeta::new(key(5), false),
+            AccountMeta::new_readonly(*TOKEN_2022_PROGRAM, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(key(4), false),
+            AccountMeta::new_readonly(key(3), false),
+            AccountMeta::new(key(7), false),
+        ];
+        assert_eq!(instruction.accounts, expected);
+
+        let data = CpSwapInstructionData::try_from_slice(&instruction.data).unwrap();
+        assert_eq!(data.instruction, [143, 190, 90, 218, 196, 30, 51, 222]);
+        assert_eq!((data.amount, data.other_amount), (1_000, 985));
+    }
+}
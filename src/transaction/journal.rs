@@ -0,0 +1,526 @@
+use crate::core::{Config, PoolType, QuoteResult, SwapError, SwapResult, TokenInfo, TransactionResult};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+/// One executed swap as recorded in the journal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: i64,
+    pub signature: String,
+    pub pool_type: PoolType,
+    pub pool_address: Pubkey,
+    /// Every pool swapped through, when the swap's events were decoded
+    #[serde(default)]
+    pub pools: Vec<Pubkey>,
+    pub token_in: TokenInfo,
+    pub token_out: TokenInfo,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub expected_amount_out: u64,
+    pub actual_slippage: f64,
+    /// Pool trading fee, in input token units
+    pub pool_fee: u64,
+    /// Network fee in lamports, priority fee included
+    pub transaction_fee: Option<u64>,
+    /// Priority fee in lamports
+    pub priority_fee: u64,
+    /// USD value of each side at execution, when the token price was known
+    pub value_in_usd: Option<f64>,
+    pub value_out_usd: Option<f64>,
+    /// The swap landed but its output could not be read back; amounts are
+    /// the quoted ones
+    #[serde(default)]
+    pub unresolved: bool,
+}
+
+/// USD value of `amount` raw units at `price`
+fn usd_value(amount: u64, decimals: u8, price: Option<f64>) -> Option<f64> {
+    price.map(|price| amount as f64 / 10f64.powi(decimals as i32) * price)
+}
+
+impl JournalEntry {
+    /// Record `result`, valuing each side at the given USD prices
+    pub fn new(
+        result: &TransactionResult,
+        token_in: TokenInfo,
+        token_out: TokenInfo,
+        price_in_usd: Option<f64>,
+        price_out_usd: Option<f64>,
+    ) -> Self {
+        Self {
+            timestamp: result.timestamp,
+            signature: result.signature.clone(),
+            pool_type: result.pool_type,
+            pool_address: result.pool_address,
+            pools: result.executions.iter().map(|execution| execution.pool_address).collect(),
+            value_in_usd: usd_value(result.amount_in, token_in.decimals, price_in_usd),
+            value_out_usd: usd_value(result.amount_out, token_out.decimals, price_out_usd),
+            token_in,
+            token_out,
+            amount_in: result.amount_in,
+            amount_out: result.amount_out,
+            expected_amount_out: result.expected_amount_out,
+            actual_slippage: result.actual_slippage,
+            pool_fee: result.fee_paid,
+            transaction_fee: result.transaction_fee,
+            // Charged on the requested limit, not on the units used
+            priority_fee: (result.compute_unit_price as u128 * result.compute_unit_limit as u128 / 1_000_000) as u64,
+            unresolved: false,
+        }
+    }
+
+    /// Record swap `signature`, which landed without a readable output, at
+    /// the `(amount_in, amount_out)` quoted across `quotes`
+    pub fn unresolved(
+        signature: String,
+        quotes: &[QuoteResult],
+        (amount_in, amount_out): (u64, u64),
+        token_in: TokenInfo,
+        token_out: TokenInfo,
+        price_in_usd: Option<f64>,
+        price_out_usd: Option<f64>,
+    ) -> Self {
+        let first = quotes.first().map(|quote| &quote.pool_info);
+        Self {
+            timestamp: chrono::Utc::now().timestamp(),
+            signature,
+            pool_type: first.map_or(PoolType::AMM, |pool| pool.pool_type),
+            pool_address: first.map_or_else(Pubkey::default, |pool| pool.address),
+            pools: quotes.iter().map(|quote| quote.pool_info.address).collect(),
+            value_in_usd: usd_value(amount_in, token_in.decimals, price_in_usd),
+            value_out_usd: usd_value(amount_out, token_out.decimals, price_out_usd),
+            // Fees of the quotes paid in the input token
+            pool_fee: quotes
+                .iter()
+                .filter(|quote| quote.token_in == token_in.mint)
+                .map(|quote| quote.fee)
+                .sum(),
+            token_in,
+            token_out,
+            amount_in,
+            amount_out,
+            expected_amount_out: amount_out,
+            actual_slippage: 0.0,
+            transaction_fee: None,
+            priority_fee: 0,
+            unresolved: true,
+        }
+    }
+
+    /// Pools the swap went through, or its first pool when unknown
+    pub fn traded_pools(&self) -> Vec<Pubkey> {
+        if self.pools.is_empty() {
+            vec![self.pool_address]
+        } else {
+            self.pools.clone()
+        }
+    }
+
+    /// USD value of the swap: what was received, else what was paid
+    fn proceeds_usd(&self) -> Option<f64> {
+        self.value_out_usd.or(self.value_in_usd)
+    }
+
+    /// USD cost of what was received: what was paid, else its own value
+    fn cost_usd(&self) -> Option<f64> {
+        self.value_in_usd.or(self.value_out_usd)
+    }
+}
+
+/// Which journal entries to report
+#[derive(Debug, Clone, Default)]
+pub struct JournalFilter {
+    /// Swaps paying or receiving this mint
+    pub token: Option<Pubkey>,
+    /// Unix timestamps bounding the swaps, inclusive
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub pool_type: Option<PoolType>,
+}
+
+impl JournalFilter {
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        self.token
+            .is_none_or(|token| entry.token_in.mint == token || entry.token_out.mint == token)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+            && self.pool_type.is_none_or(|pool_type| entry.pool_type == pool_type)
+    }
+}
+
+/// Append-only file of executed swaps, one JSON entry per line
+#[derive(Debug, Clone)]
+pub struct SwapJournal {
+    path: PathBuf,
+}
+
+impl SwapJournal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(&config.journal_path)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Append `entry` to the journal, creating it if needed
+    pub fn append(&self, entry: &JournalEntry) -> SwapResult<()> {
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(&self.path)?;
+        // Start on a fresh line if a previous write was cut short
+        if file.metadata()?.len() > 0 {
+            let mut last = [0u8];
+            file.seek(SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                line.insert(0, '\n');
+            }
+        }
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// Every entry in the journal, oldest first
+    ///
+    /// A missing journal is empty; lines that do not parse, such as one cut
+    /// short by a crash, are skipped with a warning.
+    pub fn entries(&self) -> SwapResult<Vec<JournalEntry>> {
+        let file = match fs::File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("Skipping journal line {} of {}: {}", number + 1, self.path.display(), e),
+            }
+        }
+        entries.sort_by_key(|entry: &JournalEntry| entry.timestamp);
+        Ok(entries)
+    }
+
+    /// Entries matching `filter`, oldest first
+    pub fn history(&self, filter: &JournalFilter) -> SwapResult<Vec<JournalEntry>> {
+        Ok(self.entries()?.into_iter().filter(|entry| filter.matches(entry)).collect())
+    }
+}
+
+/// Realized profit and loss on one token
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenPnl {
+    pub token: TokenInfo,
+    /// Raw amounts bought and sold in the reported period
+    pub bought: u64,
+    pub sold: u64,
+    /// Proceeds less FIFO cost basis of what was sold, in USD
+    pub realized_usd: f64,
+    /// Amount still held from journaled buys and its cost basis
+    pub open_amount: u64,
+    pub open_cost_usd: f64,
+    /// Amount sold without a journaled buy to match, counted at zero profit
+    pub unmatched_sold: u64,
+    /// Swaps skipped because neither side had a USD price
+    pub unpriced_swaps: usize,
+    /// Reported swaps counted at their quoted amounts because their output
+    /// could not be read back
+    pub unresolved_swaps: usize,
+}
+
+impl TokenPnl {
+    fn new(token: TokenInfo) -> Self {
+        Self {
+            token,
+            bought: 0,
+            sold: 0,
+            realized_usd: 0.0,
+            open_amount: 0,
+            open_cost_usd: 0.0,
+            unmatched_sold: 0,
+            unpriced_swaps: 0,
+            unresolved_swaps: 0,
+        }
+    }
+}
+
+/// Tokens bought at one cost, consumed oldest first
+struct Lot {
+    amount: u64,
+    cost_usd: f64,
+}
+
+/// PnL and open lots of `token`, added on first use
+fn book<'a>(books: &'a mut Vec<(TokenPnl, VecDeque<Lot>)>, token: &TokenInfo) -> &'a mut (TokenPnl, VecDeque<Lot>) {
+    let index = match books.iter().position(|(pnl, _)| pnl.token.mint == token.mint) {
+        Some(index) => index,
+        None => {
+            books.push((TokenPnl::new(token.clone()), VecDeque::new()));
+            books.len() - 1
+        }
+    };
+    &mut books[index]
+}
+
+/// Realized PnL per token over `entries` with FIFO cost basis
+///
+/// Every swap sells its input and buys its output at the swap's USD value.
+/// Cost basis is built from every swap up to `filter.until`, whatever its
+/// pool; only swaps matching the rest of the filter are reported, and token
+/// filters select which tokens are.
+pub fn realized_pnl(entries: &[JournalEntry], filter: &JournalFilter) -> Vec<TokenPnl> {
+    let basis_filter = JournalFilter {
+        until: filter.until,
+        ..Default::default()
+    };
+    let report_filter = JournalFilter {
+        token: None,
+        ..filter.clone()
+    };
+    let mut entries: Vec<&JournalEntry> = entries.iter().filter(|entry| basis_filter.matches(entry)).collect();
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    let mut books: Vec<(TokenPnl, VecDeque<Lot>)> = Vec::new();
+    for entry in entries {
+        let in_period = report_filter.matches(entry);
+        if in_period && entry.unresolved {
+            book(&mut books, &entry.token_in).0.unresolved_swaps += 1;
+            book(&mut books, &entry.token_out).0.unresolved_swaps += 1;
+        }
+        let (Some(proceeds), Some(cost)) = (entry.proceeds_usd(), entry.cost_usd()) else {
+            book(&mut books, &entry.token_in).0.unpriced_swaps += 1;
+            book(&mut books, &entry.token_out).0.unpriced_swaps += 1;
+            continue;
+        };
+
+        // Sell the input against its oldest lots
+        let (pnl, lots) = book(&mut books, &entry.token_in);
+        let mut remaining = entry.amount_in;
+        let mut matched_cost = 0.0;
+        while remaining > 0 {
+            let Some(lot) = lots.front_mut() else {
+                break;
+            };
+            let taken = remaining.min(lot.amount);
+            let cost = lot.cost_usd * taken as f64 / lot.amount as f64;
+            matched_cost += cost;
+            lot.cost_usd -= cost;
+            lot.amount -= taken;
+            remaining -= taken;
+            if lot.amount == 0 {
+                lots.pop_front();
+            }
+        }
+        if in_period {
+            let matched = entry.amount_in - remaining;
+            let matched_proceeds = proceeds * matched as f64 / entry.amount_in.max(1) as f64;
+            pnl.sold += entry.amount_in;
+            pnl.realized_usd += matched_proceeds - matched_cost;
+            pnl.unmatched_sold += remaining;
+        }
+
+        // Buy the output at what the input was worth
+        let (pnl, lots) = book(&mut books, &entry.token_out);
+        if entry.amount_out > 0 {
+            lots.push_back(Lot {
+                amount: entry.amount_out,
+                cost_usd: cost,
+            });
+        }
+        if in_period {
+            pnl.bought += entry.amount_out;
+        }
+    }
+
+    books
+        .into_iter()
+        .filter(|(pnl, _)| filter.token.is_none_or(|token| pnl.token.mint == token))
+        .map(|(mut pnl, lots)| {
+            pnl.open_amount = lots.iter().map(|lot| lot.amount).sum();
+            pnl.open_cost_usd = lots.iter().map(|lot| lot.cost_usd).sum();
+            pnl
+        })
+        .collect()
+}
+
+/// Unix timestamp of the start of `date` (YYYY-MM-DD), UTC
+pub fn parse_date(date: &str) -> SwapResult<i64> {
+    chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp())
+        .map_err(|e| SwapError::InvalidInput(format!("Invalid date '{}' (expected YYYY-MM-DD): {}", date, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(seed: u8, symbol: &str, decimals: u8) -> TokenInfo {
+        TokenInfo {
+            mint: Pubkey::new_from_array([seed; 32]),
+            symbol: symbol.to_string(),
+            decimals,
+            name: String::new(),
+        }
+    }
+
+    fn entry(timestamp: i64, token_in: &TokenInfo, amount_in: u64, token_out: &TokenInfo, amount_out: u64, usd: f64) -> JournalEntry {
+        JournalEntry {
+            timestamp,
+            signature: format!("sig{}", timestamp),
+            pool_type: PoolType::AMM,
+            pool_address: Pubkey::new_unique(),
+            pools: Vec::new(),
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            amount_in,
+            amount_out,
+            expected_amount_out: amount_out,
+            actual_slippage: 0.0,
+            pool_fee: 0,
+            transaction_fee: Some(5_000),
+            priority_fee: 0,
+            value_in_usd: Some(usd),
+            value_out_usd: Some(usd),
+            unresolved: false,
+        }
+    }
+
+    #[test]
+    fn test_fifo_realized_pnl() {
+        let (usdc, bonk) = (token(1, "USDC", 6), token(2, "BONK", 5));
+        let entries = vec![
+            entry(100, &usdc, 100_000_000, &bonk, 1_000, 100.0),
+            entry(200, &usdc, 300_000_000, &bonk, 1_000, 300.0),
+            // Sells the whole first lot and half of the second: 250 - (100 + 150)
+            entry(300, &bonk, 1_500, &usdc, 250_000_000, 250.0),
+            // The last 500 cost 150 and sell for 400
+            entry(400, &bonk, 500, &usdc, 400_000_000, 400.0),
+        ];
+
+        let filter = JournalFilter { token: Some(bonk.mint), ..Default::default() };
+        let pnl = realized_pnl(&entries, &filter);
+        assert_eq!(pnl.len(), 1);
+        assert_eq!((pnl[0].bought, pnl[0].sold, pnl[0].open_amount, pnl[0].unmatched_sold), (2_000, 2_000, 0, 0));
+        assert!((pnl[0].realized_usd - 250.0).abs() < 1e-9);
+
+        // Earlier swaps still set the basis of later sales
+        let filter = JournalFilter { token: Some(bonk.mint), since: Some(400), ..Default::default() };
+        let pnl = realized_pnl(&entries, &filter);
+        assert_eq!((pnl[0].bought, pnl[0].sold), (0, 500));
+        assert!((pnl[0].realized_usd - 250.0).abs() < 1e-9);
+
+        // Buys through other pools still set the basis of reported sales
+        let mut by_pool = entries.clone();
+        by_pool[3].pool_type = PoolType::CLMM;
+        let filter = JournalFilter { token: Some(bonk.mint), pool_type: Some(PoolType::CLMM), ..Default::default() };
+        let pnl = realized_pnl(&by_pool, &filter);
+        assert_eq!((pnl[0].bought, pnl[0].sold, pnl[0].unmatched_sold), (0, 500, 0));
+        assert!((pnl[0].realized_usd - 250.0).abs() < 1e-9);
+
+        // Swaps after the period neither report nor consume basis
+        let filter = JournalFilter { token: Some(bonk.mint), until: Some(300), ..Default::default() };
+        let pnl = realized_pnl(&entries, &filter);
+        assert_eq!((pnl[0].sold, pnl[0].open_amount), (1_500, 500));
+        assert!((pnl[0].open_cost_usd - 150.0).abs() < 1e-9);
+
+        // Selling more than was journaled leaves the rest unmatched
+        let pnl = realized_pnl(&entries[2..3], &JournalFilter { token: Some(bonk.mint), ..Default::default() });
+        assert_eq!((pnl[0].unmatched_sold, pnl[0].realized_usd), (1_500, 0.0));
+    }
+
+    #[test]
+    fn test_unresolved_swaps_recorded_at_quoted_amounts() {
+        let (usdc, bonk) = (token(1, "USDC", 6), token(2, "BONK", 5));
+        let pool = crate::core::fixtures::standard_pool(usdc.clone(), bonk.clone(), 1_000_000_000, 1_000_000_000);
+        let quote = QuoteResult {
+            pool_info: pool.clone(),
+            amount_in: 100_000_000,
+            amount_out: 1_000,
+            min_amount_out: 995,
+            max_amount_in: 100_000_000,
+            swap_mode: crate::core::SwapMode::ExactIn,
+            price_impact: 0.0,
+            fee: 250_000,
+            route: vec![pool.address],
+            token_in: usdc.mint,
+            token_out: bonk.mint,
+            tick_traversal: None,
+            execution_costs: None,
+        };
+
+        let bought = JournalEntry::unresolved(
+            "sig".to_string(),
+            &[quote],
+            (100_000_000, 1_000),
+            usdc.clone(),
+            bonk.clone(),
+            Some(1.0),
+            None,
+        );
+        assert!(bought.unresolved);
+        assert_eq!((bought.pool_type, bought.pool_address, bought.pools.clone()), (PoolType::Standard, pool.address, vec![pool.address]));
+        assert_eq!((bought.amount_in, bought.amount_out, bought.pool_fee), (100_000_000, 1_000, 250_000));
+        assert_eq!((bought.value_in_usd, bought.transaction_fee), (Some(100.0), None));
+
+        // Counted in PnL, and flagged on both tokens
+        let entries = vec![bought.clone(), entry(bought.timestamp + 1, &bonk, 1_000, &usdc, 150_000_000, 150.0)];
+        let pnl = realized_pnl(&entries, &JournalFilter { token: Some(bonk.mint), ..Default::default() });
+        assert_eq!((pnl[0].unresolved_swaps, pnl[0].unmatched_sold), (1, 0));
+        assert!((pnl[0].realized_usd - 50.0).abs() < 1e-9);
+
+        // Entries journaled before the flag existed are resolved
+        let mut line = serde_json::to_value(&entries[1]).unwrap();
+        line.as_object_mut().unwrap().remove("unresolved");
+        assert!(!serde_json::from_value::<JournalEntry>(line).unwrap().unresolved);
+    }
+
+    #[test]
+    fn test_journal_appends_and_filters() {
+        let path = std::env::temp_dir().join(format!("swap-journal-{}.jsonl", Pubkey::new_unique()));
+        let journal = SwapJournal::new(&path);
+        assert!(journal.entries().unwrap().is_empty());
+
+        let (sol, usdc) = (token(1, "SOL", 9), token(2, "USDC", 6));
+        let mut clmm = entry(200, &usdc, 150_000_000, &sol, 1_000_000_000, 150.0);
+        clmm.pool_type = PoolType::CLMM;
+        clmm.value_out_usd = None;
+        journal.append(&clmm).unwrap();
+        journal.append(&entry(100, &sol, 1_000_000_000, &usdc, 150_000_000, 150.0)).unwrap();
+        // A line cut short by a crash is skipped
+        OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{\"timestamp\":3").unwrap();
+
+        let late = entry(300, &sol, 1, &usdc, 1, 0.0);
+        journal.append(&late).unwrap();
+
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.iter().map(|entry| entry.timestamp).collect::<Vec<_>>(), vec![100, 200, 300]);
+        assert_eq!(entries[1], clmm);
+
+        let filter = JournalFilter { pool_type: Some(PoolType::CLMM), ..Default::default() };
+        assert_eq!(journal.history(&filter).unwrap(), vec![clmm.clone()]);
+        let filter = JournalFilter { since: Some(150), token: Some(usdc.mint), ..Default::default() };
+        assert_eq!(journal.history(&filter).unwrap(), vec![clmm, late]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
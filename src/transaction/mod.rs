@@ -3,13 +3,23 @@ pub mod stable_swap;
 pub mod clmm_swap;
 pub mod cp_swap;
 pub mod monitor;
+pub mod journal;
+pub mod priority_fee;
 pub mod wsol;
 pub mod alt;
 
 use crate::core::{
-    constants::{AMM_V4_PROGRAM, STABLE_PROGRAM, CLMM_PROGRAM},
-    PoolType, SwapError, SwapParams, SwapResult, TransactionResult,
+    constants::{
+        AMM_V4_PROGRAM, CLMM_PROGRAM, DEFAULT_COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT, STABLE_PROGRAM,
+        TOKEN_2022_PROGRAM,
+    },
+    events::SwapEvent,
+    PoolInfo, PoolType, QuoteResult, RouteSwapParams, SplitSwapParams, SwapError, SwapMode, SwapParams,
+    SwapExecution, SwapResult, TransactionResult,
 };
+use crate::discovery::PoolDiscovery;
+use crate::quotes::QuoteEngine;
+use borsh::BorshDeserialize;
 use chrono::Utc;
 use log::{debug, info, warn};
 use solana_client::nonblocking::rpc_client::RpcClient;
@@ -22,17 +32,20 @@ use solana_sdk::{
     transaction::{Transaction, VersionedTransaction},
     message::{VersionedMessage, v0},
     compute_budget::ComputeBudgetInstruction,
+    packet::PACKET_DATA_SIZE,
 };
 use solana_client::rpc_config::RpcTransactionConfig;
-use solana_transaction_status::UiTransactionEncoding;
-use alt::AltManager;
-use std::str::FromStr;
-use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
-
-// Token-2022 program ID
-const TOKEN_2022_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use priority_fee::compute_unit_limit_with_margin;
+use std::sync::Arc;
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+};
 
-pub use monitor::{TransactionMonitor, MonitorConfig, RetryConfig, BalanceChange};
+pub use monitor::{TransactionMonitor, MonitorConfig, RetryConfig, BalanceChange, OwnerBalanceChange};
+pub use alt::{AltManager, LookupTable};
+pub use journal::{JournalEntry, JournalFilter, SwapJournal, TokenPnl};
+pub use priority_fee::PriorityFeeEstimator;
 
 /// Transaction version preference
 #[derive(Debug, Clone, Copy)]
@@ -47,6 +60,80 @@ impl Default for TransactionVersion {
     }
 }
 
+/// A confirmed swap transaction and how it was sent
+struct SentTransaction {
+    signature: Signature,
+    retry_attempts: u32,
+    confirmation_time_ms: u64,
+    compute_units_consumed: Option<u64>,
+    compute_unit_limit: u32,
+    compute_unit_price: u64,
+}
+
+/// What a confirmed swap transaction delivered
+struct Settlement {
+    amount_out: u64,
+    actual_slippage: f64,
+    transaction_fee: Option<u64>,
+    finalized: bool,
+    executions: Vec<SwapExecution>,
+}
+
+/// Refreshes pool state and requotes a swap before it is sent
+struct Requoter {
+    discovery: Arc<PoolDiscovery>,
+    quote_engine: Arc<QuoteEngine>,
+    max_quote_age_slots: u64,
+    threshold_bps: u16,
+}
+
+impl Requoter {
+    /// Pools behind `quotes`, with the stale ones read again; None when
+    /// every quote is still fresh
+    async fn current_pools(&self, quotes: &[QuoteResult], current_slot: u64) -> SwapResult<Option<Vec<PoolInfo>>> {
+        if !quotes.iter().any(|quote| quote.is_stale(current_slot, self.max_quote_age_slots)) {
+            return Ok(None);
+        }
+
+        let mut pools = Vec::with_capacity(quotes.len());
+        for quote in quotes {
+            if quote.is_stale(current_slot, self.max_quote_age_slots) {
+                pools.push(self.discovery.refresh_pool(&quote.pool_info).await?);
+            } else {
+                pools.push(quote.pool_info.clone());
+            }
+        }
+        Ok(Some(pools))
+    }
+
+    /// Refuse a requote worse than the confirmed (input, output) by more
+    /// than the threshold
+    fn check_drift(
+        &self,
+        swap_mode: SwapMode,
+        drift_bps: u64,
+        confirmed: (u64, u64),
+        requoted: (u64, u64),
+    ) -> SwapResult<()> {
+        let (expected, requoted) = match swap_mode {
+            SwapMode::ExactIn => (confirmed.1, requoted.1),
+            SwapMode::ExactOut => (confirmed.0, requoted.0),
+        };
+
+        if drift_bps > self.threshold_bps as u64 {
+            warn!("Quote moved {} bps: {} -> {}", drift_bps, expected, requoted);
+            return Err(SwapError::QuoteMoved {
+                expected,
+                requoted,
+                drift_bps,
+            });
+        }
+
+        info!("Requoted: {} -> {} ({} bps worse)", expected, requoted, drift_bps);
+        Ok(())
+    }
+}
+
 /// Transaction executor for different pool types
 pub struct TransactionExecutor {
     rpc_client: RpcClient,
@@ -55,6 +142,10 @@ pub struct TransactionExecutor {
     transaction_version: TransactionVersion,
     alt_manager: Option<AltManager>,
     use_alts: bool,
+    /// Swap AMM V4 pools through the v2 instructions, without OpenBook accounts
+    amm_swap_v2: bool,
+    priority_fee: PriorityFeeEstimator,
+    requoter: Option<Requoter>,
 }
 
 impl TransactionExecutor {
@@ -77,6 +168,9 @@ impl TransactionExecutor {
             transaction_version: TransactionVersion::default(),
             alt_manager: None,
             use_alts: false,
+            amm_swap_v2: false,
+            priority_fee: PriorityFeeEstimator::default(),
+            requoter: None,
         }
     }
 
@@ -104,6 +198,9 @@ impl TransactionExecutor {
             transaction_version: TransactionVersion::default(),
             alt_manager: None,
             use_alts: false,
+            amm_swap_v2: false,
+            priority_fee: PriorityFeeEstimator::default(),
+            requoter: None,
         }
     }
 
@@ -112,22 +209,90 @@ impl TransactionExecutor {
         self.transaction_version = version;
     }
 
-    /// Enable ALT usage
-    pub async fn enable_alts(&mut self, rpc_url: String) {
+    /// Enable ALT usage: the wallet's own tables plus `lookup_tables`
+    pub async fn enable_alts(&mut self, rpc_url: String, lookup_tables: Vec<Pubkey>) {
         let rpc_client = std::sync::Arc::new(RpcClient::new_with_commitment(
             rpc_url,
             CommitmentConfig::confirmed(),
         ));
-        self.alt_manager = Some(AltManager::new(rpc_client));
+        self.alt_manager = Some(AltManager::new(rpc_client, lookup_tables, Some(self.keypair.pubkey())));
         self.use_alts = true;
         info!("Address Lookup Tables enabled");
     }
 
+    /// Add the accounts of the `traded` pools that `entries` show swapped
+    /// through at least `min_trades` times to the wallet's lookup tables
+    pub async fn add_frequent_pools_to_alts(
+        &self,
+        entries: &[JournalEntry],
+        traded: &[Pubkey],
+        min_trades: usize,
+    ) -> SwapResult<Vec<Signature>> {
+        let frequent = alt::frequent_pools(entries, min_trades);
+        let pools: Vec<Pubkey> = traded
+            .iter()
+            .filter(|pool| frequent.iter().any(|(address, _)| address == *pool))
+            .copied()
+            .collect();
+        if pools.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let standalone;
+        let manager = match &self.alt_manager {
+            Some(manager) => manager,
+            None => {
+                let rpc_client = Arc::new(RpcClient::new_with_commitment(
+                    self.rpc_client.url(),
+                    CommitmentConfig::confirmed(),
+                ));
+                standalone = AltManager::new(rpc_client, Vec::new(), Some(self.keypair.pubkey()));
+                &standalone
+            }
+        };
+
+        let mut signatures = Vec::new();
+        for pool in pools {
+            let accounts = manager.pool_accounts(&pool).await?;
+            debug!("Adding {} accounts of frequent pool {} to lookup tables", accounts.len(), pool);
+            signatures.extend(manager.add_addresses(&self.keypair, &accounts).await?);
+        }
+        Ok(signatures)
+    }
+
+    /// Requote swaps whose quote is older than `max_quote_age_slots` before
+    /// sending them, refusing when the output moved by more than `threshold_bps`
+    pub fn enable_requote(
+        &mut self,
+        discovery: Arc<PoolDiscovery>,
+        quote_engine: Arc<QuoteEngine>,
+        max_quote_age_slots: u64,
+        threshold_bps: u16,
+    ) {
+        self.requoter = Some(Requoter {
+            discovery,
+            quote_engine,
+            max_quote_age_slots,
+            threshold_bps,
+        });
+    }
+
     /// Set ALT usage preference
     pub fn set_use_alts(&mut self, use_alts: bool) {
         self.use_alts = use_alts;
     }
 
+    /// Swap every AMM V4 pool through the v2 instructions; pools without an
+    /// OpenBook market always use them
+    pub fn set_amm_swap_v2(&mut self, amm_swap_v2: bool) {
+        self.amm_swap_v2 = amm_swap_v2;
+    }
+
+    /// Set how the compute unit price is chosen
+    pub fn set_priority_fee(&mut self, priority_fee: PriorityFeeEstimator) {
+        self.priority_fee = priority_fee;
+    }
+
     /// Create compute budget instructions
     fn create_compute_budget_instructions(compute_units: Option<u32>, priority_fee: Option<u64>) -> Vec<Instruction> {
         let mut instructions = vec![];
@@ -154,23 +319,19 @@ impl TransactionExecutor {
     ) -> SwapResult<VersionedTransaction> {
         // Get recent blockhash
         let recent_blockhash = self.rpc_client.get_latest_blockhash().await
-            .map_err(SwapError::RpcError)?;
-
-        // Add compute budget instructions at the beginning
-        let mut all_instructions = Self::create_compute_budget_instructions(
-            Some(400_000), // Default compute units
-            Some(1_000),   // Default priority fee (1000 microlamports)
-        );
-        all_instructions.extend(instructions);
+            .map_err(SwapError::from)?;
+        let all_instructions = instructions;
 
         // Prepare ALT lookups if enabled
         let alt_accounts = if self.use_alts && self.alt_manager.is_some() {
-            // Extract all unique accounts from instructions
+            // Signers and invoked programs must stay in the message itself
+            let program_ids: Vec<Pubkey> = all_instructions.iter().map(|ix| ix.program_id).collect();
             let mut accounts = Vec::new();
             for ix in &all_instructions {
-                accounts.push(ix.program_id);
                 for meta in &ix.accounts {
-                    accounts.push(meta.pubkey);
+                    if !meta.is_signer && meta.pubkey != *payer && !program_ids.contains(&meta.pubkey) {
+                        accounts.push(meta.pubkey);
+                    }
                 }
             }
             accounts.sort();
@@ -214,6 +375,7 @@ impl TransactionExecutor {
         // Create versioned transaction
         let transaction = VersionedTransaction::try_new(versioned_message, signers)
             .map_err(|e| SwapError::Other(format!("Failed to create versioned transaction: {}", e)))?;
+        ensure_fits_packet(&transaction)?;
 
         Ok(transaction)
     }
@@ -235,23 +397,333 @@ impl TransactionExecutor {
 
     /// Execute a swap transaction
     pub async fn execute_swap(&self, params: SwapParams) -> SwapResult<TransactionResult> {
+        let params = self.refresh_quote(params).await?;
+
         info!(
             "Executing swap on {:?} pool {}",
             params.quote.pool_info.pool_type, params.quote.pool_info.address
         );
-        info!("Swap details: {} {} -> {} (amount: {})", 
+        info!("Swap details: {} {} -> {} (amount: {}, max in: {}, {})", 
             params.token_in, 
             params.quote.pool_info.token_a.symbol,
             params.quote.pool_info.token_b.symbol,
-            params.quote.amount_in
+            params.quote.amount_in,
+            params.quote.max_amount_in,
+            params.quote.swap_mode
         );
 
+        // Build pool-specific instruction
+        let swap_instruction = self.build_swap_instruction(&params).await?;
+
+        let sent = self
+            .send_swap_transaction(
+                params.token_in,
+                params.token_out,
+                &[],
+                params.quote.max_amount_in,
+                vec![swap_instruction],
+                self.transaction_version,
+            )
+            .await?;
+
+        let settlement = self
+            .settle_swap(&sent.signature, &params.token_out, params.quote.amount_out, std::slice::from_ref(&params.quote))
+            .await?;
+
+        Ok(TransactionResult {
+            signature: sent.signature.to_string(),
+            pool_type: params.quote.pool_info.pool_type,
+            pool_address: params.quote.pool_info.address,
+            token_in: params.token_in,
+            token_out: params.token_out,
+            amount_in: params.quote.amount_in,
+            amount_out: settlement.amount_out,
+            expected_amount_out: params.quote.amount_out,
+            actual_slippage: settlement.actual_slippage,
+            fee_paid: params.quote.fee,
+            timestamp: Utc::now().timestamp(),
+            retry_attempts: sent.retry_attempts,
+            confirmation_time_ms: sent.confirmation_time_ms,
+            finalized: settlement.finalized,
+            transaction_fee: settlement.transaction_fee,
+            compute_units_consumed: sent.compute_units_consumed,
+            compute_unit_limit: sent.compute_unit_limit,
+            compute_unit_price: sent.compute_unit_price,
+            executions: settlement.executions,
+        })
+    }
+
+    /// Quote the same trade again against freshly read pool state
+    pub async fn requote(&self, quote: &QuoteResult, slippage_bps: u16) -> SwapResult<QuoteResult> {
+        let requoter = self
+            .requoter
+            .as_ref()
+            .ok_or_else(|| SwapError::ConfigError("Requoting is not enabled".to_string()))?;
+
+        let pool = requoter.discovery.refresh_pool(&quote.pool_info).await?;
+        requoter.quote_engine.requote(quote, &pool, slippage_bps).await
+    }
+
+    /// Requote a stale quote before sending it
+    ///
+    /// Fails with [`SwapError::QuoteMoved`] when the fresh quote is worse than
+    /// the confirmed one by more than the requote threshold.
+    async fn refresh_quote(&self, mut params: SwapParams) -> SwapResult<SwapParams> {
+        let Some(requoter) = &self.requoter else {
+            return Ok(params);
+        };
+
+        let current_slot = self.rpc_client.get_slot().await.map_err(SwapError::from)?;
+        if !params.quote.is_stale(current_slot, requoter.max_quote_age_slots) {
+            debug!(
+                "Quote from slot {} is fresh at slot {}",
+                params.quote.context_slot(),
+                current_slot
+            );
+            return Ok(params);
+        }
+
+        let requote = self.requote(&params.quote, params.slippage_bps).await?;
+        requoter.check_drift(
+            params.quote.swap_mode,
+            params.quote.drift_bps(&requote),
+            (params.quote.amount_in, params.quote.amount_out),
+            (requote.amount_in, requote.amount_out),
+        )?;
+        params.quote = requote;
+        Ok(params)
+    }
+
+    /// Requote a split whose legs include a stale quote before sending it,
+    /// checking the whole order against the requote threshold
+    async fn refresh_split_quote(&self, mut params: SplitSwapParams) -> SwapResult<SplitSwapParams> {
+        let Some(requoter) = &self.requoter else {
+            return Ok(params);
+        };
+
+        let current_slot = self.rpc_client.get_slot().await.map_err(SwapError::from)?;
+        let Some(pools) = requoter.current_pools(&params.quote.legs, current_slot).await? else {
+            debug!("Split legs are fresh at slot {}", current_slot);
+            return Ok(params);
+        };
+
+        let requote = requoter
+            .quote_engine
+            .requote_split(&params.quote, &pools, params.slippage_bps)
+            .await?;
+        requoter.check_drift(
+            params.quote.swap_mode,
+            params.quote.drift_bps(&requote),
+            (params.quote.amount_in, params.quote.amount_out),
+            (requote.amount_in, requote.amount_out),
+        )?;
+        params.quote = requote;
+        Ok(params)
+    }
+
+    /// Requote a route with a stale hop before sending it, chaining every
+    /// hop again and checking the route end to end against the requote threshold
+    async fn refresh_route_quote(&self, mut params: RouteSwapParams) -> SwapResult<RouteSwapParams> {
+        let Some(requoter) = &self.requoter else {
+            return Ok(params);
+        };
+
+        let current_slot = self.rpc_client.get_slot().await.map_err(SwapError::from)?;
+        let Some(pools) = requoter.current_pools(&params.quote.hops, current_slot).await? else {
+            debug!("Route hops are fresh at slot {}", current_slot);
+            return Ok(params);
+        };
+
+        let requote = requoter
+            .quote_engine
+            .requote_route(&params.quote, &pools, params.slippage_bps)
+            .await?;
+        requoter.check_drift(
+            params.quote.swap_mode,
+            params.quote.drift_bps(&requote),
+            (params.quote.amount_in, params.quote.amount_out),
+            (requote.amount_in, requote.amount_out),
+        )?;
+        params.quote = requote;
+        Ok(params)
+    }
+
+    /// Execute a split swap, one instruction per leg, in a single transaction
+    ///
+    /// The result reports the pool of the largest leg.
+    pub async fn execute_split_swap(&self, params: SplitSwapParams) -> SwapResult<TransactionResult> {
+        let params = self.refresh_split_quote(params).await?;
+        let primary = params
+            .quote
+            .primary_leg()
+            .ok_or_else(|| SwapError::InvalidAmount("Split quote has no legs".to_string()))?
+            .clone();
+
+        info!(
+            "Executing split swap over {} pools (amount in: {}, max in: {}, {})",
+            params.quote.legs.len(),
+            params.quote.amount_in,
+            params.quote.max_amount_in,
+            params.quote.swap_mode
+        );
+
+        let mut swap_instructions = Vec::with_capacity(params.quote.legs.len());
+        for leg in &params.quote.legs {
+            info!(
+                "Leg: {:?} pool {} ({} -> {})",
+                leg.pool_info.pool_type, leg.pool_info.address, leg.amount_in, leg.amount_out
+            );
+            let leg_params = SwapParams {
+                quote: leg.clone(),
+                user_pubkey: params.user_pubkey,
+                slippage_bps: params.slippage_bps,
+                token_in: params.token_in,
+                token_out: params.token_out,
+            };
+            swap_instructions.push(self.build_swap_instruction(&leg_params).await?);
+        }
+
+        let sent = self
+            .send_swap_transaction(
+                params.token_in,
+                params.token_out,
+                &[],
+                params.quote.max_amount_in,
+                swap_instructions,
+                self.transaction_version,
+            )
+            .await?;
+
+        let settlement = self
+            .settle_swap(&sent.signature, &params.token_out, params.quote.amount_out, &params.quote.legs)
+            .await?;
+
+        Ok(TransactionResult {
+            signature: sent.signature.to_string(),
+            pool_type: primary.pool_info.pool_type,
+            pool_address: primary.pool_info.address,
+            token_in: params.token_in,
+            token_out: params.token_out,
+            amount_in: params.quote.amount_in,
+            amount_out: settlement.amount_out,
+            expected_amount_out: params.quote.amount_out,
+            actual_slippage: settlement.actual_slippage,
+            fee_paid: params.quote.fee,
+            timestamp: Utc::now().timestamp(),
+            retry_attempts: sent.retry_attempts,
+            confirmation_time_ms: sent.confirmation_time_ms,
+            finalized: settlement.finalized,
+            transaction_fee: settlement.transaction_fee,
+            compute_units_consumed: sent.compute_units_consumed,
+            compute_unit_limit: sent.compute_unit_limit,
+            compute_unit_price: sent.compute_unit_price,
+            executions: settlement.executions,
+        })
+    }
+
+    /// Execute a multi-hop route atomically in one v0 transaction
+    ///
+    /// The result reports the pool of the first hop.
+    pub async fn execute_route_swap(&self, params: RouteSwapParams) -> SwapResult<TransactionResult> {
+        let params = self.refresh_route_quote(params).await?;
+        let first_hop = params
+            .quote
+            .hops
+            .first()
+            .ok_or_else(|| SwapError::InvalidAmount("Route has no hops".to_string()))?
+            .clone();
+
+        info!(
+            "Executing {}-hop route {} -> {} (amount in: {}, max in: {}, {})",
+            params.quote.hops.len(),
+            params.quote.token_in,
+            params.quote.token_out,
+            params.quote.amount_in,
+            params.quote.max_amount_in,
+            params.quote.swap_mode
+        );
+
+        let mut swap_instructions = Vec::with_capacity(params.quote.hops.len());
+        for hop in &params.quote.hops {
+            info!(
+                "Hop: {:?} pool {} {} -> {} ({} -> {})",
+                hop.pool_info.pool_type, hop.pool_info.address, hop.token_in, hop.token_out, hop.amount_in, hop.amount_out
+            );
+            let hop_params = SwapParams {
+                quote: hop.clone(),
+                user_pubkey: params.user_pubkey,
+                slippage_bps: params.slippage_bps,
+                token_in: hop.token_in,
+                token_out: hop.token_out,
+            };
+            swap_instructions.push(self.build_swap_instruction(&hop_params).await?);
+        }
+
+        // Hops must land together, and multi-hop account lists need v0
+        if matches!(self.transaction_version, TransactionVersion::Legacy) && params.quote.hops.len() > 1 {
+            warn!("Multi-hop routes are always sent as v0 transactions, ignoring legacy preference");
+        }
+        let version = if params.quote.hops.len() > 1 {
+            TransactionVersion::V0
+        } else {
+            self.transaction_version
+        };
+
+        let sent = self
+            .send_swap_transaction(
+                params.quote.token_in,
+                params.quote.token_out,
+                &params.quote.intermediate_mints(),
+                params.quote.max_amount_in,
+                swap_instructions,
+                version,
+            )
+            .await?;
+
+        let settlement = self
+            .settle_swap(&sent.signature, &params.quote.token_out, params.quote.amount_out, &params.quote.hops)
+            .await?;
+
+        Ok(TransactionResult {
+            signature: sent.signature.to_string(),
+            pool_type: first_hop.pool_info.pool_type,
+            pool_address: first_hop.pool_info.address,
+            token_in: params.quote.token_in,
+            token_out: params.quote.token_out,
+            amount_in: params.quote.amount_in,
+            amount_out: settlement.amount_out,
+            expected_amount_out: params.quote.amount_out,
+            actual_slippage: settlement.actual_slippage,
+            fee_paid: first_hop.fee,
+            timestamp: Utc::now().timestamp(),
+            retry_attempts: sent.retry_attempts,
+            confirmation_time_ms: sent.confirmation_time_ms,
+            finalized: settlement.finalized,
+            transaction_fee: settlement.transaction_fee,
+            compute_units_consumed: sent.compute_units_consumed,
+            compute_unit_limit: sent.compute_unit_limit,
+            compute_unit_price: sent.compute_unit_price,
+            executions: settlement.executions,
+        })
+    }
+
+    /// Wrap the swap instructions with wSOL and ATA handling, then simulate,
+    /// sign, send and confirm them as one transaction
+    ///
+    /// The compute unit limit is the simulated usage plus a margin.
+    async fn send_swap_transaction(
+        &self,
+        token_in_mint: Pubkey,
+        token_out_mint: Pubkey,
+        intermediate_mints: &[Pubkey],
+        max_amount_in: u64,
+        swap_instructions: Vec<Instruction>,
+        version: TransactionVersion,
+    ) -> SwapResult<SentTransaction> {
+        // Compute budget instructions are prepended once the swap is measured
+        let mut instructions = Vec::new();
+
         // Check and create associated token accounts if needed
-        let mut instructions = vec![];
-        
-        // Get token mints
-        let token_in_mint = params.token_in;
-        let token_out_mint = params.token_out;
         let user_pubkey = self.keypair.pubkey();
         
         // Native SOL mint
@@ -260,11 +732,12 @@ impl TransactionExecutor {
         // Handle native SOL wrapping
         if token_in_mint == native_sol_mint {
             info!("Input token is native SOL, checking if wrapping is needed");
+            // Exact-out swaps may spend up to the slippage-adjusted maximum
             let (needs_wrapping, _amount_to_wrap, wrap_instructions) = 
                 wsol::check_and_prepare_wsol_wrapping(
                     &self.rpc_client,
                     &user_pubkey,
-                    params.quote.amount_in,
+                    max_amount_in,
                 ).await?;
             
             if needs_wrapping {
@@ -273,34 +746,33 @@ impl TransactionExecutor {
             }
         }
         
-        // Check if user has associated token account for output token
-        let user_out_ata = get_associated_token_address(&user_pubkey, &token_out_mint);
-        let out_ata_exists = self.rpc_client.get_account(&user_out_ata).await.is_ok();
-        info!("Output ATA {} exists: {}", user_out_ata, out_ata_exists);
-        
-        if !out_ata_exists {
-            debug!("Creating associated token account for output token");
-            
-            // Determine the correct token program for the output mint
-            let token_program = self.get_token_program_for_mint(&token_out_mint).await?;
-            info!("Output token {} uses program: {} (Token-2022: {})", 
-                token_out_mint, 
-                token_program,
-                token_program == Pubkey::from_str(TOKEN_2022_PROGRAM_ID).unwrap()
-            );
-            
-            let create_ata_ix = create_associated_token_account(
-                &user_pubkey,
-                &user_pubkey,
-                &token_out_mint,
-                &token_program,
-            );
-            instructions.push(create_ata_ix);
+        // Check if user has associated token accounts for intermediate and output tokens
+        for mint in intermediate_mints.iter().chain(std::iter::once(&token_out_mint)) {
+            // Token-2022 accounts live at a different associated address
+            let token_program = self.get_token_program_for_mint(mint).await?;
+            let user_ata = get_associated_token_address_with_program_id(&user_pubkey, mint, &token_program);
+            let ata_exists = self.rpc_client.get_account(&user_ata).await.is_ok();
+            info!("ATA {} for {} exists: {}", user_ata, mint, ata_exists);
+
+            if !ata_exists {
+                debug!("Creating associated token account for {}", mint);
+                info!("Token {} uses program: {} (Token-2022: {})",
+                    mint,
+                    token_program,
+                    token_program == *TOKEN_2022_PROGRAM
+                );
+
+                let create_ata_ix = create_associated_token_account(
+                    &user_pubkey,
+                    &user_pubkey,
+                    mint,
+                    &token_program,
+                );
+                instructions.push(create_ata_ix);
+            }
         }
 
-        // Build pool-specific instruction
-        let swap_instruction = self.build_swap_instruction(&params).await?;
-        instructions.push(swap_instruction);
+        instructions.extend(swap_instructions.iter().cloned());
         
         // Handle native SOL unwrapping if output is SOL
         if token_out_mint == native_sol_mint {
@@ -309,9 +781,29 @@ impl TransactionExecutor {
             instructions.push(unwrap_ix);
         }
 
+        // Size the limit from a simulation, then price it by contention on
+        // the swap's accounts
+        let compute_units_consumed = self.simulate_compute_units(&instructions, version).await?;
+        let compute_unit_limit =
+            compute_units_consumed.map_or(DEFAULT_COMPUTE_UNIT_LIMIT, compute_unit_limit_with_margin);
+        info!(
+            "Compute units: {} simulated, limit {}",
+            compute_units_consumed.map_or("unknown".to_string(), |units| units.to_string()),
+            compute_unit_limit
+        );
+        let compute_unit_price = self
+            .priority_fee
+            .compute_unit_price(&self.rpc_client, &swap_instructions, compute_unit_limit)
+            .await;
+        let instructions: Vec<Instruction> =
+            Self::create_compute_budget_instructions(Some(compute_unit_limit), Some(compute_unit_price))
+                .into_iter()
+                .chain(instructions)
+                .collect();
+
         // Create transaction based on version preference
         let start_time = std::time::Instant::now();
-        let (signature, retry_attempts) = match self.transaction_version {
+        let (signature, retry_attempts) = match version {
             TransactionVersion::V0 => {
                 info!("Creating v0 transaction");
                 let transaction = self.build_versioned_transaction(
@@ -320,20 +812,6 @@ impl TransactionExecutor {
                     &[&self.keypair],
                 ).await?;
 
-                // Simulate versioned transaction first
-                debug!("Simulating v0 transaction...");
-                match self.rpc_client.simulate_transaction(&transaction).await {
-                    Ok(result) => {
-                        if let Some(err) = result.value.err {
-                            return Err(SwapError::SimulationFailed(format!("{:?}", err)));
-                        }
-                        debug!("Simulation successful");
-                    }
-                    Err(e) => {
-                        return Err(SwapError::SimulationFailed(e.to_string()));
-                    }
-                }
-
                 // Send versioned transaction with monitoring and retry logic
                 info!("Sending v0 transaction with monitoring and retry...");
                 
@@ -353,20 +831,6 @@ impl TransactionExecutor {
                 );
                 transaction.sign(&[&self.keypair], recent_blockhash);
 
-                // Simulate transaction first
-                debug!("Simulating legacy transaction...");
-                match self.rpc_client.simulate_transaction(&transaction).await {
-                    Ok(result) => {
-                        if let Some(err) = result.value.err {
-                            return Err(SwapError::SimulationFailed(format!("{:?}", err)));
-                        }
-                        debug!("Simulation successful");
-                    }
-                    Err(e) => {
-                        return Err(SwapError::SimulationFailed(e.to_string()));
-                    }
-                }
-
                 // Send transaction with monitoring and retry logic
                 info!("Sending legacy transaction with monitoring and retry...");
                 
@@ -379,25 +843,72 @@ impl TransactionExecutor {
         let confirmation_time = start_time.elapsed().as_millis() as u64;
         info!("Transaction confirmed in {}ms: {}", confirmation_time, signature);
 
+        Ok(SentTransaction {
+            signature,
+            retry_attempts,
+            confirmation_time_ms: confirmation_time,
+            compute_units_consumed,
+            compute_unit_limit,
+            compute_unit_price,
+        })
+    }
+
+    /// Simulate `instructions` under the maximum compute unit limit and
+    /// return the units they consumed, if the RPC reports it
+    async fn simulate_compute_units(
+        &self,
+        instructions: &[Instruction],
+        version: TransactionVersion,
+    ) -> SwapResult<Option<u64>> {
+        // Same instruction count as the real transaction; the price does not change usage
+        let mut simulated = Self::create_compute_budget_instructions(Some(MAX_COMPUTE_UNIT_LIMIT), Some(0));
+        simulated.extend_from_slice(instructions);
+
+        debug!("Simulating {:?} transaction...", version);
+        let payer = self.keypair.pubkey();
+        let result = match version {
+            TransactionVersion::V0 => {
+                let transaction = self.build_versioned_transaction(simulated, &payer, &[&self.keypair]).await?;
+                self.rpc_client.simulate_transaction(&transaction).await
+            }
+            TransactionVersion::Legacy => {
+                let recent_blockhash = self.rpc_client.get_latest_blockhash().await?;
+                let transaction =
+                    Transaction::new_signed_with_payer(&simulated, Some(&payer), &[&self.keypair], recent_blockhash);
+                ensure_fits_packet(&transaction)?;
+                self.rpc_client.simulate_transaction(&transaction).await
+            }
+        }
+        .map_err(|e| SwapError::SimulationFailed(e.to_string()))?;
+
+        if let Some(err) = result.value.err {
+            return Err(SwapError::SimulationFailed(format!("{:?}", err)));
+        }
+        debug!("Simulation successful");
+        Ok(result.value.units_consumed)
+    }
+
+    /// Look up what a confirmed swap actually delivered
+    ///
+    /// `quotes` are the transaction's per-pool swaps in instruction order.
+    async fn settle_swap(
+        &self,
+        signature: &Signature,
+        token_out_mint: &Pubkey,
+        expected_amount_out: u64,
+        quotes: &[QuoteResult],
+    ) -> SwapResult<Settlement> {
         // Get transaction details to calculate actual slippage
-        let actual_amount_out = self.get_actual_output_amount(&signature).await?;
+        let (actual_amount_out, executions) = self.get_actual_output_amount(signature, token_out_mint, quotes).await?;
         info!("Got actual_amount_out from transaction: {}", actual_amount_out);
         
-        // If we couldn't parse the actual output, use the expected amount as fallback
-        let actual_amount_out = if actual_amount_out == 0 {
-            warn!("Could not parse actual output amount, using expected amount");
-            params.quote.amount_out
-        } else {
-            actual_amount_out
-        };
-        
         let actual_slippage = calculate_actual_slippage(
-            params.quote.amount_out,
+            expected_amount_out,
             actual_amount_out,
         );
 
         // Get transaction fee
-        let transaction_fee = match monitor::utils::calculate_transaction_fee(&self.rpc_client, &signature).await {
+        let transaction_fee = match monitor::utils::calculate_transaction_fee(&self.rpc_client, signature).await {
             Ok(fee) => Some(fee),
             Err(e) => {
                 warn!("Could not get transaction fee: {}", e);
@@ -406,25 +917,17 @@ impl TransactionExecutor {
         };
 
         // Check if transaction is finalized
-        let finalized = match monitor::utils::is_transaction_successful(&self.rpc_client, &signature).await {
+        let finalized = match monitor::utils::is_transaction_successful(&self.rpc_client, signature).await {
             Ok(success) => success,
             Err(_) => false,
         };
 
-        Ok(TransactionResult {
-            signature: signature.to_string(),
-            pool_type: params.quote.pool_info.pool_type,
-            pool_address: params.quote.pool_info.address,
-            amount_in: params.quote.amount_in,
+        Ok(Settlement {
             amount_out: actual_amount_out,
-            expected_amount_out: params.quote.amount_out,
             actual_slippage,
-            fee_paid: params.quote.fee,
-            timestamp: Utc::now().timestamp(),
-            retry_attempts,
-            confirmation_time_ms: confirmation_time,
-            finalized,
             transaction_fee,
+            finalized,
+            executions,
         })
     }
 
@@ -456,16 +959,18 @@ impl TransactionExecutor {
             pool_state.serum_market
         );
         
-        // Check if serum market is a placeholder
-        let serum_market_str = pool_state.serum_market.to_string();
-        let market_data = if serum_market_str.starts_with("11111111") {
-            info!("Pool has placeholder serum market, using empty data");
-            vec![0u8; 388] // Minimum size for serum market parsing
-        } else {
-            self.rpc_client
-                .get_account_data(&pool_state.serum_market)
-                .await?
-        };
+        // The v2 instructions need no OpenBook market
+        if self.amm_swap_v2 || crate::core::is_placeholder_market(&pool_state.serum_market) {
+            return amm_swap::build_amm_swap_v2_instruction(
+                params,
+                &self.keypair.pubkey(),
+                &AMM_V4_PROGRAM,
+                &pool_data,
+            ).await;
+        }
+        let market_data = self.rpc_client
+            .get_account_data(&pool_state.serum_market)
+            .await?;
             
         // Build the swap instruction
         amm_swap::build_amm_swap_instruction(
@@ -484,14 +989,26 @@ impl TransactionExecutor {
             .rpc_client
             .get_account(&params.quote.pool_info.address)
             .await
-            .map_err(SwapError::RpcError)?
+            .map_err(SwapError::from)?
             .data;
-        
+
+        // The swap passes through the pool's OpenBook market accounts
+        let pool_state = crate::core::layouts::StablePoolState::from_bytes(&pool_data)
+            .map_err(SwapError::ParseError)?;
+        let market_data = if crate::core::is_placeholder_market(&pool_state.market) {
+            Vec::new()
+        } else {
+            self.rpc_client
+                .get_account_data(&pool_state.market)
+                .await?
+        };
+
         stable_swap::build_stable_swap_instruction(
             params,
             &self.keypair.pubkey(),
             &STABLE_PROGRAM,
             &pool_data,
+            &market_data,
         ).await
     }
 
@@ -502,14 +1019,28 @@ impl TransactionExecutor {
             .rpc_client
             .get_account(&params.quote.pool_info.address)
             .await
-            .map_err(SwapError::RpcError)?
+            .map_err(SwapError::from)?
             .data;
-        
+
+        // Token programs, bitmap extension and tick arrays in the swap direction
+        let pool_state = crate::core::layouts::ClmmPoolState::try_from_slice(&pool_data)
+            .map_err(|e| SwapError::ParseError(e.to_string()))?;
+        let swap_accounts = clmm_swap::load_clmm_swap_accounts(
+            &self.rpc_client,
+            &params.quote.pool_info.address,
+            &CLMM_PROGRAM,
+            &pool_state,
+            params.token_in == pool_state.token_mint_0,
+            clmm_swap::swap_tick_array_count(&params.quote),
+        )
+        .await?;
+
         clmm_swap::build_clmm_swap_instruction(
             params,
             &self.keypair.pubkey(),
             &CLMM_PROGRAM,
             &pool_data,
+            &swap_accounts,
         ).await
     }
 
@@ -545,165 +1076,87 @@ impl TransactionExecutor {
         self.rpc_client.get_transaction_with_config(signature, config).await
     }
 
-    /// Get actual output amount from transaction
-    async fn get_actual_output_amount(&self, signature: &Signature) -> SwapResult<u64> {
+    /// Get actual output amount of `token_out_mint` from transaction, with what
+    /// each pool of `quotes` logged about its swap when the logs cover them all
+    ///
+    /// Without pool events, the output is the change in the user's own
+    /// balance of `token_out_mint`.
+    async fn get_actual_output_amount(
+        &self,
+        signature: &Signature,
+        token_out_mint: &Pubkey,
+        quotes: &[QuoteResult],
+    ) -> SwapResult<(u64, Vec<SwapExecution>)> {
         info!("Analyzing transaction output for signature: {}", signature);
+        let unresolved = |reason: String| SwapError::UnresolvedOutput {
+            signature: signature.to_string(),
+            reason,
+        };
         
         // Wait a bit for transaction to be fully processed
         tokio::time::sleep(std::time::Duration::from_millis(2000)).await;
         
         // Get transaction details with full metadata
-        match self.get_transaction_with_config(signature).await {
-            Ok(transaction) => {
-                if let Some(meta) = transaction.transaction.meta {
-                    // Parse token balance changes from pre/post token balances
-                    use solana_transaction_status::option_serializer::OptionSerializer;
-                    
-                    let (pre_balances, post_balances) = match (&meta.pre_token_balances, &meta.post_token_balances) {
-                        (OptionSerializer::Some(pre), OptionSerializer::Some(post)) => (pre, post),
-                        _ => {
-                            debug!("Token balance data not available");
-                            return Ok(0);
-                        }
-                    };
-                    
-                    info!("Pre token balances: {} accounts", pre_balances.len());
-                    info!("Post token balances: {} accounts", post_balances.len());
-                    
-                    // Find balance changes for each account
-                    for post_balance in post_balances {
-                        if let Some(pre_balance) = pre_balances.iter()
-                            .find(|pb| pb.account_index == post_balance.account_index) {
-                            
-                            // Parse amounts
-                            info!("Account {} - Pre amount string: '{}', Post amount string: '{}'", 
-                                post_balance.account_index,
-                                pre_balance.ui_token_amount.amount,
-                                post_balance.ui_token_amount.amount
-                            );
-                            let pre_amount = pre_balance.ui_token_amount.amount.parse::<u64>().unwrap_or(0);
-                            let post_amount = post_balance.ui_token_amount.amount.parse::<u64>().unwrap_or(0);
-                            
-                            if post_amount > pre_amount {
-                                let amount_received = post_amount - pre_amount;
-                                info!("Found token balance increase: {} tokens (account {})", 
-                                    amount_received, post_balance.account_index);
-                                
-                                info!("Token mint: {}", post_balance.mint);
-                                info!("Decimals: {}", post_balance.ui_token_amount.decimals);
-                                info!("UI Amount String: {}", post_balance.ui_token_amount.ui_amount_string);
-                                info!("Amount received: {} units", amount_received);
-                                
-                                return Ok(amount_received);
-                            }
-                        } else {
-                            // New token account created during swap
-                            info!("New account {} - Amount string: '{}'", 
-                                post_balance.account_index,
-                                post_balance.ui_token_amount.amount
-                            );
-                            let amount_received = post_balance.ui_token_amount.amount.parse::<u64>().unwrap_or(0);
-                            if amount_received > 0 {
-                                info!("Found new token account with balance: {} tokens (account {})", 
-                                    amount_received, post_balance.account_index);
-                                
-                                info!("Token mint: {}", post_balance.mint);
-                                info!("Decimals: {}", post_balance.ui_token_amount.decimals);
-                                info!("UI Amount String: {}", post_balance.ui_token_amount.ui_amount_string);
-                                info!("Amount received: {} units", amount_received);
-                                
-                                return Ok(amount_received);
-                            }
-                        }
-                    }
-                    
-                    // Alternative: Parse from instruction logs
-                    match &meta.log_messages {
-                        solana_transaction_status::option_serializer::OptionSerializer::Some(log_messages) => {
-                            for log in log_messages {
-                                // Raydium AMM swap logs contain transfer information
-                                if log.contains("Program log: ray_log:") {
-                                    debug!("Raydium log: {}", log);
-                                    
-                                    // Try to extract amounts from Raydium logs
-                                    if let Some(amount) = self.parse_swap_amount_from_logs(log) {
-                                        info!("Parsed swap amount from logs: {}", amount);
-                                        return Ok(amount);
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                    
-                    debug!("Could not determine actual output amount from transaction data");
-                } else {
-                    warn!("Transaction metadata not available");
-                }
-            }
-            Err(e) => {
-                warn!("Could not get transaction details: {}", e);
-            }
-        }
-        
-        // Return 0 if we couldn't parse the actual amount
-        Ok(0)
-    }
-    
-    /// Parse swap amount from Raydium log messages
-    fn parse_swap_amount_from_logs(&self, log: &str) -> Option<u64> {
-        // Raydium logs contain swap information in different formats for each pool type
-        
-        // AMM pool logs format: "ray_log: ... swap_base_out:123456, swap_quote_out:789012"
-        if log.contains("swap_base_out:") || log.contains("swap_quote_out:") {
-            for pattern in ["swap_base_out:", "swap_quote_out:"] {
-                if let Some(start) = log.find(pattern) {
-                    let remaining = &log[start + pattern.len()..];
-                    // Find the number - it ends at comma, space, or end of string
-                    let end = remaining.find(|c: char| !c.is_numeric())
-                        .unwrap_or(remaining.len());
-                    if let Ok(amount) = remaining[..end].trim().parse::<u64>() {
-                        if amount > 0 {
-                            debug!("Parsed {} amount: {}", pattern, amount);
-                            return Some(amount);
-                        }
-                    }
-                }
-            }
-        }
-        
-        // CLMM pool logs format: "amount_0:123456, amount_1:789012"
-        if log.contains("amount_0:") || log.contains("amount_1:") {
-            for pattern in ["amount_0:", "amount_1:"] {
-                if let Some(start) = log.find(pattern) {
-                    let remaining = &log[start + pattern.len()..];
-                    let end = remaining.find(|c: char| !c.is_numeric())
-                        .unwrap_or(remaining.len());
-                    if let Ok(amount) = remaining[..end].trim().parse::<u64>() {
-                        if amount > 0 {
-                            debug!("Parsed CLMM {} amount: {}", pattern, amount);
-                            return Some(amount);
-                        }
-                    }
-                }
+        let transaction = self
+            .get_transaction_with_config(signature)
+            .await
+            .map_err(|e| unresolved(format!("could not fetch transaction: {}", e)))?;
+        let meta = transaction
+            .transaction
+            .meta
+            .as_ref()
+            .ok_or_else(|| unresolved("transaction metadata not available".to_string()))?;
+
+        // Pool programs log the exact fill of each swap
+        if let OptionSerializer::Some(log_messages) = &meta.log_messages {
+            let events = SwapEvent::decode_all(log_messages);
+            if events_match_quotes(&events, quotes) {
+                let amount_out = events
+                    .iter()
+                    .zip(quotes)
+                    .filter(|(_, quote)| quote.token_out == *token_out_mint)
+                    .map(|(event, _)| event.amount_received())
+                    .sum();
+                info!("Parsed swap output from pool events: {}", amount_out);
+                let executions = events.iter().zip(quotes).map(|(event, quote)| event.execution(quote)).collect();
+                return Ok((amount_out, executions));
             }
+            debug!("{} swap events logged for {} swaps, reading balances", events.len(), quotes.len());
         }
-        
-        // Stable pool logs format: "amount_out:123456"
-        if log.contains("amount_out:") {
-            if let Some(start) = log.find("amount_out:") {
-                let remaining = &log[start + "amount_out:".len()..];
-                let end = remaining.find(|c: char| !c.is_numeric())
-                    .unwrap_or(remaining.len());
-                if let Ok(amount) = remaining[..end].trim().parse::<u64>() {
-                    debug!("Parsed Stable amount_out: {}", amount);
-                    return Some(amount);
-                }
-            }
+
+        let account_keys = monitor::utils::message_account_keys(&transaction.transaction.transaction);
+        let user = self.keypair.pubkey();
+        let change = monitor::utils::owner_balance_changes(meta, &account_keys, &user)
+            .into_iter()
+            .find(|change| change.mint == *token_out_mint)
+            .map_or(0, |change| change.change);
+        if change <= 0 {
+            return Err(unresolved(format!("{} balance of {} changed by {}", token_out_mint, user, change)));
         }
-        
-        None
+
+        info!("User received {} units of {}", change, token_out_mint);
+        Ok((change as u64, Vec::new()))
+    }
+}
+
+/// Refuse transactions too large for the network to accept
+fn ensure_fits_packet<T: serde::Serialize>(transaction: &T) -> SwapResult<()> {
+    let size = bincode::serialized_size(transaction)
+        .map_err(|e| SwapError::SerializationError(e.to_string()))? as usize;
+    if size > PACKET_DATA_SIZE {
+        warn!("Lookup tables can shrink the transaction: run `alt sync` or pass tables in LOOKUP_TABLES");
+        return Err(SwapError::TransactionTooLarge { size, limit: PACKET_DATA_SIZE });
     }
+    Ok(())
+}
+
+/// Whether `events` are one per quoted swap, each from the quoted pool
+fn events_match_quotes(events: &[SwapEvent], quotes: &[QuoteResult]) -> bool {
+    events.len() == quotes.len()
+        && events.iter().zip(quotes).all(|(event, quote)| {
+            event.matches_pool_type(quote.pool_info.pool_type)
+                && event.pool().is_none_or(|pool| pool == quote.pool_info.address)
+        })
 }
 
 /// Calculate actual slippage percentage
@@ -712,11 +1165,7 @@ fn calculate_actual_slippage(expected: u64, actual: u64) -> f64 {
         return 0.0;
     }
     
-    let diff = if expected > actual {
-        expected - actual
-    } else {
-        actual - expected
-    };
+    let diff = expected.abs_diff(actual);
     
     (diff as f64 / expected as f64) * 100.0
 }
\ No newline at end of file
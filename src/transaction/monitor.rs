@@ -30,6 +30,17 @@ pub struct BalanceChange {
     pub decimals: u8,
 }
 
+/// Net change of one mint across everything an owner holds in a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OwnerBalanceChange {
+    /// Token mint; native SOL is reported under the native mint
+    pub mint: Pubkey,
+    /// Change amount (can be negative)
+    pub change: i64,
+    /// Token decimals
+    pub decimals: u8,
+}
+
 /// Transaction status monitoring configuration
 #[derive(Debug, Clone)]
 pub struct MonitorConfig {
@@ -230,7 +241,7 @@ impl TransactionMonitor {
         let signature = self.rpc_client
             .send_transaction(transaction)
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         info!("Transaction sent: {}", signature);
 
@@ -353,7 +364,7 @@ impl TransactionMonitor {
         let recent_blockhash = self.rpc_client
             .get_latest_blockhash()
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         transaction.message.recent_blockhash = recent_blockhash;
         transaction.signatures.clear();
@@ -426,8 +437,14 @@ impl TransactionMonitor {
             SwapError::ParseError(_) => false,
             SwapError::ConfigError(_) => false,
             SwapError::InsufficientBalance(_) => false,
+            SwapError::UnsupportedMint(_) => false,
             SwapError::InsufficientLiquidity { .. } => false,
+            SwapError::TickRangeExceeded { .. } => false,
             SwapError::SlippageExceeded { .. } => false,
+            SwapError::QuoteMoved { .. } => false,
+            // The swap already landed; resending would trade again
+            SwapError::UnresolvedOutput { .. } => false,
+            SwapError::TransactionTooLarge { .. } => false,
             SwapError::NoPoolsFound(_, _) => false,
             SwapError::UnsupportedPoolType(_) => false,
             SwapError::InvalidTokenMint(_) => false,
@@ -462,20 +479,35 @@ impl TransactionMonitor {
             .await
         {
             Ok(_transaction) => Ok(UiTransactionEncoding::Json),
-            Err(e) => Err(SwapError::RpcError(e)),
+            Err(e) => Err(SwapError::from(e)),
         }
     }
 
-    /// Extract token balance changes from transaction
+    /// Extract `owner`'s balance change of each mint from transaction
     pub async fn get_token_balance_changes(
         &self,
         signature: &Signature,
-    ) -> SwapResult<Vec<(String, i64)>> {
-        // This would parse transaction logs to find token balance changes
-        // For now, return empty vector as placeholder
-        // TODO
+        owner: &Pubkey,
+    ) -> SwapResult<Vec<OwnerBalanceChange>> {
         debug!("Getting token balance changes for transaction: {}", signature);
-        Ok(vec![])
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::confirmed()),
+            max_supported_transaction_version: Some(0),
+        };
+
+        let transaction = self
+            .rpc_client
+            .get_transaction_with_config(signature, config)
+            .await
+            .map_err(SwapError::from)?;
+        let meta = transaction
+            .transaction
+            .meta
+            .as_ref()
+            .ok_or_else(|| SwapError::Other("No transaction metadata available".to_string()))?;
+        let account_keys = utils::message_account_keys(&transaction.transaction.transaction);
+        Ok(utils::owner_balance_changes(meta, &account_keys, owner))
     }
 
     /// Send versioned transaction and monitor its status
@@ -486,7 +518,7 @@ impl TransactionMonitor {
         let signature = self.rpc_client
             .send_transaction(transaction)
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         info!("Versioned transaction sent: {}", signature);
 
@@ -505,7 +537,7 @@ impl TransactionMonitor {
         let recent_blockhash = self.rpc_client
             .get_latest_blockhash()
             .await
-            .map_err(SwapError::RpcError)?;
+            .map_err(SwapError::from)?;
 
         // Update blockhash in the versioned message
         match &mut transaction.message {
@@ -539,6 +571,94 @@ impl TransactionMonitor {
 pub mod utils {
     use super::*;
     use solana_sdk::pubkey::Pubkey;
+    use solana_transaction_status::{
+        option_serializer::OptionSerializer, EncodedTransaction, UiMessage, UiTransactionStatusMeta,
+        UiTransactionTokenBalance,
+    };
+
+    /// Account keys a JSON-encoded transaction lists in its message
+    pub fn message_account_keys(transaction: &EncodedTransaction) -> Vec<String> {
+        match transaction {
+            EncodedTransaction::Json(json_tx) => match &json_tx.message {
+                UiMessage::Raw(raw_msg) => raw_msg.account_keys.clone(),
+                UiMessage::Parsed(parsed_msg) => parsed_msg
+                    .account_keys
+                    .iter()
+                    .map(|key| key.pubkey.clone())
+                    .collect(),
+            },
+            _ => vec![],
+        }
+    }
+
+    fn token_balances(balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>) -> &[UiTransactionTokenBalance] {
+        match balances {
+            OptionSerializer::Some(balances) => balances,
+            _ => &[],
+        }
+    }
+
+    /// Net change of each mint `owner` holds over a transaction, counting only
+    /// token accounts `owner` owns
+    ///
+    /// Native SOL is the lamport change of the owner's wallet and token
+    /// accounts, plus the fee when the owner paid it. Rent moved into new
+    /// accounts or back from closed ones and SOL (un)wrapping net out.
+    pub fn owner_balance_changes(
+        meta: &UiTransactionStatusMeta,
+        account_keys: &[String],
+        owner: &Pubkey,
+    ) -> Vec<OwnerBalanceChange> {
+        let owner_str = owner.to_string();
+        let (pre_token, post_token) = (token_balances(&meta.pre_token_balances), token_balances(&meta.post_token_balances));
+
+        let mut changes: Vec<OwnerBalanceChange> = Vec::new();
+        let mut owned_accounts: Vec<usize> = Vec::new();
+        for (balance, sign) in pre_token.iter().map(|b| (b, -1i64)).chain(post_token.iter().map(|b| (b, 1))) {
+            if !matches!(&balance.owner, OptionSerializer::Some(balance_owner) if *balance_owner == owner_str) {
+                continue;
+            }
+            let Ok(mint) = Pubkey::from_str(&balance.mint) else {
+                continue;
+            };
+            if !owned_accounts.contains(&(balance.account_index as usize)) {
+                owned_accounts.push(balance.account_index as usize);
+            }
+            // Wrapped SOL is counted in lamports below
+            if mint == spl_token::native_mint::ID {
+                continue;
+            }
+
+            let amount = balance.ui_token_amount.amount.parse::<i64>().unwrap_or(0);
+            match changes.iter_mut().find(|change| change.mint == mint) {
+                Some(change) => change.change += sign * amount,
+                None => changes.push(OwnerBalanceChange {
+                    mint,
+                    change: sign * amount,
+                    decimals: balance.ui_token_amount.decimals,
+                }),
+            }
+        }
+
+        let wallet = account_keys.iter().position(|key| *key == owner_str);
+        let lamport_change = |index: usize| match (meta.pre_balances.get(index), meta.post_balances.get(index)) {
+            (Some(pre), Some(post)) => *post as i64 - *pre as i64,
+            _ => 0,
+        };
+        let fee = if wallet == Some(0) { meta.fee as i64 } else { 0 };
+        let native_change =
+            wallet.into_iter().chain(owned_accounts).map(lamport_change).sum::<i64>() + fee;
+
+        changes.retain(|change| change.change != 0);
+        if native_change != 0 {
+            changes.push(OwnerBalanceChange {
+                mint: spl_token::native_mint::ID,
+                change: native_change,
+                decimals: 9,
+            });
+        }
+        changes
+    }
 
     /// Calculate transaction fee
     pub async fn calculate_transaction_fee(
@@ -559,7 +679,7 @@ pub mod utils {
                     Err(SwapError::Other("No transaction metadata available".to_string()))
                 }
             }
-            Err(e) => Err(SwapError::RpcError(e)),
+            Err(e) => Err(SwapError::from(e)),
         }
     }
 
@@ -649,7 +769,7 @@ pub mod utils {
                     Err(SwapError::Other("No transaction metadata available".to_string()))
                 }
             }
-            Err(e) => Err(SwapError::RpcError(e)),
+            Err(e) => Err(SwapError::from(e)),
         }
     }
 
@@ -672,17 +792,7 @@ pub mod utils {
                     use solana_transaction_status::option_serializer::OptionSerializer;
                     
                     // Get account keys
-                    let account_keys: Vec<String> = match &transaction.transaction.transaction {
-                            solana_transaction_status::EncodedTransaction::Json(json_tx) => {
-                                match &json_tx.message {
-                                    solana_transaction_status::UiMessage::Parsed(_) => vec![],
-                                    solana_transaction_status::UiMessage::Raw(raw_msg) => {
-                                        raw_msg.account_keys.clone()
-                                    }
-                                }
-                            }
-                            _ => vec![]
-                        };
+                    let account_keys = message_account_keys(&transaction.transaction.transaction);
                     
                     // Parse token balance changes
                     if let (OptionSerializer::Some(pre_token), OptionSerializer::Some(post_token)) = 
@@ -740,7 +850,7 @@ pub mod utils {
                     Err(SwapError::Other("No transaction metadata available".to_string()))
                 }
             }
-            Err(e) => Err(SwapError::RpcError(e)),
+            Err(e) => Err(SwapError::from(e)),
         }
     }
 
@@ -752,7 +862,73 @@ pub mod utils {
         match rpc_client.get_signature_status(signature).await {
             Ok(Some(status)) => Ok(status.is_ok()),
             Ok(None) => Ok(false),
-            Err(e) => Err(SwapError::RpcError(e)),
+            Err(e) => Err(SwapError::from(e)),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_transaction_status::UiTransactionStatusMeta;
+
+    #[test]
+    fn test_owner_balance_changes_ignore_other_owners_and_net_out_rent() {
+        // SOL -> TKN: the user wraps 1 SOL, opens wSOL and TKN accounts and
+        // receives 250 TKN while the pool vault and a fee account also move
+        let (user, authority, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let tkn = Pubkey::new_unique();
+        let rent = 2_039_280u64;
+        let token_balance = |index: u8, mint: Pubkey, owner: Pubkey, amount: u64| {
+            json!({
+                "accountIndex": index,
+                "mint": mint.to_string(),
+                "owner": owner.to_string(),
+                "uiTokenAmount": {
+                    "uiAmount": null,
+                    "decimals": 6,
+                    "amount": amount.to_string(),
+                    "uiAmountString": "",
+                },
+            })
+        };
+        let wsol = spl_token::native_mint::ID;
+        let meta: UiTransactionStatusMeta = serde_json::from_value(json!({
+            "err": null,
+            "status": { "Ok": null },
+            "fee": 15_000,
+            "preBalances": [5_000_000_000u64, 0, 0, rent, rent],
+            "postBalances": [5_000_000_000u64 - 15_000 - 2 * rent - 1_000_000, rent, rent, rent, rent],
+            "preTokenBalances": [
+                token_balance(3, tkn, authority, 100_000),
+                token_balance(4, tkn, other, 0),
+            ],
+            "postTokenBalances": [
+                token_balance(1, wsol, user, 0),
+                token_balance(2, tkn, user, 250),
+                token_balance(3, tkn, authority, 99_749),
+                token_balance(4, tkn, other, 1),
+            ],
+        }))
+        .unwrap();
+        let account_keys: Vec<String> =
+            [user, Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()]
+                .iter()
+                .map(|key| key.to_string())
+                .collect();
+
+        let changes = utils::owner_balance_changes(&meta, &account_keys, &user);
+        assert_eq!(
+            changes,
+            vec![
+                OwnerBalanceChange { mint: tkn, change: 250, decimals: 6 },
+                OwnerBalanceChange { mint: wsol, change: -1_000_000, decimals: 9 },
+            ]
+        );
+
+        // The fee account's owner sees only its own account
+        let changes = utils::owner_balance_changes(&meta, &account_keys, &other);
+        assert_eq!(changes, vec![OwnerBalanceChange { mint: tkn, change: 1, decimals: 6 }]);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::core::{
+    Config, PriorityFee, COMPUTE_UNIT_LIMIT_MARGIN_PERCENT, DEFAULT_COMPUTE_UNIT_PRICE,
+    DEFAULT_MAX_PRIORITY_FEE_LAMPORTS, DEFAULT_PRIORITY_FEE_PERCENTILE, MAX_COMPUTE_UNIT_LIMIT,
+};
+use log::{debug, info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+
+/// Most accounts `getRecentPrioritizationFees` accepts
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Chooses the compute unit price of swap transactions
+#[derive(Debug, Clone)]
+pub struct PriorityFeeEstimator {
+    mode: PriorityFee,
+    percentile: u8,
+    max_lamports: u64,
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(mode: PriorityFee, percentile: u8, max_lamports: u64) -> Self {
+        Self {
+            mode,
+            percentile: percentile.min(100),
+            max_lamports,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.priority_fee, config.priority_fee_percentile, config.max_priority_fee_lamports)
+    }
+
+    /// Compute unit price in micro-lamports for a transaction requesting
+    /// `compute_unit_limit` units around `swap_instructions`
+    pub async fn compute_unit_price(
+        &self,
+        rpc_client: &RpcClient,
+        swap_instructions: &[Instruction],
+        compute_unit_limit: u32,
+    ) -> u64 {
+        let accounts = writable_accounts(swap_instructions);
+        let price = self
+            .compute_unit_price_for_accounts(Some(rpc_client), &accounts, compute_unit_limit)
+            .await;
+        info!("Compute unit price: {} micro-lamports", price);
+        price
+    }
+
+    /// Compute unit price for a transaction requesting `compute_unit_limit`
+    /// units and writing `accounts`; without an RPC client automatic fees
+    /// fall back to the default price
+    pub async fn compute_unit_price_for_accounts(
+        &self,
+        rpc_client: Option<&RpcClient>,
+        accounts: &[Pubkey],
+        compute_unit_limit: u32,
+    ) -> u64 {
+        let price = match (self.mode, rpc_client) {
+            (PriorityFee::Fixed(price), _) => price,
+            (PriorityFee::Auto, None) => DEFAULT_COMPUTE_UNIT_PRICE,
+            (PriorityFee::Auto, Some(rpc_client)) => {
+                let accounts = &accounts[..accounts.len().min(MAX_FEE_ACCOUNTS)];
+                match rpc_client.get_recent_prioritization_fees(accounts).await {
+                    Ok(fees) => {
+                        let mut fees: Vec<u64> = fees.into_iter().map(|fee| fee.prioritization_fee).collect();
+                        let price = fee_percentile(&mut fees, self.percentile);
+                        debug!(
+                            "p{} of {} recent prioritization fees on {} accounts: {}",
+                            self.percentile,
+                            fees.len(),
+                            accounts.len(),
+                            price
+                        );
+                        price
+                    }
+                    Err(e) => {
+                        warn!("Failed to fetch recent prioritization fees: {}, using {}", e, DEFAULT_COMPUTE_UNIT_PRICE);
+                        DEFAULT_COMPUTE_UNIT_PRICE
+                    }
+                }
+            }
+        };
+
+        let capped = cap_compute_unit_price(price, compute_unit_limit, self.max_lamports);
+        if capped < price {
+            warn!(
+                "Priority fee of {} micro-lamports/CU capped at {} ({} lamports max)",
+                price, capped, self.max_lamports
+            );
+        }
+        capped
+    }
+}
+
+impl Default for PriorityFeeEstimator {
+    fn default() -> Self {
+        Self::new(PriorityFee::default(), DEFAULT_PRIORITY_FEE_PERCENTILE, DEFAULT_MAX_PRIORITY_FEE_LAMPORTS)
+    }
+}
+
+/// Accounts the swap writes to (pools, vaults, tick arrays), which are what
+/// other transactions compete for
+fn writable_accounts(instructions: &[Instruction]) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for meta in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if meta.is_writable && !meta.is_signer && !accounts.contains(&meta.pubkey) {
+            accounts.push(meta.pubkey);
+        }
+    }
+    accounts.truncate(MAX_FEE_ACCOUNTS);
+    accounts
+}
+
+/// Fee at `percentile` (0-100) of `fees`, nearest rank
+fn fee_percentile(fees: &mut [u64], percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    let rank = (fees.len() * percentile as usize).div_ceil(100).max(1);
+    fees[rank - 1]
+}
+
+/// Compute unit limit for a transaction that used `units_consumed` in
+/// simulation, with headroom for state changing before it lands
+pub fn compute_unit_limit_with_margin(units_consumed: u64) -> u32 {
+    let limit = (units_consumed * (100 + COMPUTE_UNIT_LIMIT_MARGIN_PERCENT)).div_ceil(100);
+    limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// Highest price at which `compute_unit_limit` units cost at most `max_lamports`
+fn cap_compute_unit_price(price: u64, compute_unit_limit: u32, max_lamports: u64) -> u64 {
+    if compute_unit_limit == 0 {
+        return price;
+    }
+    let max_price = max_lamports as u128 * 1_000_000 / compute_unit_limit as u128;
+    price.min(max_price.min(u64::MAX as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    #[test]
+    fn test_fee_percentile() {
+        let mut fees = vec![0, 500, 100, 0, 10_000, 2_000, 0, 300, 50, 1_000];
+        assert_eq!(fee_percentile(&mut fees, 50), 100);
+        assert_eq!(fee_percentile(&mut fees, 75), 1_000);
+        assert_eq!(fee_percentile(&mut fees, 100), 10_000);
+        assert_eq!(fee_percentile(&mut fees, 0), 0);
+        assert_eq!(fee_percentile(&mut [], 75), 0);
+    }
+
+    #[test]
+    fn test_cap_and_writable_accounts() {
+        // 400k units at 2,500 micro-lamports is exactly 1,000 lamports
+        assert_eq!(cap_compute_unit_price(10_000, 400_000, 1_000), 2_500);
+        assert_eq!(cap_compute_unit_price(2_000, 400_000, 1_000), 2_000);
+
+        let (user, pool, vault, program) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![
+                AccountMeta::new_readonly(user, true),
+                AccountMeta::new(pool, false),
+                AccountMeta::new_readonly(program, false),
+                AccountMeta::new(vault, false),
+                AccountMeta::new(pool, false),
+            ],
+            data: vec![],
+        };
+        assert_eq!(writable_accounts(&[instruction]), vec![pool, vault]);
+    }
+
+    #[test]
+    fn test_compute_unit_limit_with_margin() {
+        assert_eq!(compute_unit_limit_with_margin(40_000), 46_000);
+        assert_eq!(compute_unit_limit_with_margin(40_001), 46_002);
+        assert_eq!(compute_unit_limit_with_margin(1_300_000), MAX_COMPUTE_UNIT_LIMIT);
+    }
+}
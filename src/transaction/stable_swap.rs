@@ -1,102 +1,149 @@
-use crate::core::{error::SwapError, SwapParams, SwapResult, PoolState, constants::STABLE_PROGRAM};
+use crate::core::{error::SwapError, layouts::StablePoolState, SwapMode, SwapParams, SwapResult};
+use crate::transaction::amm_swap::parse_serum_market_accounts;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
-    sysvar,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use log::debug;
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::ID as TOKEN_PROGRAM_ID;
+
+/// Stable swap instruction discriminators (shared with the AMM V4 program)
+const STABLE_SWAP_BASE_IN: u8 = 9;
+const STABLE_SWAP_BASE_OUT: u8 = 11;
+
+/// Seed of the pool authority PDA, bumped by the pool's nonce
+const STABLE_AUTHORITY_SEED: &[u8] = b"amm authority";
 
 /// Raydium Stable swap instruction data
 #[derive(Debug, BorshSerialize, BorshDeserialize)]
 struct StableSwapInstructionData {
     /// Instruction discriminator for Stable swap
     instruction: u8,
-    /// Amount of input token
+    /// Amount of input token (maximum input for base out)
     amount_in: u64,
-    /// Minimum amount of output token
+    /// Minimum amount of output token (exact output for base out)
     minimum_amount_out: u64,
 }
 
-/// Build Raydium Stable swap instruction
+impl StableSwapInstructionData {
+    fn new_base_in(amount_in: u64, minimum_amount_out: u64) -> Self {
+        Self {
+            instruction: STABLE_SWAP_BASE_IN,
+            amount_in,
+            minimum_amount_out,
+        }
+    }
+
+    fn new_base_out(max_amount_in: u64, amount_out: u64) -> Self {
+        Self {
+            instruction: STABLE_SWAP_BASE_OUT,
+            amount_in: max_amount_in,
+            minimum_amount_out: amount_out,
+        }
+    }
+}
+
+/// Build Raydium Stable swap instruction from the pool and market accounts
 pub async fn build_stable_swap_instruction(
     params: &SwapParams,
     user_pubkey: &Pubkey,
     pool_program: &Pubkey,
-    _pool_data: &[u8],
+    pool_data: &[u8],
+    market_data: &[u8],
 ) -> SwapResult<Instruction> {
     debug!("Building Stable swap instruction");
-    
-    let pool_info = &params.quote.pool_info;
-    
-    // Extract pool state
-    let (_reserves, _amp_factor) = match &pool_info.pool_state {
-        PoolState::Stable { reserves, amp_factor } => (reserves, *amp_factor),
-        _ => return Err(SwapError::InvalidPoolState("Expected Stable pool state".to_string())),
-    };
-    
-    // Use token mints from params
-    let token_in = params.token_in;
-    let token_out = params.token_out;
-    
-    // Determine token indices  
-    let (token_in_index, _token_out_index) = if pool_info.token_a.mint == token_in {
-        (0, 1)
+
+    let pool_address = params.quote.pool_info.address;
+    let pool_state = StablePoolState::from_bytes(pool_data).map_err(SwapError::ParseError)?;
+
+    // Swap direction from the pool's own mints
+    let (source_mint, destination_mint) = if params.token_in == pool_state.coin_mint {
+        (pool_state.coin_mint, pool_state.pc_mint)
+    } else if params.token_in == pool_state.pc_mint {
+        (pool_state.pc_mint, pool_state.coin_mint)
     } else {
-        (1, 0)
+        return Err(SwapError::InvalidTokenMint(format!(
+            "{} is not traded by Stable pool {}",
+            params.token_in, pool_address
+        )));
     };
-    
+
+    let authority = derive_stable_pool_authority(pool_program, pool_state.nonce)?;
+    let user_token_in = get_associated_token_address(user_pubkey, &source_mint);
+    let user_token_out = get_associated_token_address(user_pubkey, &destination_mint);
+
+    let (market_bids, market_asks, market_event_queue, market_coin_vault, market_pc_vault, market_authority) =
+        if crate::core::is_placeholder_market(&pool_state.market) {
+            debug!("Stable pool {} has placeholder market, using dummy accounts", pool_address);
+            let dummy = Pubkey::default();
+            (dummy, dummy, dummy, dummy, dummy, dummy)
+        } else {
+            parse_serum_market_accounts(market_data, &pool_state.market, &pool_state.market_program)?
+        };
+
     debug!(
-        "Stable swap: {} -> {}, amount: {}, min_out: {}",
-        if token_in_index == 0 { &pool_info.token_a.symbol } else { &pool_info.token_b.symbol },
-        if token_in_index == 0 { &pool_info.token_b.symbol } else { &pool_info.token_a.symbol },
-        params.quote.amount_in,
-        params.quote.min_amount_out
+        "Stable swap: {} -> {}, amount: {}, min_out: {}, authority: {}",
+        source_mint, destination_mint, params.quote.amount_in, params.quote.min_amount_out, authority
     );
-    
-    // Get user token accounts
-    let user_token_in = get_associated_token_address(user_pubkey, &token_in);
-    let user_token_out = get_associated_token_address(user_pubkey, &token_out);
-    
-    // Derive pool accounts
-    let pool_token_accounts = derive_stable_pool_token_accounts(&pool_info.address)?;
-    let pool_authority = derive_stable_pool_authority(&pool_info.address)?;
-    
-    // Stable swap instruction discriminator is typically 1
-    let instruction_data = StableSwapInstructionData {
-        instruction: 1, // Swap instruction
-        amount_in: params.quote.amount_in,
-        minimum_amount_out: params.quote.min_amount_out,
+
+    let instruction_data = match params.quote.swap_mode {
+        SwapMode::ExactIn => StableSwapInstructionData::new_base_in(
+            params.quote.amount_in,
+            params.quote.min_amount_out,
+        ),
+        SwapMode::ExactOut => StableSwapInstructionData::new_base_out(
+            params.quote.max_amount_in,
+            params.quote.amount_out,
+        ),
     };
-    
+
     let mut data = Vec::new();
     instruction_data.serialize(&mut data)
         .map_err(|e| SwapError::SerializationError(e.to_string()))?;
-    
-    // Build accounts for stable swap
-    // Account order for Raydium Stable:
-    // 0. [signer] User
-    // 1. [] Pool state account
-    // 2. [] Pool authority
-    // 3. [writable] User token in account
-    // 4. [writable] User token out account
-    // 5. [writable] Pool token A vault
-    // 6. [writable] Pool token B vault
-    // 7. [] Token program
-    // 8. [] Clock sysvar (for time-based checks)
-    
+
+    // Same ordering for base in and base out: the AMM V4 accounts without
+    // target orders, with the model data after the vaults
     let accounts = vec![
-        AccountMeta::new_readonly(*user_pubkey, true), // User (signer)
-        AccountMeta::new_readonly(pool_info.address, false), // Pool state
-        AccountMeta::new_readonly(pool_authority, false), // Pool authority
-        AccountMeta::new(user_token_in, false), // User token in
-        AccountMeta::new(user_token_out, false), // User token out
-        AccountMeta::new(pool_token_accounts.0, false), // Pool token A vault
-        AccountMeta::new(pool_token_accounts.1, false), // Pool token B vault
-        AccountMeta::new_readonly(spl_token::id(), false), // Token program
-        AccountMeta::new_readonly(sysvar::clock::id(), false), // Clock sysvar
+        // 0. Token program
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        // 1. Pool account (writable)
+        AccountMeta::new(pool_address, false),
+        // 2. Pool authority
+        AccountMeta::new_readonly(authority, false),
+        // 3. Pool open orders (writable)
+        AccountMeta::new(pool_state.open_orders, false),
+        // 4. Pool coin vault (writable)
+        AccountMeta::new(pool_state.coin_vault, false),
+        // 5. Pool pc vault (writable)
+        AccountMeta::new(pool_state.pc_vault, false),
+        // 6. Model data, read by every Stable pool
+        AccountMeta::new_readonly(pool_state.model_data_account, false),
+        // 7. OpenBook program
+        AccountMeta::new_readonly(pool_state.market_program, false),
+        // 8. Market (writable)
+        AccountMeta::new(pool_state.market, false),
+        // 9. Market bids (writable)
+        AccountMeta::new(market_bids, false),
+        // 10. Market asks (writable)
+        AccountMeta::new(market_asks, false),
+        // 11. Market event queue (writable)
+        AccountMeta::new(market_event_queue, false),
+        // 12. Market coin vault (writable)
+        AccountMeta::new(market_coin_vault, false),
+        // 13. Market pc vault (writable)
+        AccountMeta::new(market_pc_vault, false),
+        // 14. Market vault signer
+        AccountMeta::new_readonly(market_authority, false),
+        // 15. User source token account (writable)
+        AccountMeta::new(user_token_in, false),
+        // 16. User destination token account (writable)
+        AccountMeta::new(user_token_out, false),
+        // 17. User owner (signer)
+        AccountMeta::new_readonly(*user_pubkey, true),
     ];
-    
+
     Ok(Instruction {
         program_id: *pool_program,
         accounts,
@@ -104,60 +151,235 @@ pub async fn build_stable_swap_instruction(
     })
 }
 
-/// Derive stable pool token vault accounts
-fn derive_stable_pool_token_accounts(pool_address: &Pubkey) -> SwapResult<(Pubkey, Pubkey)> {
-    // In Raydium Stable, token vaults are PDAs derived from pool address
-    // Seeds: [pool_address, token_index]
-    
-    let (token_a_vault, _) = Pubkey::find_program_address(
-        &[pool_address.as_ref(), &[0u8]],
-        &STABLE_PROGRAM,
-    );
-    
-    let (token_b_vault, _) = Pubkey::find_program_address(
-        &[pool_address.as_ref(), &[1u8]],
-        &STABLE_PROGRAM,
-    );
-    
-    Ok((token_a_vault, token_b_vault))
-}
-
-/// Derive stable pool authority
-fn derive_stable_pool_authority(pool_address: &Pubkey) -> SwapResult<Pubkey> {
-    // Pool authority is a PDA
-    let (authority, _) = Pubkey::find_program_address(
-        &[pool_address.as_ref()],
-        &STABLE_PROGRAM,
-    );
-    
-    Ok(authority)
-}
-
-/// Get associated token address
-fn get_associated_token_address(wallet: &Pubkey, mint: &Pubkey) -> Pubkey {
-    spl_associated_token_account::get_associated_token_address(wallet, mint)
+/// Derive the Stable pool authority from the nonce stored in the pool
+pub(crate) fn derive_stable_pool_authority(pool_program: &Pubkey, nonce: u64) -> SwapResult<Pubkey> {
+    let nonce = u8::try_from(nonce)
+        .map_err(|_| SwapError::InvalidPoolState(format!("Invalid Stable pool nonce {}", nonce)))?;
+    Pubkey::create_program_address(&[STABLE_AUTHORITY_SEED, &[nonce]], pool_program)
+        .map_err(|e| SwapError::InvalidPoolState(format!("Invalid Stable pool authority: {}", e)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use crate::core::{
+        fixtures::{fixture_files, fixture_path, load_account, load_transaction},
+        is_placeholder_market, PoolInfo, PoolState, PoolType, QuoteResult, TokenInfo, STABLE_PROGRAM,
+    };
+
+    fn key(seed: u8) -> Pubkey {
+        Pubkey::new_from_array([seed; 32])
+    }
+
+    /// Stable pool account fixture: coin vault 1, pc vault 2, coin mint 3,
+    /// pc mint 4, model data 6, open orders 7, market 8, market program 9
+    fn pool_account(nonce: u64) -> Vec<u8> {
+        let mut data = vec![0u8; StablePoolState::LEN];
+        data[8..16].copy_from_slice(&6u64.to_le_bytes());
+        data[16..24].copy_from_slice(&nonce.to_le_bytes());
+        for (offset, seed) in [(368, 1), (400, 2), (432, 3), (464, 4), (496, 5), (528, 6), (560, 7), (592, 8), (624, 9), (656, 10)] {
+            data[offset..offset + 32].copy_from_slice(&key(seed).to_bytes());
+        }
+        data
+    }
+
+    /// OpenBook market account fixture: coin vault 11, pc vault 12, event
+    /// queue 13, bids 14, asks 15
+    fn market_account(vault_signer_nonce: u64) -> Vec<u8> {
+        let mut data = vec![0u8; crate::core::MarketState::LEN];
+        data[45..53].copy_from_slice(&vault_signer_nonce.to_le_bytes());
+        for (offset, seed) in [(117, 11), (165, 12), (253, 13), (285, 14), (317, 15)] {
+            data[offset..offset + 32].copy_from_slice(&key(seed).to_bytes());
+        }
+        data
+    }
+
+    fn swap_params(token_in: Pubkey, token_out: Pubkey, swap_mode: SwapMode) -> SwapParams {
+        let token = |mint| TokenInfo { mint, symbol: String::new(), decimals: 6, name: String::new() };
+        let quote = QuoteResult {
+            pool_info: PoolInfo {
+                pool_type: PoolType::Stable,
+                address: key(20),
+                token_a: token(key(3)),
+                token_b: token(key(4)),
+                liquidity_usd: 0.0,
+                volume_24h_usd: 0.0,
+                fee_rate: 0.0005,
+                program_id: *STABLE_PROGRAM,
+                context_slot: 0,
+                open_time: 0,
+                pool_state: PoolState::Stable {
+                    reserves: vec![1, 1],
+                    amp_factor: 1,
+                    swap_fee_numerator: 5,
+                    swap_fee_denominator: 10_000,
+                },
+            },
+            amount_in: 1_000_000,
+            amount_out: 999_000,
+            min_amount_out: 994_000,
+            max_amount_in: 1_005_000,
+            swap_mode,
+            price_impact: 0.0,
+            fee: 500,
+            route: vec![key(20)],
+            token_in,
+            token_out,
+            tick_traversal: None,
+            execution_costs: None,
+        };
+        SwapParams { quote, user_pubkey: key(30), slippage_bps: 50, token_in, token_out }
+    }
+
+    /// Instruction layout against synthetic accounts; the builder is checked
+    /// against the program's own accounts in `test_golden_mainnet_swaps`
+    #[tokio::test]
+    async fn test_synthetic_stable_swap_instructions() {
+        let (_, nonce) = Pubkey::find_program_address(&[STABLE_AUTHORITY_SEED], &STABLE_PROGRAM);
+        let authority = Pubkey::create_program_address(&[STABLE_AUTHORITY_SEED, &[nonce]], &STABLE_PROGRAM).unwrap();
+        let vault_signer_nonce = (0u64..)
+            .find(|n| Pubkey::create_program_address(&[&key(8).to_bytes(), &n.to_le_bytes()], &key(9)).is_ok())
+            .unwrap();
+        let vault_signer =
+            Pubkey::create_program_address(&[&key(8).to_bytes(), &vault_signer_nonce.to_le_bytes()], &key(9)).unwrap();
+        let (pool_data, market_data) = (pool_account(nonce as u64), market_account(vault_signer_nonce));
+        let user = key(30);
+
+        // Selling pc for coin with an exact output
+        let params = swap_params(key(4), key(3), SwapMode::ExactOut);
+        let instruction = build_stable_swap_instruction(&params, &user, &STABLE_PROGRAM, &pool_data, &market_data)
+            .await
+            .unwrap();
+
+        let expected = [
+            (TOKEN_PROGRAM_ID, false),
+            (key(20), true),
+            (authority, false),
+            (key(7), true),
+            (key(1), true),
+            (key(2), true),
+            (key(6), false),
+            (key(9), false),
+            (key(8), true),
+            (key(14), true),
+            (key(15), true),
+            (key(13), true),
+            (key(11), true),
+            (key(12), true),
+            (vault_signer, false),
+            (get_associated_token_address(&user, &key(4)), true),
+            (get_associated_token_address(&user, &key(3)), true),
+        ];
+        assert_eq!(instruction.program_id, *STABLE_PROGRAM);
+        assert_eq!(instruction.accounts.len(), 18);
+        for (i, (meta, (pubkey, is_writable))) in instruction.accounts.iter().zip(expected).enumerate() {
+            assert_eq!((meta.pubkey, meta.is_writable, meta.is_signer), (pubkey, is_writable, false), "account {}", i);
+        }
+        assert_eq!(instruction.accounts[17], AccountMeta::new_readonly(user, true));
+
+        // 11 | max amount in | amount out
+        let mut data = vec![STABLE_SWAP_BASE_OUT];
+        data.extend_from_slice(&1_005_000u64.to_le_bytes());
+        data.extend_from_slice(&999_000u64.to_le_bytes());
+        assert_eq!(instruction.data, data);
+
+        // Base in keeps the accounts and sends amount in | minimum out
+        let params = swap_params(key(3), key(4), SwapMode::ExactIn);
+        let instruction = build_stable_swap_instruction(&params, &user, &STABLE_PROGRAM, &pool_data, &market_data)
+            .await
+            .unwrap();
+        assert_eq!(instruction.accounts[15].pubkey, get_associated_token_address(&user, &key(3)));
+        let mut data = vec![STABLE_SWAP_BASE_IN];
+        data.extend_from_slice(&1_000_000u64.to_le_bytes());
+        data.extend_from_slice(&994_000u64.to_le_bytes());
+        assert_eq!(instruction.data, data);
+
+        // Mints outside the pool are rejected
+        let params = swap_params(key(40), key(4), SwapMode::ExactIn);
+        assert!(build_stable_swap_instruction(&params, &user, &STABLE_PROGRAM, &pool_data, &market_data)
+            .await
+            .is_err());
+    }
+
+    /// Rebuild every Stable swap in the captured transactions from the
+    /// captured pool, model data and market accounts, and compare the result
+    /// with what was executed
+    #[tokio::test]
+    #[ignore = "needs captured mainnet accounts and transactions, see fixtures/README.md"]
+    async fn test_golden_mainnet_swaps() {
+        let model_data = load_account(&fixture_path("stable/model_data.json"));
+        let files = fixture_files("stable/swaps");
+        assert!(!files.is_empty(), "no transactions captured under fixtures/stable/swaps");
+
+        for path in files {
+            let transaction = load_transaction(&path);
+            let swaps: Vec<_> = transaction
+                .instructions_of(&STABLE_PROGRAM)
+                .into_iter()
+                .filter(|instruction| matches!(instruction.data.first(), Some(&STABLE_SWAP_BASE_IN | &STABLE_SWAP_BASE_OUT)))
+                .collect();
+            assert!(!swaps.is_empty(), "{}: no Stable swaps", transaction.signature);
+
+            for captured in swaps {
+                let context = format!(
+                    "pool {} at slot {} in {}",
+                    captured.accounts[1], transaction.slot, transaction.signature
+                );
+                let pool = load_account(&fixture_path(&format!("stable/pools/{}.json", captured.accounts[1])));
+                let pool_state = StablePoolState::from_bytes(&pool.data).unwrap();
+                assert_eq!(pool_state.model_data_account, model_data.address, "{}", context);
+                let market_data = if is_placeholder_market(&pool_state.market) {
+                    Vec::new()
+                } else {
+                    load_account(&fixture_path(&format!("stable/markets/{}.json", pool_state.market))).data
+                };
+
+                // Direction from the user's source account
+                let user = captured.accounts[17];
+                let (token_in, token_out) = if captured.accounts[15] == get_associated_token_address(&user, &pool_state.coin_mint) {
+                    (pool_state.coin_mint, pool_state.pc_mint)
+                } else if captured.accounts[15] == get_associated_token_address(&user, &pool_state.pc_mint) {
+                    (pool_state.pc_mint, pool_state.coin_mint)
+                } else {
+                    panic!("{}: captured swaps must use associated token accounts", context);
+                };
+
+                let (first, second) = (
+                    u64::from_le_bytes(captured.data[1..9].try_into().unwrap()),
+                    u64::from_le_bytes(captured.data[9..17].try_into().unwrap()),
+                );
+                let swap_mode = if captured.data[0] == STABLE_SWAP_BASE_IN { SwapMode::ExactIn } else { SwapMode::ExactOut };
+                let mut params = swap_params(token_in, token_out, swap_mode);
+                params.user_pubkey = user;
+                params.quote.pool_info.address = pool.address;
+                params.quote.route = vec![pool.address];
+                match swap_mode {
+                    SwapMode::ExactIn => (params.quote.amount_in, params.quote.min_amount_out) = (first, second),
+                    SwapMode::ExactOut => (params.quote.max_amount_in, params.quote.amount_out) = (first, second),
+                }
+
+                let instruction = build_stable_swap_instruction(&params, &user, &STABLE_PROGRAM, &pool.data, &market_data)
+                    .await
+                    .unwrap();
+                let accounts: Vec<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+                assert_eq!(accounts, captured.accounts, "{}", context);
+                assert_eq!(instruction.data, captured.data, "{}", context);
+            }
+        }
+    }
+
     #[test]
     fn test_stable_swap_instruction_serialization() {
-        let instruction_data = StableSwapInstructionData {
-            instruction: 1,
-            amount_in: 1_000_000_000,
-            minimum_amount_out: 900_000_000,
-        };
-        
+        let instruction_data = StableSwapInstructionData::new_base_in(1_000_000_000, 900_000_000);
+
         let mut serialized = Vec::new();
         instruction_data.serialize(&mut serialized).unwrap();
         assert!(!serialized.is_empty());
-        
+
         // Verify we can deserialize
         let deserialized = StableSwapInstructionData::try_from_slice(&serialized).unwrap();
-        assert_eq!(deserialized.instruction, 1);
+        assert_eq!(deserialized.instruction, STABLE_SWAP_BASE_IN);
         assert_eq!(deserialized.amount_in, 1_000_000_000);
         assert_eq!(deserialized.minimum_amount_out, 900_000_000);
     }
-}
\ No newline at end of file
+}